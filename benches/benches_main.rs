@@ -5,5 +5,7 @@ mod benchmarks;
 criterion_main!(
     benchmarks::key::benches,
     benchmarks::message::benches,
-    benchmarks::s2k::benches
+    benchmarks::s2k::benches,
+    benchmarks::signature::benches,
+    benchmarks::sym::benches
 );
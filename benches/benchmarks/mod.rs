@@ -6,6 +6,8 @@ use smallvec::smallvec;
 pub mod key;
 pub mod message;
 pub mod s2k;
+pub mod signature;
+pub mod sym;
 
 #[cfg(feature = "profile")]
 pub mod profiler {
@@ -0,0 +1,82 @@
+use criterion::{black_box, criterion_group, BenchmarkId, Criterion};
+use pgp::composed::{KeyType, SignedPublicKey};
+use pgp::crypto::hash::HashAlgorithm;
+use pgp::packet::{
+    Signature, SignatureConfig, SignatureType, SignatureVersion, Subpacket, SubpacketData,
+};
+use pgp::types::KeyTrait;
+
+use super::build_key;
+
+fn signed_items(count: usize) -> Vec<(Signature, Vec<u8>, SignedPublicKey)> {
+    (0..count)
+        .map(|i| {
+            let key = build_key(KeyType::EdDSA, KeyType::ECDH)
+                .sign(|| "".into())
+                .unwrap();
+            let data = format!("message number {i}").into_bytes();
+            let config = SignatureConfig::new_v4(
+                SignatureVersion::V4,
+                SignatureType::Binary,
+                key.algorithm(),
+                HashAlgorithm::SHA2_256,
+                vec![Subpacket::regular(SubpacketData::IssuerFingerprint(
+                    Default::default(),
+                    key.fingerprint().into(),
+                ))],
+                vec![],
+            );
+            let sig = config.sign(&key, || "".into(), &data[..]).unwrap();
+
+            (sig, data, SignedPublicKey::from(key))
+        })
+        .collect()
+}
+
+fn bench_signature(c: &mut Criterion) {
+    let mut g = c.benchmark_group("signature");
+
+    for count in [16, 64, 256] {
+        let items = signed_items(count);
+        let items: Vec<_> = items
+            .iter()
+            .map(|(sig, data, key)| (sig, &data[..], key))
+            .collect();
+
+        g.bench_with_input(
+            BenchmarkId::new("ed25519_verify_one_by_one", count),
+            &items,
+            |b, items| {
+                b.iter(|| {
+                    for (sig, data, key) in items {
+                        sig.verify(*key, *data).unwrap();
+                    }
+                })
+            },
+        );
+
+        g.bench_with_input(
+            BenchmarkId::new("ed25519_verify_batch", count),
+            &items,
+            |b, items| b.iter(|| Signature::verify_batch(black_box(items)).unwrap()),
+        );
+    }
+
+    g.finish();
+}
+
+#[cfg(feature = "profile")]
+fn profiled() -> Criterion {
+    Criterion::default().with_profiler(super::profiler::GProfiler)
+}
+
+#[cfg(not(feature = "profile"))]
+fn profiled() -> Criterion {
+    Criterion::default()
+}
+
+criterion_group!(
+    name = benches;
+    config = profiled();
+    targets = bench_signature
+);
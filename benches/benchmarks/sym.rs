@@ -0,0 +1,64 @@
+use criterion::{black_box, criterion_group, BenchmarkId, Criterion, Throughput};
+use pgp::crypto::sym::{aes_hardware_accelerated, SymmetricKeyAlgorithm};
+use rand::RngCore;
+
+/// Benchmarks the symmetric ciphers. The AES benchmarks are named after the implementation
+/// they use, run them a second time with `RUSTFLAGS="--cfg aes_force_soft"` to compare the
+/// hardware accelerated and the software implementation in the criterion report.
+fn bench_sym(c: &mut Criterion) {
+    let aes_impl = if aes_hardware_accelerated() {
+        "hardware"
+    } else {
+        "software"
+    };
+
+    const KB: usize = 1000;
+    let sizes = [KB, 100 * KB, 1000 * KB];
+    let algs = [
+        SymmetricKeyAlgorithm::AES128,
+        SymmetricKeyAlgorithm::AES256,
+        SymmetricKeyAlgorithm::Twofish,
+        SymmetricKeyAlgorithm::Camellia128,
+    ];
+    let mut rng = rand::thread_rng();
+
+    let mut group = c.benchmark_group("sym");
+    for alg in algs {
+        for size in sizes {
+            group.throughput(Throughput::BytesDecimal(size as u64));
+            let name = match alg {
+                SymmetricKeyAlgorithm::AES128 | SymmetricKeyAlgorithm::AES256 => {
+                    format!("{alg:?}-{aes_impl}/{size}")
+                }
+                _ => format!("{alg:?}/{size}"),
+            };
+            group.bench_with_input(
+                BenchmarkId::new("encrypt_protected", name),
+                &(alg, size),
+                |b, &(alg, size): &(SymmetricKeyAlgorithm, usize)| {
+                    let mut bytes = vec![0u8; size];
+                    rng.fill_bytes(&mut bytes);
+                    let key = alg.new_session_key(&mut rng);
+
+                    b.iter(|| {
+                        let res = alg
+                            .encrypt_protected_with_rng(&mut rng, &key, &bytes)
+                            .unwrap();
+                        black_box(res);
+                    })
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+fn profiled() -> Criterion {
+    Criterion::default()
+}
+
+criterion_group!(
+    name = benches;
+    config = profiled();
+    targets = bench_sym
+);
@@ -11,7 +11,7 @@ use nom::branch::alt;
 use nom::bytes::streaming::{tag, take_until1};
 use nom::bytes::streaming::{take, take_until};
 use nom::character::streaming::{alphanumeric1, digit1, line_ending, not_line_ending, space0};
-use nom::combinator::{complete, map, map_res, opt, success, value};
+use nom::combinator::{complete, map, map_res, opt, peek, success, value};
 use nom::multi::many0;
 use nom::sequence::{delimited, pair, preceded, terminated};
 use nom::IResult;
@@ -49,6 +49,9 @@ pub enum BlockType {
     File,
     /// Cleartext Framework message
     CleartextMessage,
+    /// A block with the non-standard label given to [`Dearmor::with_custom_label`], which is
+    /// returned by [`Dearmor::custom_label`]
+    Custom,
 }
 
 impl fmt::Display for BlockType {
@@ -59,6 +62,10 @@ impl fmt::Display for BlockType {
 
 impl Serialize for BlockType {
     fn to_writer<W: io::Write>(&self, w: &mut W) -> Result<()> {
+        ensure!(
+            *self != BlockType::Custom,
+            "the label of a custom block is not known"
+        );
         w.write_all(self.as_string().as_bytes())?;
 
         Ok(())
@@ -81,6 +88,7 @@ impl BlockType {
             BlockType::PrivateKeyPKCS8 => "PRIVATE KEY".into(),
             BlockType::PrivateKeyOpenssh => "OPENSSH PRIVATE KEY".into(),
             BlockType::CleartextMessage => "PGP SIGNED MESSAGE".into(),
+            BlockType::Custom => "CUSTOM".into(),
         }
     }
 }
@@ -180,11 +188,27 @@ fn armor_header_type(i: &[u8]) -> IResult<&[u8], BlockType> {
     ))(i)
 }
 
+/// Parses the type inside of an ascii armor header, trying the custom `label` first.
+fn armor_header_type_with_label<'a>(
+    i: &'a [u8],
+    label: Option<&str>,
+) -> IResult<&'a [u8], BlockType> {
+    if let Some(label) = label {
+        match terminated(tag(label.as_bytes()), peek(armor_header_sep))(i) {
+            Ok((i, _)) => return Ok((i, BlockType::Custom)),
+            Err(nom::Err::Error(_)) => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    armor_header_type(i)
+}
+
 /// Parses a single armor header line.
-fn armor_header_line(i: &[u8]) -> IResult<&[u8], BlockType> {
+fn armor_header_line<'a>(i: &'a [u8], label: Option<&str>) -> IResult<&'a [u8], BlockType> {
     delimited(
         pair(armor_header_sep, tag(b"BEGIN ")),
-        armor_header_type,
+        |i| armor_header_type_with_label(i, label),
         pair(armor_header_sep, line_ending),
     )(i)
 }
@@ -235,8 +259,8 @@ fn armor_headers(i: &[u8]) -> IResult<&[u8], Headers> {
 }
 
 /// Armor Header
-fn armor_header(i: &[u8]) -> IResult<&[u8], (BlockType, Headers)> {
-    let (i, typ) = armor_header_line(i)?;
+fn armor_header<'a>(i: &'a [u8], label: Option<&str>) -> IResult<&'a [u8], (BlockType, Headers)> {
+    let (i, typ) = armor_header_line(i, label)?;
     let (i, headers) = match typ {
         BlockType::CleartextMessage => armor_headers_hash(i)?,
         _ => armor_headers(i)?,
@@ -288,15 +312,26 @@ fn read_checksum(input: &[u8]) -> std::io::Result<u64> {
 }
 
 pub fn header_parser(i: &[u8]) -> IResult<&[u8], (BlockType, Headers, bool)> {
+    header_parser_with_label(i, None)
+}
+
+/// Like [`header_parser`], but also accepts blocks with the non-standard `label`.
+fn header_parser_with_label<'a>(
+    i: &'a [u8],
+    label: Option<&str>,
+) -> IResult<&'a [u8], (BlockType, Headers, bool)> {
     let (i, prefix) = take_until("-----")(i)?;
     let has_leading_data = !prefix.is_empty();
-    let (i, (typ, headers)) = armor_header(i)?;
+    let (i, (typ, headers)) = armor_header(i, label)?;
     let (i, _) = many0(pair(space0, line_ending))(i)?;
 
     Ok((i, (typ, headers, has_leading_data)))
 }
 
-fn footer_parser(i: &[u8]) -> IResult<&[u8], (Option<u64>, BlockType)> {
+fn footer_parser<'a>(
+    i: &'a [u8],
+    label: Option<&str>,
+) -> IResult<&'a [u8], (Option<u64>, BlockType)> {
     let (i, checksum) = map_res(
         alt((
             delimited(
@@ -312,17 +347,17 @@ fn footer_parser(i: &[u8]) -> IResult<&[u8], (Option<u64>, BlockType)> {
         )),
         |c| c.map(read_checksum).transpose(),
     )(i)?;
-    let (i, typ) = armor_footer_line(i)?;
+    let (i, typ) = armor_footer_line(i, label)?;
 
     Ok((i, (checksum, typ)))
 }
 
 /// Parses a single armor footer line
-fn armor_footer_line(i: &[u8]) -> IResult<&[u8], BlockType> {
+fn armor_footer_line<'a>(i: &'a [u8], label: Option<&str>) -> IResult<&'a [u8], BlockType> {
     // Only 3, because we parsed two already in the `footer_parser`.
     delimited(
         tag(b"---END "),
-        armor_header_type,
+        |i| armor_header_type_with_label(i, label),
         pair(armor_header_sep, opt(complete(line_ending))),
     )(i)
 }
@@ -338,6 +373,11 @@ pub struct Dearmor<R: BufRead> {
     /// current state
     current_part: Part<R>,
     crc: crc24::Crc24Hasher,
+    /// Return the data even if the checksum doesn't match.
+    checksum_recovery: bool,
+    checksum_mismatch: bool,
+    /// Non-standard label to accept in addition to the known block types.
+    custom_label: Option<String>,
 }
 
 /// Internal indicator, where in the parsing phase we are
@@ -359,9 +399,40 @@ impl<R: BufRead> Dearmor<R> {
             checksum: None,
             current_part: Part::Header(input),
             crc: Default::default(),
+            checksum_recovery: false,
+            checksum_mismatch: false,
+            custom_label: None,
         }
     }
 
+    /// Also accept blocks with the given non-standard header label, such as `CERTIFICATE`,
+    /// which are reported as [`BlockType::Custom`].
+    ///
+    /// The base64 body and the optional checksum are validated as for any other block.
+    pub fn with_custom_label(mut self, label: impl Into<String>) -> Self {
+        self.custom_label = Some(label.into());
+        self
+    }
+
+    /// The non-standard label set with [`Dearmor::with_custom_label`], if any.
+    pub fn custom_label(&self) -> Option<&str> {
+        self.custom_label.as_deref()
+    }
+
+    /// Don't fail if the checksum doesn't match the data, to salvage data from damaged
+    /// inputs. Use [`Dearmor::checksum_mismatch`] to check if the checksum was wrong.
+    pub fn with_checksum_recovery(mut self) -> Self {
+        self.checksum_recovery = true;
+        self
+    }
+
+    /// Returns true if the data was read completely, and the checksum didn't match.
+    ///
+    /// Only ever true with [`Dearmor::with_checksum_recovery`], otherwise reading fails.
+    pub fn checksum_mismatch(&self) -> bool {
+        self.checksum_mismatch
+    }
+
     pub fn into_parts(self) -> (Option<BlockType>, Headers, Option<u64>, BufReader<R>) {
         let Self {
             typ,
@@ -380,13 +451,18 @@ impl<R: BufRead> Dearmor<R> {
     pub fn read_only_header(mut self) -> Result<(BlockType, Headers, bool, R)> {
         let header = std::mem::replace(&mut self.current_part, Part::Temp);
         if let Part::Header(mut b) = header {
-            let (typ, headers, leading) = Self::read_header_internal(&mut b)?;
+            let (typ, headers, leading) =
+                Self::read_header_internal(&mut b, self.custom_label.as_deref())?;
             return Ok((typ, headers, leading, b));
         }
 
         bail!("invalid state, cannot read header");
     }
 
+    /// Continues reading a block after its header, as returned by [`Dearmor::read_only_header`].
+    ///
+    /// For a [`BlockType::Custom`] block, the label has to be set again with
+    /// [`Dearmor::with_custom_label`] to find the footer.
     pub fn after_header(typ: BlockType, headers: Headers, input: R) -> Self {
         Self {
             typ: Some(typ),
@@ -394,13 +470,17 @@ impl<R: BufRead> Dearmor<R> {
             checksum: None,
             current_part: Part::Body(Base64Decoder::new(Base64Reader::new(input))),
             crc: Default::default(),
+            checksum_recovery: false,
+            checksum_mismatch: false,
+            custom_label: None,
         }
     }
 
     pub fn read_header(&mut self) -> Result<()> {
         let header = std::mem::replace(&mut self.current_part, Part::Temp);
         if let Part::Header(mut b) = header {
-            let (typ, headers, _has_leading_data) = Self::read_header_internal(&mut b)?;
+            let (typ, headers, _has_leading_data) =
+                Self::read_header_internal(&mut b, self.custom_label.as_deref())?;
             self.typ = Some(typ);
             self.headers = headers;
             self.current_part = Part::Body(Base64Decoder::new(Base64Reader::new(b)));
@@ -410,8 +490,9 @@ impl<R: BufRead> Dearmor<R> {
         bail!("invalid state, cannot read header");
     }
 
-    fn read_header_internal(b: &mut R) -> Result<(BlockType, Headers, bool)> {
-        let (typ, headers, leading) = read_from_buf(b, "armor header", header_parser)?;
+    fn read_header_internal(b: &mut R, label: Option<&str>) -> Result<(BlockType, Headers, bool)> {
+        let (typ, headers, leading) =
+            read_from_buf(b, "armor header", |i| header_parser_with_label(i, label))?;
         Ok((typ, headers, leading))
     }
 
@@ -430,7 +511,9 @@ impl<R: BufRead> Dearmor<R> {
     }
 
     fn read_footer(&mut self, mut b: BufReader<R>) -> Result<()> {
-        let (checksum, footer_typ) = read_from_buf(&mut b, "armor footer", footer_parser)?;
+        let label = self.custom_label.as_deref();
+        let (checksum, footer_typ) =
+            read_from_buf(&mut b, "armor footer", |i| footer_parser(i, label))?;
         if let Some(ref header_typ) = self.typ {
             if header_typ != &footer_typ {
                 self.current_part = Part::Done(b);
@@ -448,7 +531,14 @@ impl<R: BufRead> Dearmor<R> {
         if let Some(expected) = self.checksum {
             let actual = self.crc.finish();
             if expected != actual {
-                bail!("invalid crc24 checksum");
+                if !self.checksum_recovery {
+                    bail!("invalid crc24 checksum");
+                }
+                warn!(
+                    "invalid crc24 checksum: expected {:06x}, got {:06x}",
+                    expected, actual
+                );
+                self.checksum_mismatch = true;
             }
         }
 
@@ -463,8 +553,9 @@ impl<R: BufRead> Read for Dearmor<R> {
             let current_part = std::mem::replace(&mut self.current_part, Part::Temp);
             match current_part {
                 Part::Header(mut b) => {
-                    let (typ, headers, _leading) = Self::read_header_internal(&mut b)
-                        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+                    let (typ, headers, _leading) =
+                        Self::read_header_internal(&mut b, self.custom_label.as_deref())
+                            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
                     self.typ = Some(typ);
                     self.headers = headers;
                     self.current_part = Part::Body(Base64Decoder::new(Base64Reader::new(b)));
@@ -537,6 +628,37 @@ pub(crate) fn read_from_buf<B: BufRead, T, P: Fn(&[u8]) -> IResult<&[u8], T>>(
     }
 }
 
+/// Returns `true` if the armored inputs `a` and `b` have the same block type and decode to the
+/// same packets.
+///
+/// Cosmetic differences are ignored: line wrapping, armor headers (including their order
+/// and the presence of a `Version` header), the checksum line and the packet header format.
+/// Inputs that fail to decode are never equal.
+#[cfg(test)]
+pub(crate) fn armor_semantically_equal(a: impl AsRef<[u8]>, b: impl AsRef<[u8]>) -> bool {
+    fn decode(input: &[u8]) -> Result<(BlockType, Vec<u8>)> {
+        let mut dearmor = Dearmor::new(input);
+        let mut bytes = Vec::new();
+        dearmor.read_to_end(&mut bytes)?;
+        let typ = dearmor
+            .typ
+            .ok_or_else(|| format_err!("missing armor header"))?;
+
+        // normalize packet headers
+        let mut packets = Vec::new();
+        for packet in crate::packet::PacketParser::new(&bytes[..]) {
+            packet?.to_writer_with_version(&mut packets, crate::types::Version::New)?;
+        }
+
+        Ok((typ, packets))
+    }
+
+    match (decode(a.as_ref()), decode(b.as_ref())) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(clippy::unwrap_used)]
@@ -565,20 +687,108 @@ mod tests {
         Ok((dearmor.typ.unwrap(), dearmor.headers, bytes))
     }
 
+    #[test]
+    fn test_checksum_recovery() {
+        let input =
+            std::fs::read_to_string("./tests/autocrypt/alice@autocrypt.example.pub.asc").unwrap();
+        let (_, _, expected) = parse(&input).unwrap();
+
+        let mut armored = Vec::new();
+        crate::armor::write(
+            &RawBytes(&expected),
+            BlockType::PublicKey,
+            &mut armored,
+            None,
+            true,
+        )
+        .unwrap();
+        let armored = String::from_utf8(armored).unwrap();
+        let crc_start = armored.rfind("\n=").unwrap() + 2;
+        let damaged = format!("{}AAAA{}", &armored[..crc_start], &armored[crc_start + 4..]);
+        assert_ne!(damaged, armored);
+        assert!(parse(&damaged).is_err());
+
+        let mut dearmor = Dearmor::new(damaged.as_bytes()).with_checksum_recovery();
+        let mut bytes = Vec::new();
+        dearmor.read_to_end(&mut bytes).unwrap();
+        assert_eq!(bytes, expected);
+        assert!(dearmor.checksum_mismatch());
+
+        let mut dearmor = Dearmor::new(armored.as_bytes()).with_checksum_recovery();
+        let mut bytes = Vec::new();
+        dearmor.read_to_end(&mut bytes).unwrap();
+        assert_eq!(bytes, expected);
+        assert!(!dearmor.checksum_mismatch());
+    }
+
+    #[test]
+    fn test_armor_semantically_equal() {
+        let input =
+            std::fs::read_to_string("./tests/autocrypt/alice@autocrypt.example.pub.asc").unwrap();
+        let (typ, _headers, bytes) = parse(&input).unwrap();
+
+        let mut headers = Headers::new();
+        headers.insert("Version".to_string(), vec!["rpgp".to_string()]);
+        headers.insert("Comment".to_string(), vec!["test".to_string()]);
+
+        let mut rewrapped = Vec::new();
+        crate::armor::write_with_line_width(
+            &RawBytes(&bytes),
+            typ,
+            &mut rewrapped,
+            Some(&headers),
+            false,
+            76,
+        )
+        .unwrap();
+        assert!(armor_semantically_equal(&input, &rewrapped));
+
+        // different content
+        let mut modified = bytes.clone();
+        let last = modified.len() - 1;
+        modified[last] ^= 1;
+        let mut other = Vec::new();
+        crate::armor::write(&RawBytes(&modified), typ, &mut other, None, true).unwrap();
+        assert!(!armor_semantically_equal(&input, &other));
+
+        // different block type
+        let mut other = Vec::new();
+        crate::armor::write(
+            &RawBytes(&bytes),
+            BlockType::PrivateKey,
+            &mut other,
+            None,
+            true,
+        )
+        .unwrap();
+        assert!(!armor_semantically_equal(&input, &other));
+
+        assert!(!armor_semantically_equal(&input, "garbage"));
+    }
+
+    struct RawBytes<'a>(&'a [u8]);
+
+    impl Serialize for RawBytes<'_> {
+        fn to_writer<W: io::Write>(&self, w: &mut W) -> Result<()> {
+            w.write_all(self.0)?;
+            Ok(())
+        }
+    }
+
     #[test]
     fn test_armor_header_line() {
         assert_eq!(
-            armor_header_line(&b"-----BEGIN PGP MESSAGE-----\n"[..]).unwrap(),
+            armor_header_line(&b"-----BEGIN PGP MESSAGE-----\n"[..], None).unwrap(),
             (&b""[..], BlockType::Message)
         );
 
         assert_eq!(
-            armor_header_line(&b"-----BEGIN PGP MESSAGE, PART 3/14-----\n"[..]).unwrap(),
+            armor_header_line(&b"-----BEGIN PGP MESSAGE, PART 3/14-----\n"[..], None).unwrap(),
             (&b""[..], BlockType::MultiPartMessage(3, 14))
         );
 
         assert_eq!(
-            armor_header_line(&b"-----BEGIN PGP MESSAGE, PART 14-----\n"[..]).unwrap(),
+            armor_header_line(&b"-----BEGIN PGP MESSAGE, PART 14-----\n"[..], None).unwrap(),
             (&b""[..], BlockType::MultiPartMessage(14, 0))
         );
     }
@@ -606,7 +816,11 @@ mod tests {
         map.insert("Mode".to_string(), vec!["Test".to_string()]);
 
         assert_eq!(
-            armor_header(&b"-----BEGIN PGP MESSAGE-----\nVersion: 1.0\nMode: Test\n"[..],).unwrap(),
+            armor_header(
+                &b"-----BEGIN PGP MESSAGE-----\nVersion: 1.0\nMode: Test\n"[..],
+                None
+            )
+            .unwrap(),
             (&b""[..], (BlockType::Message, map))
         );
 
@@ -614,8 +828,11 @@ mod tests {
         map.insert("Version".to_string(), vec!["GnuPG v1".to_string()]);
 
         assert_eq!(
-            armor_header(&b"-----BEGIN PGP PUBLIC KEY BLOCK-----\nVersion: GnuPG v1\n"[..],)
-                .unwrap(),
+            armor_header(
+                &b"-----BEGIN PGP PUBLIC KEY BLOCK-----\nVersion: GnuPG v1\n"[..],
+                None
+            )
+            .unwrap(),
             (&b""[..], (BlockType::PublicKey, map))
         );
     }
@@ -821,6 +1038,66 @@ y5Zgv9TWZlmW9FDTp4XVgn5zQTEN1LdL7vNXWV9aOvfrqPk5ClBkxhndgq7j6MFs
         assert_eq!(res.as_slice()[0], b'd'); // unchanged
     }
 
+    #[test]
+    fn test_dearmor_custom_label() {
+        let armored =
+            b"-----BEGIN CERTIFICATE-----\n\naGVsbG8gd29ybGQ=\n=sDy3\n-----END CERTIFICATE-----\n";
+
+        // not accepted by default
+        let mut dec = Dearmor::new(BufReader::new(&armored[..]));
+        assert!(dec.read_to_end(&mut Vec::new()).is_err());
+
+        let mut dec = Dearmor::new(BufReader::new(&armored[..])).with_custom_label("CERTIFICATE");
+        let mut res = Vec::new();
+        dec.read_to_end(&mut res).unwrap();
+        assert_eq!(dec.typ, Some(BlockType::Custom));
+        assert_eq!(dec.custom_label(), Some("CERTIFICATE"));
+        assert_eq!(res, b"hello world");
+        assert!(dec.checksum.is_some());
+
+        // the header can be read on its own
+        let dec = Dearmor::new(BufReader::new(&armored[..])).with_custom_label("CERTIFICATE");
+        let (typ, headers, _, rest) = dec.read_only_header().unwrap();
+        assert_eq!(typ, BlockType::Custom);
+        let mut dec = Dearmor::after_header(typ, headers, rest).with_custom_label("CERTIFICATE");
+        let mut res = Vec::new();
+        dec.read_to_end(&mut res).unwrap();
+        assert_eq!(res, b"hello world");
+
+        // custom blocks can't be written without their label
+        assert!(crate::armor::write_bytes(
+            b"hello world",
+            BlockType::Custom,
+            &mut Vec::new(),
+            None,
+            true
+        )
+        .is_err());
+
+        // standard labels are still accepted
+        let (typ, _headers, res) =
+            parse("-----BEGIN PGP MESSAGE-----\n\naGVsbG8gd29ybGQ=\n-----END PGP MESSAGE-----\n")
+                .unwrap();
+        assert_eq!(typ, BlockType::Message);
+        assert_eq!(res, b"hello world");
+
+        // the footer has to match
+        let c = "-----BEGIN CERTIFICATE-----\n\naGVsbG8gd29ybGQ=\n-----END PGP MESSAGE-----\n";
+        let mut dec = Dearmor::new(BufReader::new(c.as_bytes())).with_custom_label("CERTIFICATE");
+        assert!(dec.read_to_end(&mut Vec::new()).is_err());
+
+        // the checksum is still validated
+        let c =
+            "-----BEGIN CERTIFICATE-----\n\naGVsbG8gd29ybGQ=\n=AAAA\n-----END CERTIFICATE-----\n";
+        let mut dec = Dearmor::new(BufReader::new(c.as_bytes())).with_custom_label("CERTIFICATE");
+        assert!(dec.read_to_end(&mut Vec::new()).is_err());
+
+        // and so is the base64 body
+        let c = "-----BEGIN CERTIFICATE-----\n\naGVsb*8gd29ybGQ=\n-----END CERTIFICATE-----\n";
+        let mut dec = Dearmor::new(BufReader::new(c.as_bytes())).with_custom_label("CERTIFICATE");
+        assert!(dec.read_to_end(&mut Vec::new()).is_err());
+    }
+
     #[test]
     fn test_key_value_pair_single() {
         assert_eq!(
@@ -883,44 +1160,44 @@ y5Zgv9TWZlmW9FDTp4XVgn5zQTEN1LdL7vNXWV9aOvfrqPk5ClBkxhndgq7j6MFs
 
     #[test]
     fn test_footer_parser() {
-        assert!(footer_parser(b"-----END PGP MESSAGE----").is_err());
+        assert!(footer_parser(b"-----END PGP MESSAGE----", None).is_err());
         assert_eq!(
-            footer_parser(b"-----END PGP PUBLIC KEY BLOCK-----"),
+            footer_parser(b"-----END PGP PUBLIC KEY BLOCK-----", None),
             Ok((&b""[..], (None, BlockType::PublicKey)))
         );
 
         assert_eq!(
-            footer_parser(b"-----END PGP PUBLIC KEY BLOCK-----\n"),
+            footer_parser(b"-----END PGP PUBLIC KEY BLOCK-----\n", None),
             Ok((&b""[..], (None, BlockType::PublicKey)))
         );
 
         assert_eq!(
-            footer_parser(b"=-----END PGP PUBLIC KEY BLOCK-----\n"),
+            footer_parser(b"=-----END PGP PUBLIC KEY BLOCK-----\n", None),
             Ok((&b""[..], (None, BlockType::PublicKey)))
         );
 
         assert_eq!(
-            footer_parser(b"=4JBj-----END PGP PUBLIC KEY BLOCK-----\r\n"),
+            footer_parser(b"=4JBj-----END PGP PUBLIC KEY BLOCK-----\r\n", None),
             Ok((&b""[..], (Some(14717027), BlockType::PublicKey)))
         );
 
         assert_eq!(
-            footer_parser(b"=4JBj\r\n-----END PGP PUBLIC KEY BLOCK-----\r\n"),
+            footer_parser(b"=4JBj\r\n-----END PGP PUBLIC KEY BLOCK-----\r\n", None),
             Ok((&b""[..], (Some(14717027), BlockType::PublicKey)))
         );
 
         assert_eq!(
-            footer_parser(b"\r\n-----END PGP PUBLIC KEY BLOCK-----\r\n"),
+            footer_parser(b"\r\n-----END PGP PUBLIC KEY BLOCK-----\r\n", None),
             Ok((&b""[..], (None, BlockType::PublicKey)))
         );
 
         assert_eq!(
-            footer_parser(&b"=XyBX\n-----END PGP PUBLIC KEY BLOCK-----\n"[..]),
+            footer_parser(&b"=XyBX\n-----END PGP PUBLIC KEY BLOCK-----\n"[..], None),
             Ok((&b""[..], (Some(6234199), BlockType::PublicKey)))
         );
 
         assert_eq!(
-            footer_parser(&b"-----END PGP MESSAGE-----\n-----BEGIN PGP MESSAGE-----\n\naGVsbG8gd29ybGQ=\n-----END PGP MESSAGE-----\n"[..]),
+            footer_parser(&b"-----END PGP MESSAGE-----\n-----BEGIN PGP MESSAGE-----\n\naGVsbG8gd29ybGQ=\n-----END PGP MESSAGE-----\n"[..], None),
             Ok((
                 &b"-----BEGIN PGP MESSAGE-----\n\naGVsbG8gd29ybGQ=\n-----END PGP MESSAGE-----\n"[..],
                 (None, BlockType::Message)
@@ -3,7 +3,7 @@ use std::io::Write;
 
 use base64::engine::{general_purpose, Engine as _};
 use crc24::Crc24Hasher;
-use generic_array::typenum::U64;
+use generic_array::typenum::U76;
 
 use crate::armor::BlockType;
 use crate::errors::Result;
@@ -13,6 +13,13 @@ use crate::util::TeeWriter;
 
 use super::Headers;
 
+/// Default number of base64 characters per line, as used by most implementations.
+pub const DEFAULT_LINE_WIDTH: usize = 64;
+
+/// Maximum number of base64 characters per line allowed by RFC 4880.
+pub const MAX_LINE_WIDTH: usize = 76;
+
+/// Writes `source` armored as `typ`, which picks the labels of the header and footer lines.
 pub fn write(
     source: &impl Serialize,
     typ: BlockType,
@@ -20,18 +27,205 @@ pub fn write(
     headers: Option<&Headers>,
     include_checksum: bool,
 ) -> Result<()> {
+    write_with_line_width(
+        source,
+        typ,
+        writer,
+        headers,
+        include_checksum,
+        DEFAULT_LINE_WIDTH,
+    )
+}
+
+/// Like [`write`], but wraps the base64 body after `line_width` characters.
+///
+/// `line_width` must be between 1 and [`MAX_LINE_WIDTH`]. The checksum is always written
+/// on its own line, independent of the width.
+pub fn write_with_line_width(
+    source: &impl Serialize,
+    typ: BlockType,
+    writer: &mut impl Write,
+    headers: Option<&Headers>,
+    include_checksum: bool,
+    line_width: usize,
+) -> Result<()> {
+    ensure!(
+        line_width > 0 && line_width <= MAX_LINE_WIDTH,
+        "invalid armor line width {}",
+        line_width
+    );
+
     write_header(writer, typ, headers)?;
 
     // write body
     let mut crc_hasher = include_checksum.then(Crc24Hasher::new);
 
-    write_body(writer, source, crc_hasher.as_mut())?;
+    write_body(writer, source, crc_hasher.as_mut(), line_width)?;
 
     write_footer(writer, typ, crc_hasher)?;
 
     Ok(())
 }
 
+/// Armors already serialized `data` as `typ`, for example to re-armor raw bytes whose type
+/// is known to the caller.
+pub fn write_bytes(
+    data: &[u8],
+    typ: BlockType,
+    writer: &mut impl Write,
+    headers: Option<&Headers>,
+    include_checksum: bool,
+) -> Result<()> {
+    let mut armor_writer = ArmorWriter::new(writer, typ, headers, include_checksum)?;
+    armor_writer.write_all(data)?;
+    armor_writer.finish()?;
+
+    Ok(())
+}
+
+/// Number of raw bytes encoded at once by [`ArmorWriter`], a multiple of 3.
+const CHUNK_SIZE: usize = 3 * 256;
+
+/// An armor writer that encodes incrementally, instead of serializing a complete source.
+///
+/// The header is written on construction, the body is base64 encoded and line wrapped as it
+/// is written, and the checksum and footer are written by [`ArmorWriter::finish`], which must
+/// be called to produce valid output.
+pub struct ArmorWriter<W: Write> {
+    inner: W,
+    typ: BlockType,
+    crc_hasher: Option<Crc24Hasher>,
+    line_width: usize,
+    /// Raw bytes that do not yet form a complete base64 group.
+    pending: [u8; 3],
+    pending_len: usize,
+    /// Number of base64 characters on the current line.
+    column: usize,
+}
+
+impl<W: Write> ArmorWriter<W> {
+    /// Writes the armor header and returns a writer for the body.
+    pub fn new(
+        inner: W,
+        typ: BlockType,
+        headers: Option<&Headers>,
+        include_checksum: bool,
+    ) -> Result<Self> {
+        Self::with_line_width(inner, typ, headers, include_checksum, DEFAULT_LINE_WIDTH)
+    }
+
+    /// Like [`ArmorWriter::new`], but wraps the base64 body after `line_width` characters.
+    pub fn with_line_width(
+        mut inner: W,
+        typ: BlockType,
+        headers: Option<&Headers>,
+        include_checksum: bool,
+        line_width: usize,
+    ) -> Result<Self> {
+        ensure!(
+            line_width > 0 && line_width <= MAX_LINE_WIDTH,
+            "invalid armor line width {}",
+            line_width
+        );
+
+        write_header(&mut inner, typ, headers)?;
+
+        Ok(ArmorWriter {
+            inner,
+            typ,
+            crc_hasher: include_checksum.then(Crc24Hasher::new),
+            line_width,
+            pending: [0u8; 3],
+            pending_len: 0,
+            column: 0,
+        })
+    }
+
+    /// Encodes the remaining data, writes the checksum and footer and returns the inner writer.
+    pub fn finish(mut self) -> Result<W> {
+        if self.pending_len > 0 {
+            let mut enc = [0u8; 4];
+            let len = general_purpose::STANDARD
+                .encode_slice(&self.pending[..self.pending_len], &mut enc)
+                .expect("buffer is large enough");
+            self.write_encoded(&enc[..len])?;
+        }
+        if self.column > 0 {
+            self.inner.write_all(&b"\n"[..])?;
+        }
+
+        write_footer(&mut self.inner, self.typ, self.crc_hasher)?;
+        self.inner.flush()?;
+
+        Ok(self.inner)
+    }
+
+    /// Writes base64 characters, breaking lines after `line_width` characters.
+    fn write_encoded(&mut self, mut enc: &[u8]) -> std::io::Result<()> {
+        while !enc.is_empty() {
+            let n = enc.len().min(self.line_width - self.column);
+            self.inner.write_all(&enc[..n])?;
+            self.column += n;
+            enc = &enc[n..];
+
+            if self.column == self.line_width {
+                self.inner.write_all(&b"\n"[..])?;
+                self.column = 0;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for ArmorWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if let Some(crc_hasher) = self.crc_hasher.as_mut() {
+            crc_hasher.write(buf);
+        }
+
+        let mut input = buf;
+
+        // complete a pending group first
+        if self.pending_len > 0 {
+            let n = input.len().min(3 - self.pending_len);
+            self.pending[self.pending_len..self.pending_len + n].copy_from_slice(&input[..n]);
+            self.pending_len += n;
+            input = &input[n..];
+
+            if self.pending_len < 3 {
+                return Ok(buf.len());
+            }
+
+            let mut enc = [0u8; 4];
+            general_purpose::STANDARD
+                .encode_slice(self.pending, &mut enc)
+                .expect("buffer is large enough");
+            self.pending_len = 0;
+            self.write_encoded(&enc)?;
+        }
+
+        let mut enc = [0u8; CHUNK_SIZE / 3 * 4];
+        while input.len() >= 3 {
+            let n = (input.len() - input.len() % 3).min(CHUNK_SIZE);
+            let len = general_purpose::STANDARD
+                .encode_slice(&input[..n], &mut enc)
+                .expect("buffer is large enough");
+            self.write_encoded(&enc[..len])?;
+            input = &input[n..];
+        }
+
+        self.pending[..input.len()].copy_from_slice(input);
+        self.pending_len = input.len();
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
 fn write_header(writer: &mut impl Write, typ: BlockType, headers: Option<&Headers>) -> Result<()> {
     // write armor header
     writer.write_all(&b"-----BEGIN "[..])?;
@@ -60,9 +254,11 @@ fn write_body(
     writer: &mut impl Write,
     source: &impl Serialize,
     crc_hasher: Option<&mut Crc24Hasher>,
+    line_width: usize,
 ) -> Result<()> {
     {
-        let mut line_wrapper = LineWriter::<_, U64>::new(writer.by_ref(), LineBreak::Lf);
+        let mut line_wrapper =
+            LineWriter::<_, U76>::with_line_length(writer.by_ref(), LineBreak::Lf, line_width)?;
         let mut enc = ZeroWrapper(base64::write::EncoderWriter::new(
             &mut line_wrapper,
             &general_purpose::STANDARD,
@@ -214,4 +410,125 @@ mod tests {
             assert_eq!(lines[lines.len() - 1], "-----END PGP MESSAGE-----");
         }
     }
+
+    #[test]
+    fn writes_line_width() {
+        let mut rng = XorShiftRng::seed_from_u64(0);
+
+        for width in [1, 64, 76] {
+            for i in 2..256 {
+                let buf: Vec<u8> = (0..i).map(|_| rng.gen()).collect();
+                let source = TestSource::new(buf.clone());
+
+                let mut dest = Vec::new();
+                write_with_line_width(&source, BlockType::Message, &mut dest, None, true, width)
+                    .unwrap();
+
+                let dest_str = std::str::from_utf8(&dest).unwrap();
+                let lines = dest_str.lines().collect::<Vec<_>>();
+                let body = &lines[2..lines.len() - 2];
+
+                assert_eq!(lines[0], "-----BEGIN PGP MESSAGE-----");
+                assert!(body[..body.len() - 1].iter().all(|l| l.len() == width));
+                assert!(!body[body.len() - 1].is_empty());
+                assert!(body[body.len() - 1].len() <= width);
+                assert_eq!(lines[lines.len() - 2].len(), 5);
+                assert!(lines[lines.len() - 2].starts_with('='));
+                assert_eq!(lines[lines.len() - 1], "-----END PGP MESSAGE-----");
+
+                let mut dearmor = crate::armor::Dearmor::new(dest.as_slice());
+                let mut decoded = Vec::new();
+                std::io::Read::read_to_end(&mut dearmor, &mut decoded).unwrap();
+                assert_eq!(decoded, buf);
+            }
+        }
+    }
+
+    #[test]
+    fn armor_writer_matches_write() {
+        let mut rng = XorShiftRng::seed_from_u64(0);
+
+        for width in [1, 64, 76] {
+            for i in 0..300 {
+                let buf: Vec<u8> = (0..i).map(|_| rng.gen()).collect();
+                let source = TestSource::new(buf.clone());
+
+                let mut expected = Vec::new();
+                write_with_line_width(
+                    &source,
+                    BlockType::Message,
+                    &mut expected,
+                    None,
+                    true,
+                    width,
+                )
+                .unwrap();
+
+                // write in uneven chunks, so groups and lines are split across writes
+                let mut writer =
+                    ArmorWriter::with_line_width(Vec::new(), BlockType::Message, None, true, width)
+                        .unwrap();
+                let mut rest = &buf[..];
+                while !rest.is_empty() {
+                    let n = rng.gen_range(1..=rest.len().min(100));
+                    writer.write_all(&rest[..n]).unwrap();
+                    rest = &rest[n..];
+                }
+                let actual = writer.finish().unwrap();
+
+                assert_eq!(
+                    std::str::from_utf8(&actual).unwrap(),
+                    std::str::from_utf8(&expected).unwrap()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn write_bytes_block_types() {
+        let data = b"hello world";
+        let cases = [
+            (BlockType::PublicKey, "PGP PUBLIC KEY BLOCK"),
+            (BlockType::PrivateKey, "PGP PRIVATE KEY BLOCK"),
+            (BlockType::Message, "PGP MESSAGE"),
+            (BlockType::Signature, "PGP SIGNATURE"),
+            (BlockType::CleartextMessage, "PGP SIGNED MESSAGE"),
+        ];
+        for (typ, label) in cases {
+            let mut dest = Vec::new();
+            write_bytes(data, typ, &mut dest, None, true).unwrap();
+
+            let mut expected = Vec::new();
+            write(
+                &TestSource::new(data.to_vec()),
+                typ,
+                &mut expected,
+                None,
+                true,
+            )
+            .unwrap();
+            assert_eq!(dest, expected);
+
+            let dest_str = std::str::from_utf8(&dest).unwrap();
+            assert!(dest_str.starts_with(&format!("-----BEGIN {label}-----\n")));
+            assert!(dest_str.ends_with(&format!("-----END {label}-----\n")));
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_line_width() {
+        let source = TestSource::new(vec![1, 2, 3]);
+        for width in [0, MAX_LINE_WIDTH + 1] {
+            let mut dest = Vec::new();
+            assert!(write_with_line_width(
+                &source,
+                BlockType::Message,
+                &mut dest,
+                None,
+                true,
+                width
+            )
+            .is_err());
+        }
+    }
 }
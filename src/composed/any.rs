@@ -10,6 +10,7 @@ use crate::{
 };
 
 /// A flexible representation of what can be represented in an armor file.
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Any {
     Cleartext(CleartextSignedMessage),
@@ -255,12 +255,13 @@ impl CleartextSignedMessage {
         writer.write_all(self.csf_encoded_text.as_bytes())?;
         writer.write_all(&[b'\n'])?;
 
-        armor::write(
+        armor::write_with_line_width(
             &self.signatures,
             armor::BlockType::Signature,
             writer,
             opts.headers,
             opts.include_checksum,
+            opts.line_width,
         )?;
 
         Ok(())
@@ -1,18 +1,27 @@
+use std::io;
 use std::time::Duration;
 
 use chrono::SubsecRound;
-use rand::{thread_rng, CryptoRng, Rng};
+use rand::rngs::StdRng;
+use rand::{thread_rng, CryptoRng, Rng, SeedableRng};
+use sha2::Sha256;
 use smallvec::SmallVec;
+use zeroize::Zeroizing;
 
 use crate::composed::{KeyDetails, SecretKey, SecretSubkey};
+use crate::crypto::aead::AeadAlgorithm;
 use crate::crypto::ecc_curve::ECCCurve;
 use crate::crypto::hash::HashAlgorithm;
+use crate::crypto::prime::Attempt;
 use crate::crypto::public_key::PublicKeyAlgorithm;
 use crate::crypto::sym::SymmetricKeyAlgorithm;
-use crate::crypto::{dsa, ecdh, ecdsa, eddsa, rsa};
-use crate::errors::Result;
-use crate::packet::{self, KeyFlags, UserAttribute, UserId};
-use crate::types::{self, CompressionAlgorithm, PublicParams, RevocationKey, S2kParams};
+use crate::crypto::{dsa, ecdh, ecdsa, ed25519, ed448, eddsa, rsa, x25519, x448};
+use crate::errors::{Error, Result};
+use crate::packet::{self, KeyFlags, Packet, PacketParser, UserAttribute, UserId};
+use crate::types::{
+    self, CompressionAlgorithm, KeyTrait, PlainSecretParams, PublicParams, RevocationKey,
+    S2kChoice, S2kParams, SecretKeyTrait,
+};
 
 #[derive(Debug, PartialEq, Eq, Builder)]
 #[builder(build_fn(validate = "Self::validate"))]
@@ -26,6 +35,8 @@ pub struct SecretKeyParams {
     can_certify: bool,
     #[builder(default)]
     can_encrypt: bool,
+    #[builder(default)]
+    can_authenticate: bool,
 
     // -- Preferences
     /// List of symmetric algorithms that indicate which algorithms the key holder prefers to use.
@@ -37,11 +48,20 @@ pub struct SecretKeyParams {
     /// List of compression algorithms that indicate which algorithms the key holder prefers to use.
     #[builder(default)]
     preferred_compression_algorithms: SmallVec<[CompressionAlgorithm; 8]>,
+    /// List of symmetric and AEAD algorithm pairs that indicate which ciphersuites the key holder prefers to use.
+    #[builder(default)]
+    preferred_aead_algorithms: SmallVec<[(SymmetricKeyAlgorithm, AeadAlgorithm); 4]>,
+    /// Sets the "no-modify" key server preference, asking key servers to only accept updates
+    /// of the key from the key holder.
+    #[builder(default)]
+    keyserver_no_modify: bool,
     #[builder(default)]
     revocation_key: Option<RevocationKey>,
 
-    #[builder]
-    primary_user_id: String,
+    /// The primary User ID. Keys without any User ID carry their preferences
+    /// in a direct key signature instead.
+    #[builder(default, setter(strip_option))]
+    primary_user_id: Option<String>,
 
     #[builder(default)]
     user_ids: Vec<String>,
@@ -51,6 +71,10 @@ pub struct SecretKeyParams {
     passphrase: Option<String>,
     #[builder(default)]
     s2k: Option<S2kParams>,
+    /// Creation time of the primary key, which is part of its fingerprint. Defaults to now.
+    ///
+    /// Keys can only be reproduced with a fixed creation time, see
+    /// [`SecretKeyParams::generate_deterministic`].
     #[builder(default = "chrono::Utc::now().trunc_subsecs(0)")]
     created_at: chrono::DateTime<chrono::Utc>,
     #[builder(default)]
@@ -59,11 +83,33 @@ pub struct SecretKeyParams {
     version: types::KeyVersion,
     #[builder(default)]
     expiration: Option<Duration>,
+    /// Absolute expiration date, an alternative to `expiration`.
+    /// Must be later than `created_at`.
+    #[builder(default, setter(strip_option))]
+    expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Also create a revocation certificate when the generated key is signed, see
+    /// [`SignedSecretKey::pregenerated_revocation`].
+    ///
+    /// [`SignedSecretKey::pregenerated_revocation`]: crate::composed::SignedSecretKey::pregenerated_revocation
+    #[builder(default)]
+    pregenerate_revocation: bool,
+    /// The largest RSA key size in bits that may be generated, for the primary key and subkeys.
+    ///
+    /// The time to generate an RSA key grows quickly with its size, so that a typo can hang
+    /// generation for minutes. Sizes above [`DEFAULT_MAX_RSA_KEY_SIZE`] are thus rejected,
+    /// unless this is raised. Keys of more than 16384 bits are never generated, as they can't
+    /// be used.
+    #[builder(default = "DEFAULT_MAX_RSA_KEY_SIZE")]
+    max_rsa_key_size: u32,
 
     #[builder(default)]
     subkeys: Vec<SubkeyParams>,
 }
 
+/// The largest RSA key size in bits that is generated by default, see
+/// [`SecretKeyParamsBuilder::max_rsa_key_size`].
+pub const DEFAULT_MAX_RSA_KEY_SIZE: u32 = 8192;
+
 #[derive(Debug, Clone, PartialEq, Eq, Builder)]
 pub struct SubkeyParams {
     key_type: KeyType,
@@ -87,6 +133,14 @@ pub struct SubkeyParams {
     s2k: Option<S2kParams>,
     #[builder(default = "chrono::Utc::now().trunc_subsecs(0)")]
     created_at: chrono::DateTime<chrono::Utc>,
+    /// Creation time of the subkey binding signature, the creation time of the subkey if
+    /// `None`.
+    #[builder(default)]
+    binding_created_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Creation time of the primary key binding signature, which signing subkeys embed in their
+    /// binding signature, the creation time of the subkey if `None`.
+    #[builder(default)]
+    primary_key_binding_created_at: Option<chrono::DateTime<chrono::Utc>>,
     #[builder(default)]
     packet_version: types::Version,
     #[builder(default)]
@@ -102,6 +156,7 @@ impl SecretKeyParamsBuilder {
                 if *size < 2048 {
                     return Err("Keys with less than 2048bits are considered insecure".into());
                 }
+                self.validate_rsa_size(*size)?;
             }
             Some(KeyType::EdDSA) => {
                 if let Some(can_encrypt) = self.can_encrypt {
@@ -118,15 +173,76 @@ impl SecretKeyParamsBuilder {
                 };
                 match curve {
                     ECCCurve::P256 | ECCCurve::P384 | ECCCurve::P521 | ECCCurve::Secp256k1 => {}
+                    ECCCurve::Curve25519 | ECCCurve::Ed25519 => {
+                        return Err(format!(
+                            "Curve {} is not a Weierstrass curve and can not be used for ECDSA, use EdDSA or ECDH instead",
+                            curve.name()
+                        ))
+                    }
+                    ECCCurve::BrainpoolP256r1
+                    | ECCCurve::BrainpoolP384r1
+                    | ECCCurve::BrainpoolP512r1 => {
+                        if !cfg!(feature = "openssl") {
+                            return Err(format!(
+                                "Curve {} is only supported for ECDSA with the openssl feature",
+                                curve.name()
+                            ));
+                        }
+                    }
                     _ => return Err(format!("Curve {} is not supported for ECDSA", curve.name())),
                 }
             }
-            Some(KeyType::ECDH) => {
+            Some(key_type @ (KeyType::ECDH | KeyType::ECDHBrainpool(_))) => {
+                if let KeyType::ECDHBrainpool(curve) = key_type {
+                    Self::validate_ecdh_brainpool_curve(curve)?;
+                }
                 if let Some(can_sign) = self.can_sign {
                     if can_sign {
                         return Err("ECDH can only be used for encryption keys".into());
                     }
                 }
+                if let Some(can_authenticate) = self.can_authenticate {
+                    if can_authenticate {
+                        return Err(
+                            "ECDH can not be used for authentication, use EdDSA or ECDSA instead"
+                                .into(),
+                        );
+                    }
+                }
+            }
+            Some(KeyType::Ed25519) => {
+                if let Some(can_encrypt) = self.can_encrypt {
+                    if can_encrypt {
+                        return Err("Ed25519 can only be used for signing keys".into());
+                    }
+                }
+            }
+            Some(KeyType::X25519) => {
+                if let Some(can_sign) = self.can_sign {
+                    if can_sign {
+                        return Err("X25519 can only be used for encryption keys".into());
+                    }
+                }
+            }
+            Some(KeyType::X448) => {
+                if let Some(can_sign) = self.can_sign {
+                    if can_sign {
+                        return Err("X448 can only be used for encryption keys".into());
+                    }
+                }
+                if !cfg!(feature = "openssl") {
+                    return Err("X448 is only supported with the openssl feature".into());
+                }
+            }
+            Some(KeyType::Ed448) => {
+                if let Some(can_encrypt) = self.can_encrypt {
+                    if can_encrypt {
+                        return Err("Ed448 can only be used for signing keys".into());
+                    }
+                }
+                if !cfg!(feature = "openssl") {
+                    return Err("Ed448 is only supported with the openssl feature".into());
+                }
             }
             Some(KeyType::Dsa(_)) => {
                 if let Some(can_encrypt) = self.can_encrypt {
@@ -138,9 +254,237 @@ impl SecretKeyParamsBuilder {
             _ => {}
         }
 
+        if let Some(Some(expires_at)) = self.expires_at {
+            if matches!(self.expiration, Some(Some(_))) {
+                return Err("Only one of expiration and expires_at can be set".into());
+            }
+            let created_at = self
+                .created_at
+                .unwrap_or_else(|| chrono::Utc::now().trunc_subsecs(0));
+            if expires_at <= created_at {
+                return Err(format!(
+                    "Expiration date {expires_at} must be after the creation time {created_at}"
+                ));
+            }
+        }
+
+        if let Some(subkeys) = &self.subkeys {
+            for subkey in subkeys {
+                match &subkey.key_type {
+                    KeyType::Rsa(size) => self.validate_rsa_size(*size)?,
+                    KeyType::ECDHBrainpool(curve) => Self::validate_ecdh_brainpool_curve(curve)?,
+                    _ => {}
+                }
+                if subkey.key_type == KeyType::X25519 && subkey.can_sign {
+                    return Err("X25519 can only be used for encryption keys".into());
+                }
+                if subkey.key_type == KeyType::X448 {
+                    if subkey.can_sign {
+                        return Err("X448 can only be used for encryption keys".into());
+                    }
+                    if !cfg!(feature = "openssl") {
+                        return Err("X448 is only supported with the openssl feature".into());
+                    }
+                }
+                if subkey.key_type == KeyType::Ed25519 && subkey.can_encrypt {
+                    return Err("Ed25519 can only be used for signing keys".into());
+                }
+                if subkey.key_type == KeyType::Ed448 {
+                    if subkey.can_encrypt {
+                        return Err("Ed448 can only be used for signing keys".into());
+                    }
+                    if !cfg!(feature = "openssl") {
+                        return Err("Ed448 is only supported with the openssl feature".into());
+                    }
+                }
+            }
+        }
+
+        // Self-signatures are made with the signature version matching the key version.
+        let version = self.version.unwrap_or_default();
+        if !matches!(version, types::KeyVersion::V4 | types::KeyVersion::V6) {
+            return Err(format!("Generating {version:?} keys is not supported"));
+        }
+        if version == types::KeyVersion::V6 {
+            // Legacy EdDSA and Curve25519 ECDH must not be used with v6 keys (RFC 9580).
+            let key_types = self
+                .key_type
+                .iter()
+                .chain(self.subkeys.iter().flatten().map(|subkey| &subkey.key_type));
+            for key_type in key_types {
+                if matches!(key_type, KeyType::EdDSA | KeyType::ECDH) {
+                    return Err(format!(
+                        "{key_type:?} is a legacy algorithm that can not be used with v6 keys"
+                    ));
+                }
+            }
+        }
+        if let Some(subkeys) = &self.subkeys {
+            if let Some(subkey) = subkeys.iter().find(|subkey| subkey.version != version) {
+                return Err(format!(
+                    "Subkey version {:?} does not match the primary key version {:?}",
+                    subkey.version, version
+                ));
+            }
+        }
+
+        let has_primary_user_id = matches!(self.primary_user_id, Some(Some(_)));
+        let has_user_ids = matches!(&self.user_ids, Some(ids) if !ids.is_empty());
+        if has_user_ids && !has_primary_user_id {
+            return Err("Additional User IDs require a primary User ID".into());
+        }
+
+        Ok(())
+    }
+
+    fn validate_rsa_size(&self, size: u32) -> std::result::Result<(), String> {
+        let max = self.max_rsa_key_size.unwrap_or(DEFAULT_MAX_RSA_KEY_SIZE);
+        if size as usize > rsa::MAX_KEY_SIZE {
+            return Err(format!(
+                "RSA keys with more than {} bits are not supported",
+                rsa::MAX_KEY_SIZE
+            ));
+        }
+        if size > max {
+            return Err(format!(
+                "RSA keys with more than {max} bits are very slow to generate, raise max_rsa_key_size to allow {size} bits"
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn validate_ecdh_brainpool_curve(curve: &ECCCurve) -> std::result::Result<(), String> {
+        match curve {
+            ECCCurve::BrainpoolP256r1 | ECCCurve::BrainpoolP384r1 | ECCCurve::BrainpoolP512r1 => {
+                if !cfg!(feature = "openssl") {
+                    return Err(format!(
+                        "Curve {} is only supported for ECDH with the openssl feature",
+                        curve.name()
+                    ));
+                }
+            }
+            _ => return Err(format!("Curve {} is not a Brainpool curve", curve.name())),
+        }
+
         Ok(())
     }
 
+    /// Returns a builder preconfigured for a modern key: an Ed25519 primary key
+    /// for certification and signing, an X25519 encryption subkey, and preferences
+    /// for SHA-2, AES and AEAD (OCB, EAX).
+    ///
+    /// The keys use the RFC 9580 Ed25519 and X25519 algorithms, which older
+    /// implementations don't support, see [`gnupg_defaults`](Self::gnupg_defaults)
+    /// for the legacy ones. Only the primary User ID is left for the caller to set.
+    pub fn modern_defaults() -> Self {
+        let mut builder = Self::default();
+        builder
+            .key_type(KeyType::Ed25519)
+            .can_certify(true)
+            .can_sign(true)
+            .preferred_symmetric_algorithms(smallvec![
+                SymmetricKeyAlgorithm::AES256,
+                SymmetricKeyAlgorithm::AES192,
+                SymmetricKeyAlgorithm::AES128,
+            ])
+            .preferred_hash_algorithms(smallvec![
+                HashAlgorithm::SHA2_256,
+                HashAlgorithm::SHA2_384,
+                HashAlgorithm::SHA2_512,
+            ])
+            .preferred_compression_algorithms(smallvec![
+                CompressionAlgorithm::ZLIB,
+                CompressionAlgorithm::ZIP,
+            ])
+            .preferred_aead_algorithms(smallvec![
+                (SymmetricKeyAlgorithm::AES256, AeadAlgorithm::Ocb),
+                (SymmetricKeyAlgorithm::AES192, AeadAlgorithm::Ocb),
+                (SymmetricKeyAlgorithm::AES128, AeadAlgorithm::Ocb),
+                (SymmetricKeyAlgorithm::AES256, AeadAlgorithm::Eax),
+            ])
+            .subkey(SubkeyParams::x25519_encryption());
+        builder
+    }
+
+    /// Returns a builder preconfigured to match the default key created by
+    /// GnuPG 2.4 (`gpg --quick-gen-key` / `gpg --full-gen-key` with default
+    /// answers): an Ed25519 primary key for certification and signing, a
+    /// Curve25519 encryption subkey, GnuPG's default preference lists and the
+    /// "no-modify" key server preference.
+    ///
+    /// GnuPG additionally sets an expiration time, which is not configured here.
+    /// BZip2 is left out of the compression preferences, as rpgp can not
+    /// decompress it. GnuPG's OCB preference uses the LibrePGP encoding, which
+    /// rpgp does not write, so no AEAD preferences or SEIPD v2 feature are
+    /// advertised: GnuPG can not decrypt SEIPD v2 messages. Only the primary
+    /// User ID is left for the caller to set.
+    pub fn gnupg_defaults() -> Self {
+        let mut builder = Self::default();
+        builder
+            .key_type(KeyType::EdDSA)
+            .can_certify(true)
+            .can_sign(true)
+            .preferred_symmetric_algorithms(smallvec![
+                SymmetricKeyAlgorithm::AES256,
+                SymmetricKeyAlgorithm::AES192,
+                SymmetricKeyAlgorithm::AES128,
+                SymmetricKeyAlgorithm::TripleDES,
+            ])
+            .preferred_hash_algorithms(smallvec![
+                HashAlgorithm::SHA2_512,
+                HashAlgorithm::SHA2_384,
+                HashAlgorithm::SHA2_256,
+                HashAlgorithm::SHA2_224,
+                HashAlgorithm::SHA1,
+            ])
+            .preferred_compression_algorithms(smallvec![
+                CompressionAlgorithm::ZLIB,
+                CompressionAlgorithm::ZIP,
+                CompressionAlgorithm::Uncompressed,
+            ])
+            .keyserver_no_modify(true)
+            .subkey(SubkeyParams::legacy_cv25519_encryption());
+        builder
+    }
+
+    /// Returns a builder preconfigured with a certification-only Ed25519 primary
+    /// key and three separate subkeys: Ed25519 for signing, X25519 for encryption
+    /// and Ed25519 for authentication. Preferences are the same as in
+    /// [`modern_defaults`](Self::modern_defaults).
+    ///
+    /// The signing subkey is cross-certified with a back signature when the key
+    /// is signed. Only the primary User ID is left for the caller to set.
+    pub fn certify_only_with_subkeys() -> Self {
+        let mut builder = Self::modern_defaults();
+        builder.can_sign(false).subkeys(vec![
+            SubkeyParams::ed25519_signing(),
+            SubkeyParams::x25519_encryption(),
+            SubkeyParams::ed25519_authentication(),
+        ]);
+        builder
+    }
+
+    /// Returns a builder preconfigured for a minimal signing identity, in the spirit of
+    /// signify or minisign: a single Ed25519 primary key for certification and signing, with
+    /// no subkeys and only SHA-2 hash preferences.
+    ///
+    /// Setting a primary User ID is optional, without one the preferences are stored in a
+    /// direct key signature.
+    pub fn signing_only_ed25519() -> Self {
+        let mut builder = Self::default();
+        builder
+            .key_type(KeyType::Ed25519)
+            .can_certify(true)
+            .can_sign(true)
+            .preferred_hash_algorithms(smallvec![
+                HashAlgorithm::SHA2_256,
+                HashAlgorithm::SHA2_384,
+                HashAlgorithm::SHA2_512,
+            ]);
+        builder
+    }
+
     pub fn user_id<VALUE: Into<String>>(&mut self, value: VALUE) -> &mut Self {
         if let Some(ref mut user_ids) = self.user_ids {
             user_ids.push(value.into());
@@ -160,87 +504,556 @@ impl SecretKeyParamsBuilder {
     }
 }
 
+impl SubkeyParams {
+    /// X25519 subkey used for encryption only.
+    fn x25519_encryption() -> Self {
+        SubkeyParams {
+            can_encrypt: true,
+            ..Self::preset(KeyType::X25519)
+        }
+    }
+
+    /// Legacy ECDH Curve25519 subkey used for encryption only.
+    fn legacy_cv25519_encryption() -> Self {
+        SubkeyParams {
+            can_encrypt: true,
+            ..Self::preset(KeyType::ECDH)
+        }
+    }
+
+    /// Ed25519 subkey used for signing only.
+    fn ed25519_signing() -> Self {
+        SubkeyParams {
+            can_sign: true,
+            ..Self::preset(KeyType::Ed25519)
+        }
+    }
+
+    /// Ed25519 subkey used for authentication only.
+    fn ed25519_authentication() -> Self {
+        SubkeyParams {
+            can_authenticate: true,
+            ..Self::preset(KeyType::Ed25519)
+        }
+    }
+
+    /// The type of key that is generated for this subkey.
+    pub fn key_type(&self) -> &KeyType {
+        &self.key_type
+    }
+
+    /// The key flags the subkey is bound with.
+    pub fn keyflags(&self) -> KeyFlags {
+        let mut keyflags = KeyFlags::default();
+        keyflags.set_certify(self.can_certify);
+        keyflags.set_encrypt_comms(self.can_encrypt);
+        keyflags.set_encrypt_storage(self.can_encrypt);
+        keyflags.set_sign(self.can_sign);
+        keyflags.set_authentication(self.can_authenticate);
+        keyflags
+    }
+
+    /// Wraps the generated or imported key packet of this subkey, to be bound to the primary
+    /// key with the configured flags and signature creation times.
+    fn secret_subkey(&self, key: packet::SecretSubkey) -> SecretSubkey {
+        let created_at = *key.created_at();
+        SecretSubkey::new(key, self.keyflags()).with_binding_times(
+            self.binding_created_at.unwrap_or(created_at),
+            self.primary_key_binding_created_at.unwrap_or(created_at),
+        )
+    }
+
+    fn preset(key_type: KeyType) -> Self {
+        SubkeyParams {
+            key_type,
+            can_sign: false,
+            can_certify: false,
+            can_encrypt: false,
+            can_authenticate: false,
+            user_ids: Vec::new(),
+            user_attributes: Vec::new(),
+            passphrase: None,
+            s2k: None,
+            created_at: chrono::Utc::now().trunc_subsecs(0),
+            binding_created_at: None,
+            primary_key_binding_created_at: None,
+            packet_version: Default::default(),
+            version: Default::default(),
+            expiration: None,
+        }
+    }
+}
+
+/// The resolved parameters of a key, as it would be generated, see [`SecretKeyParams::plan`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyPlan {
+    pub key_type: KeyType,
+    pub algorithm: PublicKeyAlgorithm,
+    pub keyflags: KeyFlags,
+    pub preferred_symmetric_algorithms: SmallVec<[SymmetricKeyAlgorithm; 8]>,
+    pub preferred_hash_algorithms: SmallVec<[HashAlgorithm; 8]>,
+    pub preferred_compression_algorithms: SmallVec<[CompressionAlgorithm; 8]>,
+    pub preferred_aead_algorithms: SmallVec<[(SymmetricKeyAlgorithm, AeadAlgorithm); 4]>,
+    pub primary_user_id: Option<String>,
+    pub user_ids: Vec<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// The validity period, also when the key was configured with an absolute expiration date.
+    pub expiration: Option<Duration>,
+    /// How the secret key material is protected, `None` if it is not.
+    pub s2k: Option<S2kChoice>,
+    pub pregenerate_revocation: bool,
+    pub subkeys: Vec<SubkeyPlan>,
+}
+
+/// The resolved parameters of a subkey, see [`KeyPlan`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubkeyPlan {
+    pub key_type: KeyType,
+    pub algorithm: PublicKeyAlgorithm,
+    pub keyflags: KeyFlags,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub expiration: Option<Duration>,
+    /// How the secret key material is protected, `None` if it is not.
+    pub s2k: Option<S2kChoice>,
+}
+
 impl SecretKeyParams {
+    /// Resolves the parameters the key would be generated with, without generating any key
+    /// material, so that they can be checked before an expensive generation.
+    ///
+    /// The S2K parameters are shown without salt and IV, those are picked when the key is
+    /// generated.
+    pub fn plan(&self) -> Result<KeyPlan> {
+        Ok(KeyPlan {
+            key_type: self.key_type.clone(),
+            algorithm: self.key_type.to_alg(),
+            keyflags: self.keyflags(),
+            preferred_symmetric_algorithms: self.preferred_symmetric_algorithms.clone(),
+            preferred_hash_algorithms: self.preferred_hash_algorithms.clone(),
+            preferred_compression_algorithms: self.preferred_compression_algorithms.clone(),
+            preferred_aead_algorithms: self.preferred_aead_algorithms.clone(),
+            primary_user_id: self.primary_user_id.clone(),
+            user_ids: self.user_ids.clone(),
+            created_at: self.created_at,
+            expiration: self.expiration()?,
+            s2k: self.passphrase.as_ref().map(|_| match self.s2k {
+                Some(ref s2k) => s2k.into(),
+                None => default_s2k_choice(self.version),
+            }),
+            pregenerate_revocation: self.pregenerate_revocation,
+            subkeys: self
+                .subkeys
+                .iter()
+                .map(|subkey| SubkeyPlan {
+                    key_type: subkey.key_type.clone(),
+                    algorithm: subkey.key_type.to_alg(),
+                    keyflags: subkey.keyflags(),
+                    created_at: subkey.created_at,
+                    expiration: subkey.expiration,
+                    s2k: subkey.passphrase.as_ref().map(|_| match subkey.s2k {
+                        Some(ref s2k) => s2k.into(),
+                        None => default_s2k_choice(subkey.version),
+                    }),
+                })
+                .collect(),
+        })
+    }
+
+    fn keyflags(&self) -> KeyFlags {
+        let mut keyflags = KeyFlags::default();
+        keyflags.set_certify(self.can_certify);
+        keyflags.set_encrypt_comms(self.can_encrypt);
+        keyflags.set_encrypt_storage(self.can_encrypt);
+        keyflags.set_sign(self.can_sign);
+        keyflags.set_authentication(self.can_authenticate);
+        keyflags
+    }
+
+    /// The validity period, from either `expiration` or `expires_at`.
+    fn expiration(&self) -> Result<Option<Duration>> {
+        match self.expires_at {
+            Some(expires_at) => {
+                Ok(Some((expires_at - self.created_at).to_std().map_err(
+                    |_| format_err!("expiration date precedes the creation time"),
+                )?))
+            }
+            None => Ok(self.expiration),
+        }
+    }
+
     pub fn generate(self) -> Result<SecretKey> {
         let rng = thread_rng();
         self.generate_with_rng(rng)
     }
 
-    pub fn generate_with_rng<R: Rng + CryptoRng>(self, mut rng: R) -> Result<SecretKey> {
-        let passphrase = self.passphrase;
-        let s2k = self.s2k.unwrap_or_else(|| S2kParams::new_default(&mut rng));
-        let (public_params, secret_params) =
-            self.key_type.generate_with_rng(&mut rng, passphrase, s2k)?;
-        let primary_key = packet::SecretKey {
+    pub fn generate_with_rng<R: Rng + CryptoRng>(self, rng: R) -> Result<SecretKey> {
+        self.generate_with_rng_and_progress(rng, |_| {})
+    }
+
+    /// Generates the key, self-signs it, and returns both the armored secret key and the
+    /// armored public key.
+    ///
+    /// `key_pw` must return the passphrase set with [`SecretKeyParamsBuilder::passphrase`], if
+    /// any, it is called once for the secret and once for the public key.
+    pub fn generate_and_export<F>(self, key_pw: F) -> Result<(String, String)>
+    where
+        F: FnOnce() -> String + Clone,
+    {
+        self.generate_and_export_with_rng(thread_rng(), key_pw)
+    }
+
+    /// Like [`Self::generate_and_export`], using the given rng.
+    pub fn generate_and_export_with_rng<R, F>(self, rng: R, key_pw: F) -> Result<(String, String)>
+    where
+        R: Rng + CryptoRng,
+        F: FnOnce() -> String + Clone,
+    {
+        let secret_key = self.generate_with_rng(rng)?.sign(key_pw.clone())?;
+        let public_key = secret_key.public_key().sign(&secret_key, key_pw)?;
+
+        Ok((
+            secret_key.to_armored_string(None.into())?,
+            public_key.to_armored_string(None.into())?,
+        ))
+    }
+
+    /// Generates the key like [`Self::generate_with_rng`], calling `progress` during the
+    /// primality search of RSA and DSA keys.
+    ///
+    /// `progress` is called before every prime candidate that is tested, with the number of
+    /// candidates tested so far, for the primary key and all subkeys. Other key types don't
+    /// search for primes, and don't report any progress. It is called often, and should return
+    /// quickly, for example by advancing a spinner.
+    pub fn generate_with_rng_and_progress<R, F>(self, rng: R, progress: F) -> Result<SecretKey>
+    where
+        R: Rng + CryptoRng,
+        F: FnMut(u64),
+    {
+        self.generate_inner(rng, progress, KeyMaterial::Random(None), None)
+    }
+
+    /// Generates the key like [`Self::generate_with_rng`], asking `passphrases` for the
+    /// passphrase of each subkey right before it is protected.
+    ///
+    /// This replaces the passphrases set with [`SubkeyParamsBuilder::passphrase`], returning
+    /// `None` leaves the subkey unprotected. The returned passphrases are zeroized once the
+    /// subkey is encrypted. The primary key is still protected with the passphrase set with
+    /// [`SecretKeyParamsBuilder::passphrase`].
+    pub fn generate_with_rng_and_passphrases<R, P>(
+        self,
+        rng: R,
+        mut passphrases: P,
+    ) -> Result<SecretKey>
+    where
+        R: Rng + CryptoRng,
+        P: FnMut(&SubkeyParams) -> Option<String>,
+    {
+        self.generate_inner(
+            rng,
+            |_| {},
+            KeyMaterial::Random(None),
+            Some(&mut passphrases),
+        )
+    }
+
+    /// Generates the key like [`Self::generate_with_rng`], stopping with [`Error::Cancelled`]
+    /// once `is_cancelled` returns `true`.
+    ///
+    /// `is_cancelled` is checked before generating each key, and between the primality
+    /// attempts of RSA and DSA keys, see [`KeyType::generate_with_rng_cancellable`].
+    pub fn generate_with_rng_cancellable<R, C>(self, rng: R, is_cancelled: C) -> Result<SecretKey>
+    where
+        R: Rng + CryptoRng,
+        C: Fn() -> bool,
+    {
+        self.generate_inner(rng, |_| {}, KeyMaterial::Random(Some(&is_cancelled)), None)
+    }
+
+    /// Deterministically generates the key from `seed`, so that it can be recovered from the
+    /// seed alone, for example one derived from a BIP39 mnemonic.
+    ///
+    /// Only Ed25519 ([`KeyType::EdDSA`], [`KeyType::Ed25519`]) and X25519 ([`KeyType::ECDH`],
+    /// [`KeyType::X25519`]) keys are supported, as their key generation is a deterministic
+    /// function of a 32 byte seed. The primary key uses `seed` as its secret key, subkeys use
+    /// secrets derived from it with HKDF-SHA256.
+    ///
+    /// The fingerprints depend on the creation time, so `created_at` is used for the primary
+    /// key and all subkeys, instead of the configured creation times. Randomness is still used
+    /// for the salts when passphrase protecting the keys.
+    pub fn generate_from_seed(
+        mut self,
+        seed: [u8; 32],
+        created_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<SecretKey> {
+        self.created_at = created_at;
+        for subkey in &mut self.subkeys {
+            subkey.created_at = created_at;
+        }
+
+        let seed = Zeroizing::new(seed);
+        self.generate_inner(thread_rng(), |_| {}, KeyMaterial::Seed(&seed), None)
+    }
+
+    /// Generates the key reproducibly, for test vectors: the key material and S2K parameters
+    /// are drawn from an rng seeded with `seed`, and `created_at` is used as the creation time
+    /// of the primary key, all subkeys and the self-signatures.
+    ///
+    /// Unlike [`Self::generate_from_seed`] all key types are supported, but the output is only
+    /// stable as long as the rng of the `rand` crate and the key generation code don't change,
+    /// so this is not suitable for recovering keys. Signatures made with a random nonce or
+    /// salt, such as DSA and version 6 signatures, still differ.
+    ///
+    /// ```
+    /// # use pgp::composed::{KeyType, SecretKeyParamsBuilder};
+    /// # use pgp::types::KeyTrait;
+    /// # use chrono::TimeZone;
+    /// let params = || {
+    ///     SecretKeyParamsBuilder::default()
+    ///         .key_type(KeyType::EdDSA)
+    ///         .can_certify(true)
+    ///         .can_sign(true)
+    ///         .primary_user_id("Me <me@example.com>".into())
+    ///         .build()
+    ///         .unwrap()
+    /// };
+    /// let created_at = chrono::Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    ///
+    /// let key = params().generate_deterministic([7; 32], created_at).unwrap();
+    /// let again = params().generate_deterministic([7; 32], created_at).unwrap();
+    /// assert_eq!(key.fingerprint(), again.fingerprint());
+    /// ```
+    pub fn generate_deterministic(
+        mut self,
+        seed: [u8; 32],
+        created_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<SecretKey> {
+        self.created_at = created_at;
+        for subkey in &mut self.subkeys {
+            subkey.created_at = created_at;
+        }
+
+        let rng = StdRng::from_seed(seed);
+        Ok(self
+            .generate_inner(rng, |_| {}, KeyMaterial::Random(None), None)?
+            .with_signed_at(created_at))
+    }
+
+    /// Re-imports a key that was generated with these params and serialized before it was
+    /// signed, see the [`Serialize`](crate::ser::Serialize) implementation of [`SecretKey`].
+    ///
+    /// The key packets are taken from `bytes`, the User IDs, key flags and preferences from
+    /// these params, as they are not part of the serialized key. The key can then be signed
+    /// with [`SecretKey::sign`].
+    pub fn import_unsigned(mut self, bytes: impl io::Read) -> Result<SecretKey> {
+        let mut packets = PacketParser::new(bytes).filter(|packet| {
+            !matches!(packet, Ok(Packet::UserId(_)) | Ok(Packet::UserAttribute(_)))
+        });
+
+        let primary_key = match packets.next() {
+            Some(Ok(Packet::SecretKey(key))) => key,
+            Some(Ok(packet)) => bail!("expected a secret key packet, found {:?}", packet.tag()),
+            Some(Err(err)) => return Err(err),
+            None => bail!("missing secret key packet"),
+        };
+        ensure_eq!(
+            primary_key.algorithm(),
+            self.key_type.to_alg(),
+            "primary key algorithm"
+        );
+
+        let subkeys = std::mem::take(&mut self.subkeys)
+            .into_iter()
+            .map(|subkey| {
+                let key = match packets.next() {
+                    Some(Ok(Packet::SecretSubkey(key))) => key,
+                    Some(Ok(packet)) => {
+                        bail!("expected a secret subkey packet, found {:?}", packet.tag())
+                    }
+                    Some(Err(err)) => return Err(err),
+                    None => bail!("missing secret subkey packet"),
+                };
+                ensure_eq!(
+                    key.algorithm(),
+                    subkey.key_type.to_alg(),
+                    "subkey algorithm"
+                );
+
+                Ok(subkey.secret_subkey(key))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if let Some(packet) = packets.next() {
+            bail!("unexpected packet {:?}", packet?.tag());
+        }
+
+        Ok(
+            SecretKey::new(primary_key, self.key_details(), Default::default(), subkeys)
+                .with_pregenerated_revocation(self.pregenerate_revocation),
+        )
+    }
+
+    fn key_details(&self) -> KeyDetails {
+        KeyDetails::new(
+            self.primary_user_id
+                .as_deref()
+                .map(|id| UserId::from_str(Default::default(), id)),
+            self.user_ids
+                .iter()
+                .map(|m| UserId::from_str(Default::default(), m))
+                .collect(),
+            self.user_attributes.clone(),
+            self.keyflags(),
+            self.preferred_symmetric_algorithms.clone(),
+            self.preferred_hash_algorithms.clone(),
+            self.preferred_compression_algorithms.clone(),
+            self.preferred_aead_algorithms.clone(),
+            self.revocation_key.clone(),
+        )
+        .with_keyserver_no_modify(self.keyserver_no_modify)
+    }
+
+    fn generate_inner<R, F>(
+        mut self,
+        mut rng: R,
+        mut progress: F,
+        material: KeyMaterial<'_>,
+        mut passphrases: Option<SubkeyPassphrases<'_>>,
+    ) -> Result<SecretKey>
+    where
+        R: Rng + CryptoRng,
+        F: FnMut(u64),
+    {
+        let mut attempts = 0;
+        let mut attempt = || {
+            attempts += 1;
+            progress(attempts);
+            Ok(())
+        };
+        let mut generate_params = |key_type: &KeyType, index: u32, rng: &mut R| match material {
+            KeyMaterial::Random(Some(is_cancelled)) => {
+                key_type.generate_with_rng_cancellable(rng, is_cancelled)
+            }
+            KeyMaterial::Random(None) => key_type.generate_plain_with_attempts(rng, &mut attempt),
+            KeyMaterial::Seed(seed) if index == 0 => key_type.generate_from_seed(seed),
+            KeyMaterial::Seed(seed) => {
+                let mut subkey_seed = Zeroizing::new([0u8; 32]);
+                hkdf::Hkdf::<Sha256>::new(None, &seed[..])
+                    .expand(
+                        &[&b"subkey"[..], &index.to_be_bytes()].concat(),
+                        &mut *subkey_seed,
+                    )
+                    .map_err(|_| format_err!("hkdf expand"))?;
+                key_type.generate_from_seed(&subkey_seed)
+            }
+        };
+        let expiration = self.expiration()?;
+        let passphrase = self.passphrase.take();
+        let s2k = self
+            .s2k
+            .take()
+            .unwrap_or_else(|| default_s2k(self.version, &mut rng));
+        let (public_params, secret_params) = generate_params(&self.key_type, 0, &mut rng)?;
+        let mut primary_key = packet::SecretKey {
             details: packet::PublicKey {
                 packet_version: self.packet_version,
                 version: self.version,
                 algorithm: self.key_type.to_alg(),
                 created_at: self.created_at,
-                expiration: self.expiration.map(|v| v.as_secs() as u16),
+                expiration: expiration_secs(expiration)?,
                 public_params,
             },
-            secret_params,
+            secret_params: types::SecretParams::Plain(secret_params),
         };
-
-        let mut keyflags = KeyFlags::default();
-        keyflags.set_certify(self.can_certify);
-        keyflags.set_encrypt_comms(self.can_encrypt);
-        keyflags.set_encrypt_storage(self.can_encrypt);
-        keyflags.set_sign(self.can_sign);
+        if let Some(passphrase) = passphrase {
+            primary_key.set_password_with_s2k(&passphrase, s2k)?;
+        }
 
         Ok(SecretKey::new(
             primary_key,
-            KeyDetails::new(
-                UserId::from_str(Default::default(), &self.primary_user_id),
-                self.user_ids
-                    .iter()
-                    .map(|m| UserId::from_str(Default::default(), m))
-                    .collect(),
-                self.user_attributes,
-                keyflags,
-                self.preferred_symmetric_algorithms,
-                self.preferred_hash_algorithms,
-                self.preferred_compression_algorithms,
-                self.revocation_key,
-            ),
+            self.key_details(),
             Default::default(),
             self.subkeys
                 .into_iter()
-                .map(|subkey| {
-                    let passphrase = subkey.passphrase;
+                .zip(1..)
+                .map(|(mut subkey, index)| {
+                    let passphrase = match passphrases {
+                        Some(ref mut passphrases) => passphrases(&subkey),
+                        None => subkey.passphrase.take(),
+                    }
+                    .map(Zeroizing::new);
                     let s2k = subkey
                         .s2k
-                        .unwrap_or_else(|| S2kParams::new_default(&mut rng));
+                        .take()
+                        .unwrap_or_else(|| default_s2k(subkey.version, &mut rng));
                     let (public_params, secret_params) =
-                        subkey.key_type.generate(passphrase, s2k)?;
-                    let mut keyflags = KeyFlags::default();
-                    keyflags.set_certify(subkey.can_certify);
-                    keyflags.set_encrypt_comms(subkey.can_encrypt);
-                    keyflags.set_encrypt_storage(subkey.can_encrypt);
-                    keyflags.set_sign(subkey.can_sign);
-                    keyflags.set_authentication(subkey.can_authenticate);
-
-                    Ok(SecretSubkey::new(
-                        packet::SecretSubkey {
-                            details: packet::PublicSubkey {
-                                packet_version: subkey.packet_version,
-                                version: subkey.version,
-                                algorithm: subkey.key_type.to_alg(),
-                                created_at: subkey.created_at,
-                                expiration: subkey.expiration.map(|v| v.as_secs() as u16),
-                                public_params,
-                            },
-                            secret_params,
+                        generate_params(&subkey.key_type, index, &mut rng)?;
+
+                    let mut key = packet::SecretSubkey {
+                        details: packet::PublicSubkey {
+                            packet_version: subkey.packet_version,
+                            version: subkey.version,
+                            algorithm: subkey.key_type.to_alg(),
+                            created_at: subkey.created_at,
+                            expiration: expiration_secs(subkey.expiration)?,
+                            public_params,
                         },
-                        keyflags,
-                    ))
+                        secret_params: types::SecretParams::Plain(secret_params),
+                    };
+                    if let Some(passphrase) = passphrase {
+                        key.set_password_with_s2k(passphrase.as_str(), s2k)?;
+                    }
+
+                    Ok(subkey.secret_subkey(key))
                 })
                 .collect::<Result<Vec<_>>>()?,
-        ))
+        )
+        .with_pregenerated_revocation(self.pregenerate_revocation))
+    }
+}
+
+/// The S2K parameters keys are protected with, if none are configured: Argon2 for version 6
+/// keys, as recommended by RFC 9580, and iterated and salted S2K otherwise.
+fn default_s2k<R: Rng + CryptoRng>(version: types::KeyVersion, rng: R) -> S2kParams {
+    match version {
+        types::KeyVersion::V6 => S2kParams::new_default_argon2(rng),
+        _ => S2kParams::new_default(rng),
+    }
+}
+
+/// The choice of [`default_s2k`], as reported by [`SecretKeyParams::plan`].
+fn default_s2k_choice(version: types::KeyVersion) -> S2kChoice {
+    match version {
+        types::KeyVersion::V6 => S2kChoice::new_default_argon2(),
+        _ => S2kChoice::new_default(),
     }
 }
 
+/// The validity period in seconds, as stored in newly generated key packets.
+fn expiration_secs(expiration: Option<Duration>) -> Result<Option<u32>> {
+    expiration
+        .map(|expiration| {
+            u32::try_from(expiration.as_secs()).map_err(|_| {
+                format_err!(
+                    "expiration of {} seconds does not fit into 32 bits",
+                    expiration.as_secs()
+                )
+            })
+        })
+        .transpose()
+}
+
+/// Provides the passphrases of subkeys, see
+/// [`SecretKeyParams::generate_with_rng_and_passphrases`].
+type SubkeyPassphrases<'a> = &'a mut dyn FnMut(&SubkeyParams) -> Option<String>;
+
+/// Where [`SecretKeyParams::generate_inner`] takes the key material from.
+#[derive(Clone, Copy)]
+enum KeyMaterial<'a> {
+    /// Random key material, with an optional cancellation check.
+    Random(Option<&'a dyn Fn() -> bool>),
+    /// Key material derived from a seed.
+    Seed(&'a [u8; 32]),
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum KeyType {
     /// Encryption & Signing with RSA and the given bitsize.
@@ -251,8 +1064,18 @@ pub enum KeyType {
     EdDSA,
     /// Signing with ECDSA
     ECDSA(ECCCurve),
+    /// Encrypting with ECDH on a Brainpool curve, requires the `openssl` feature
+    ECDHBrainpool(ECCCurve),
     /// Signing with DSA for the given bitsize.
     Dsa(DsaKeySize),
+    /// Encrypting with X25519, as defined in RFC 9580
+    X25519,
+    /// Signing with Ed25519, as defined in RFC 9580
+    Ed25519,
+    /// Encrypting with X448, as defined in RFC 9580, requires the `openssl` feature
+    X448,
+    /// Signing with Ed448, as defined in RFC 9580, requires the `openssl` feature
+    Ed448,
 }
 
 #[derive(Clone, Debug, Copy, PartialEq, Eq)]
@@ -266,6 +1089,17 @@ pub enum DsaKeySize {
     B3072 = 3072,
 }
 
+impl DsaKeySize {
+    /// The sizes of the parameters `p` and `q`, in bits.
+    fn param_sizes(self) -> (usize, usize) {
+        match self {
+            DsaKeySize::B1024 => (1024, 160),
+            DsaKeySize::B2048 => (2048, 256),
+            DsaKeySize::B3072 => (3072, 256),
+        }
+    }
+}
+
 impl From<DsaKeySize> for dsa::KeySize {
     fn from(value: DsaKeySize) -> Self {
         match value {
@@ -281,10 +1115,14 @@ impl KeyType {
     pub fn to_alg(&self) -> PublicKeyAlgorithm {
         match self {
             KeyType::Rsa(_) => PublicKeyAlgorithm::RSA,
-            KeyType::ECDH => PublicKeyAlgorithm::ECDH,
+            KeyType::ECDH | KeyType::ECDHBrainpool(_) => PublicKeyAlgorithm::ECDH,
             KeyType::EdDSA => PublicKeyAlgorithm::EdDSA,
             KeyType::ECDSA(_) => PublicKeyAlgorithm::ECDSA,
             KeyType::Dsa(_) => PublicKeyAlgorithm::DSA,
+            KeyType::X25519 => PublicKeyAlgorithm::X25519,
+            KeyType::X448 => PublicKeyAlgorithm::X448,
+            KeyType::Ed25519 => PublicKeyAlgorithm::Ed25519,
+            KeyType::Ed448 => PublicKeyAlgorithm::Ed448,
         }
     }
 
@@ -303,13 +1141,7 @@ impl KeyType {
         passphrase: Option<String>,
         s2k: types::S2kParams,
     ) -> Result<(PublicParams, types::SecretParams)> {
-        let (pub_params, plain) = match self {
-            KeyType::Rsa(bit_size) => rsa::generate_key(rng, *bit_size as usize)?,
-            KeyType::ECDH => ecdh::generate_key(rng),
-            KeyType::EdDSA => eddsa::generate_key(rng),
-            KeyType::ECDSA(curve) => ecdsa::generate_key(rng, curve)?,
-            KeyType::Dsa(key_size) => dsa::generate_key(rng, (*key_size).into())?,
-        };
+        let (pub_params, plain) = self.generate_plain_with_rng(rng)?;
 
         let secret = match passphrase {
             Some(passphrase) => {
@@ -323,19 +1155,106 @@ impl KeyType {
 
         Ok((pub_params, secret))
     }
-}
-
-#[cfg(test)]
-mod tests {
-    #![allow(clippy::unwrap_used)]
 
-    use super::*;
+    /// Generates the key material, the secret parameters are returned unencrypted.
+    ///
+    /// Unlike [`Self::generate_with_rng`], this supports AEAD protection, which authenticates
+    /// the public key packet: protect the key packet once it is assembled, using
+    /// `set_password_with_s2k` on [`packet::SecretKey`] or [`packet::SecretSubkey`].
+    pub fn generate_plain_with_rng<R: Rng + CryptoRng>(
+        &self,
+        rng: R,
+    ) -> Result<(PublicParams, PlainSecretParams)> {
+        self.generate_plain_with_attempts(rng, &mut || Ok(()))
+    }
 
-    use crate::composed::{Deserializable, SignedPublicKey, SignedSecretKey};
-    use crate::types::SecretKeyTrait;
+    /// Generates the key material like [`Self::generate_plain_with_rng`], calling `attempt`
+    /// before every prime candidate that RSA and DSA generation tests.
+    fn generate_plain_with_attempts<R: Rng + CryptoRng>(
+        &self,
+        rng: R,
+        attempt: Attempt<'_>,
+    ) -> Result<(PublicParams, PlainSecretParams)> {
+        let (pub_params, plain) = match self {
+            KeyType::Rsa(bit_size) => {
+                rsa::generate_key_with_attempts(rng, *bit_size as usize, attempt)?
+            }
+            KeyType::ECDH => ecdh::generate_key(rng),
+            KeyType::ECDHBrainpool(curve) => ecdh::generate_brainpool_key(rng, curve)?,
+            KeyType::EdDSA => eddsa::generate_key(rng),
+            KeyType::ECDSA(curve) => ecdsa::generate_key(rng, curve)?,
+            KeyType::Dsa(key_size) => {
+                let (l, n) = key_size.param_sizes();
+                dsa::generate_key_with_attempts(rng, l, n, attempt)?
+            }
+            KeyType::X25519 => x25519::generate_key(rng),
+            KeyType::X448 => x448::generate_key(rng)?,
+            KeyType::Ed25519 => ed25519::generate_key(rng),
+            KeyType::Ed448 => ed448::generate_key(rng)?,
+        };
 
-    use rand::SeedableRng;
-    use rand_chacha::ChaCha8Rng;
+        Ok((pub_params, plain))
+    }
+
+    /// Derives the key material from a 32 byte seed.
+    ///
+    /// Only supported for [`KeyType::EdDSA`], [`KeyType::ECDH`], [`KeyType::Ed25519`] and
+    /// [`KeyType::X25519`], other algorithms don't generate keys from a fixed size seed.
+    pub fn generate_from_seed(&self, seed: &[u8; 32]) -> Result<(PublicParams, PlainSecretParams)> {
+        match self {
+            KeyType::ECDH => Ok(ecdh::key_from_seed(seed)),
+            KeyType::EdDSA => Ok(eddsa::key_from_seed(seed)),
+            KeyType::X25519 => Ok(x25519::key_from_seed(seed)),
+            KeyType::Ed25519 => Ok(ed25519::key_from_seed(seed)),
+            KeyType::Rsa(_)
+            | KeyType::ECDHBrainpool(_)
+            | KeyType::ECDSA(_)
+            | KeyType::Dsa(_)
+            | KeyType::X448
+            | KeyType::Ed448 => {
+                unsupported_err!("generating {:?} keys from a seed", self)
+            }
+        }
+    }
+
+    /// Generates the key material like [`Self::generate_plain_with_rng`], returning
+    /// [`Error::Cancelled`] once `is_cancelled` returns `true`.
+    ///
+    /// `is_cancelled` is checked before the key is generated, and before every prime
+    /// candidate that RSA and DSA generation tests, so slow generation stops promptly.
+    pub fn generate_with_rng_cancellable<R: Rng + CryptoRng>(
+        &self,
+        rng: R,
+        is_cancelled: &dyn Fn() -> bool,
+    ) -> Result<(PublicParams, PlainSecretParams)> {
+        let mut attempt = || {
+            if is_cancelled() {
+                return Err(Error::Cancelled);
+            }
+            Ok(())
+        };
+        attempt()?;
+
+        self.generate_plain_with_attempts(rng, &mut attempt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    use crate::composed::{
+        Deserializable, Esk, Message, SignedPublicKey, SignedSecretKey, StandaloneSignature,
+    };
+    use crate::crypto::aead::AeadAlgorithm;
+    use crate::packet::{RevocationCode, SignatureType, SubpacketType};
+    use crate::ser::Serialize;
+    use crate::types::{KeyTrait, SecretKeyTrait, StringToKey, StringToKeyChoice, Tag};
+
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
 
     #[test]
     #[ignore] // slow in debug mode
@@ -651,6 +1570,118 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "openssl")]
+    fn gen_brainpool<R: Rng + CryptoRng>(rng: &mut R, curve: ECCCurve) {
+        let key_params = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::ECDSA(curve.clone()))
+            .can_certify(true)
+            .can_sign(true)
+            .primary_user_id("Me-X <me-brainpool@mail.com>".into())
+            .passphrase(None)
+            .subkey(
+                SubkeyParamsBuilder::default()
+                    .key_type(KeyType::ECDHBrainpool(curve.clone()))
+                    .can_encrypt(true)
+                    .passphrase(None)
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let key = key_params
+            .generate_with_rng(rng)
+            .expect("failed to generate secret key");
+        let signed_key = key.sign(|| "".into()).expect("failed to sign key");
+        signed_key.verify().expect("invalid key");
+
+        assert_eq!(
+            signed_key.primary_key.public_params().curve(),
+            Some(curve.clone())
+        );
+        assert_eq!(
+            signed_key.secret_subkeys[0].key.public_params().curve(),
+            Some(curve.clone())
+        );
+
+        let armor = signed_key
+            .to_armored_string(None.into())
+            .expect("failed to serialize key");
+        let (signed_key2, _headers) =
+            SignedSecretKey::from_string(&armor).expect("failed to parse key");
+        signed_key2.verify().expect("invalid key");
+        assert_eq!(signed_key, signed_key2);
+
+        let public_signed_key = signed_key
+            .public_key()
+            .sign(&signed_key, || "".into())
+            .expect("failed to sign public key");
+        public_signed_key.verify().expect("invalid public key");
+    }
+
+    #[test]
+    #[cfg(feature = "openssl")]
+    fn key_gen_brainpool() {
+        let rng = &mut ChaCha8Rng::seed_from_u64(0);
+        for curve in [
+            ECCCurve::BrainpoolP256r1,
+            ECCCurve::BrainpoolP384r1,
+            ECCCurve::BrainpoolP512r1,
+        ] {
+            gen_brainpool(rng, curve);
+        }
+    }
+
+    #[test]
+    #[cfg(not(feature = "openssl"))]
+    fn key_gen_brainpool_requires_openssl() {
+        let err = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::ECDSA(ECCCurve::BrainpoolP256r1))
+            .can_certify(true)
+            .can_sign(true)
+            .primary_user_id("Me <me@mail.com>".into())
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("openssl feature"), "{err}");
+
+        let err = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::ECDHBrainpool(ECCCurve::BrainpoolP256r1))
+            .can_encrypt(true)
+            .primary_user_id("Me <me@mail.com>".into())
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("openssl feature"), "{err}");
+    }
+
+    #[test]
+    fn key_gen_ecdh_brainpool_rejects_other_curves() {
+        for curve in [ECCCurve::Curve25519, ECCCurve::P256, ECCCurve::Secp256k1] {
+            let err = SecretKeyParamsBuilder::default()
+                .key_type(KeyType::ECDHBrainpool(curve.clone()))
+                .can_encrypt(true)
+                .primary_user_id("Me <me@mail.com>".into())
+                .build()
+                .unwrap_err();
+            assert!(err.to_string().contains("not a Brainpool curve"), "{err}");
+
+            let err = SecretKeyParamsBuilder::default()
+                .key_type(KeyType::Ed25519)
+                .can_certify(true)
+                .can_sign(true)
+                .primary_user_id("Me <me@mail.com>".into())
+                .subkey(
+                    SubkeyParamsBuilder::default()
+                        .key_type(KeyType::ECDHBrainpool(curve))
+                        .can_encrypt(true)
+                        .build()
+                        .unwrap(),
+                )
+                .build()
+                .unwrap_err();
+            assert!(err.to_string().contains("not a Brainpool curve"), "{err}");
+        }
+    }
+
     fn gen_dsa<R: Rng + CryptoRng>(rng: &mut R, key_size: DsaKeySize) {
         let _ = pretty_env_logger::try_init();
 
@@ -735,4 +1766,1739 @@ mod tests {
             gen_dsa(rng, DsaKeySize::B3072);
         }
     }
+
+    fn gen_aead_protected<R: Rng + CryptoRng>(rng: &mut R, aead_mode: AeadAlgorithm) {
+        let _ = pretty_env_logger::try_init();
+
+        let mut nonce = vec![0u8; aead_mode.nonce_size()];
+        rng.fill(&mut nonce[..]);
+        let s2k = S2kParams::Aead {
+            sym_alg: SymmetricKeyAlgorithm::AES256,
+            aead_mode,
+            s2k: StringToKey::new_default(&mut *rng),
+            nonce,
+        };
+
+        let key_params = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::EdDSA)
+            .can_certify(true)
+            .can_sign(true)
+            .primary_user_id("Me-X <me-aead@mail.com>".into())
+            .passphrase(Some("hello".into()))
+            .s2k(Some(s2k.clone()))
+            .subkey(
+                SubkeyParamsBuilder::default()
+                    .key_type(KeyType::ECDH)
+                    .can_encrypt(true)
+                    .passphrase(Some("hello".into()))
+                    .s2k(Some(s2k))
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let key = key_params
+            .generate_with_rng(&mut *rng)
+            .expect("failed to generate secret key");
+        let signed_key = key.sign(|| "hello".into()).expect("failed to sign key");
+
+        let armor = signed_key
+            .to_armored_string(None.into())
+            .expect("failed to serialize key");
+
+        let (signed_key2, _headers) =
+            SignedSecretKey::from_string(&armor).expect("failed to parse key");
+        signed_key2.verify().expect("invalid key");
+        assert_eq!(signed_key, signed_key2);
+
+        assert!(signed_key2.primary_key.secret_params().is_encrypted());
+        signed_key2
+            .unlock(|| "hello".into(), |_| Ok(()))
+            .expect("failed to unlock primary key");
+        signed_key2.secret_subkeys[0]
+            .unlock(|| "hello".into(), |_| Ok(()))
+            .expect("failed to unlock subkey");
+
+        assert!(signed_key2.unlock(|| "wrong".into(), |_| Ok(())).is_err());
+    }
+
+    #[test]
+    fn key_type_generate_protected() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let s2k = S2kParams::new_default(&mut rng);
+        let (public_params, secret_params) = KeyType::Ed25519
+            .generate_with_rng(&mut rng, Some("hello".into()), s2k)
+            .unwrap();
+        let types::SecretParams::Encrypted(encrypted) = secret_params else {
+            panic!("not encrypted");
+        };
+        encrypted
+            .unlock(
+                || "hello".into(),
+                PublicKeyAlgorithm::Ed25519,
+                &public_params,
+            )
+            .unwrap();
+        assert!(encrypted
+            .unlock(
+                || "wrong".into(),
+                PublicKeyAlgorithm::Ed25519,
+                &public_params
+            )
+            .is_err());
+
+        // AEAD protection needs the public key packet, which doesn't exist yet
+        let s2k = S2kParams::new_default_argon2(&mut rng);
+        assert!(KeyType::Ed25519
+            .generate_with_rng(&mut rng, Some("hello".into()), s2k)
+            .is_err());
+    }
+
+    #[test]
+    fn key_gen_aead_ocb() {
+        let rng = &mut ChaCha8Rng::seed_from_u64(0);
+        gen_aead_protected(rng, AeadAlgorithm::Ocb);
+    }
+
+    #[test]
+    fn key_gen_aead_eax() {
+        let rng = &mut ChaCha8Rng::seed_from_u64(0);
+        gen_aead_protected(rng, AeadAlgorithm::Eax);
+    }
+
+    #[test]
+    fn key_gen_modern_defaults() {
+        let rng = &mut ChaCha8Rng::seed_from_u64(0);
+
+        let key = SecretKeyParamsBuilder::modern_defaults()
+            .primary_user_id("Me <me@mail.com>".into())
+            .build()
+            .unwrap()
+            .generate_with_rng(rng)
+            .expect("failed to generate secret key");
+        let signed_key = key.sign(String::new).expect("failed to sign key");
+
+        let armor = signed_key
+            .to_armored_string(None.into())
+            .expect("failed to serialize key");
+        let (signed_key2, _headers) =
+            SignedSecretKey::from_string(&armor).expect("failed to parse key");
+        signed_key2.verify().expect("invalid key");
+        assert_eq!(signed_key, signed_key2);
+
+        assert_eq!(
+            signed_key2.primary_key.algorithm(),
+            PublicKeyAlgorithm::Ed25519
+        );
+        assert_eq!(signed_key2.secret_subkeys.len(), 1);
+        assert_eq!(
+            signed_key2.secret_subkeys[0].key.algorithm(),
+            PublicKeyAlgorithm::X25519
+        );
+
+        let sig = &signed_key2.details.users[0].signatures[0];
+        assert!(sig.key_flags().certify());
+        assert!(sig.key_flags().sign());
+        assert_eq!(
+            sig.preferred_hash_algs(),
+            &[
+                HashAlgorithm::SHA2_256,
+                HashAlgorithm::SHA2_384,
+                HashAlgorithm::SHA2_512
+            ][..]
+        );
+        assert_eq!(
+            sig.preferred_symmetric_algs(),
+            &[
+                SymmetricKeyAlgorithm::AES256,
+                SymmetricKeyAlgorithm::AES192,
+                SymmetricKeyAlgorithm::AES128
+            ][..]
+        );
+        assert_eq!(
+            sig.preferred_aead_ciphersuites()[0],
+            (SymmetricKeyAlgorithm::AES256, AeadAlgorithm::Ocb)
+        );
+        assert_eq!(sig.features(), &[0x09][..]);
+        assert!(signed_key2.secret_subkeys[0].signatures[0]
+            .key_flags()
+            .encrypt_comms());
+    }
+
+    #[test]
+    fn key_gen_gnupg_defaults() {
+        let rng = &mut ChaCha8Rng::seed_from_u64(0);
+
+        let key = SecretKeyParamsBuilder::gnupg_defaults()
+            .primary_user_id("Me <me@mail.com>".into())
+            .build()
+            .unwrap()
+            .generate_with_rng(rng)
+            .expect("failed to generate secret key");
+        let signed_key = key.sign(String::new).expect("failed to sign key");
+        signed_key.verify().expect("invalid key");
+
+        assert_eq!(
+            signed_key.primary_key.algorithm(),
+            PublicKeyAlgorithm::EdDSA
+        );
+        assert_eq!(
+            signed_key.secret_subkeys[0].key.algorithm(),
+            PublicKeyAlgorithm::ECDH
+        );
+
+        // the preference subpackets of a key generated by GnuPG 2.4, without BZip2
+        // and without the LibrePGP AEAD preferences and features
+        let sig = &signed_key.details.users[0].signatures[0];
+        let mut preferences = Vec::new();
+        for subpacket in sig.config.hashed_subpackets() {
+            if matches!(
+                subpacket.typ(),
+                SubpacketType::PreferredSymmetricAlgorithms
+                    | SubpacketType::PreferredHashAlgorithms
+                    | SubpacketType::PreferredCompressionAlgorithms
+                    | SubpacketType::PreferredAeadCiphersuites
+                    | SubpacketType::Features
+                    | SubpacketType::KeyServerPreferences
+            ) {
+                subpacket.to_writer(&mut preferences).unwrap();
+            }
+        }
+        assert_eq!(
+            hex::encode(preferences),
+            concat!(
+                "050b09080702",   // AES256, AES192, AES128, TripleDES
+                "06150a09080b02", // SHA2-512, SHA2-384, SHA2-256, SHA2-224, SHA1
+                "0416020100",     // ZLIB, ZIP, Uncompressed
+                "021780",         // no-modify
+            )
+        );
+
+        // GnuPG can not decrypt SEIPD v2, so the key must not ask for it
+        let public_key = SignedPublicKey::from(signed_key);
+        assert!(!public_key.supports_seipd_v2(&chrono::Utc::now()));
+    }
+
+    #[test]
+    fn key_gen_without_user_id() {
+        let rng = &mut ChaCha8Rng::seed_from_u64(0);
+
+        let key = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::EdDSA)
+            .can_certify(true)
+            .can_sign(true)
+            .preferred_hash_algorithms(smallvec![HashAlgorithm::SHA2_256])
+            .build()
+            .unwrap()
+            .generate_with_rng(rng)
+            .expect("failed to generate secret key");
+        let signed_key = key.sign(String::new).expect("failed to sign key");
+
+        assert!(signed_key.details.users.is_empty());
+        assert_eq!(signed_key.details.direct_signatures.len(), 1);
+
+        let sig = &signed_key.details.direct_signatures[0];
+        assert_eq!(sig.typ(), packet::SignatureType::Key);
+        assert!(sig.key_flags().certify());
+        assert_eq!(sig.preferred_hash_algs(), &[HashAlgorithm::SHA2_256][..]);
+
+        let armor = signed_key
+            .to_armored_string(None.into())
+            .expect("failed to serialize key");
+        let (signed_key2, _headers) =
+            SignedSecretKey::from_string(&armor).expect("failed to parse key");
+        signed_key2.verify().expect("invalid key");
+        assert_eq!(signed_key, signed_key2);
+
+        let public_key = signed_key2.public_key();
+        let signed_public_key = public_key
+            .sign(&signed_key2, String::new)
+            .expect("failed to sign public key");
+        signed_public_key.verify().expect("invalid public key");
+        assert!(signed_public_key.details.users.is_empty());
+    }
+
+    #[test]
+    fn key_gen_user_ids_without_primary() {
+        let res = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::EdDSA)
+            .can_certify(true)
+            .user_id("Me <me@mail.com>")
+            .build();
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn key_gen_ecdsa_rejects_non_weierstrass_curves() {
+        for curve in [ECCCurve::Curve25519, ECCCurve::Ed25519] {
+            let err = SecretKeyParamsBuilder::default()
+                .key_type(KeyType::ECDSA(curve.clone()))
+                .can_certify(true)
+                .can_sign(true)
+                .primary_user_id("Me <me@mail.com>".into())
+                .build()
+                .unwrap_err();
+            assert!(
+                err.to_string().contains("not a Weierstrass curve"),
+                "{curve:?}: {err}"
+            );
+        }
+    }
+
+    #[test]
+    fn key_gen_from_seed() {
+        use chrono::TimeZone;
+
+        let created_at = chrono::Utc.with_ymd_and_hms(2024, 5, 6, 7, 8, 9).unwrap();
+        let generate = |seed: [u8; 32]| {
+            SecretKeyParamsBuilder::certify_only_with_subkeys()
+                .primary_user_id("Me <me@mail.com>".into())
+                .build()
+                .unwrap()
+                .generate_from_seed(seed, created_at)
+                .and_then(|key| key.sign(String::new))
+        };
+        let fingerprints = |key: &SignedSecretKey| {
+            std::iter::once(key.fingerprint())
+                .chain(key.secret_subkeys.iter().map(|k| k.key.fingerprint()))
+                .collect::<Vec<_>>()
+        };
+
+        let key = generate([1; 32]).unwrap();
+        key.verify().unwrap();
+        assert_eq!(key.primary_key.created_at(), &created_at);
+        assert_eq!(
+            fingerprints(&key),
+            fingerprints(&generate([1; 32]).unwrap())
+        );
+        let other = fingerprints(&generate([2; 32]).unwrap());
+        assert!(fingerprints(&key).iter().all(|fp| !other.contains(fp)));
+
+        // the seed is the primary Ed25519 secret key, subkeys are derived from it
+        let types::SecretParams::Plain(PlainSecretParams::Ed25519(ref secret)) =
+            key.primary_key.secret_params()
+        else {
+            panic!("unexpected secret params");
+        };
+        assert_eq!(secret, &[1; 32]);
+        let mut unique = fingerprints(&key);
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), 4);
+
+        let rsa = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::Rsa(2048))
+            .can_certify(true)
+            .primary_user_id("Me <me@mail.com>".into())
+            .build()
+            .unwrap()
+            .generate_from_seed([1; 32], created_at);
+        assert!(rsa.is_err());
+    }
+
+    #[test]
+    fn key_gen_and_export() {
+        let rng = ChaCha8Rng::seed_from_u64(0);
+        let (secret, public) = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::EdDSA)
+            .can_certify(true)
+            .can_sign(true)
+            .primary_user_id("Me <me@mail.com>".into())
+            .passphrase(Some("hello".into()))
+            .subkey(
+                SubkeyParamsBuilder::default()
+                    .key_type(KeyType::ECDH)
+                    .can_encrypt(true)
+                    .passphrase(Some("hello".into()))
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap()
+            .generate_and_export_with_rng(rng, || "hello".into())
+            .expect("failed to generate key");
+
+        let (secret, _) = SignedSecretKey::from_string(&secret).unwrap();
+        secret.verify().unwrap();
+        secret.unlock(|| "hello".into(), |_| Ok(())).unwrap();
+        let (public, _) = SignedPublicKey::from_string(&public).unwrap();
+        public.verify().unwrap();
+        assert_eq!(public.fingerprint(), secret.fingerprint());
+        assert_eq!(public.public_subkeys.len(), 1);
+    }
+
+    #[test]
+    fn key_gen_progress() {
+        let rng = &mut ChaCha8Rng::seed_from_u64(0);
+        let mut reports = Vec::new();
+        let key = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::Dsa(DsaKeySize::B2048))
+            .can_certify(true)
+            .can_sign(true)
+            .primary_user_id("Me <me@mail.com>".into())
+            .build()
+            .unwrap()
+            .generate_with_rng_and_progress(&mut *rng, |attempts| reports.push(attempts))
+            .expect("failed to generate secret key")
+            .sign(String::new)
+            .expect("failed to sign key");
+        key.verify().unwrap();
+
+        // one report per prime candidate, and finding the DSA primes takes many
+        assert!(reports.len() > 10);
+        assert!(reports.iter().copied().eq(1..=reports.len() as u64));
+
+        // no prime search for Ed25519 keys
+        let mut reports = Vec::new();
+        SecretKeyParamsBuilder::default()
+            .key_type(KeyType::EdDSA)
+            .can_certify(true)
+            .primary_user_id("Me <me@mail.com>".into())
+            .build()
+            .unwrap()
+            .generate_with_rng_and_progress(rng, |attempts| reports.push(attempts))
+            .expect("failed to generate secret key");
+        assert!(reports.is_empty());
+    }
+
+    #[test]
+    fn key_gen_cancellable() {
+        let params = || {
+            SecretKeyParamsBuilder::default()
+                .key_type(KeyType::Rsa(2048))
+                .can_certify(true)
+                .can_sign(true)
+                .primary_user_id("Me <me@mail.com>".into())
+                .subkey(
+                    SubkeyParamsBuilder::default()
+                        .key_type(KeyType::ECDH)
+                        .can_encrypt(true)
+                        .build()
+                        .unwrap(),
+                )
+                .build()
+                .unwrap()
+        };
+
+        // cancelled while searching for the RSA primes
+        let checks = std::cell::Cell::new(0);
+        let res = params().generate_with_rng_cancellable(ChaCha8Rng::seed_from_u64(0), || {
+            checks.set(checks.get() + 1);
+            checks.get() > 5
+        });
+        assert!(matches!(res, Err(Error::Cancelled)));
+        assert_eq!(checks.get(), 6);
+
+        // cancelled while generating the DSA parameters
+        let checks = std::cell::Cell::new(0);
+        let res = KeyType::Dsa(DsaKeySize::B2048).generate_with_rng_cancellable(
+            ChaCha8Rng::seed_from_u64(0),
+            &|| {
+                checks.set(checks.get() + 1);
+                checks.get() > 5
+            },
+        );
+        assert!(matches!(res, Err(Error::Cancelled)));
+        assert_eq!(checks.get(), 6);
+
+        let key = params()
+            .generate_with_rng_cancellable(ChaCha8Rng::seed_from_u64(0), || false)
+            .expect("failed to generate secret key")
+            .sign(String::new)
+            .expect("failed to sign key");
+        key.verify().unwrap();
+        let PublicParams::RSA { ref n, .. } = key.primary_key.public_params() else {
+            panic!("invalid key generated")
+        };
+        assert_eq!(n.as_bytes().len(), 256);
+    }
+
+    #[test]
+    fn key_gen_rsa_max_size() {
+        let params = |size: u32| {
+            let mut builder = SecretKeyParamsBuilder::default();
+            builder
+                .key_type(KeyType::Rsa(size))
+                .can_certify(true)
+                .primary_user_id("Me <me@mail.com>".into());
+            builder
+        };
+
+        // only validated, nothing is generated
+        assert!(params(4096).build().is_ok());
+        assert!(params(DEFAULT_MAX_RSA_KEY_SIZE).build().is_ok());
+        assert!(params(40960).build().is_err());
+        assert!(params(12288).build().is_err());
+        assert!(params(12288).max_rsa_key_size(16384).build().is_ok());
+        assert!(params(32768).max_rsa_key_size(32768).build().is_err());
+
+        let err = params(2048)
+            .subkey(
+                SubkeyParamsBuilder::default()
+                    .key_type(KeyType::Rsa(10240))
+                    .can_encrypt(true)
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("max_rsa_key_size"));
+    }
+
+    #[test]
+    fn key_gen_subkey_versions() {
+        let subkey = |version: types::KeyVersion| {
+            SubkeyParamsBuilder::default()
+                .key_type(KeyType::EdDSA)
+                .can_sign(true)
+                .version(version)
+                .build()
+                .unwrap()
+        };
+        let params = |version: types::KeyVersion, subkey_version: types::KeyVersion| {
+            SecretKeyParamsBuilder::default()
+                .key_type(KeyType::EdDSA)
+                .can_certify(true)
+                .primary_user_id("Me <me@mail.com>".into())
+                .version(version)
+                .subkey(subkey(subkey_version))
+                .subkey(
+                    SubkeyParamsBuilder::default()
+                        .key_type(KeyType::ECDH)
+                        .can_encrypt(true)
+                        .build()
+                        .unwrap(),
+                )
+                .build()
+        };
+
+        let key = params(types::KeyVersion::V4, types::KeyVersion::V4)
+            .unwrap()
+            .generate()
+            .unwrap()
+            .sign(String::new)
+            .unwrap();
+        key.verify().unwrap();
+        for subkey in &key.secret_subkeys {
+            assert_eq!(subkey.key.version(), types::KeyVersion::V4);
+            assert_eq!(
+                subkey.signatures[0].config.version,
+                packet::SignatureVersion::V4
+            );
+        }
+
+        assert!(params(types::KeyVersion::V4, types::KeyVersion::V5).is_err());
+        assert!(params(types::KeyVersion::V5, types::KeyVersion::V5).is_err());
+        assert!(params(types::KeyVersion::V3, types::KeyVersion::V4).is_err());
+    }
+
+    #[test]
+    fn key_gen_x25519_rfc9580() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let params = |can_sign: bool| {
+            SecretKeyParamsBuilder::default()
+                .key_type(KeyType::EdDSA)
+                .can_certify(true)
+                .can_sign(true)
+                .primary_user_id("Me <me@mail.com>".into())
+                .subkey(
+                    SubkeyParamsBuilder::default()
+                        .key_type(KeyType::X25519)
+                        .can_encrypt(true)
+                        .can_sign(can_sign)
+                        .build()
+                        .unwrap(),
+                )
+                .build()
+        };
+
+        let signed_key = params(false)
+            .unwrap()
+            .generate_with_rng(&mut rng)
+            .unwrap()
+            .sign(String::new)
+            .unwrap();
+        signed_key.verify().unwrap();
+
+        let subkey = &signed_key.secret_subkeys[0].key;
+        assert_eq!(subkey.algorithm(), PublicKeyAlgorithm::X25519);
+        assert!(matches!(
+            subkey.public_params(),
+            PublicParams::X25519 { .. }
+        ));
+
+        let armor = signed_key.to_armored_string(None.into()).unwrap();
+        let (parsed, _headers) = SignedSecretKey::from_string(&armor).unwrap();
+        parsed.verify().unwrap();
+        assert_eq!(parsed, signed_key);
+
+        let public_key = signed_key
+            .public_key()
+            .sign(&signed_key, String::new)
+            .unwrap();
+        let armor = public_key.to_armored_string(None.into()).unwrap();
+        let (parsed, _headers) = SignedPublicKey::from_string(&armor).unwrap();
+        parsed.verify().unwrap();
+        assert_eq!(parsed, public_key);
+
+        // encrypt to the subkey, with version 3 and version 6 PKESK packets
+        let msg = Message::new_literal("hello.txt", "hello world");
+        let public_subkey = &public_key.public_subkeys[0].key;
+        let encrypted = [
+            msg.encrypt_to_keys(&mut rng, SymmetricKeyAlgorithm::AES128, &[public_subkey])
+                .unwrap(),
+            msg.encrypt_to_keys_seipdv2(
+                &mut rng,
+                SymmetricKeyAlgorithm::AES256,
+                AeadAlgorithm::Ocb,
+                0,
+                &[public_subkey],
+            )
+            .unwrap(),
+        ];
+        for (encrypted, pkesk_version) in encrypted.into_iter().zip([3, 6]) {
+            let armor = encrypted.to_armored_string(None.into()).unwrap();
+            let (parsed, _headers) = Message::from_string(&armor).unwrap();
+            assert_eq!(parsed, encrypted);
+
+            let Message::Encrypted { ref esk, .. } = parsed else {
+                panic!("not encrypted");
+            };
+            let Esk::PublicKeyEncryptedSessionKey(ref pkesk) = esk[0] else {
+                panic!("no PKESK");
+            };
+            assert_eq!(pkesk.version(), pkesk_version);
+            assert_eq!(pkesk.algorithm(), PublicKeyAlgorithm::X25519);
+
+            let (decrypted, key_ids) = parsed.decrypt(String::new, &[&signed_key]).unwrap();
+            assert_eq!(key_ids, vec![public_subkey.key_id()]);
+            assert_eq!(
+                decrypted.get_content().unwrap().unwrap(),
+                b"hello world".to_vec()
+            );
+        }
+
+        let err = params(true).unwrap_err();
+        assert!(err.to_string().contains("only be used for encryption"));
+        assert!(SecretKeyParamsBuilder::default()
+            .key_type(KeyType::X25519)
+            .can_sign(true)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "openssl")]
+    fn key_gen_x448_rfc9580() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let signed_key = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::Ed25519)
+            .can_certify(true)
+            .can_sign(true)
+            .primary_user_id("Me <me@mail.com>".into())
+            .subkey(
+                SubkeyParamsBuilder::default()
+                    .key_type(KeyType::X448)
+                    .can_encrypt(true)
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap()
+            .generate_with_rng(&mut rng)
+            .unwrap()
+            .sign(String::new)
+            .unwrap();
+        signed_key.verify().unwrap();
+
+        let subkey = &signed_key.secret_subkeys[0].key;
+        assert_eq!(subkey.algorithm(), PublicKeyAlgorithm::X448);
+        assert!(matches!(subkey.public_params(), PublicParams::X448 { .. }));
+
+        let armor = signed_key.to_armored_string(None.into()).unwrap();
+        let (parsed, _headers) = SignedSecretKey::from_string(&armor).unwrap();
+        parsed.verify().unwrap();
+        assert_eq!(parsed, signed_key);
+
+        // encrypt to the subkey, with version 3 and version 6 PKESK packets
+        let msg = Message::new_literal("hello.txt", "hello world");
+        let public_subkey = &signed_key.secret_subkeys[0].public_key();
+        let encrypted = [
+            msg.encrypt_to_keys(&mut rng, SymmetricKeyAlgorithm::AES128, &[public_subkey])
+                .unwrap(),
+            msg.encrypt_to_keys_seipdv2(
+                &mut rng,
+                SymmetricKeyAlgorithm::AES256,
+                AeadAlgorithm::Ocb,
+                0,
+                &[public_subkey],
+            )
+            .unwrap(),
+        ];
+        for (encrypted, pkesk_version) in encrypted.into_iter().zip([3, 6]) {
+            let armor = encrypted.to_armored_string(None.into()).unwrap();
+            let (parsed, _headers) = Message::from_string(&armor).unwrap();
+            assert_eq!(parsed, encrypted);
+
+            let Message::Encrypted { ref esk, .. } = parsed else {
+                panic!("not encrypted");
+            };
+            let Esk::PublicKeyEncryptedSessionKey(ref pkesk) = esk[0] else {
+                panic!("no PKESK");
+            };
+            assert_eq!(pkesk.version(), pkesk_version);
+            assert_eq!(pkesk.algorithm(), PublicKeyAlgorithm::X448);
+
+            let (decrypted, _key_ids) = parsed.decrypt(String::new, &[&signed_key]).unwrap();
+            assert_eq!(
+                decrypted.get_content().unwrap().unwrap(),
+                b"hello world".to_vec()
+            );
+        }
+
+        assert!(SecretKeyParamsBuilder::default()
+            .key_type(KeyType::X448)
+            .can_sign(true)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    #[cfg(not(feature = "openssl"))]
+    fn key_gen_x448_requires_openssl() {
+        let err = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::Ed25519)
+            .can_certify(true)
+            .primary_user_id("Me <me@mail.com>".into())
+            .subkey(
+                SubkeyParamsBuilder::default()
+                    .key_type(KeyType::X448)
+                    .can_encrypt(true)
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("openssl feature"), "{err}");
+    }
+
+    #[test]
+    #[cfg(feature = "openssl")]
+    fn key_gen_ed448_rfc9580() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let signed_key = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::Ed448)
+            .can_certify(true)
+            .can_sign(true)
+            .primary_user_id("Me <me@mail.com>".into())
+            .subkey(
+                SubkeyParamsBuilder::default()
+                    .key_type(KeyType::X448)
+                    .can_encrypt(true)
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap()
+            .generate_with_rng(&mut rng)
+            .unwrap()
+            .sign(String::new)
+            .unwrap();
+        signed_key.verify().unwrap();
+
+        // version, creation time, then the algorithm
+        let key_bytes = signed_key.primary_key.public_key().to_bytes().unwrap();
+        assert_eq!(key_bytes[5], u8::from(PublicKeyAlgorithm::Ed448));
+        assert_eq!(key_bytes.len(), 6 + 57);
+
+        let armor = signed_key.to_armored_string(None.into()).unwrap();
+        let (parsed, _headers) = SignedSecretKey::from_string(&armor).unwrap();
+        parsed.verify().unwrap();
+        assert_eq!(parsed, signed_key);
+
+        let data = b"hello world";
+        let armored = signed_key
+            .detach_sign_armored(&data[..], signed_key.hash_alg(), String::new)
+            .unwrap();
+        let (sig, _headers) = StandaloneSignature::from_string(&armored).unwrap();
+        assert_eq!(sig.signature.config.pub_alg, PublicKeyAlgorithm::Ed448);
+        assert_eq!(sig.signature.config.hash_alg, HashAlgorithm::SHA2_512);
+        sig.verify(&signed_key.public_key(), data).unwrap();
+        assert!(sig.verify(&signed_key.public_key(), b"hello moon").is_err());
+
+        // the native signature, without MPI length prefixes
+        let sig_bytes = sig.signature.to_bytes().unwrap();
+        assert!(sig_bytes.ends_with(sig.signature.signature[0].as_bytes()));
+        assert_eq!(sig.signature.signature[0].as_bytes().len(), 114);
+
+        assert!(SecretKeyParamsBuilder::default()
+            .key_type(KeyType::Ed448)
+            .can_certify(true)
+            .can_encrypt(true)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    #[cfg(not(feature = "openssl"))]
+    fn key_gen_ed448_requires_openssl() {
+        let err = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::Ed448)
+            .can_certify(true)
+            .primary_user_id("Me <me@mail.com>".into())
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("openssl feature"), "{err}");
+    }
+
+    #[test]
+    fn key_gen_ed25519_rfc9580() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let signed_key = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::Ed25519)
+            .can_certify(true)
+            .can_sign(true)
+            .primary_user_id("Me <me@mail.com>".into())
+            .build()
+            .unwrap()
+            .generate_with_rng(&mut rng)
+            .unwrap()
+            .sign(String::new)
+            .unwrap();
+        signed_key.verify().unwrap();
+
+        // version, creation time, then the algorithm
+        let key_bytes = signed_key.primary_key.public_key().to_bytes().unwrap();
+        assert_eq!(key_bytes[5], u8::from(PublicKeyAlgorithm::Ed25519));
+        assert_eq!(key_bytes.len(), 6 + 32);
+
+        let armor = signed_key.to_armored_string(None.into()).unwrap();
+        let (parsed, _headers) = SignedSecretKey::from_string(&armor).unwrap();
+        parsed.verify().unwrap();
+        assert_eq!(parsed, signed_key);
+
+        let data = b"hello world";
+        let armored = signed_key
+            .detach_sign_armored(&data[..], HashAlgorithm::SHA2_256, String::new)
+            .unwrap();
+        let (sig, _headers) = StandaloneSignature::from_string(&armored).unwrap();
+        assert_eq!(sig.signature.config.pub_alg, PublicKeyAlgorithm::Ed25519);
+        sig.verify(&signed_key.public_key(), data).unwrap();
+        assert!(sig.verify(&signed_key.public_key(), b"hello moon").is_err());
+
+        // version, type, then the algorithm
+        let sig_bytes = sig.signature.to_bytes().unwrap();
+        assert_eq!(sig_bytes[2], u8::from(PublicKeyAlgorithm::Ed25519));
+        // the native signature, without MPI length prefixes
+        assert!(sig_bytes.ends_with(sig.signature.signature[0].as_bytes()));
+        assert_eq!(sig.signature.signature[0].as_bytes().len(), 64);
+
+        assert!(SecretKeyParamsBuilder::default()
+            .key_type(KeyType::Ed25519)
+            .can_certify(true)
+            .can_encrypt(true)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn key_gen_v6_ed25519() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let signed_key = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::Ed25519)
+            .can_certify(true)
+            .can_sign(true)
+            .version(types::KeyVersion::V6)
+            .subkey(
+                SubkeyParamsBuilder::default()
+                    .key_type(KeyType::X25519)
+                    .can_encrypt(true)
+                    .version(types::KeyVersion::V6)
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap()
+            .generate_with_rng(&mut rng)
+            .unwrap()
+            .sign(String::new)
+            .unwrap();
+        signed_key.verify().unwrap();
+        assert_eq!(signed_key.version(), types::KeyVersion::V6);
+        assert_eq!(
+            signed_key.details.direct_signatures[0].config.version,
+            packet::SignatureVersion::V6
+        );
+
+        let armor = signed_key.to_armored_string(None.into()).unwrap();
+        let (parsed, _headers) = SignedSecretKey::from_string(&armor).unwrap();
+        parsed.verify().unwrap();
+        assert_eq!(parsed, signed_key);
+
+        let public_key = signed_key
+            .public_key()
+            .sign(&signed_key, String::new)
+            .unwrap();
+        let armor = public_key.to_armored_string(None.into()).unwrap();
+        let (parsed, _headers) = SignedPublicKey::from_string(&armor).unwrap();
+        parsed.verify().unwrap();
+        assert_eq!(parsed, public_key);
+    }
+
+    #[test]
+    fn key_gen_subkey_passphrases() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+
+        let subkey = |key_type: KeyType, can_encrypt: bool| {
+            SubkeyParamsBuilder::default()
+                .key_type(key_type)
+                .can_encrypt(can_encrypt)
+                .can_authenticate(!can_encrypt)
+                // replaced by the closure below
+                .passphrase(Some("ignored".into()))
+                .build()
+                .unwrap()
+        };
+        let params = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::EdDSA)
+            .can_certify(true)
+            .primary_user_id("Me <me@mail.com>".into())
+            .passphrase(Some("primary".into()))
+            .subkeys(vec![
+                subkey(KeyType::ECDH, true),
+                subkey(KeyType::EdDSA, false),
+                subkey(KeyType::ECDH, true),
+            ])
+            .build()
+            .unwrap();
+
+        let mut asked = 0;
+        let key = params
+            .generate_with_rng_and_passphrases(&mut rng, |subkey| {
+                asked += 1;
+                match (subkey.key_type(), subkey.keyflags().encrypt_comms()) {
+                    (KeyType::ECDH, true) if asked == 1 => Some("encryption".into()),
+                    (KeyType::EdDSA, false) => Some("authentication".into()),
+                    _ => None,
+                }
+            })
+            .unwrap()
+            .sign(|| "primary".into())
+            .unwrap();
+        assert_eq!(asked, 3);
+
+        key.unlock(|| "primary".into(), |_| Ok(())).unwrap();
+        let subkeys = &key.secret_subkeys;
+        subkeys[0]
+            .unlock(|| "encryption".into(), |_| Ok(()))
+            .unwrap();
+        assert!(subkeys[0]
+            .unlock(|| "authentication".into(), |_| Ok(()))
+            .is_err());
+        subkeys[1]
+            .unlock(|| "authentication".into(), |_| Ok(()))
+            .unwrap();
+        assert!(subkeys[1].unlock(|| "ignored".into(), |_| Ok(())).is_err());
+        assert!(matches!(
+            subkeys[2].key.secret_params(),
+            types::SecretParams::Plain(_)
+        ));
+    }
+
+    #[test]
+    fn key_gen_signing_subkey_passphrase() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let key = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::EdDSA)
+            .can_certify(true)
+            .primary_user_id("Me <me@mail.com>".into())
+            .passphrase(Some("primary".into()))
+            .subkey(
+                SubkeyParamsBuilder::default()
+                    .key_type(KeyType::EdDSA)
+                    .can_sign(true)
+                    .passphrase(Some("sub".into()))
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap()
+            .generate_with_rng(&mut rng)
+            .unwrap();
+
+        // the back signature needs the passphrase of the subkey
+        assert!(key.clone().sign(|| "primary".into()).is_err());
+
+        let mut asked = 0;
+        let signed = key
+            .sign_with_subkey_passphrases(
+                || "primary".into(),
+                |subkey| {
+                    asked += 1;
+                    assert!(subkey.keyflags().sign());
+                    "sub".into()
+                },
+            )
+            .unwrap();
+        assert_eq!(asked, 1);
+        signed.verify().unwrap();
+        assert!(signed.secret_subkeys[0].signatures[0]
+            .embedded_signature()
+            .is_some());
+        signed.secret_subkeys[0]
+            .unlock(|| "sub".into(), |_| Ok(()))
+            .unwrap();
+    }
+
+    #[test]
+    fn key_gen_unsigned_transport() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let params = || {
+            SecretKeyParamsBuilder::default()
+                .key_type(KeyType::EdDSA)
+                .can_certify(true)
+                .can_sign(true)
+                .primary_user_id("Me <me@mail.com>".into())
+                .user_id("Also me <me@example.com>")
+                .preferred_symmetric_algorithms(smallvec![SymmetricKeyAlgorithm::AES256])
+                .passphrase(Some("hello".into()))
+                .subkeys(vec![
+                    SubkeyParams::x25519_encryption(),
+                    SubkeyParams::ed25519_signing(),
+                ])
+                .build()
+                .unwrap()
+        };
+
+        let key = params().generate_with_rng(&mut rng).unwrap();
+        let bytes = key.to_bytes().unwrap();
+        // only key and User ID packets, no signatures
+        let tags: Vec<_> = PacketParser::new(&bytes[..])
+            .map(|packet| packet.unwrap().tag())
+            .collect();
+        assert_eq!(
+            tags,
+            vec![
+                Tag::SecretKey,
+                Tag::UserId,
+                Tag::UserId,
+                Tag::SecretSubkey,
+                Tag::SecretSubkey
+            ]
+        );
+
+        let imported = params().import_unsigned(&bytes[..]).unwrap();
+        assert_eq!(imported, key);
+
+        let signed = imported.sign(|| "hello".into()).unwrap();
+        signed.verify().unwrap();
+        let public = SignedPublicKey::from(signed.clone());
+        public.verify().unwrap();
+        assert!(public.subkeys_missing_backsig().is_empty());
+        assert_eq!(
+            public.details.users[0].signatures[0].preferred_symmetric_algs(),
+            &[SymmetricKeyAlgorithm::AES256]
+        );
+
+        // the params must match the key
+        let other = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::EdDSA)
+            .can_certify(true)
+            .primary_user_id("Me <me@mail.com>".into())
+            .build()
+            .unwrap();
+        assert!(other.import_unsigned(&bytes[..]).is_err());
+        assert!(params()
+            .import_unsigned(&signed.to_bytes().unwrap()[..])
+            .is_err());
+    }
+
+    #[test]
+    fn key_gen_deterministic() {
+        use chrono::TimeZone;
+
+        let params = || {
+            SecretKeyParamsBuilder::default()
+                .key_type(KeyType::EdDSA)
+                .can_certify(true)
+                .can_sign(true)
+                .primary_user_id("Me <me@mail.com>".into())
+                .passphrase(Some("hello".into()))
+                .subkeys(vec![
+                    SubkeyParams::x25519_encryption(),
+                    SubkeyParams::ed25519_signing(),
+                ])
+                .build()
+                .unwrap()
+        };
+        let created_at = chrono::Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let generate = |seed| {
+            params()
+                .generate_deterministic(seed, created_at)
+                .unwrap()
+                .sign(|| "hello".into())
+                .unwrap()
+                .to_armored_string(None.into())
+                .unwrap()
+        };
+
+        let armored = generate([1; 32]);
+        assert_eq!(armored, generate([1; 32]));
+        assert_ne!(armored, generate([2; 32]));
+
+        let (key, _) = SignedSecretKey::from_string(&armored).unwrap();
+        key.verify().unwrap();
+        assert_eq!(key.primary_key.created_at(), &created_at);
+        assert!(key
+            .secret_subkeys
+            .iter()
+            .all(|subkey| subkey.key.created_at() == &created_at));
+        assert_eq!(
+            key.details.users[0].signatures[0].created(),
+            Some(&created_at)
+        );
+    }
+
+    #[test]
+    fn key_gen_argon2() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let params = |version, s2k| {
+            SecretKeyParamsBuilder::default()
+                .key_type(KeyType::ECDSA(ECCCurve::P256))
+                .can_certify(true)
+                .can_sign(true)
+                .version(version)
+                .passphrase(Some("hello".into()))
+                .s2k(s2k)
+                .build()
+                .unwrap()
+        };
+
+        // v6 keys are protected with Argon2 by default
+        let plan = params(types::KeyVersion::V6, None).plan().unwrap();
+        assert_eq!(
+            plan.s2k,
+            Some(S2kChoice::Aead {
+                sym_alg: SymmetricKeyAlgorithm::AES256,
+                aead_mode: AeadAlgorithm::Ocb,
+                s2k: StringToKeyChoice::Argon2 {
+                    t: 3,
+                    p: 4,
+                    m_enc: 16,
+                },
+            })
+        );
+        let plan = params(types::KeyVersion::V4, None).plan().unwrap();
+        assert_eq!(plan.s2k, Some(S2kChoice::new_default()));
+
+        // cheap parameters, the defaults are slow in debug mode
+        let s2k = S2kParams::new_argon2(&mut rng, AeadAlgorithm::Ocb, 1, 1, 10);
+        let key = params(types::KeyVersion::V6, Some(s2k.clone()))
+            .generate_with_rng(&mut rng)
+            .unwrap()
+            .sign(|| "hello".into())
+            .unwrap();
+        let types::SecretParams::Encrypted(encrypted) = key.primary_key.secret_params() else {
+            panic!("not encrypted");
+        };
+        assert_eq!(encrypted.string_to_key_params(), &s2k);
+
+        let armor = key.to_armored_string(None.into()).unwrap();
+        let (parsed, _headers) = SignedSecretKey::from_string(&armor).unwrap();
+        parsed.verify().unwrap();
+        assert_eq!(parsed, key);
+        parsed.unlock(|| "hello".into(), |_| Ok(())).unwrap();
+        assert!(parsed.unlock(|| "wrong".into(), |_| Ok(())).is_err());
+
+        // other AEAD modes can be used as well
+        let s2k = S2kParams::new_argon2(&mut rng, AeadAlgorithm::Eax, 1, 1, 10);
+        let key = params(types::KeyVersion::V6, Some(s2k))
+            .generate_with_rng(&mut rng)
+            .unwrap()
+            .sign(|| "hello".into())
+            .unwrap();
+        key.unlock(|| "hello".into(), |_| Ok(())).unwrap();
+        assert!(key.unlock(|| "wrong".into(), |_| Ok(())).is_err());
+    }
+
+    #[test]
+    fn key_gen_binding_times() {
+        use chrono::TimeZone;
+
+        let created_at = chrono::Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let subkey_created_at = chrono::Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap();
+        let binding_at = chrono::Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
+        let backsig_at = chrono::Utc.with_ymd_and_hms(2024, 2, 15, 0, 0, 0).unwrap();
+        let params = |binding: Option<_>, backsig: Option<_>| {
+            SecretKeyParamsBuilder::default()
+                .key_type(KeyType::EdDSA)
+                .can_certify(true)
+                .primary_user_id("Me <me@mail.com>".into())
+                .created_at(created_at)
+                .subkey(
+                    SubkeyParamsBuilder::default()
+                        .key_type(KeyType::EdDSA)
+                        .can_sign(true)
+                        .created_at(subkey_created_at)
+                        .binding_created_at(binding)
+                        .primary_key_binding_created_at(backsig)
+                        .build()
+                        .unwrap(),
+                )
+                .build()
+                .unwrap()
+        };
+        let times = |key: &SignedSecretKey| {
+            let binding = &key.secret_subkeys[0].signatures[0];
+            (
+                *binding.created().unwrap(),
+                *binding.embedded_signature().unwrap().created().unwrap(),
+            )
+        };
+
+        // both default to the creation time of the subkey
+        let key = params(None, None)
+            .generate_with_rng(ChaCha8Rng::seed_from_u64(0))
+            .unwrap()
+            .sign(String::new)
+            .unwrap();
+        key.verify().unwrap();
+        assert_eq!(times(&key), (subkey_created_at, subkey_created_at));
+
+        // Ed25519 signatures are deterministic, so the exact signatures are reproduced
+        let generate = || {
+            params(Some(binding_at), Some(backsig_at))
+                .generate_with_rng(ChaCha8Rng::seed_from_u64(0))
+                .unwrap()
+                .sign(String::new)
+                .unwrap()
+        };
+        let key = generate();
+        key.verify().unwrap();
+        assert_eq!(times(&key), (binding_at, backsig_at));
+        assert_eq!(
+            key.secret_subkeys[0].signatures,
+            generate().secret_subkeys[0].signatures
+        );
+    }
+
+    #[test]
+    fn key_gen_v6() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let key = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::ECDSA(ECCCurve::P256))
+            .can_certify(true)
+            .can_sign(true)
+            .version(types::KeyVersion::V6)
+            .subkey(
+                SubkeyParamsBuilder::default()
+                    .key_type(KeyType::ECDSA(ECCCurve::P256))
+                    .can_sign(true)
+                    .version(types::KeyVersion::V6)
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap()
+            .generate_with_rng(&mut rng)
+            .unwrap();
+        let signed_key = key.sign(String::new).unwrap();
+        signed_key.verify().unwrap();
+
+        assert_eq!(signed_key.version(), types::KeyVersion::V6);
+        assert_eq!(signed_key.fingerprint().len(), 32);
+        assert!(signed_key.details.users.is_empty());
+        let direct = &signed_key.details.direct_signatures[0];
+        assert_eq!(direct.typ(), SignatureType::Key);
+        assert_eq!(direct.config.version, packet::SignatureVersion::V6);
+        assert_eq!(direct.config.salt.as_ref().map(Vec::len), Some(16));
+        assert!(direct.issuer().is_empty());
+        assert_eq!(
+            signed_key.secret_subkeys[0].signatures[0].config.version,
+            packet::SignatureVersion::V6
+        );
+
+        let armor = signed_key.to_armored_string(None.into()).unwrap();
+        let (parsed, _headers) = SignedSecretKey::from_string(&armor).unwrap();
+        parsed.verify().unwrap();
+        assert_eq!(parsed, signed_key);
+
+        let public_key = signed_key
+            .public_key()
+            .sign(&signed_key, String::new)
+            .unwrap();
+        let armor = public_key.to_armored_string(None.into()).unwrap();
+        let (parsed, _headers) = SignedPublicKey::from_string(&armor).unwrap();
+        parsed.verify().unwrap();
+        assert_eq!(parsed, public_key);
+        assert_eq!(parsed.fingerprint(), signed_key.fingerprint());
+
+        let err = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::EdDSA)
+            .can_certify(true)
+            .version(types::KeyVersion::V6)
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("legacy"));
+    }
+
+    #[test]
+    fn key_gen_expires_at() {
+        let created_at = chrono::DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        let params = |expires_at: Option<chrono::DateTime<chrono::Utc>>| {
+            let mut builder = SecretKeyParamsBuilder::default();
+            builder
+                .key_type(KeyType::EdDSA)
+                .can_sign(true)
+                .primary_user_id("Me <me@mail.com>".into())
+                .created_at(created_at);
+            if let Some(expires_at) = expires_at {
+                builder.expires_at(expires_at);
+            }
+            builder
+        };
+
+        let key = params(Some(created_at + chrono::Duration::hours(1)))
+            .build()
+            .unwrap()
+            .generate()
+            .unwrap()
+            .sign(String::new)
+            .unwrap();
+        let expected = params(None)
+            .expiration(Some(Duration::from_secs(3600)))
+            .build()
+            .unwrap()
+            .generate()
+            .unwrap()
+            .sign(String::new)
+            .unwrap();
+        assert_eq!(
+            key.primary_key.expiration(),
+            expected.primary_key.expiration()
+        );
+        assert!(key.primary_key.expiration().is_some());
+
+        for expires_at in [created_at, created_at - chrono::Duration::days(1)] {
+            let err = params(Some(expires_at)).build().unwrap_err();
+            assert!(err.to_string().contains("must be after"), "{err}");
+        }
+
+        let err = params(Some(created_at + chrono::Duration::hours(1)))
+            .expiration(Some(Duration::from_secs(3600)))
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("Only one of"), "{err}");
+    }
+
+    #[test]
+    fn key_gen_primary_authentication() {
+        let key = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::EdDSA)
+            .can_certify(true)
+            .can_sign(true)
+            .can_authenticate(true)
+            .primary_user_id("Me <me@mail.com>".into())
+            .build()
+            .unwrap()
+            .generate()
+            .unwrap()
+            .sign(String::new)
+            .unwrap();
+
+        let mut expected = KeyFlags::default();
+        expected.set_certify(true);
+        expected.set_sign(true);
+        expected.set_authentication(true);
+        assert_eq!(key.details.users[0].signatures[0].key_flags(), expected);
+
+        assert!(SecretKeyParamsBuilder::default()
+            .key_type(KeyType::ECDSA(ECCCurve::P256))
+            .can_authenticate(true)
+            .build()
+            .is_ok());
+
+        let err = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::ECDH)
+            .can_authenticate(true)
+            .build()
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("ECDH can not be used for authentication"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn key_gen_expiration_roundtrip() {
+        let one_year = Duration::from_secs(365 * 24 * 60 * 60);
+        let params = |expiration| {
+            SecretKeyParamsBuilder::default()
+                .key_type(KeyType::EdDSA)
+                .can_certify(true)
+                .primary_user_id("Me <me@mail.com>".into())
+                .expiration(Some(expiration))
+                .subkey(
+                    SubkeyParamsBuilder::default()
+                        .key_type(KeyType::ECDH)
+                        .can_encrypt(true)
+                        .expiration(Some(expiration))
+                        .build()
+                        .unwrap(),
+                )
+                .build()
+                .unwrap()
+        };
+
+        let key = params(one_year)
+            .generate()
+            .unwrap()
+            .sign(String::new)
+            .unwrap();
+        assert_eq!(key.primary_key.expiration(), Some(365 * 24 * 60 * 60));
+
+        let armored = key.to_armored_string(None.into()).unwrap();
+        let (parsed, _headers) = SignedSecretKey::from_string(&armored).unwrap();
+        parsed.verify().unwrap();
+        let expected = *parsed.primary_key.created_at() + chrono::Duration::days(365);
+        assert_eq!(parsed.expires_at(), Some(expected));
+
+        let public_key = SignedPublicKey::from(parsed);
+        assert_eq!(public_key.expires_at(), Some(expected));
+        let binding = &public_key.public_subkeys[0].signatures[0];
+        assert_eq!(
+            binding.key_expiration_time(),
+            Some(&chrono::Duration::days(365))
+        );
+
+        let too_long = Duration::from_secs(u64::from(u32::MAX) + 1);
+        let err = params(too_long).generate().unwrap_err();
+        assert!(err.to_string().contains("32 bits"), "{err}");
+    }
+
+    #[test]
+    fn key_gen_certify_only_with_subkeys() {
+        let rng = &mut ChaCha8Rng::seed_from_u64(0);
+
+        let key = SecretKeyParamsBuilder::certify_only_with_subkeys()
+            .primary_user_id("Me <me@mail.com>".into())
+            .build()
+            .unwrap()
+            .generate_with_rng(rng)
+            .expect("failed to generate secret key");
+        let signed_key = key.sign(String::new).expect("failed to sign key");
+        signed_key.verify().expect("invalid key");
+
+        let primary_flags = signed_key.details.users[0].signatures[0].key_flags();
+        assert!(primary_flags.certify());
+        assert!(!primary_flags.sign());
+        assert!(!primary_flags.encrypt_comms());
+        assert!(!primary_flags.authentication());
+
+        let subkeys = &signed_key.secret_subkeys;
+        assert_eq!(subkeys.len(), 3);
+        assert_eq!(
+            subkeys
+                .iter()
+                .map(|k| k.key.algorithm())
+                .collect::<Vec<_>>(),
+            vec![
+                PublicKeyAlgorithm::Ed25519,
+                PublicKeyAlgorithm::X25519,
+                PublicKeyAlgorithm::Ed25519
+            ]
+        );
+
+        let sign_sig = &subkeys[0].signatures[0];
+        assert!(sign_sig.key_flags().sign());
+        let backsig = sign_sig
+            .embedded_signature()
+            .expect("missing back signature");
+        assert_eq!(backsig.typ(), packet::SignatureType::KeyBinding);
+        backsig
+            .verify_backwards_key_binding(&subkeys[0].key, &signed_key.primary_key)
+            .expect("invalid back signature");
+
+        let enc_sig = &subkeys[1].signatures[0];
+        assert!(enc_sig.key_flags().encrypt_comms());
+        assert!(enc_sig.embedded_signature().is_none());
+
+        let auth_sig = &subkeys[2].signatures[0];
+        assert!(auth_sig.key_flags().authentication());
+        assert!(auth_sig.embedded_signature().is_none());
+
+        let armor = signed_key
+            .to_armored_string(None.into())
+            .expect("failed to serialize key");
+        let (signed_key2, _headers) =
+            SignedSecretKey::from_string(&armor).expect("failed to parse key");
+        signed_key2.verify().expect("invalid key");
+        assert_eq!(signed_key, signed_key2);
+    }
+
+    #[test]
+    fn key_gen_user_attribute_self_signatures() {
+        let rng = &mut ChaCha8Rng::seed_from_u64(0);
+        let photo = |data: &[u8]| {
+            // header version 1, JPEG, reserved
+            let mut header = vec![1, 1];
+            header.resize(14, 0);
+            UserAttribute::Image {
+                packet_version: Default::default(),
+                header,
+                data: data.to_vec(),
+            }
+        };
+
+        let mut key = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::EdDSA)
+            .can_certify(true)
+            .can_sign(true)
+            .primary_user_id("Me <me@mail.com>".into())
+            .user_attributes(vec![photo(&[0xff, 0xd8, 1, 2, 3])])
+            .build()
+            .unwrap()
+            .generate_with_rng(rng)
+            .expect("failed to generate secret key")
+            .sign(String::new)
+            .expect("failed to sign key");
+        key.add_user_attribute(photo(&[0xff, 0xd8, 4, 5, 6]), String::new)
+            .unwrap();
+
+        let armored = SignedPublicKey::from(key)
+            .to_armored_string(None.into())
+            .unwrap();
+        let (public_key, _headers) = SignedPublicKey::from_string(&armored).unwrap();
+        public_key.verify().unwrap();
+
+        assert_eq!(public_key.details.user_attributes.len(), 2);
+        for attr in &public_key.details.user_attributes {
+            assert_eq!(attr.signatures.len(), 1);
+            assert_eq!(attr.signatures[0].typ(), SignatureType::CertPositive);
+            assert_eq!(
+                attr.signatures[0].issuer_fingerprint(),
+                vec![&public_key.fingerprint()]
+            );
+            attr.verify(&public_key.primary_key).unwrap();
+            assert_eq!(
+                attr.attr.as_image().unwrap().0,
+                crate::packet::ImageFormat::Jpeg
+            );
+        }
+    }
+
+    #[test]
+    fn key_plan() {
+        let params = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::Rsa(4096))
+            .can_certify(true)
+            .can_sign(true)
+            .primary_user_id("Me <me@mail.com>".into())
+            .preferred_hash_algorithms(smallvec![HashAlgorithm::SHA2_512])
+            .passphrase(Some("hello".into()))
+            .expires_at(chrono::Utc::now().trunc_subsecs(0) + chrono::Duration::days(2))
+            .subkey(
+                SubkeyParamsBuilder::default()
+                    .key_type(KeyType::ECDH)
+                    .can_encrypt(true)
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        // no RSA 4096 key is generated, this returns right away
+        let plan = params.plan().unwrap();
+        assert_eq!(plan.algorithm, PublicKeyAlgorithm::RSA);
+        assert!(plan.keyflags.certify() && plan.keyflags.sign());
+        assert!(!plan.keyflags.encrypt_comms());
+        assert_eq!(
+            &plan.preferred_hash_algorithms[..],
+            &[HashAlgorithm::SHA2_512]
+        );
+        assert_eq!(plan.primary_user_id.as_deref(), Some("Me <me@mail.com>"));
+        let expiration = plan.expiration.unwrap().as_secs();
+        assert!(expiration > 24 * 60 * 60 && expiration <= 2 * 24 * 60 * 60);
+        assert_eq!(
+            plan.s2k,
+            Some(S2kChoice::Cfb {
+                sym_alg: SymmetricKeyAlgorithm::AES256,
+                s2k: StringToKeyChoice::IteratedAndSalted {
+                    hash_alg: HashAlgorithm::default(),
+                    count: 224,
+                },
+            })
+        );
+        // no salt or IV is drawn for the plan, it is the same every time
+        assert_eq!(params.plan().unwrap(), plan);
+
+        assert_eq!(plan.subkeys.len(), 1);
+        let subkey = &plan.subkeys[0];
+        assert_eq!(subkey.algorithm, PublicKeyAlgorithm::ECDH);
+        assert!(subkey.keyflags.encrypt_comms() && subkey.keyflags.encrypt_storage());
+        assert!(subkey.s2k.is_none());
+    }
+
+    #[test]
+    fn key_gen_pregenerate_revocation() {
+        let rng = &mut ChaCha8Rng::seed_from_u64(0);
+        let mut gen_key = |pregenerate_revocation: bool| {
+            SecretKeyParamsBuilder::default()
+                .key_type(KeyType::EdDSA)
+                .can_certify(true)
+                .can_sign(true)
+                .primary_user_id("Me <me@mail.com>".into())
+                .pregenerate_revocation(pregenerate_revocation)
+                .build()
+                .unwrap()
+                .generate_with_rng(&mut *rng)
+                .expect("failed to generate secret key")
+                .sign(|| "".into())
+                .expect("failed to sign key")
+        };
+
+        let key = gen_key(false);
+        assert!(key.pregenerated_revocation().is_none());
+
+        let key = gen_key(true);
+        let revocation = key
+            .pregenerated_revocation()
+            .expect("missing revocation certificate");
+        let signature = &revocation.signature;
+        assert_eq!(signature.typ(), SignatureType::KeyRevocation);
+        assert_eq!(
+            signature.revocation_reason_code(),
+            Some(&RevocationCode::NoReason)
+        );
+        signature.verify_key(&key.primary_key).unwrap();
+
+        let armored = revocation.to_armored_string(None.into()).unwrap();
+        let (parsed, _headers) = StandaloneSignature::from_string(&armored).unwrap();
+        parsed.signature.verify_key(&key.public_key()).unwrap();
+    }
+
+    #[test]
+    fn key_signing_hash_from_preferences() {
+        let rng = &mut ChaCha8Rng::seed_from_u64(0);
+        let mut gen_key = |key_type: KeyType, hashes: &[HashAlgorithm]| {
+            SecretKeyParamsBuilder::default()
+                .key_type(key_type)
+                .can_certify(true)
+                .can_sign(true)
+                .primary_user_id("Me <me@mail.com>".into())
+                .preferred_hash_algorithms(SmallVec::from_slice(hashes))
+                .build()
+                .unwrap()
+                .generate_with_rng(&mut *rng)
+                .expect("failed to generate secret key")
+                .sign(String::new)
+                .expect("failed to sign key")
+        };
+        let check = |key: &SignedSecretKey, expected: HashAlgorithm| {
+            assert_eq!(key.hash_alg(), expected);
+            assert_eq!(key.details.users[0].signatures[0].hash_alg(), expected);
+
+            let msg =
+                crate::composed::cleartext::CleartextSignedMessage::sign("hello", key, String::new)
+                    .unwrap();
+            assert_eq!(msg.signatures()[0].signature.hash_alg(), expected);
+        };
+
+        let key = gen_key(
+            KeyType::EdDSA,
+            &[HashAlgorithm::SHA2_512, HashAlgorithm::SHA2_256],
+        );
+        check(&key, HashAlgorithm::SHA2_512);
+
+        // too weak hashes are skipped
+        let key = gen_key(
+            KeyType::EdDSA,
+            &[HashAlgorithm::SHA1, HashAlgorithm::SHA3_256],
+        );
+        check(&key, HashAlgorithm::SHA3_256);
+        let key = gen_key(
+            KeyType::ECDSA(ECCCurve::P384),
+            &[HashAlgorithm::SHA2_256, HashAlgorithm::SHA2_512],
+        );
+        check(&key, HashAlgorithm::SHA2_512);
+
+        // without usable preferences the default is used
+        let key = gen_key(KeyType::EdDSA, &[]);
+        check(&key, HashAlgorithm::SHA2_256);
+        let key = gen_key(KeyType::EdDSA, &[HashAlgorithm::SHA1]);
+        check(&key, HashAlgorithm::SHA2_256);
+    }
+
+    #[test]
+    fn key_gen_signing_only_ed25519() {
+        use crate::composed::StandaloneSignature;
+        use crate::ser::Serialize;
+
+        let rng = &mut ChaCha8Rng::seed_from_u64(0);
+        let key = SecretKeyParamsBuilder::signing_only_ed25519()
+            .build()
+            .unwrap()
+            .generate_with_rng(rng)
+            .expect("failed to generate secret key")
+            .sign(String::new)
+            .expect("failed to sign key");
+        key.verify().expect("invalid key");
+
+        assert_eq!(key.algorithm(), PublicKeyAlgorithm::Ed25519);
+        assert!(key.secret_subkeys.is_empty());
+        assert!(key.public_subkeys.is_empty());
+        assert!(key.details.users.is_empty());
+        assert_eq!(key.details.direct_signatures.len(), 1);
+        let flags = key.details.direct_signatures[0].key_flags();
+        assert!(flags.certify() && flags.sign());
+        assert!(!flags.encrypt_comms() && !flags.encrypt_storage());
+
+        // key packet and direct key signature only
+        let bytes = SignedPublicKey::from(key.clone()).to_bytes().unwrap();
+        assert_eq!(crate::packet::PacketParser::new(&bytes[..]).count(), 2);
+
+        let data = b"some file contents";
+        let armored = key
+            .detach_sign_armored(&data[..], HashAlgorithm::SHA2_256, String::new)
+            .unwrap();
+        let (sig, _headers) = StandaloneSignature::from_string(&armored).unwrap();
+        sig.verify(&key.primary_key, &data[..]).unwrap();
+        assert!(sig
+            .verify(&key.primary_key, &b"other contents"[..])
+            .is_err());
+    }
+
+    #[test]
+    fn key_gen_skips_empty_preferences() {
+        let rng = &mut ChaCha8Rng::seed_from_u64(0);
+
+        let key = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::EdDSA)
+            .can_certify(true)
+            .can_sign(true)
+            .primary_user_id("Me <me@mail.com>".into())
+            .user_id("Me <me2@mail.com>")
+            .preferred_hash_algorithms(smallvec![HashAlgorithm::SHA2_256])
+            .build()
+            .unwrap()
+            .generate_with_rng(rng)
+            .expect("failed to generate secret key")
+            .sign(String::new)
+            .expect("failed to sign key");
+
+        for user in &key.details.users {
+            let sig = &user.signatures[0];
+            assert_eq!(sig.preferred_hash_algs(), &[HashAlgorithm::SHA2_256][..]);
+            assert!(!sig.config.hashed_subpackets.iter().any(|p| matches!(
+                p.data,
+                packet::SubpacketData::PreferredSymmetricAlgorithms(_)
+                    | packet::SubpacketData::PreferredCompressionAlgorithms(_)
+                    | packet::SubpacketData::PreferredAeadCiphersuites(_)
+            )));
+        }
+    }
 }
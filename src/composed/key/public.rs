@@ -2,16 +2,19 @@ use std::io;
 
 use chrono::SubsecRound;
 use rand::{CryptoRng, Rng};
-use smallvec::SmallVec;
 
+use crate::composed::key::{
+    issuer_fingerprint_subpacket, issuer_subpackets, key_expiration_subpacket, signature_version,
+};
 use crate::composed::{KeyDetails, SignedPublicKey, SignedPublicSubKey};
 use crate::crypto::hash::HashAlgorithm;
 use crate::crypto::public_key::PublicKeyAlgorithm;
 use crate::errors::Result;
 use crate::packet::{
-    self, KeyFlags, SignatureConfigBuilder, SignatureType, Subpacket, SubpacketData,
+    self, write_packet, KeyFlags, SignatureConfigBuilder, SignatureType, Subpacket, SubpacketData,
 };
-use crate::types::{KeyId, KeyTrait, Mpi, PublicKeyTrait, SecretKeyTrait};
+use crate::ser::Serialize;
+use crate::types::{KeyId, KeyTrait, KeyVersion, Mpi, PublicKeyTrait, SecretKeyTrait};
 
 /// User facing interface to work with a public key.
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -45,7 +48,14 @@ impl PublicKey {
         F: (FnOnce() -> String) + Clone,
     {
         let primary_key = self.primary_key;
-        let details = self.details.sign(sec_key, key_pw.clone())?;
+        let key_expiration =
+            key_expiration_subpacket(primary_key.version(), primary_key.expiration());
+        let details = self.details.sign_with_key_expiration(
+            sec_key,
+            key_pw.clone(),
+            key_expiration,
+            chrono::Utc::now().trunc_subsecs(0),
+        )?;
         let public_subkeys = self
             .public_subkeys
             .into_iter()
@@ -72,6 +82,10 @@ impl KeyTrait for PublicKey {
     fn algorithm(&self) -> PublicKeyAlgorithm {
         self.primary_key.algorithm()
     }
+
+    fn version(&self) -> KeyVersion {
+        self.primary_key.version()
+    }
 }
 
 impl PublicKeyTrait for PublicKey {
@@ -94,29 +108,37 @@ impl PublicSubkey {
     }
 
     pub fn sign<F>(self, sec_key: &impl SecretKeyTrait, key_pw: F) -> Result<SignedPublicSubKey>
+    where
+        F: (FnOnce() -> String) + Clone,
+    {
+        self.sign_at(sec_key, key_pw, chrono::Utc::now().trunc_subsecs(0))
+    }
+
+    /// Like [`Self::sign`], with the binding signature created at `created_at`.
+    pub(crate) fn sign_at<F>(
+        self,
+        sec_key: &impl SecretKeyTrait,
+        key_pw: F,
+        created_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<SignedPublicSubKey>
     where
         F: (FnOnce() -> String) + Clone,
     {
         let key = self.key;
-        let hashed_subpackets = vec![
-            Subpacket::regular(SubpacketData::SignatureCreationTime(
-                chrono::Utc::now().trunc_subsecs(0),
-            )),
+        let mut hashed_subpackets = vec![
+            Subpacket::regular(SubpacketData::SignatureCreationTime(created_at)),
             Subpacket::regular(SubpacketData::KeyFlags(self.keyflags.into())),
-            Subpacket::regular(SubpacketData::IssuerFingerprint(
-                Default::default(),
-                SmallVec::from_slice(&sec_key.fingerprint()),
-            )),
+            issuer_fingerprint_subpacket(sec_key),
         ];
+        hashed_subpackets.extend(key_expiration_subpacket(key.version(), key.expiration()));
 
         let config = SignatureConfigBuilder::default()
+            .version(signature_version(sec_key))
             .typ(SignatureType::SubkeyBinding)
             .pub_alg(sec_key.algorithm())
             .hash_alg(sec_key.hash_alg())
             .hashed_subpackets(hashed_subpackets)
-            .unhashed_subpackets(vec![Subpacket::regular(SubpacketData::Issuer(
-                sec_key.key_id(),
-            ))])
+            .unhashed_subpackets(issuer_subpackets(sec_key))
             .build()?;
 
         let signatures = vec![config.sign_key_binding(sec_key, key_pw, &key)?];
@@ -125,6 +147,12 @@ impl PublicSubkey {
     }
 }
 
+impl Serialize for PublicSubkey {
+    fn to_writer<W: io::Write>(&self, writer: &mut W) -> Result<()> {
+        write_packet(writer, &self.key)
+    }
+}
+
 impl KeyTrait for PublicSubkey {
     fn fingerprint(&self) -> Vec<u8> {
         self.key.fingerprint()
@@ -137,6 +165,10 @@ impl KeyTrait for PublicSubkey {
     fn algorithm(&self) -> PublicKeyAlgorithm {
         self.key.algorithm()
     }
+
+    fn version(&self) -> KeyVersion {
+        self.key.version()
+    }
 }
 
 impl PublicKeyTrait for PublicSubkey {
@@ -1,13 +1,20 @@
+use std::io;
+
 use chrono::SubsecRound;
-use smallvec::SmallVec;
+use zeroize::Zeroizing;
 
+use crate::composed::key::{
+    issuer_fingerprint_subpacket, issuer_subpackets, key_expiration_subpacket, signature_version,
+};
 use crate::composed::{KeyDetails, PublicSubkey, SignedSecretKey, SignedSecretSubKey};
 use crate::crypto::public_key::PublicKeyAlgorithm;
 use crate::errors::Result;
 use crate::packet::{
-    self, KeyFlags, SignatureConfigBuilder, SignatureType, Subpacket, SubpacketData,
+    self, write_packet, KeyFlags, RevocationCode, SignatureConfigBuilder, SignatureType, Subpacket,
+    SubpacketData,
 };
-use crate::types::{KeyId, KeyTrait, SecretKeyTrait};
+use crate::ser::Serialize;
+use crate::types::{KeyId, KeyTrait, KeyVersion, SecretKeyTrait};
 
 /// User facing interface to work with a secret key.
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -16,12 +23,20 @@ pub struct SecretKey {
     details: KeyDetails,
     public_subkeys: Vec<PublicSubkey>,
     secret_subkeys: Vec<SecretSubkey>,
+    pregenerate_revocation: bool,
+    /// Creation time of the self-signatures, the time of signing if `None`.
+    signed_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct SecretSubkey {
     key: packet::SecretSubkey,
     keyflags: KeyFlags,
+    /// Creation time of the binding signature, the time of signing if `None`.
+    binding_created_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Creation time of the embedded primary key binding signature, the time of signing if
+    /// `None`.
+    primary_key_binding_created_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 impl SecretKey {
@@ -36,32 +51,120 @@ impl SecretKey {
             details,
             public_subkeys,
             secret_subkeys,
+            pregenerate_revocation: false,
+            signed_at: None,
         }
     }
 
+    pub(crate) fn with_pregenerated_revocation(mut self, pregenerate_revocation: bool) -> Self {
+        self.pregenerate_revocation = pregenerate_revocation;
+        self
+    }
+
+    pub(crate) fn with_signed_at(mut self, signed_at: chrono::DateTime<chrono::Utc>) -> Self {
+        self.signed_at = Some(signed_at);
+        self
+    }
+
+    /// Creates the self-signatures, binding the User IDs and subkeys to the primary key.
+    ///
+    /// If the key was generated with `pregenerate_revocation` set, a revocation certificate is
+    /// also created, see [`SignedSecretKey::pregenerated_revocation`].
+    ///
+    /// This does not have to happen right after generating the key, the unsigned key can be
+    /// serialized and signed elsewhere, see the [`Serialize`] implementation.
     pub fn sign<F>(self, key_pw: F) -> Result<SignedSecretKey>
     where
         F: (FnOnce() -> String) + Clone,
+    {
+        self.sign_with_subkey_passphrases(key_pw.clone(), |_| (key_pw.clone())())
+    }
+
+    /// Signs the key like [`SecretKey::sign`], for signing subkeys that are protected with
+    /// their own passphrase.
+    ///
+    /// Signing subkeys have to be unlocked to create their back signature, `subkey_pw` is asked
+    /// for the passphrase of each of them that is encrypted. [`SecretKey::sign`] uses the
+    /// passphrase of the primary key for them.
+    pub fn sign_with_subkey_passphrases<F, P>(
+        self,
+        key_pw: F,
+        mut subkey_pw: P,
+    ) -> Result<SignedSecretKey>
+    where
+        F: (FnOnce() -> String) + Clone,
+        P: FnMut(&SecretSubkey) -> String,
     {
         let primary_key = self.primary_key;
-        let details = self.details.sign(&primary_key, key_pw.clone())?;
+        let signed_at = self
+            .signed_at
+            .unwrap_or_else(|| chrono::Utc::now().trunc_subsecs(0));
+        let key_expiration =
+            key_expiration_subpacket(primary_key.version(), primary_key.expiration());
+        let details = self.details.sign_with_key_expiration(
+            &primary_key,
+            key_pw.clone(),
+            key_expiration,
+            signed_at,
+        )?;
         let public_subkeys = self
             .public_subkeys
             .into_iter()
-            .map(|k| k.sign(&primary_key, key_pw.clone()))
+            .map(|k| k.sign_at(&primary_key, key_pw.clone(), signed_at))
             .collect::<Result<Vec<_>>>()?;
         let secret_subkeys = self
             .secret_subkeys
             .into_iter()
-            .map(|k| k.sign(&primary_key, key_pw.clone()))
+            .map(|k| {
+                let sub_pw = if k.keyflags.sign() && k.key.secret_params().is_encrypted() {
+                    Zeroizing::new(subkey_pw(&k))
+                } else {
+                    Zeroizing::default()
+                };
+                k.sign_at(
+                    &primary_key,
+                    key_pw.clone(),
+                    || sub_pw.to_string(),
+                    signed_at,
+                )
+            })
             .collect::<Result<Vec<_>>>()?;
 
-        Ok(SignedSecretKey {
+        let mut key = SignedSecretKey {
             primary_key,
             details,
             public_subkeys,
             secret_subkeys,
-        })
+            pregenerated_revocation: None,
+        };
+        if self.pregenerate_revocation {
+            key.pregenerated_revocation =
+                Some(key.revocation_certificate(key_pw, RevocationCode::NoReason, "")?);
+        }
+
+        Ok(key)
+    }
+}
+
+/// Writes the key as a minimal transferable secret key, without any self-signatures.
+///
+/// This allows generating a key on one machine and self-signing it on another, for example
+/// an air-gapped one. The key flags and preferences are only added by the self-signatures, and
+/// are not part of the output, use [`SecretKeyParams::import_unsigned`] to restore them.
+///
+/// [`SecretKeyParams::import_unsigned`]: crate::composed::SecretKeyParams::import_unsigned
+impl Serialize for SecretKey {
+    fn to_writer<W: io::Write>(&self, writer: &mut W) -> Result<()> {
+        write_packet(writer, &self.primary_key)?;
+        self.details.to_writer(writer)?;
+        for ps in &self.public_subkeys {
+            ps.to_writer(writer)?;
+        }
+        for ps in &self.secret_subkeys {
+            ps.to_writer(writer)?;
+        }
+
+        Ok(())
     }
 }
 
@@ -77,37 +180,101 @@ impl KeyTrait for SecretKey {
     fn algorithm(&self) -> PublicKeyAlgorithm {
         self.primary_key.algorithm()
     }
+
+    fn version(&self) -> KeyVersion {
+        self.primary_key.version()
+    }
 }
 
 impl SecretSubkey {
     pub fn new(key: packet::SecretSubkey, keyflags: KeyFlags) -> Self {
-        SecretSubkey { key, keyflags }
+        SecretSubkey {
+            key,
+            keyflags,
+            binding_created_at: None,
+            primary_key_binding_created_at: None,
+        }
+    }
+
+    /// The key flags the subkey is bound with.
+    pub fn keyflags(&self) -> KeyFlags {
+        self.keyflags
+    }
+
+    pub(crate) fn with_binding_times(
+        mut self,
+        binding_created_at: chrono::DateTime<chrono::Utc>,
+        primary_key_binding_created_at: chrono::DateTime<chrono::Utc>,
+    ) -> Self {
+        self.binding_created_at = Some(binding_created_at);
+        self.primary_key_binding_created_at = Some(primary_key_binding_created_at);
+        self
     }
 
     pub fn sign<F>(self, sec_key: &impl SecretKeyTrait, key_pw: F) -> Result<SignedSecretSubKey>
     where
         F: (FnOnce() -> String) + Clone,
+    {
+        self.sign_at(
+            sec_key,
+            key_pw.clone(),
+            key_pw,
+            chrono::Utc::now().trunc_subsecs(0),
+        )
+    }
+
+    /// Like [`Self::sign`], with the binding signature and back signature created at
+    /// `created_at`, unless other creation times were set for them. The subkey is unlocked
+    /// with `sub_pw` for the back signature.
+    pub(crate) fn sign_at<F, G>(
+        self,
+        sec_key: &impl SecretKeyTrait,
+        key_pw: F,
+        sub_pw: G,
+        created_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<SignedSecretSubKey>
+    where
+        F: (FnOnce() -> String) + Clone,
+        G: FnOnce() -> String,
     {
         let key = self.key;
-        let hashed_subpackets = vec![
+        let mut hashed_subpackets = vec![
             Subpacket::regular(SubpacketData::SignatureCreationTime(
-                chrono::Utc::now().trunc_subsecs(0),
+                self.binding_created_at.unwrap_or(created_at),
             )),
             Subpacket::regular(SubpacketData::KeyFlags(self.keyflags.into())),
-            Subpacket::regular(SubpacketData::IssuerFingerprint(
-                Default::default(),
-                SmallVec::from_slice(&sec_key.fingerprint()),
-            )),
+            issuer_fingerprint_subpacket(sec_key),
         ];
+        hashed_subpackets.extend(key_expiration_subpacket(key.version(), key.expiration()));
+
+        // Signing subkeys must prove possession by cross-certifying the primary key.
+        if self.keyflags.sign() {
+            let backsig = SignatureConfigBuilder::default()
+                .version(signature_version(&key))
+                .typ(SignatureType::KeyBinding)
+                .pub_alg(key.algorithm())
+                .hash_alg(key.hash_alg())
+                .hashed_subpackets(vec![
+                    Subpacket::regular(SubpacketData::SignatureCreationTime(
+                        self.primary_key_binding_created_at.unwrap_or(created_at),
+                    )),
+                    issuer_fingerprint_subpacket(&key),
+                ])
+                .unhashed_subpackets(issuer_subpackets(&key))
+                .build()?
+                .sign_primary_key_binding(&key, sub_pw, sec_key)?;
+            hashed_subpackets.push(Subpacket::regular(SubpacketData::EmbeddedSignature(
+                Box::new(backsig),
+            )));
+        }
 
         let config = SignatureConfigBuilder::default()
+            .version(signature_version(sec_key))
             .typ(SignatureType::SubkeyBinding)
             .pub_alg(sec_key.algorithm())
             .hash_alg(sec_key.hash_alg())
             .hashed_subpackets(hashed_subpackets)
-            .unhashed_subpackets(vec![Subpacket::regular(SubpacketData::Issuer(
-                sec_key.key_id(),
-            ))])
+            .unhashed_subpackets(issuer_subpackets(sec_key))
             .build()?;
         let signatures = vec![config.sign_key_binding(sec_key, key_pw, &key)?];
 
@@ -115,6 +282,12 @@ impl SecretSubkey {
     }
 }
 
+impl Serialize for SecretSubkey {
+    fn to_writer<W: io::Write>(&self, writer: &mut W) -> Result<()> {
+        write_packet(writer, &self.key)
+    }
+}
+
 impl KeyTrait for SecretSubkey {
     fn fingerprint(&self) -> Vec<u8> {
         self.key.fingerprint()
@@ -127,4 +300,8 @@ impl KeyTrait for SecretSubkey {
     fn algorithm(&self) -> PublicKeyAlgorithm {
         self.key.algorithm()
     }
+
+    fn version(&self) -> KeyVersion {
+        self.key.version()
+    }
 }
@@ -1,38 +1,45 @@
-use chrono::SubsecRound;
+use std::io;
+
+use chrono::{Duration, SubsecRound};
 use smallvec::SmallVec;
 
 use crate::composed::SignedKeyDetails;
+use crate::crypto::aead::AeadAlgorithm;
 use crate::crypto::hash::HashAlgorithm;
 use crate::crypto::sym::SymmetricKeyAlgorithm;
 use crate::errors::Result;
 use crate::packet::{
-    KeyFlags, PacketTrait, SignatureConfigBuilder, SignatureType, Subpacket, SubpacketData,
-    UserAttribute, UserId,
+    write_packet, KeyFlags, PacketTrait, SignatureConfigBuilder, SignatureType, SignatureVersion,
+    Subpacket, SubpacketData, UserAttribute, UserId,
 };
-use crate::types::{CompressionAlgorithm, RevocationKey, SecretKeyTrait};
+use crate::ser::Serialize;
+use crate::types::{CompressionAlgorithm, KeyTrait, KeyVersion, RevocationKey, SecretKeyTrait};
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct KeyDetails {
-    primary_user_id: UserId,
+    primary_user_id: Option<UserId>,
     user_ids: Vec<UserId>,
     user_attributes: Vec<UserAttribute>,
     keyflags: KeyFlags,
     preferred_symmetric_algorithms: SmallVec<[SymmetricKeyAlgorithm; 8]>,
     preferred_hash_algorithms: SmallVec<[HashAlgorithm; 8]>,
     preferred_compression_algorithms: SmallVec<[CompressionAlgorithm; 8]>,
+    preferred_aead_algorithms: SmallVec<[(SymmetricKeyAlgorithm, AeadAlgorithm); 4]>,
+    keyserver_no_modify: bool,
     revocation_key: Option<RevocationKey>,
 }
 
 impl KeyDetails {
     #[allow(clippy::too_many_arguments)] // FIXME
     pub fn new(
-        primary_user_id: UserId,
+        primary_user_id: Option<UserId>,
         user_ids: Vec<UserId>,
         user_attributes: Vec<UserAttribute>,
         keyflags: KeyFlags,
         preferred_symmetric_algorithms: SmallVec<[SymmetricKeyAlgorithm; 8]>,
         preferred_hash_algorithms: SmallVec<[HashAlgorithm; 8]>,
         preferred_compression_algorithms: SmallVec<[CompressionAlgorithm; 8]>,
+        preferred_aead_algorithms: SmallVec<[(SymmetricKeyAlgorithm, AeadAlgorithm); 4]>,
         revocation_key: Option<RevocationKey>,
     ) -> Self {
         KeyDetails {
@@ -43,11 +50,35 @@ impl KeyDetails {
             preferred_symmetric_algorithms,
             preferred_hash_algorithms,
             preferred_compression_algorithms,
+            preferred_aead_algorithms,
+            keyserver_no_modify: false,
             revocation_key,
         }
     }
 
+    /// Sets the "no-modify" key server preference in the self-signatures.
+    pub(crate) fn with_keyserver_no_modify(mut self, keyserver_no_modify: bool) -> Self {
+        self.keyserver_no_modify = keyserver_no_modify;
+        self
+    }
+
     pub fn sign<F>(self, key: &impl SecretKeyTrait, key_pw: F) -> Result<SignedKeyDetails>
+    where
+        F: (FnOnce() -> String) + Clone,
+    {
+        self.sign_with_key_expiration(key, key_pw, None, chrono::Utc::now().trunc_subsecs(0))
+    }
+
+    /// Like [`Self::sign`], additionally adding `key_expiration`, see
+    /// [`key_expiration_subpacket`], to the self-signatures of the User IDs, which are created
+    /// at `created_at`.
+    pub(crate) fn sign_with_key_expiration<F>(
+        self,
+        key: &impl SecretKeyTrait,
+        key_pw: F,
+        key_expiration: Option<Subpacket>,
+        created_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<SignedKeyDetails>
     where
         F: (FnOnce() -> String) + Clone,
     {
@@ -55,50 +86,60 @@ impl KeyDetails {
         let preferred_symmetric_algorithms = self.preferred_symmetric_algorithms;
         let preferred_hash_algorithms = self.preferred_hash_algorithms;
         let preferred_compression_algorithms = self.preferred_compression_algorithms;
+        let preferred_aead_algorithms = self.preferred_aead_algorithms;
+        let keyserver_no_modify = self.keyserver_no_modify;
         let revocation_key = self.revocation_key;
 
+        let hash_alg = preferred_signing_hash(key.hash_alg(), &preferred_hash_algorithms);
         let mut users = vec![];
+        let mut direct_signatures = vec![];
 
-        // primary user id
+        // primary user id, or a direct key signature if there are no user ids
         {
-            let id = self.primary_user_id;
             let mut hashed_subpackets = vec![
-                Subpacket::regular(SubpacketData::IsPrimary(true)),
-                Subpacket::regular(SubpacketData::SignatureCreationTime(
-                    chrono::Utc::now().trunc_subsecs(0),
-                )),
+                Subpacket::regular(SubpacketData::SignatureCreationTime(created_at)),
                 Subpacket::regular(SubpacketData::KeyFlags(keyflags.clone())),
-                Subpacket::regular(SubpacketData::PreferredSymmetricAlgorithms(
-                    preferred_symmetric_algorithms.clone(),
-                )),
-                Subpacket::regular(SubpacketData::PreferredHashAlgorithms(
-                    preferred_hash_algorithms.clone(),
-                )),
-                Subpacket::regular(SubpacketData::PreferredCompressionAlgorithms(
-                    preferred_compression_algorithms.clone(),
-                )),
-                Subpacket::regular(SubpacketData::IssuerFingerprint(
-                    Default::default(),
-                    SmallVec::from_slice(&key.fingerprint()),
-                )),
             ];
+            hashed_subpackets.extend(key_expiration.clone());
+            hashed_subpackets.extend(preference_subpackets(
+                &preferred_symmetric_algorithms,
+                &preferred_hash_algorithms,
+                &preferred_compression_algorithms,
+            ));
+            hashed_subpackets.push(issuer_fingerprint_subpacket(key));
+            hashed_subpackets.extend(aead_subpackets(&preferred_aead_algorithms));
+            hashed_subpackets.extend(keyserver_subpackets(keyserver_no_modify));
             if let Some(rkey) = revocation_key {
                 hashed_subpackets.push(Subpacket::regular(SubpacketData::RevocationKey(rkey)));
             }
 
+            let typ = match self.primary_user_id {
+                Some(_) => {
+                    hashed_subpackets.insert(0, Subpacket::regular(SubpacketData::IsPrimary(true)));
+                    SignatureType::CertGeneric
+                }
+                None => SignatureType::Key,
+            };
+
             let config = SignatureConfigBuilder::default()
-                .typ(SignatureType::CertGeneric)
+                .version(signature_version(key))
+                .typ(typ)
                 .pub_alg(key.algorithm())
-                .hash_alg(key.hash_alg())
+                .hash_alg(hash_alg)
                 .hashed_subpackets(hashed_subpackets)
-                .unhashed_subpackets(vec![Subpacket::regular(SubpacketData::Issuer(
-                    key.key_id(),
-                ))])
+                .unhashed_subpackets(issuer_subpackets(key))
                 .build()?;
 
-            let sig = config.sign_certification(key, key_pw.clone(), id.tag(), &id)?;
-
-            users.push(id.into_signed(sig));
+            match self.primary_user_id {
+                Some(id) => {
+                    let sig = config.sign_certification(key, key_pw.clone(), id.tag(), &id)?;
+                    users.push(id.into_signed(sig));
+                }
+                None => {
+                    let sig = config.sign_key(key, key_pw.clone(), key)?;
+                    direct_signatures.push(sig);
+                }
+            }
         }
 
         // other user ids
@@ -107,32 +148,27 @@ impl KeyDetails {
             self.user_ids
                 .into_iter()
                 .map(|id| {
+                    let mut hashed_subpackets = vec![
+                        Subpacket::regular(SubpacketData::SignatureCreationTime(created_at)),
+                        Subpacket::regular(SubpacketData::KeyFlags(keyflags.clone())),
+                    ];
+                    hashed_subpackets.extend(key_expiration.clone());
+                    hashed_subpackets.extend(preference_subpackets(
+                        &preferred_symmetric_algorithms,
+                        &preferred_hash_algorithms,
+                        &preferred_compression_algorithms,
+                    ));
+                    hashed_subpackets.push(issuer_fingerprint_subpacket(key));
+                    hashed_subpackets.extend(aead_subpackets(&preferred_aead_algorithms));
+                    hashed_subpackets.extend(keyserver_subpackets(keyserver_no_modify));
+
                     let config = SignatureConfigBuilder::default()
+                        .version(signature_version(key))
                         .typ(SignatureType::CertGeneric)
                         .pub_alg(key.algorithm())
-                        .hash_alg(key.hash_alg())
-                        .hashed_subpackets(vec![
-                            Subpacket::regular(SubpacketData::SignatureCreationTime(
-                                chrono::Utc::now().trunc_subsecs(0),
-                            )),
-                            Subpacket::regular(SubpacketData::KeyFlags(keyflags.clone())),
-                            Subpacket::regular(SubpacketData::PreferredSymmetricAlgorithms(
-                                preferred_symmetric_algorithms.clone(),
-                            )),
-                            Subpacket::regular(SubpacketData::PreferredHashAlgorithms(
-                                preferred_hash_algorithms.clone(),
-                            )),
-                            Subpacket::regular(SubpacketData::PreferredCompressionAlgorithms(
-                                preferred_compression_algorithms.clone(),
-                            )),
-                            Subpacket::regular(SubpacketData::IssuerFingerprint(
-                                Default::default(),
-                                SmallVec::from_slice(&key.fingerprint()),
-                            )),
-                        ])
-                        .unhashed_subpackets(vec![Subpacket::regular(SubpacketData::Issuer(
-                            key.key_id(),
-                        ))])
+                        .hash_alg(hash_alg)
+                        .hashed_subpackets(hashed_subpackets)
+                        .unhashed_subpackets(issuer_subpackets(key))
                         .build()?;
 
                     let sig = config.sign_certification(key, key_pw.clone(), id.tag(), &id)?;
@@ -145,14 +181,152 @@ impl KeyDetails {
         let user_attributes = self
             .user_attributes
             .into_iter()
-            .map(|u| u.sign(key, key_pw.clone()))
+            .map(|attr| {
+                // like the User Attributes added later, see `SignedSecretKey::add_user_attribute`
+                let config = SignatureConfigBuilder::default()
+                    .version(signature_version(key))
+                    .typ(SignatureType::CertPositive)
+                    .pub_alg(key.algorithm())
+                    .hash_alg(hash_alg)
+                    .hashed_subpackets(vec![
+                        Subpacket::regular(SubpacketData::SignatureCreationTime(created_at)),
+                        issuer_fingerprint_subpacket(key),
+                    ])
+                    .unhashed_subpackets(issuer_subpackets(key))
+                    .build()?;
+
+                let sig = config.sign_certification(key, key_pw.clone(), attr.tag(), &attr)?;
+
+                Ok(attr.into_signed(sig))
+            })
             .collect::<Result<Vec<_>>>()?;
 
         Ok(SignedKeyDetails {
             revocation_signatures: Default::default(),
-            direct_signatures: Default::default(),
+            direct_signatures,
             users,
             user_attributes,
         })
     }
 }
+
+/// Writes the User ID and User Attribute packets, without any certifications.
+impl Serialize for KeyDetails {
+    fn to_writer<W: io::Write>(&self, writer: &mut W) -> Result<()> {
+        for id in self.primary_user_id.iter().chain(&self.user_ids) {
+            write_packet(writer, id)?;
+        }
+        for attr in &self.user_attributes {
+            write_packet(writer, attr)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The key expiration time subpacket for the `expiration` of a key packet, which newly
+/// generated version 4 and 6 keys store in seconds. Older key versions carry their expiration
+/// in the key packet itself.
+pub(crate) fn key_expiration_subpacket(
+    version: KeyVersion,
+    expiration: Option<u32>,
+) -> Option<Subpacket> {
+    match version {
+        KeyVersion::V4 | KeyVersion::V6 => expiration.map(|expiration| {
+            Subpacket::regular(SubpacketData::KeyExpirationTime(Duration::seconds(
+                i64::from(expiration),
+            )))
+        }),
+        _ => None,
+    }
+}
+
+/// The version of the signatures made by `key`, version 6 keys make version 6 signatures.
+pub(crate) fn signature_version(key: &impl KeyTrait) -> SignatureVersion {
+    match key.version() {
+        KeyVersion::V6 => SignatureVersion::V6,
+        _ => SignatureVersion::V4,
+    }
+}
+
+/// The issuer fingerprint subpacket for signatures made by `key`.
+pub(crate) fn issuer_fingerprint_subpacket(key: &impl KeyTrait) -> Subpacket {
+    Subpacket::regular(SubpacketData::IssuerFingerprint(
+        key.version(),
+        SmallVec::from_slice(&key.fingerprint()),
+    ))
+}
+
+/// The issuer key ID subpackets for signatures made by `key`. Version 6 keys are only
+/// named by their fingerprint, so they get none.
+pub(crate) fn issuer_subpackets(key: &impl KeyTrait) -> Vec<Subpacket> {
+    match key.version() {
+        KeyVersion::V6 => Vec::new(),
+        _ => vec![Subpacket::regular(SubpacketData::Issuer(key.key_id()))],
+    }
+}
+
+/// Picks the first of the `preferred` hash algorithms that is at least as strong as `minimum`,
+/// the hash the signing key needs by default, falling back to `minimum` itself.
+pub(crate) fn preferred_signing_hash(
+    minimum: HashAlgorithm,
+    preferred: &[HashAlgorithm],
+) -> HashAlgorithm {
+    preferred
+        .iter()
+        .copied()
+        .find(|hash| hash.digest_size() >= minimum.digest_size())
+        .unwrap_or(minimum)
+}
+
+/// Algorithm preference subpackets, skipping empty lists, which some parsers mishandle.
+fn preference_subpackets(
+    preferred_symmetric_algorithms: &[SymmetricKeyAlgorithm],
+    preferred_hash_algorithms: &[HashAlgorithm],
+    preferred_compression_algorithms: &[CompressionAlgorithm],
+) -> Vec<Subpacket> {
+    let mut subpackets = Vec::new();
+    if !preferred_symmetric_algorithms.is_empty() {
+        subpackets.push(Subpacket::regular(
+            SubpacketData::PreferredSymmetricAlgorithms(SmallVec::from_slice(
+                preferred_symmetric_algorithms,
+            )),
+        ));
+    }
+    if !preferred_hash_algorithms.is_empty() {
+        subpackets.push(Subpacket::regular(SubpacketData::PreferredHashAlgorithms(
+            SmallVec::from_slice(preferred_hash_algorithms),
+        )));
+    }
+    if !preferred_compression_algorithms.is_empty() {
+        subpackets.push(Subpacket::regular(
+            SubpacketData::PreferredCompressionAlgorithms(SmallVec::from_slice(
+                preferred_compression_algorithms,
+            )),
+        ));
+    }
+
+    subpackets
+}
+
+/// Subpackets advertising AEAD support, only emitted when AEAD preferences are set.
+fn aead_subpackets(
+    preferred_aead_algorithms: &[(SymmetricKeyAlgorithm, AeadAlgorithm)],
+) -> Vec<Subpacket> {
+    if preferred_aead_algorithms.is_empty() {
+        return Vec::new();
+    }
+
+    vec![
+        Subpacket::regular(SubpacketData::PreferredAeadCiphersuites(
+            SmallVec::from_slice(preferred_aead_algorithms),
+        )),
+        // SEIPD v1 and SEIPD v2
+        Subpacket::regular(SubpacketData::Features(smallvec![0x01 | 0x08])),
+    ]
+}
+
+/// The key server preferences subpacket, only emitted when the "no-modify" flag is set.
+fn keyserver_subpackets(no_modify: bool) -> Option<Subpacket> {
+    no_modify.then(|| Subpacket::regular(SubpacketData::KeyServerPreferences(smallvec![0x80])))
+}
@@ -1,7 +1,7 @@
 use std::iter::Peekable;
 
 use crate::armor::BlockType;
-use crate::composed::message::Message;
+use crate::composed::message::{Message, DEFAULT_MAX_NESTING_DEPTH};
 use crate::composed::Deserializable;
 use crate::errors::{Error, Result};
 use crate::packet::Packet;
@@ -10,9 +10,21 @@ use crate::Edata;
 
 pub struct MessageParser<I: Sized + Iterator<Item = Result<Packet>>> {
     source: Peekable<I>,
+    max_depth: usize,
 }
 
-fn next<I: Iterator<Item = Result<Packet>>>(packets: &mut Peekable<I>) -> Option<Result<Message>> {
+impl<I: Sized + Iterator<Item = Result<Packet>>> MessageParser<I> {
+    /// Creates a parser that rejects messages with more than `max_depth` nested signed layers.
+    pub(crate) fn with_max_depth(source: Peekable<I>, max_depth: usize) -> Self {
+        MessageParser { source, max_depth }
+    }
+}
+
+/// Parses the next message, `depth_left` is the number of layers it may still nest.
+fn next<I: Iterator<Item = Result<Packet>>>(
+    packets: &mut Peekable<I>,
+    depth_left: usize,
+) -> Option<Result<Message>> {
     while let Some(res) = packets.by_ref().next() {
         let packet = match res {
             Ok(packet) => packet,
@@ -84,7 +96,10 @@ fn next<I: Iterator<Item = Result<Packet>>>(packets: &mut Peekable<I>) -> Option
             Tag::Signature => {
                 return match packet.try_into() {
                     Ok(signature) => {
-                        let message = match next(packets.by_ref()) {
+                        let Some(depth_left) = depth_left.checked_sub(1) else {
+                            return Some(Err(nesting_error()));
+                        };
+                        let message = match next(packets.by_ref(), depth_left) {
                             Some(Ok(m)) => Some(Box::new(m)),
                             Some(Err(err)) => return Some(Err(err)),
                             None => None,
@@ -104,7 +119,10 @@ fn next<I: Iterator<Item = Result<Packet>>>(packets: &mut Peekable<I>) -> Option
                     Ok(p) => {
                         let one_pass_signature = Some(p);
 
-                        let message = match next(packets.by_ref()) {
+                        let Some(depth_left) = depth_left.checked_sub(1) else {
+                            return Some(Err(nesting_error()));
+                        };
+                        let message = match next(packets.by_ref(), depth_left) {
                             Some(Ok(m)) => Some(Box::new(m)),
                             Some(Err(err)) => return Some(Err(err)),
                             None => None,
@@ -149,11 +167,15 @@ fn next<I: Iterator<Item = Result<Packet>>>(packets: &mut Peekable<I>) -> Option
     None
 }
 
+fn nesting_error() -> Error {
+    format_err!("message nesting exceeds the maximum depth")
+}
+
 impl<I: Sized + Iterator<Item = Result<Packet>>> Iterator for MessageParser<I> {
     type Item = Result<Message>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        next(self.source.by_ref())
+        next(self.source.by_ref(), self.max_depth)
     }
 }
 
@@ -163,9 +185,10 @@ impl Deserializable for Message {
     fn from_packets<'a, I: Iterator<Item = Result<Packet>> + 'a>(
         packets: std::iter::Peekable<I>,
     ) -> Box<dyn Iterator<Item = Result<Self>> + 'a> {
-        Box::new(MessageParser {
-            source: packets.peekable(),
-        })
+        Box::new(MessageParser::with_max_depth(
+            packets.peekable(),
+            DEFAULT_MAX_NESTING_DEPTH,
+        ))
     }
 
     fn matches_block_type(typ: BlockType) -> bool {
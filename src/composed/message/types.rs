@@ -8,24 +8,81 @@ use rand::{CryptoRng, Rng};
 use smallvec::SmallVec;
 
 use crate::armor;
+use crate::composed::key::{issuer_fingerprint_subpacket, issuer_subpackets, signature_version};
 use crate::composed::message::decrypt::*;
+use crate::composed::message::parser::MessageParser;
+use crate::composed::shared::filter_parsed_packet_results;
 use crate::composed::shared::Deserializable;
-use crate::composed::signed_key::SignedSecretKey;
-use crate::composed::StandaloneSignature;
+use crate::composed::signed_key::{
+    describe_key_id, negotiate_aead_ciphersuite, SignedPublicKey, SignedSecretKey,
+    SignedSecretSubKey,
+};
+use crate::composed::{StandaloneSignature, UnauthenticatedKey};
+use crate::crypto::aead::AeadAlgorithm;
 use crate::crypto::hash::HashAlgorithm;
 use crate::crypto::sym::SymmetricKeyAlgorithm;
 use crate::errors::{Error, Result};
 use crate::packet::{
-    write_packet, CompressedData, LiteralData, OnePassSignature, Packet,
-    PublicKeyEncryptedSessionKey, Signature, SignatureConfig, SignatureType, Subpacket,
-    SubpacketData, SymEncryptedData, SymEncryptedProtectedData, SymKeyEncryptedSessionKey,
+    self, write_packet, CompressedData, LiteralData, OnePassSignature, Packet, PacketParser,
+    Padding, PublicKeyEncryptedSessionKey, Signature, SignatureConfig, SignatureType,
+    SignatureVersion, Subpacket, SubpacketData, SymEncryptedData, SymEncryptedProtectedData,
+    SymKeyEncryptedSessionKey,
 };
 use crate::ser::Serialize;
 use crate::types::{
     CompressionAlgorithm, KeyId, KeyTrait, KeyVersion, PublicKeyTrait, SecretKeyTrait, StringToKey,
-    Tag,
+    Tag, Version,
 };
 
+/// The default maximum number of signed layers a parsed [`Message`] may nest, see
+/// [`Message::from_bytes_with_max_depth`].
+pub const DEFAULT_MAX_NESTING_DEPTH: usize = 16;
+
+/// The encoded AEAD chunk size [`Message::encrypt_to_recipients`] uses for version 2 SEIPD
+/// packets, 256 KiB.
+pub const DEFAULT_AEAD_CHUNK_SIZE: u8 = 12;
+
+/// Which version of SEIPD packet [`Message::encrypt_to_recipients`] uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeipdVersionPolicy {
+    /// Version 2 if all recipients announce support for it, version 1 otherwise.
+    Negotiate,
+    /// Always version 2, recipients without support for it are skipped.
+    ForceV2,
+}
+
+/// The SEIPD packet version [`Message::encrypt_to_recipients`] used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeipdVersion {
+    /// Version 1.
+    V1,
+    /// Version 2, with the given AEAD ciphersuite.
+    V2(SymmetricKeyAlgorithm, AeadAlgorithm),
+}
+
+/// How [`Message::compress_with_policy`] compresses a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionPolicy {
+    /// Always compress with the given algorithm.
+    Always(CompressionAlgorithm),
+    /// Compress with the given algorithm, unless the literal data starts like an already
+    /// compressed format (gzip, zip, JPEG or PNG).
+    Auto(CompressionAlgorithm),
+}
+
+/// How the intended recipients of a signed message relate to the key that decrypted it,
+/// see [`Message::check_intended_recipient`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntendedRecipientStatus {
+    /// The key is listed as an intended recipient.
+    Listed,
+    /// The signature doesn't list any intended recipients.
+    Unspecified,
+    /// The signature lists only other recipients. The message was possibly decrypted and
+    /// re-encrypted by one of them, to make it look like it was sent by the signer directly.
+    NotListed,
+}
+
 /// An [OpenPGP message](https://tools.ietf.org/html/rfc4880.html#section-11.3)
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Message {
@@ -167,7 +224,24 @@ impl Edata {
         }
     }
 
+    /// Decrypts the data with the given session key.
+    ///
+    /// Legacy Symmetrically Encrypted Data packets (tag 9) are rejected, as they have no
+    /// integrity protection, see [`Edata::decrypt_legacy_sed`].
     pub fn decrypt(&self, key: PlainSessionKey) -> Result<Message> {
+        self.decrypt_inner(key, false)
+    }
+
+    /// Decrypts the data like [`Edata::decrypt`], but also accepts legacy Symmetrically
+    /// Encrypted Data packets (tag 9).
+    ///
+    /// **This is insecure.** Their contents may have been modified without this being
+    /// detected, see [`Message::encrypt_to_keys_legacy_sed`].
+    pub fn decrypt_legacy_sed(&self, key: PlainSessionKey) -> Result<Message> {
+        self.decrypt_inner(key, true)
+    }
+
+    fn decrypt_inner(&self, key: PlainSessionKey, allow_legacy_sed: bool) -> Result<Message> {
         let protected = self.tag() == Tag::SymEncryptedProtectedData;
         debug!("decrypting protected = {:?}", protected);
 
@@ -189,6 +263,10 @@ impl Edata {
                         Message::from_bytes(&data[..])
                     }
                     Self::SymEncryptedData(p) => {
+                        ensure!(
+                            allow_legacy_sed,
+                            "refusing to decrypt a Symmetrically Encrypted Data packet without integrity protection"
+                        );
                         ensure_eq!(
                             self.version(),
                             None,
@@ -253,14 +331,17 @@ impl Serialize for Message {
                 signature,
                 ..
             } => {
-                if let Some(ops) = one_pass_signature {
-                    write_packet(writer, ops)?;
+                // without a one-pass signature, the signature precedes the message
+                match one_pass_signature {
+                    Some(ops) => write_packet(writer, ops)?,
+                    None => write_packet(writer, signature)?,
                 }
                 if let Some(message) = message {
                     (**message).to_writer(writer)?;
                 }
-
-                write_packet(writer, signature)?;
+                if one_pass_signature.is_some() {
+                    write_packet(writer, signature)?;
+                }
 
                 Ok(())
             }
@@ -277,6 +358,16 @@ impl Serialize for Message {
 }
 
 impl Message {
+    /// Parses a single byte encoded message, rejecting it if it nests more than `max_depth`
+    /// signed layers.
+    ///
+    /// [`Message::from_bytes`] uses [`DEFAULT_MAX_NESTING_DEPTH`].
+    pub fn from_bytes_with_max_depth(bytes: impl io::Read, max_depth: usize) -> Result<Self> {
+        let packets = PacketParser::new(bytes).filter_map(filter_parsed_packet_results);
+        let mut messages = MessageParser::with_max_depth(packets.peekable(), max_depth);
+        messages.next().ok_or(Error::NoMatchingPacket)?
+    }
+
     pub fn new_literal(file_name: impl AsRef<BStr>, data: &str) -> Self {
         Message::Literal(LiteralData::from_str(file_name.as_ref(), data))
     }
@@ -314,6 +405,29 @@ impl Message {
         )))
     }
 
+    /// Compresses the message according to `policy`, returning the message and whether it
+    /// was compressed.
+    ///
+    /// Compressing already compressed data wastes work, and the resulting length can tell
+    /// something about the content of media files.
+    pub fn compress_with_policy(&self, policy: CompressionPolicy) -> Result<(Self, bool)> {
+        let alg = match policy {
+            CompressionPolicy::Always(alg) => alg,
+            CompressionPolicy::Auto(alg) => {
+                let incompressible = self
+                    .get_literal()
+                    .is_some_and(|literal| is_compressed_format(literal.data()));
+                if incompressible {
+                    debug!("not compressing already compressed data");
+                    return Ok((self.clone(), false));
+                }
+                alg
+            }
+        };
+
+        Ok((self.compress(alg)?, true))
+    }
+
     /// Decompresses the data if compressed.
     pub fn decompress(self) -> Result<Self> {
         match self {
@@ -323,12 +437,79 @@ impl Message {
     }
 
     /// Encrypt the message to the list of passed in public keys.
+    ///
+    /// If all keys are version 6 keys, the session key is encrypted into version 6 PKESK
+    /// packets, and the message into a version 2 SEIPD packet using OCB and
+    /// [`DEFAULT_AEAD_CHUNK_SIZE`]. Otherwise version 3 PKESK and version 1 SEIPD packets are
+    /// used. Mixing version 6 keys with other keys is an error, as they can't share a SEIPD
+    /// packet version; use [`Self::encrypt_to_keys_seipdv2`] for them instead.
     pub fn encrypt_to_keys<R: CryptoRng + Rng>(
         &self,
         rng: &mut R,
         alg: SymmetricKeyAlgorithm,
         pkeys: &[&impl PublicKeyTrait],
     ) -> Result<Self> {
+        self.encrypt_to_keys_inner(rng, alg, pkeys, None, false)
+    }
+
+    /// Encrypt the message to the list of passed in public keys, appending a padding packet
+    /// inside the encrypted data, so the length of the result doesn't reveal the exact length
+    /// of the message.
+    pub fn encrypt_to_keys_with_padding<R: CryptoRng + Rng>(
+        &self,
+        rng: &mut R,
+        alg: SymmetricKeyAlgorithm,
+        pkeys: &[&impl PublicKeyTrait],
+        padding: PaddingPolicy,
+    ) -> Result<Self> {
+        self.encrypt_to_keys_inner(rng, alg, pkeys, Some(padding), false)
+    }
+
+    /// Encrypt the message to the list of passed in public keys, into a legacy Symmetrically
+    /// Encrypted Data packet (tag 9) instead of a SEIPD packet.
+    ///
+    /// **This is insecure.** The legacy packet has no integrity protection, so an attacker can
+    /// modify the encrypted message without the recipient noticing, and exploit decryption
+    /// errors to recover the plaintext. Only use it for recipients that can't decrypt anything
+    /// else, all other encryption functions always use SEIPD packets.
+    pub fn encrypt_to_keys_legacy_sed<R: CryptoRng + Rng>(
+        &self,
+        rng: &mut R,
+        alg: SymmetricKeyAlgorithm,
+        pkeys: &[&impl PublicKeyTrait],
+    ) -> Result<Self> {
+        self.encrypt_to_keys_inner(rng, alg, pkeys, None, true)
+    }
+
+    fn encrypt_to_keys_inner<R: CryptoRng + Rng>(
+        &self,
+        rng: &mut R,
+        alg: SymmetricKeyAlgorithm,
+        pkeys: &[&impl PublicKeyTrait],
+        padding: Option<PaddingPolicy>,
+        legacy_sed: bool,
+    ) -> Result<Self> {
+        // Version 6 keys get version 6 PKESK packets, which must be followed by a version 2
+        // SEIPD packet, that other recipients might not be able to decrypt.
+        if pkeys.iter().any(|pkey| pkey.version() == KeyVersion::V6) {
+            ensure!(
+                pkeys.iter().all(|pkey| pkey.version() == KeyVersion::V6),
+                "version 6 keys can not be mixed with other key versions in a version 1 SEIPD packet"
+            );
+            ensure!(
+                !legacy_sed,
+                "version 6 keys can not receive legacy SED packets"
+            );
+            return self.encrypt_to_keys_seipdv2_inner(
+                rng,
+                alg,
+                AeadAlgorithm::Ocb,
+                DEFAULT_AEAD_CHUNK_SIZE,
+                pkeys,
+                padding,
+            );
+        }
+
         // 1. Generate a session key.
         let session_key = alg.new_session_key(rng);
 
@@ -343,7 +524,185 @@ impl Message {
             .collect::<Result<_>>()?;
 
         // 3. Encrypt (sym) the data using the session key.
-        self.encrypt_symmetric(rng, esk, alg, session_key)
+        if legacy_sed {
+            ensure!(padding.is_none(), "padding requires a SEIPD packet");
+            let edata = Edata::SymEncryptedData(SymEncryptedData::encrypt_with_rng(
+                rng,
+                alg,
+                &session_key,
+                &self.to_bytes()?,
+            )?);
+            return Ok(Message::Encrypted { esk, edata });
+        }
+        self.encrypt_symmetric(rng, esk, alg, session_key, padding)
+    }
+
+    /// Encrypt the message to the valid encryption subkeys of the `recipients`, picking the
+    /// SEIPD version according to `policy` and the Features the recipients announce.
+    ///
+    /// Returns the encrypted message and the SEIPD version that was used. Version 2 packets
+    /// use `aead` and [`DEFAULT_AEAD_CHUNK_SIZE`].
+    pub fn encrypt_to_recipients<R: CryptoRng + Rng>(
+        &self,
+        rng: &mut R,
+        sym_alg: SymmetricKeyAlgorithm,
+        aead: AeadAlgorithm,
+        recipients: &[&SignedPublicKey],
+        policy: SeipdVersionPolicy,
+    ) -> Result<(Self, SeipdVersion)> {
+        self.encrypt_to_recipients_inner(rng, sym_alg, Some(aead), recipients, policy, false)
+    }
+
+    /// Like [`Self::encrypt_to_recipients`], but version 2 packets use the AEAD ciphersuite
+    /// negotiated from the preferences of the recipients, see [`negotiate_aead_ciphersuite`].
+    ///
+    /// `sym_alg` is only used for version 1 packets.
+    pub fn encrypt_to_recipients_negotiated<R: CryptoRng + Rng>(
+        &self,
+        rng: &mut R,
+        sym_alg: SymmetricKeyAlgorithm,
+        recipients: &[&SignedPublicKey],
+        policy: SeipdVersionPolicy,
+    ) -> Result<(Self, SeipdVersion)> {
+        self.encrypt_to_recipients_inner(rng, sym_alg, None, recipients, policy, false)
+    }
+
+    /// Like [`Self::encrypt_to_recipients`], but additionally encrypts to the valid additional
+    /// decryption subkeys (ADSK) of the `recipients`, see [`SignedPublicKey::adsk_subkeys`].
+    ///
+    /// Each recipient still needs a regular encryption subkey.
+    pub fn encrypt_to_recipients_with_adsk<R: CryptoRng + Rng>(
+        &self,
+        rng: &mut R,
+        sym_alg: SymmetricKeyAlgorithm,
+        aead: AeadAlgorithm,
+        recipients: &[&SignedPublicKey],
+        policy: SeipdVersionPolicy,
+    ) -> Result<(Self, SeipdVersion)> {
+        self.encrypt_to_recipients_inner(rng, sym_alg, Some(aead), recipients, policy, true)
+    }
+
+    fn encrypt_to_recipients_inner<R: CryptoRng + Rng>(
+        &self,
+        rng: &mut R,
+        sym_alg: SymmetricKeyAlgorithm,
+        aead: Option<AeadAlgorithm>,
+        recipients: &[&SignedPublicKey],
+        policy: SeipdVersionPolicy,
+        include_adsk: bool,
+    ) -> Result<(Self, SeipdVersion)> {
+        let now = chrono::Utc::now();
+        let use_v2 = match policy {
+            SeipdVersionPolicy::Negotiate => recipients.iter().all(|r| r.supports_seipd_v2(&now)),
+            SeipdVersionPolicy::ForceV2 => true,
+        };
+
+        let mut subkeys = Vec::new();
+        let mut used_recipients = Vec::new();
+        for recipient in recipients {
+            if use_v2 && !recipient.supports_seipd_v2(&now) {
+                warn!(
+                    "skipping recipient {} without support for SEIPD v2",
+                    describe_key_id(recipients, &recipient.key_id())
+                );
+                continue;
+            }
+
+            let encryption_subkeys = recipient.encryption_subkeys(now);
+            if encryption_subkeys.is_empty() {
+                bail!(
+                    "no valid encryption subkey for {}",
+                    describe_key_id(recipients, &recipient.key_id())
+                );
+            }
+            subkeys.extend(encryption_subkeys);
+            used_recipients.push(*recipient);
+            if include_adsk {
+                for adsk in recipient.adsk_subkeys(now) {
+                    if !subkeys
+                        .iter()
+                        .any(|s| s.fingerprint() == adsk.fingerprint())
+                    {
+                        subkeys.push(adsk);
+                    }
+                }
+            }
+        }
+        ensure!(!subkeys.is_empty(), "no recipients to encrypt to");
+
+        if use_v2 {
+            let (sym_alg, aead) = match aead {
+                Some(aead) => (sym_alg, aead),
+                None => negotiate_aead_ciphersuite(&used_recipients, &now)?,
+            };
+            let msg = self.encrypt_to_keys_seipdv2(
+                rng,
+                sym_alg,
+                aead,
+                DEFAULT_AEAD_CHUNK_SIZE,
+                &subkeys,
+            )?;
+            Ok((msg, SeipdVersion::V2(sym_alg, aead)))
+        } else {
+            Ok((
+                self.encrypt_to_keys(rng, sym_alg, &subkeys)?,
+                SeipdVersion::V1,
+            ))
+        }
+    }
+
+    /// Encrypt the message to the list of passed in public keys, into a version 2 SEIPD packet
+    /// using the given AEAD algorithm.
+    ///
+    /// The session key is encrypted into version 6 PKESK packets, which name the recipients by
+    /// fingerprint. `chunk_size` is the encoded chunk size, the actual size is
+    /// `2^(chunk_size + 6)`.
+    pub fn encrypt_to_keys_seipdv2<R: CryptoRng + Rng>(
+        &self,
+        rng: &mut R,
+        sym_alg: SymmetricKeyAlgorithm,
+        aead: AeadAlgorithm,
+        chunk_size: u8,
+        pkeys: &[&impl PublicKeyTrait],
+    ) -> Result<Self> {
+        self.encrypt_to_keys_seipdv2_inner(rng, sym_alg, aead, chunk_size, pkeys, None)
+    }
+
+    fn encrypt_to_keys_seipdv2_inner<R: CryptoRng + Rng>(
+        &self,
+        rng: &mut R,
+        sym_alg: SymmetricKeyAlgorithm,
+        aead: AeadAlgorithm,
+        chunk_size: u8,
+        pkeys: &[&impl PublicKeyTrait],
+        padding: Option<PaddingPolicy>,
+    ) -> Result<Self> {
+        let session_key = sym_alg.new_session_key(rng);
+
+        let esk = pkeys
+            .iter()
+            .map(|pkey| {
+                let pkes =
+                    PublicKeyEncryptedSessionKey::from_session_key_v6(rng, &session_key, pkey)?;
+                Ok(Esk::PublicKeyEncryptedSessionKey(pkes))
+            })
+            .collect::<Result<_>>()?;
+
+        let mut data = self.to_bytes()?;
+        if let Some(padding) = padding {
+            padding_packet(rng, data.len(), padding)?.to_writer(&mut data)?;
+        }
+        let edata =
+            Edata::SymEncryptedProtectedData(SymEncryptedProtectedData::encrypt_seipdv2_with_rng(
+                rng,
+                sym_alg,
+                aead,
+                chunk_size,
+                &session_key,
+                &data,
+            )?);
+
+        Ok(Message::Encrypted { esk, edata })
     }
 
     /// Encrypt the message using the given password.
@@ -354,6 +713,38 @@ impl Message {
         alg: SymmetricKeyAlgorithm,
         msg_pw: F,
     ) -> Result<Self>
+    where
+        R: Rng + CryptoRng,
+        F: FnOnce() -> String + Clone,
+    {
+        self.encrypt_with_password_inner(rng, s2k, alg, msg_pw, None)
+    }
+
+    /// Encrypt the message using the given password, padding it like
+    /// [`Message::encrypt_to_keys_with_padding`].
+    pub fn encrypt_with_password_with_padding<R, F>(
+        &self,
+        rng: &mut R,
+        s2k: StringToKey,
+        alg: SymmetricKeyAlgorithm,
+        msg_pw: F,
+        padding: PaddingPolicy,
+    ) -> Result<Self>
+    where
+        R: Rng + CryptoRng,
+        F: FnOnce() -> String + Clone,
+    {
+        self.encrypt_with_password_inner(rng, s2k, alg, msg_pw, Some(padding))
+    }
+
+    fn encrypt_with_password_inner<R, F>(
+        &self,
+        rng: &mut R,
+        s2k: StringToKey,
+        alg: SymmetricKeyAlgorithm,
+        msg_pw: F,
+        padding: Option<PaddingPolicy>,
+    ) -> Result<Self>
     where
         R: Rng + CryptoRng,
         F: FnOnce() -> String + Clone,
@@ -370,7 +761,31 @@ impl Message {
         )?);
 
         // 3. Encrypt (sym) the data using the session key.
-        self.encrypt_symmetric(rng, vec![skesk], alg, session_key)
+        self.encrypt_symmetric(rng, vec![skesk], alg, session_key, padding)
+    }
+
+    /// Encrypt the message with the given `session_key`, for recipients that received it via
+    /// the given `esk` packets.
+    ///
+    /// This allows assembling the session key packets separately, e.g. with
+    /// [`PublicKeyEncryptedSessionKey::from_session_key`], in a custom order or mixed with
+    /// packets that were created elsewhere. The session key can be created with
+    /// [`SymmetricKeyAlgorithm::new_session_key`].
+    pub fn encrypt_with_session_key<R: CryptoRng + Rng>(
+        &self,
+        rng: &mut R,
+        esk: Vec<Esk>,
+        alg: SymmetricKeyAlgorithm,
+        session_key: Vec<u8>,
+    ) -> Result<Self> {
+        ensure_eq!(
+            session_key.len(),
+            alg.key_size(),
+            "invalid session key length for {:?}",
+            alg
+        );
+
+        self.encrypt_symmetric(rng, esk, alg, session_key, None)
     }
 
     /// Symmetrically encrypts oneself using the provided `session_key`.
@@ -380,8 +795,12 @@ impl Message {
         esk: Vec<Esk>,
         alg: SymmetricKeyAlgorithm,
         session_key: Vec<u8>,
+        padding: Option<PaddingPolicy>,
     ) -> Result<Self> {
-        let data = self.to_bytes()?;
+        let mut data = self.to_bytes()?;
+        if let Some(padding) = padding {
+            padding_packet(rng, data.len(), padding)?.to_writer(&mut data)?;
+        }
 
         let edata = Edata::SymEncryptedProtectedData(SymEncryptedProtectedData::encrypt_with_rng(
             rng,
@@ -403,18 +822,94 @@ impl Message {
     where
         F: FnOnce() -> String,
     {
-        let key_id = key.key_id();
-        let algorithm = key.algorithm();
-        let hashed_subpackets = vec![
-            Subpacket::regular(SubpacketData::IssuerFingerprint(
-                KeyVersion::V4,
-                SmallVec::from_slice(&key.fingerprint()),
-            )),
+        self.sign_with_subpackets(key, key_pw, hash_algorithm, Vec::new())
+    }
+
+    /// Sign this message using the provided key, listing the primary keys of the `recipients`
+    /// it is going to be encrypted to as intended recipients in the signature.
+    ///
+    /// This binds the signature to the recipients, so that a recipient can't re-encrypt the
+    /// signed message to someone else without it being detected, see
+    /// [`Message::verify_for_recipient`].
+    pub fn sign_for_recipients<F>(
+        self,
+        key: &impl SecretKeyTrait,
+        key_pw: F,
+        hash_algorithm: HashAlgorithm,
+        recipients: &[&impl KeyTrait],
+    ) -> Result<Self>
+    where
+        F: FnOnce() -> String,
+    {
+        let subpackets = recipients
+            .iter()
+            .map(|recipient| {
+                Subpacket::regular(SubpacketData::IntendedRecipientFingerprint(
+                    recipient.version(),
+                    SmallVec::from_slice(&recipient.fingerprint()),
+                ))
+            })
+            .collect();
+
+        self.sign_with_subpackets(key, key_pw, hash_algorithm, subpackets)
+    }
+
+    /// Sign this message using the provided key, hashing exactly the given subpackets in the
+    /// given order instead of the default ones.
+    ///
+    /// This is meant for reproducing signatures made by other implementations. The signature
+    /// creation time is appended if missing, unless `strict` is set, in which case its absence
+    /// is an error. Only the issuer key id is added, to the unhashed area.
+    pub fn sign_with_hashed_subpackets<F>(
+        self,
+        key: &impl SecretKeyTrait,
+        key_pw: F,
+        hash_algorithm: HashAlgorithm,
+        hashed_subpackets: Vec<Subpacket>,
+        strict: bool,
+    ) -> Result<Self>
+    where
+        F: FnOnce() -> String,
+    {
+        self.sign_internal(key, key_pw, hash_algorithm, hashed_subpackets, strict)
+    }
+
+    fn sign_with_subpackets<F>(
+        self,
+        key: &impl SecretKeyTrait,
+        key_pw: F,
+        hash_algorithm: HashAlgorithm,
+        extra_hashed_subpackets: Vec<Subpacket>,
+    ) -> Result<Self>
+    where
+        F: FnOnce() -> String,
+    {
+        let mut hashed_subpackets = vec![
+            issuer_fingerprint_subpacket(key),
             Subpacket::regular(SubpacketData::SignatureCreationTime(
                 chrono::Utc::now().trunc_subsecs(0),
             )),
         ];
-        let unhashed_subpackets = vec![Subpacket::regular(SubpacketData::Issuer(key_id.clone()))];
+        hashed_subpackets.extend(extra_hashed_subpackets);
+
+        self.sign_internal(key, key_pw, hash_algorithm, hashed_subpackets, true)
+    }
+
+    fn sign_internal<F>(
+        self,
+        key: &impl SecretKeyTrait,
+        key_pw: F,
+        hash_algorithm: HashAlgorithm,
+        hashed_subpackets: Vec<Subpacket>,
+        strict: bool,
+    ) -> Result<Self>
+    where
+        F: FnOnce() -> String,
+    {
+        let key_id = key.key_id();
+        let algorithm = key.algorithm();
+        let version = signature_version(key);
+        let unhashed_subpackets = issuer_subpackets(key);
 
         let (typ, signature) = match self {
             Message::Literal(ref l) => {
@@ -424,26 +919,28 @@ impl Message {
                     SignatureType::Text
                 };
 
-                let signature_config = SignatureConfig::new_v4(
-                    Default::default(),
+                let mut signature_config = SignatureConfig::new_v4_with_hashed_subpackets(
                     typ,
                     algorithm,
                     hash_algorithm,
                     hashed_subpackets,
                     unhashed_subpackets,
-                );
+                    strict,
+                )?;
+                signature_config.version = version;
                 (typ, signature_config.sign(key, key_pw, l.data())?)
             }
             _ => {
                 let typ = SignatureType::Binary;
-                let signature_config = SignatureConfig::new_v4(
-                    Default::default(),
+                let mut signature_config = SignatureConfig::new_v4_with_hashed_subpackets(
                     typ,
                     algorithm,
                     hash_algorithm,
                     hashed_subpackets,
                     unhashed_subpackets,
-                );
+                    strict,
+                )?;
+                signature_config.version = version;
 
                 let data = self.to_bytes()?;
                 let signature = signature_config.sign(key, key_pw, &data[..])?;
@@ -451,11 +948,21 @@ impl Message {
                 (typ, signature)
             }
         };
-        let ops = OnePassSignature::from_details(typ, hash_algorithm, algorithm, key_id);
+        // Version 3 one-pass signatures can only announce version 4 signatures, version 6
+        // signatures are placed in front of the message instead.
+        let one_pass_signature = match version {
+            SignatureVersion::V6 => None,
+            _ => Some(OnePassSignature::from_details(
+                typ,
+                hash_algorithm,
+                algorithm,
+                key_id,
+            )),
+        };
 
         Ok(Message::Signed {
             message: Some(Box::new(self)),
-            one_pass_signature: Some(ops),
+            one_pass_signature,
             signature,
         })
     }
@@ -477,6 +984,100 @@ impl Message {
         self.verify_internal(key, true)
     }
 
+    /// Verify this message like [`Message::verify`], and check that `recipient`, the primary key
+    /// the message was decrypted with, is one of the intended recipients listed in the signature.
+    ///
+    /// Signatures without intended recipients are accepted, as most implementations don't
+    /// emit them. If the signature lists only other recipients,
+    /// [`Error::IntendedRecipientMismatch`] is returned: the signature itself is valid, but the
+    /// message was likely re-encrypted by one of the original recipients.
+    pub fn verify_for_recipient(
+        &self,
+        key: &impl PublicKeyTrait,
+        recipient: &impl KeyTrait,
+    ) -> Result<()> {
+        self.verify(key)?;
+
+        match self.check_intended_recipient(recipient)? {
+            IntendedRecipientStatus::Listed | IntendedRecipientStatus::Unspecified => Ok(()),
+            IntendedRecipientStatus::NotListed => Err(Error::IntendedRecipientMismatch),
+        }
+    }
+
+    /// Verify this message with the signer's key embedded in the key block subpacket of its
+    /// signature, see [`UnauthenticatedKey`].
+    ///
+    /// Decompresses up to one layer of compressed data.
+    pub fn verify_with_key_block(&self) -> Result<UnauthenticatedKey> {
+        let signature = self.signature()?;
+        let key = UnauthenticatedKey::from_signature(&signature)?;
+        self.verify(&key.signer(&signature)?)?;
+
+        Ok(key)
+    }
+
+    /// Returns the signature of this signed message.
+    ///
+    /// Decompresses up to one layer of compressed data.
+    pub(crate) fn signature(&self) -> Result<Signature> {
+        match self {
+            Message::Signed { signature, .. } => Ok(signature.clone()),
+            Message::Compressed(data) => match Message::from_bytes(data.decompress()?)? {
+                Message::Signed { signature, .. } => Ok(signature),
+                _ => bail!("compressed message is not signed"),
+            },
+            _ => bail!("message is not signed"),
+        }
+    }
+
+    /// Returns the fingerprints of the intended recipients listed in the signature of this
+    /// message, see [`Signature::intended_recipients`].
+    ///
+    /// Decompresses up to one layer of compressed data.
+    pub fn intended_recipients(&self) -> Result<Vec<Vec<u8>>> {
+        let fingerprints = |signature: &Signature| {
+            signature
+                .intended_recipients()
+                .into_iter()
+                .map(|fp| fp.to_vec())
+                .collect()
+        };
+
+        match self {
+            Message::Signed { signature, .. } => Ok(fingerprints(signature)),
+            Message::Compressed(data) => match Message::from_bytes(data.decompress()?)? {
+                Message::Signed { signature, .. } => Ok(fingerprints(&signature)),
+                _ => bail!("compressed message is not signed"),
+            },
+            _ => bail!("message is not signed"),
+        }
+    }
+
+    /// Checks whether `recipient`, the primary key the message was decrypted with, is among the
+    /// intended recipients of this signed message.
+    ///
+    /// This does not verify the signature, which should be done first, for example with
+    /// [`Message::verify`].
+    pub fn check_intended_recipient(
+        &self,
+        recipient: &impl KeyTrait,
+    ) -> Result<IntendedRecipientStatus> {
+        let intended = self.intended_recipients()?;
+        let fingerprint = recipient.fingerprint();
+
+        if intended.is_empty() {
+            Ok(IntendedRecipientStatus::Unspecified)
+        } else if intended.contains(&fingerprint) {
+            Ok(IntendedRecipientStatus::Listed)
+        } else {
+            warn!(
+                "{} is not an intended recipient of the signed message",
+                hex::encode(&fingerprint)
+            );
+            Ok(IntendedRecipientStatus::NotListed)
+        }
+    }
+
     /// Verifies this message.
     /// For signed messages this verifies the signature.
     ///
@@ -530,81 +1131,86 @@ impl Message {
 
     /// Decrypt the message using the given key.
     /// Returns a message decrypter, and a list of [KeyId]s that are valid recipients of this message.
+    ///
+    /// Recipients with a wildcard key id are trial decrypted with all matching keys; the
+    /// returned [KeyId]s always name the key that was actually used.
+    ///
+    /// Legacy Symmetrically Encrypted Data packets (tag 9) are rejected, as they have no
+    /// integrity protection, see [`Message::decrypt_legacy_sed`].
     pub fn decrypt<G>(&self, key_pw: G, keys: &[&SignedSecretKey]) -> Result<(Message, Vec<KeyId>)>
+    where
+        G: FnOnce() -> String + Clone,
+    {
+        self.decrypt_inner(key_pw, keys, false)
+    }
+
+    /// Decrypt the message like [`Message::decrypt`], but also accept legacy Symmetrically
+    /// Encrypted Data packets (tag 9), as created by [`Message::encrypt_to_keys_legacy_sed`].
+    ///
+    /// **This is insecure.** The legacy packet has no integrity protection, so the decrypted
+    /// message may have been modified by an attacker. Only use it for messages from senders
+    /// that can't create anything else.
+    pub fn decrypt_legacy_sed<G>(
+        &self,
+        key_pw: G,
+        keys: &[&SignedSecretKey],
+    ) -> Result<(Message, Vec<KeyId>)>
+    where
+        G: FnOnce() -> String + Clone,
+    {
+        self.decrypt_inner(key_pw, keys, true)
+    }
+
+    fn decrypt_inner<G>(
+        &self,
+        key_pw: G,
+        keys: &[&SignedSecretKey],
+        allow_legacy_sed: bool,
+    ) -> Result<(Message, Vec<KeyId>)>
     where
         G: FnOnce() -> String + Clone,
     {
         match self {
-            Message::Compressed { .. } | Message::Literal { .. } => {
+            Message::Compressed(data) => {
+                decompress_once(data)?.decrypt_inner(key_pw, keys, allow_legacy_sed)
+            }
+            Message::Literal { .. } => {
                 bail!("not encrypted");
             }
             Message::Signed { message, .. } => match message {
-                Some(message) => message.as_ref().decrypt(key_pw, keys),
+                Some(message) => message
+                    .as_ref()
+                    .decrypt_inner(key_pw, keys, allow_legacy_sed),
                 None => bail!("not encrypted"),
             },
             Message::Encrypted { esk, edata, .. } => {
                 let valid_keys = keys
                     .iter()
-                    .filter_map(|key| {
-                        // search for a packet with a key id that we have and that key.
-                        let mut packet = None;
-                        let mut encoding_key = None;
-                        let mut encoding_subkey = None;
-
-                        for esk_packet in esk.iter().filter_map(|k| match k {
-                            Esk::PublicKeyEncryptedSessionKey(k) => Some(k),
-                            _ => None,
-                        }) {
-                            debug!("esk packet: {:?}", esk_packet);
-                            debug!("{:?}", key.key_id());
-                            debug!(
-                                "{:?}",
-                                key.secret_subkeys
-                                    .iter()
-                                    .map(KeyTrait::key_id)
-                                    .collect::<Vec<_>>()
-                            );
-
-                            // find the key with the matching key id
-
-                            if &key.primary_key.key_id() == esk_packet.id() {
-                                encoding_key = Some(&key.primary_key);
-                            }
-
-                            if encoding_key.is_none() {
-                                encoding_subkey = key
-                                    .secret_subkeys
-                                    .iter()
-                                    .find(|&subkey| &subkey.key_id() == esk_packet.id());
-                            }
-
-                            if encoding_key.is_some() || encoding_subkey.is_some() {
-                                packet = Some(esk_packet);
-                                break;
-                            }
-                        }
-
-                        packet.map(|packet| (packet, encoding_key, encoding_subkey))
-                    })
+                    .flat_map(|key| recipient_keys(esk, key))
                     .collect::<Vec<_>>();
 
                 if valid_keys.is_empty() {
-                    return Err(Error::MissingKey);
+                    let recipients = describe_recipients(esk);
+                    if recipients.is_empty() {
+                        return Err(Error::MissingKey);
+                    }
+                    return Err(Error::MissingSecretKey(recipients));
                 }
 
                 let session_keys = valid_keys
                     .iter()
                     .map(|(packet, encoding_key, encoding_subkey)| {
+                        if packet.is_anonymous() {
+                            let key_id = encoding_key
+                                .map(KeyTrait::key_id)
+                                .or_else(|| encoding_subkey.map(KeyTrait::key_id));
+                            debug!("trying wildcard recipient with key {:?}", key_id);
+                        }
+
                         if let Some(ek) = encoding_key {
-                            Ok((
-                                ek.key_id(),
-                                decrypt_session_key(ek, key_pw.clone(), packet.mpis())?,
-                            ))
+                            Ok((ek.key_id(), packet.decrypt(ek, key_pw.clone())?))
                         } else if let Some(ek) = encoding_subkey {
-                            Ok((
-                                ek.key_id(),
-                                decrypt_session_key(ek, key_pw.clone(), packet.mpis())?,
-                            ))
+                            Ok((ek.key_id(), packet.decrypt(ek, key_pw.clone())?))
                         } else {
                             unreachable!("either a key or a subkey were found");
                         }
@@ -632,7 +1238,7 @@ impl Message {
                 };
 
                 let ids = session_keys.into_iter().map(|(k, _)| k).collect();
-                let msg = edata.decrypt(session_key)?;
+                let msg = edata.decrypt_inner(session_key, allow_legacy_sed)?;
 
                 Ok((msg, ids))
             }
@@ -646,7 +1252,8 @@ impl Message {
         F: FnOnce() -> String + Clone,
     {
         match self {
-            Message::Compressed { .. } | Message::Literal { .. } => {
+            Message::Compressed(data) => decompress_once(data)?.decrypt_with_password(msg_pw),
+            Message::Literal { .. } => {
                 bail!("not encrypted");
             }
             Message::Signed { message, .. } => match message {
@@ -654,17 +1261,32 @@ impl Message {
                 None => bail!("not encrypted"),
             },
             Message::Encrypted { esk, edata, .. } => {
-                // TODO: handle multiple passwords
-                let skesk = esk.iter().find_map(|esk| match esk {
-                    Esk::SymKeyEncryptedSessionKey(k) => Some(k),
-                    _ => None,
-                });
-
-                ensure!(skesk.is_some(), "message is not password protected");
+                let mut skesks = esk
+                    .iter()
+                    .filter_map(|esk| match esk {
+                        Esk::SymKeyEncryptedSessionKey(k) => Some(k),
+                        _ => None,
+                    })
+                    .peekable();
+
+                ensure!(skesks.peek().is_some(), "message is not password protected");
+
+                // Each packet might protect the session key with a different password, and a
+                // wrong password is not always detected before decrypting the data.
+                let mut last_err = None;
+                for skesk in skesks {
+                    match decrypt_session_key_with_password(skesk, msg_pw.clone())
+                        .and_then(|session_key| edata.decrypt(session_key))
+                    {
+                        Ok(msg) => return Ok(msg),
+                        Err(err) => {
+                            debug!("failed to decrypt with SKESK packet: {:?}", err);
+                            last_err = Some(err);
+                        }
+                    }
+                }
 
-                let session_key =
-                    decrypt_session_key_with_password(skesk.expect("checked above"), msg_pw)?;
-                edata.decrypt(session_key)
+                Err(last_err.expect("checked above"))
             }
         }
     }
@@ -711,10 +1333,10 @@ impl Message {
     fn get_content_internal(&self, decompress: bool) -> Result<Option<Vec<u8>>> {
         match self {
             Message::Literal(ref data) => Ok(Some(data.data().to_vec())),
-            Message::Signed { message, .. } => Ok(message
-                .as_ref()
-                .and_then(|m| m.get_literal())
-                .map(|l| l.data().to_vec())),
+            Message::Signed { message, .. } => match message {
+                Some(message) => message.get_content_internal(decompress),
+                None => Ok(None),
+            },
             Message::Compressed(data) => {
                 if decompress {
                     let msg = Message::from_bytes(data.decompress()?)?;
@@ -732,12 +1354,13 @@ impl Message {
         writer: &mut impl io::Write,
         opts: ArmorOptions<'_>,
     ) -> Result<()> {
-        armor::write(
+        armor::write_with_line_width(
             self,
             armor::BlockType::Message,
             writer,
             opts.headers,
             opts.include_checksum,
+            opts.line_width,
         )
     }
 
@@ -755,6 +1378,231 @@ impl Message {
     }
 }
 
+/// Lists the keys in `keyring` that [`Message::decrypt`] would try to decrypt `message` with,
+/// without unlocking any of them, for example to only ask for the passphrases of those.
+///
+/// Each key is returned with the key id of the primary key or subkey that matches a recipient.
+/// Recipients with a wildcard key id match all primary keys and subkeys with the same
+/// algorithm. Messages that are not encrypted have no candidates.
+pub fn decryption_candidates<'a>(
+    keyring: &[&'a SignedSecretKey],
+    message: &Message,
+) -> Result<Vec<(&'a SignedSecretKey, KeyId)>> {
+    let esk = match message {
+        Message::Encrypted { esk, .. } => esk,
+        Message::Compressed(data) => {
+            return decryption_candidates(keyring, &decompress_once(data)?)
+        }
+        Message::Signed {
+            message: Some(message),
+            ..
+        } => return decryption_candidates(keyring, message),
+        _ => return Ok(Vec::new()),
+    };
+
+    let mut candidates: Vec<(&'a SignedSecretKey, KeyId)> = Vec::new();
+    for key in keyring {
+        for (_, primary, subkey) in recipient_keys(esk, key) {
+            let key_id = match (primary, subkey) {
+                (Some(primary), _) => primary.key_id(),
+                (None, Some(subkey)) => subkey.key_id(),
+                (None, None) => unreachable!("either a key or a subkey were found"),
+            };
+            let known = candidates
+                .iter()
+                .any(|(k, id)| std::ptr::eq(*k, *key) && id == &key_id);
+            if !known {
+                candidates.push((*key, key_id));
+            }
+        }
+    }
+
+    Ok(candidates)
+}
+
+/// A PKESK packet, together with the primary key or subkey of a secret key to decrypt it with.
+type RecipientKey<'a> = (
+    &'a PublicKeyEncryptedSessionKey,
+    Option<&'a packet::SecretKey>,
+    Option<&'a SignedSecretSubKey>,
+);
+
+/// The PKESK packets in `esk` that `key` can decrypt, with the primary key or subkey to use.
+///
+/// A packet naming the key or one of its subkeys is preferred. Only if there is none, all
+/// primary keys and subkeys with a matching algorithm are paired with the wildcard recipients.
+fn recipient_keys<'a>(esk: &'a [Esk], key: &'a SignedSecretKey) -> Vec<RecipientKey<'a>> {
+    // search for a packet with a key id that we have and that key.
+    let mut packet = None;
+    let mut encoding_key = None;
+    let mut encoding_subkey = None;
+
+    for esk_packet in esk.iter().filter_map(|k| match k {
+        Esk::PublicKeyEncryptedSessionKey(k) => Some(k),
+        _ => None,
+    }) {
+        debug!("esk packet: {:?}", esk_packet);
+        debug!("{:?}", key.key_id());
+        debug!(
+            "{:?}",
+            key.secret_subkeys
+                .iter()
+                .map(KeyTrait::key_id)
+                .collect::<Vec<_>>()
+        );
+
+        // find the key with the matching key id or fingerprint
+
+        if esk_packet.is_recipient(&key.primary_key) {
+            encoding_key = Some(&key.primary_key);
+        }
+
+        if encoding_key.is_none() {
+            encoding_subkey = key
+                .secret_subkeys
+                .iter()
+                .find(|&subkey| esk_packet.is_recipient(subkey));
+        }
+
+        if encoding_key.is_some() || encoding_subkey.is_some() {
+            packet = Some(esk_packet);
+            break;
+        }
+    }
+
+    if let Some(packet) = packet {
+        return vec![(packet, encoding_key, encoding_subkey)];
+    }
+
+    // No direct match, so try every key with a matching algorithm
+    // against the wildcard (anonymous) recipients.
+    esk.iter()
+        .filter_map(|k| match k {
+            Esk::PublicKeyEncryptedSessionKey(k) if k.is_anonymous() => Some(k),
+            _ => None,
+        })
+        .flat_map(|esk_packet| {
+            let primary = Some(&key.primary_key)
+                .filter(|k| k.algorithm() == esk_packet.algorithm())
+                .map(|k| (esk_packet, Some(k), None));
+            let subkeys = key
+                .secret_subkeys
+                .iter()
+                .filter(|k| k.algorithm() == esk_packet.algorithm())
+                .map(|k| (esk_packet, None, Some(k)));
+
+            primary.into_iter().chain(subkeys).collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Lists the public key recipients in `esk`, for error messages.
+fn describe_recipients(esk: &[Esk]) -> String {
+    esk.iter()
+        .filter_map(|esk| match esk {
+            Esk::PublicKeyEncryptedSessionKey(pkesk) => Some(pkesk),
+            Esk::SymKeyEncryptedSessionKey(_) => None,
+        })
+        .map(|pkesk| match pkesk.fingerprint() {
+            Some((_, fingerprint)) => format!("0x{}", hex::encode_upper(fingerprint)),
+            None if pkesk.is_anonymous() => "an anonymous recipient".to_string(),
+            None => pkesk.id().to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Whether `data` starts with the magic bytes of a compressed file format.
+fn is_compressed_format(data: &[u8]) -> bool {
+    const MAGIC: &[&[u8]] = &[
+        // gzip
+        &[0x1f, 0x8b],
+        // zip, including empty and spanned archives
+        b"PK\x03\x04",
+        b"PK\x05\x06",
+        b"PK\x07\x08",
+        // JPEG
+        &[0xff, 0xd8, 0xff],
+        // PNG
+        b"\x89PNG\r\n\x1a\n",
+    ];
+
+    MAGIC.iter().any(|magic| data.starts_with(magic))
+}
+
+/// How [`Message::encrypt_to_keys_with_padding`] pads a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaddingPolicy {
+    /// Pad to the next power of two bytes.
+    PowerOfTwo,
+    /// Pad to the next multiple of the given number of bytes.
+    Multiple(usize),
+}
+
+impl PaddingPolicy {
+    /// The padded size for `len` bytes.
+    fn padded_len(self, len: usize) -> Result<usize> {
+        let padded = match self {
+            PaddingPolicy::PowerOfTwo => len.checked_next_power_of_two(),
+            PaddingPolicy::Multiple(size) => {
+                ensure!(size > 0, "padding to a multiple of 0 bytes");
+                len.div_ceil(size).checked_mul(size)
+            }
+        };
+
+        padded.ok_or_else(|| format_err!("padded size of {} bytes is too large", len))
+    }
+}
+
+/// Creates a padding packet, that pads `len` bytes of serialized message to the size given by
+/// `policy`, including its own header.
+fn padding_packet<R: CryptoRng + Rng>(
+    rng: &mut R,
+    len: usize,
+    policy: PaddingPolicy,
+) -> Result<Packet> {
+    // the header is 2 bytes for bodies below 192 bytes, 3 bytes below 8384 and 6 bytes above
+    let header_len = |body_len: usize| match body_len {
+        0..=191 => 2,
+        192..=8383 => 3,
+        _ => 6,
+    };
+
+    // the smallest padding packet has one byte of padding
+    let mut target = policy.padded_len(len + 2)?;
+    loop {
+        let total = target - len;
+        let body_len = [2, 3, 6]
+            .into_iter()
+            .filter_map(|header| total.checked_sub(header))
+            .find(|&body_len| body_len > 0 && header_len(body_len) + body_len == total);
+        if let Some(body_len) = body_len {
+            return Ok(Padding::new(rng, Version::New, body_len).into());
+        }
+
+        // no header fits exactly, use the next bucket
+        target = policy.padded_len(target + 1)?;
+    }
+}
+
+/// Decompresses `data` into the message it contains, which must not contain compressed data
+/// again, at any layer.
+fn decompress_once(data: &CompressedData) -> Result<Message> {
+    let msg = Message::from_bytes(data.decompress()?)?;
+
+    let mut layer = &msg;
+    loop {
+        match layer {
+            Message::Compressed(_) => bail!("Recursive decompression not allowed"),
+            Message::Signed {
+                message: Some(message),
+                ..
+            } => layer = message,
+            _ => return Ok(msg),
+        }
+    }
+}
+
 /// Options for generating armored content.
 #[derive(Debug, Clone)]
 pub struct ArmorOptions<'a> {
@@ -762,23 +1610,37 @@ pub struct ArmorOptions<'a> {
     pub headers: Option<&'a armor::Headers>,
     /// Should a checksum be included? Default to `true`.
     pub include_checksum: bool,
+    /// Number of base64 characters per line. Default to
+    /// [`armor::DEFAULT_LINE_WIDTH`], must not exceed [`armor::MAX_LINE_WIDTH`].
+    pub line_width: usize,
 }
 
-impl Default for ArmorOptions<'_> {
-    fn default() -> Self {
+impl<'a> ArmorOptions<'a> {
+    /// Options with the given headers and checksum setting, and the default line width.
+    pub fn new(headers: Option<&'a armor::Headers>, include_checksum: bool) -> Self {
         Self {
-            headers: None,
-            include_checksum: true,
+            headers,
+            include_checksum,
+            line_width: armor::DEFAULT_LINE_WIDTH,
         }
     }
+
+    /// Sets the number of base64 characters per line, see [`ArmorOptions::line_width`].
+    pub fn with_line_width(mut self, line_width: usize) -> Self {
+        self.line_width = line_width;
+        self
+    }
+}
+
+impl Default for ArmorOptions<'_> {
+    fn default() -> Self {
+        Self::new(None, true)
+    }
 }
 
 impl<'a> From<Option<&'a armor::Headers>> for ArmorOptions<'a> {
     fn from(headers: Option<&'a armor::Headers>) -> Self {
-        Self {
-            headers,
-            include_checksum: true,
-        }
+        Self::new(headers, true)
     }
 }
 
@@ -787,6 +1649,7 @@ mod tests {
     #![allow(clippy::unwrap_used)]
 
     use super::*;
+    use chrono::{TimeZone, Utc};
     use rand::thread_rng;
     use std::fs;
 
@@ -822,6 +1685,27 @@ mod tests {
         assert_eq!(&lit_msg, &uncompressed_msg);
     }
 
+    #[test]
+    fn test_armor_line_width() {
+        let lit_msg = Message::new_literal("hello.txt", &"hello world ".repeat(20));
+
+        let armored = lit_msg
+            .to_armored_string(ArmorOptions::new(None, false).with_line_width(76))
+            .unwrap();
+        let lines = armored.lines().collect::<Vec<_>>();
+        // header, empty line, body lines and the footer, without a checksum
+        assert!(lines.len() > 4);
+        assert_eq!(lines[2].len(), 76);
+        assert!(!lines[lines.len() - 2].starts_with('='));
+
+        let (parsed, _) = Message::from_string(&armored).unwrap();
+        assert_eq!(parsed, lit_msg);
+
+        assert!(lit_msg
+            .to_armored_string(ArmorOptions::default().with_line_width(armor::MAX_LINE_WIDTH + 1))
+            .is_err());
+    }
+
     #[test]
     fn test_rsa_encryption() {
         use rand::SeedableRng;
@@ -889,153 +1773,1602 @@ mod tests {
     }
 
     #[test]
-    fn test_password_encryption() {
-        let _ = pretty_env_logger::try_init();
+    fn test_wildcard_recipient_decryption() {
+        let (skey, _headers) = SignedSecretKey::from_armor_single(
+            fs::File::open("./tests/autocrypt/alice@autocrypt.example.sec.asc").unwrap(),
+        )
+        .unwrap();
 
+        // subkey[0] is the encryption key
+        let pkey = skey.secret_subkeys[0].public_key();
         let mut rng = thread_rng();
 
         let lit_msg = Message::new_literal("hello.txt", "hello world\n");
-        let compressed_msg = lit_msg.compress(CompressionAlgorithm::ZLIB).unwrap();
-
-        let s2k = StringToKey::new_default(&mut rng);
-
-        let encrypted = compressed_msg
-            .encrypt_with_password(&mut rng, s2k, SymmetricKeyAlgorithm::AES128, || {
-                "secret".into()
-            })
+        let encrypted = lit_msg
+            .encrypt_to_keys(&mut rng, SymmetricKeyAlgorithm::AES128, &[&pkey][..])
             .unwrap();
 
-        let armored = encrypted.to_armored_bytes(None.into()).unwrap();
-        fs::write("./message-password.asc", &armored).unwrap();
-
-        let parsed = Message::from_armor_single(&armored[..]).unwrap().0;
+        // hide the recipient by replacing the key id with the wildcard
+        let mut bytes = encrypted.to_bytes().unwrap();
+        let key_id = pkey.key_id();
+        let pos = bytes.windows(8).position(|w| w == key_id.as_ref()).unwrap();
+        bytes[pos..pos + 8].fill(0);
 
-        let decrypted = parsed.decrypt_with_password(|| "secret".into()).unwrap();
+        let parsed = Message::from_bytes(&bytes[..]).unwrap();
+        assert!(parsed.get_recipients()[0].is_wildcard());
 
-        assert_eq!(compressed_msg, decrypted);
+        let (decrypted, ids) = parsed.decrypt(|| "".into(), &[&skey]).unwrap();
+        assert_eq!(lit_msg, decrypted);
+        assert_eq!(ids, vec![key_id]);
     }
 
     #[test]
-    fn test_no_plaintext_decryption() {
-        // Invalid message "encrypted" with plaintext algorithm.
-        // Generated with the Python script below.
-        let msg_raw = b"\xc3\x04\x04\x00\x00\x08\xd2-\x01\x00\x00\xcb\x12b\x00\x00\x00\x00\x00Hello world!\xd3\x14\xc3\xadw\x022\x05\x0ek'k\x8d\x12\xaa8\r'\x8d\xc0\x82)";
-        /*
-                import hashlib
-                import sys
-                data = (
-                    b"\xc3"  # PTag = 11000011, new packet format, tag 3 = SKESK
-                    b"\x04"  # Packet length, 4
-                    b"\x04"  # Version number, 4
-                    b"\x00"  # Algorithm, plaintext
-                    b"\x00\x08"  # S2K specifier, Simple S2K, SHA256
-                    b"\xd2"  # PTag = 1101 0010, new packet format, tag 18 = SEIPD
-                    b"\x2d"  # Packet length, 45
-                    b"\x01"  # Version number, 1
-                )
-                inner_data = (
-                    b"\x00\x00"  # IV
-                    b"\xcb"  # PTag = 11001011, new packet format, tag 11 = literal data packet
-                    b"\x12"  # Packet length, 18
-                    b"\x62"  # Binary data ('b')
-                    b"\x00"  # No filename, empty filename length
-                    b"\x00\x00\x00\x00"  # Date
-                    b"Hello world!"
-                )
-                data += inner_data
-                data += (
-                    b"\xd3"  # Modification Detection Code packet, tag 19
-                    b"\x14"  # MDC packet length, 20 bytes
-                )
-                data += hashlib.new("SHA1", inner_data + b"\xd3\x14").digest()
-                print(data)
-        */
+    fn test_decryption_candidates() {
+        let mut rng = thread_rng();
+        let (alice, _headers) = SignedSecretKey::from_armor_single(
+            fs::File::open("./tests/autocrypt/alice@autocrypt.example.sec.asc").unwrap(),
+        )
+        .unwrap();
+        let (bob, _headers) = SignedSecretKey::from_armor_single(
+            fs::File::open("./tests/autocrypt/bob@autocrypt.example.sec.asc").unwrap(),
+        )
+        .unwrap();
+        let keyring = [&alice, &bob];
+        // subkey[0] is the encryption key
+        let alice_id = alice.secret_subkeys[0].key_id();
+        let bob_id = bob.secret_subkeys[0].key_id();
+        let alice_pkey = alice.secret_subkeys[0].public_key();
+        let bob_pkey = bob.secret_subkeys[0].public_key();
 
-        let msg = Message::from_bytes(&msg_raw[..]).unwrap();
+        let lit_msg = Message::new_literal("hello.txt", "hello world\n");
+        assert!(decryption_candidates(&keyring, &lit_msg)
+            .unwrap()
+            .is_empty());
 
-        // Before the fix message eventually decrypted to
-        //   Literal(LiteralData { packet_version: New, mode: Binary, created: 1970-01-01T00:00:00Z, file_name: "", data: "48656c6c6f20776f726c6421" })
-        // where "48656c6c6f20776f726c6421" is an encoded "Hello world!" string.
-        assert!(msg
-            .decrypt_with_password(|| "foobarbaz".into())
-            .err()
+        let encrypted = lit_msg
+            .encrypt_to_keys(&mut rng, SymmetricKeyAlgorithm::AES128, &[&alice_pkey])
+            .unwrap();
+        assert_eq!(
+            decryption_candidates(&keyring, &encrypted).unwrap(),
+            vec![(&alice, alice_id.clone())]
+        );
+        assert!(decryption_candidates(&keyring[1..], &encrypted)
             .unwrap()
-            .to_string()
-            .contains("plaintext"));
+            .is_empty());
+        let compressed = encrypted.compress(CompressionAlgorithm::ZLIB).unwrap();
+        assert_eq!(
+            decryption_candidates(&keyring, &compressed).unwrap(),
+            vec![(&alice, alice_id.clone())]
+        );
+
+        // version 6 PKESK packets name the recipients by fingerprint
+        let encrypted = lit_msg
+            .encrypt_to_keys_seipdv2(
+                &mut rng,
+                SymmetricKeyAlgorithm::AES128,
+                AeadAlgorithm::Ocb,
+                6,
+                &[&bob_pkey, &alice_pkey],
+            )
+            .unwrap();
+        assert_eq!(
+            decryption_candidates(&keyring, &encrypted).unwrap(),
+            vec![(&alice, alice_id.clone()), (&bob, bob_id.clone())]
+        );
+
+        // a wildcard recipient may be any key with the same algorithm
+        let encrypted = lit_msg
+            .encrypt_to_keys(&mut rng, SymmetricKeyAlgorithm::AES128, &[&alice_pkey])
+            .unwrap();
+        let mut bytes = encrypted.to_bytes().unwrap();
+        let pos = bytes
+            .windows(8)
+            .position(|w| w == alice_id.as_ref())
+            .unwrap();
+        bytes[pos..pos + 8].fill(0);
+        let parsed = Message::from_bytes(&bytes[..]).unwrap();
+        assert_eq!(
+            decryption_candidates(&keyring, &parsed).unwrap(),
+            vec![(&alice, alice_id), (&bob, bob_id)]
+        );
     }
 
     #[test]
-    fn test_x25519_signing_string() {
+    fn test_intended_recipients() {
         let (skey, _headers) = SignedSecretKey::from_armor_single(
             fs::File::open("./tests/autocrypt/alice@autocrypt.example.sec.asc").unwrap(),
         )
         .unwrap();
-
-        let pkey = skey.public_key();
+        let (other, _headers) = SignedSecretKey::from_armor_single(
+            fs::File::open("./tests/key-with-password-123.asc").unwrap(),
+        )
+        .unwrap();
+        let public = skey.public_key();
+        let pkey = skey.secret_subkeys[0].public_key();
+        let mut rng = thread_rng();
 
         let lit_msg = Message::new_literal("hello.txt", "hello world\n");
-        assert!(lit_msg.verify(&pkey).is_err()); // Unsigned message shouldn't verify
+        let signed = lit_msg
+            .clone()
+            .sign_for_recipients(&skey, || "".into(), HashAlgorithm::SHA2_256, &[&skey])
+            .unwrap();
+        let encrypted = signed
+            .encrypt_to_keys(&mut rng, SymmetricKeyAlgorithm::AES128, &[&pkey][..])
+            .unwrap();
+        let (decrypted, _ids) = encrypted.decrypt(|| "".into(), &[&skey]).unwrap();
 
-        let signed_msg = lit_msg
+        let Message::Signed { ref signature, .. } = decrypted else {
+            panic!("unexpected message {decrypted:?}");
+        };
+        assert_eq!(
+            signature.intended_recipients(),
+            vec![&skey.fingerprint()[..]]
+        );
+        decrypted.verify_for_recipient(&public, &skey).unwrap();
+
+        // re-encrypted to someone else, the signature is still valid
+        decrypted.verify(&public).unwrap();
+        assert!(matches!(
+            decrypted.verify_for_recipient(&public, &other),
+            Err(Error::IntendedRecipientMismatch)
+        ));
+
+        // signatures without intended recipients are accepted
+        let signed = lit_msg
             .sign(&skey, || "".into(), HashAlgorithm::SHA2_256)
             .unwrap();
-
-        let armored = signed_msg.to_armored_bytes(None.into()).unwrap();
-        fs::write("./message-string-signed-x25519.asc", &armored).unwrap();
-
-        signed_msg.verify(&pkey).unwrap();
-
-        let parsed = Message::from_armor_single(&armored[..]).unwrap().0;
-        parsed.verify(&pkey).unwrap();
+        signed.verify_for_recipient(&public, &other).unwrap();
     }
 
     #[test]
-    fn test_x25519_signing_bytes() {
+    fn test_check_intended_recipient() {
         let (skey, _headers) = SignedSecretKey::from_armor_single(
             fs::File::open("./tests/autocrypt/alice@autocrypt.example.sec.asc").unwrap(),
         )
         .unwrap();
+        let (other, _headers) = SignedSecretKey::from_armor_single(
+            fs::File::open("./tests/key-with-password-123.asc").unwrap(),
+        )
+        .unwrap();
 
-        let pkey = skey.public_key();
+        let lit_msg = Message::new_literal("hello.txt", "hello world\n");
+        let signed = lit_msg
+            .clone()
+            .sign_for_recipients(
+                &skey,
+                || "".into(),
+                HashAlgorithm::SHA2_256,
+                &[&skey, &other],
+            )
+            .unwrap()
+            .compress(CompressionAlgorithm::ZLIB)
+            .unwrap();
+        assert_eq!(
+            signed.intended_recipients().unwrap(),
+            vec![skey.fingerprint(), other.fingerprint()]
+        );
+        assert_eq!(
+            signed.check_intended_recipient(&other).unwrap(),
+            IntendedRecipientStatus::Listed
+        );
+
+        let signed = lit_msg
+            .clone()
+            .sign_for_recipients(&skey, || "".into(), HashAlgorithm::SHA2_256, &[&skey])
+            .unwrap();
+        assert_eq!(
+            signed.check_intended_recipient(&other).unwrap(),
+            IntendedRecipientStatus::NotListed
+        );
 
-        let lit_msg = Message::new_literal_bytes("hello.txt", &b"hello world\n"[..]);
-        let signed_msg = lit_msg
+        let signed = lit_msg
+            .clone()
             .sign(&skey, || "".into(), HashAlgorithm::SHA2_256)
             .unwrap();
+        assert!(signed.intended_recipients().unwrap().is_empty());
+        assert_eq!(
+            signed.check_intended_recipient(&other).unwrap(),
+            IntendedRecipientStatus::Unspecified
+        );
 
-        let armored = signed_msg.to_armored_bytes(None.into()).unwrap();
-        fs::write("./message-bytes-signed-x25519.asc", &armored).unwrap();
-
-        signed_msg.verify(&pkey).unwrap();
-
-        let parsed = Message::from_armor_single(&armored[..]).unwrap().0;
-        parsed.verify(&pkey).unwrap();
+        assert!(lit_msg.check_intended_recipient(&other).is_err());
     }
 
     #[test]
-    fn test_x25519_signing_bytes_compressed() {
+    fn test_intended_recipient_v6() {
+        use crate::composed::{KeyType, SecretKeyParamsBuilder};
+
         let (skey, _headers) = SignedSecretKey::from_armor_single(
             fs::File::open("./tests/autocrypt/alice@autocrypt.example.sec.asc").unwrap(),
         )
         .unwrap();
+        let recipient = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::Ed25519)
+            .can_certify(true)
+            .version(KeyVersion::V6)
+            .build()
+            .unwrap()
+            .generate_with_rng(thread_rng())
+            .unwrap()
+            .sign(String::new)
+            .unwrap();
+
+        let signed = Message::new_literal("hello.txt", "hello world\n")
+            .sign_for_recipients(
+                &skey,
+                || "".into(),
+                HashAlgorithm::SHA2_256,
+                &[&skey, &recipient],
+            )
+            .unwrap();
+        let armor = signed.to_armored_string(None.into()).unwrap();
+        let (parsed, _headers) = Message::from_string(&armor).unwrap();
+
+        let Message::Signed { ref signature, .. } = parsed else {
+            panic!("unexpected message {parsed:?}");
+        };
+        let versions = signature
+            .config
+            .hashed_subpackets
+            .iter()
+            .filter_map(|subpacket| match subpacket.data {
+                SubpacketData::IntendedRecipientFingerprint(version, ref fp) => {
+                    Some((version, fp.len()))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(versions, vec![(KeyVersion::V4, 20), (KeyVersion::V6, 32)]);
+        assert_eq!(
+            parsed.check_intended_recipient(&recipient).unwrap(),
+            IntendedRecipientStatus::Listed
+        );
+        parsed.verify(&skey.public_key()).unwrap();
+    }
 
+    #[test]
+    fn test_sign_v6() {
+        use crate::composed::{KeyType, SecretKeyParamsBuilder};
+
+        let skey = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::Ed25519)
+            .can_certify(true)
+            .can_sign(true)
+            .version(KeyVersion::V6)
+            .build()
+            .unwrap()
+            .generate_with_rng(thread_rng())
+            .unwrap()
+            .sign(String::new)
+            .unwrap();
         let pkey = skey.public_key();
 
-        let lit_msg = Message::new_literal_bytes("hello.txt", &b"hello world\n"[..]);
+        let lit_msg = Message::new_literal("hello.txt", "hello world\n");
         let signed_msg = lit_msg
-            .sign(&skey, || "".into(), HashAlgorithm::SHA2_256)
+            .sign(&skey, String::new, HashAlgorithm::SHA2_256)
             .unwrap();
-        let compressed_msg = signed_msg.compress(CompressionAlgorithm::ZLIB).unwrap();
-
-        let armored = compressed_msg.to_armored_bytes(None.into()).unwrap();
-        fs::write("./message-bytes-compressed-signed-x25519.asc", &armored).unwrap();
-
         signed_msg.verify(&pkey).unwrap();
 
+        let Message::Signed { ref signature, .. } = signed_msg else {
+            panic!("unexpected message {signed_msg:?}");
+        };
+        assert_eq!(signature.config.version, SignatureVersion::V6);
+        assert_eq!(signature.config.salt.as_ref().map(Vec::len), Some(16));
+        assert!(signature.issuer().is_empty());
+        assert_eq!(
+            signature.issuer_fingerprint(),
+            vec![&skey.fingerprint()[..]]
+        );
+        assert!(!signed_msg.is_one_pass_signed());
+
+        let armored = signed_msg.to_armored_bytes(None.into()).unwrap();
         let parsed = Message::from_armor_single(&armored[..]).unwrap().0;
+        assert_eq!(parsed, signed_msg);
         parsed.verify(&pkey).unwrap();
     }
 
+    #[test]
+    #[cfg(feature = "openssl")]
+    fn test_brainpool_sign_encrypt() {
+        use crate::composed::{KeyType, SecretKeyParamsBuilder, SubkeyParamsBuilder};
+        use crate::crypto::ecc_curve::ECCCurve;
+
+        let mut rng = thread_rng();
+        for (curve, hash) in [
+            (ECCCurve::BrainpoolP256r1, HashAlgorithm::SHA2_256),
+            (ECCCurve::BrainpoolP384r1, HashAlgorithm::SHA2_384),
+            (ECCCurve::BrainpoolP512r1, HashAlgorithm::SHA2_512),
+        ] {
+            let key = SecretKeyParamsBuilder::default()
+                .key_type(KeyType::ECDSA(curve.clone()))
+                .can_certify(true)
+                .can_sign(true)
+                .primary_user_id("Me <me@mail.com>".into())
+                .subkey(
+                    SubkeyParamsBuilder::default()
+                        .key_type(KeyType::ECDHBrainpool(curve.clone()))
+                        .can_encrypt(true)
+                        .build()
+                        .unwrap(),
+                )
+                .build()
+                .unwrap()
+                .generate_with_rng(&mut rng)
+                .unwrap()
+                .sign(String::new)
+                .unwrap();
+            let public = key.public_key();
+            let subkey = key.secret_subkeys[0].public_key();
+
+            let lit_msg = Message::new_literal("hello.txt", "hello world\n");
+            let signed = lit_msg.clone().sign(&key, String::new, hash).unwrap();
+            signed.verify(&public).unwrap();
+
+            let encrypted = signed
+                .encrypt_to_keys(&mut rng, SymmetricKeyAlgorithm::AES128, &[&subkey])
+                .unwrap();
+            let armored = encrypted.to_armored_string(None.into()).unwrap();
+            let (parsed, _headers) = Message::from_string(&armored).unwrap();
+
+            let (decrypted, _ids) = parsed.decrypt(String::new, &[&key]).unwrap();
+            decrypted.verify(&public).unwrap();
+            assert_eq!(decrypted.get_literal(), lit_msg.get_literal());
+        }
+    }
+
+    #[test]
+    fn test_encrypt_to_keys_v6() {
+        use crate::composed::{KeyType, SecretKeyParamsBuilder, SubkeyParamsBuilder};
+
+        let mut rng = thread_rng();
+        let (v4_key, _headers) = SignedSecretKey::from_armor_single(
+            fs::File::open("./tests/autocrypt/alice@autocrypt.example.sec.asc").unwrap(),
+        )
+        .unwrap();
+        let v6_key = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::Ed25519)
+            .can_certify(true)
+            .version(KeyVersion::V6)
+            .subkey(
+                SubkeyParamsBuilder::default()
+                    .key_type(KeyType::X25519)
+                    .can_encrypt(true)
+                    .version(KeyVersion::V6)
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap()
+            .generate_with_rng(&mut rng)
+            .unwrap()
+            .sign(String::new)
+            .unwrap();
+
+        let lit_msg = Message::new_literal("hello.txt", "hello world\n");
+        let v4_subkey = v4_key.secret_subkeys[0].public_key();
+        let v6_subkey = v6_key.secret_subkeys[0].public_key();
+
+        // only version 4 recipients get version 3 PKESK and version 1 SEIPD packets
+        let encrypted = lit_msg
+            .encrypt_to_keys(&mut rng, SymmetricKeyAlgorithm::AES128, &[&v4_subkey])
+            .unwrap();
+        let Message::Encrypted {
+            esk,
+            edata: Edata::SymEncryptedProtectedData(edata),
+        } = &encrypted
+        else {
+            panic!("unexpected message {encrypted:?}");
+        };
+        assert!(esk
+            .iter()
+            .all(|esk| matches!(esk, Esk::PublicKeyEncryptedSessionKey(p) if p.version() == 3)));
+        assert_eq!(edata.version(), 1);
+
+        // version 6 recipients get version 6 PKESK and version 2 SEIPD packets
+        let encrypted = lit_msg
+            .encrypt_to_keys(&mut rng, SymmetricKeyAlgorithm::AES128, &[&v6_subkey])
+            .unwrap();
+        let armored = encrypted.to_armored_string(None.into()).unwrap();
+        let (parsed, _headers) = Message::from_string(&armored).unwrap();
+        let Message::Encrypted {
+            esk,
+            edata: Edata::SymEncryptedProtectedData(edata),
+        } = &parsed
+        else {
+            panic!("unexpected message {parsed:?}");
+        };
+        assert!(esk
+            .iter()
+            .all(|esk| matches!(esk, Esk::PublicKeyEncryptedSessionKey(p) if p.version() == 6)));
+        assert_eq!(edata.version(), 2);
+        let (decrypted, _ids) = parsed.decrypt(String::new, &[&v6_key]).unwrap();
+        assert_eq!(decrypted, lit_msg);
+
+        // the version 4 recipient might not support version 2 SEIPD packets
+        assert!(lit_msg
+            .encrypt_to_keys(
+                &mut rng,
+                SymmetricKeyAlgorithm::AES128,
+                &[&v4_subkey, &v6_subkey],
+            )
+            .is_err());
+
+        assert!(lit_msg
+            .encrypt_to_keys_legacy_sed(&mut rng, SymmetricKeyAlgorithm::AES128, &[&v6_subkey])
+            .is_err());
+    }
+
+    #[test]
+    fn test_encrypt_to_recipients_v6() {
+        use crate::composed::{KeyType, SecretKeyParamsBuilder, SubkeyParamsBuilder};
+
+        let generate = |version: KeyVersion, aead: &[(SymmetricKeyAlgorithm, AeadAlgorithm)]| {
+            let (key_type, subkey_type) = match version {
+                KeyVersion::V6 => (KeyType::Ed25519, KeyType::X25519),
+                _ => (KeyType::EdDSA, KeyType::ECDH),
+            };
+            let secret = SecretKeyParamsBuilder::default()
+                .key_type(key_type)
+                .can_certify(true)
+                .version(version)
+                .primary_user_id("Me <me@example.com>".into())
+                .preferred_aead_algorithms(SmallVec::from_slice(aead))
+                .subkey(
+                    SubkeyParamsBuilder::default()
+                        .key_type(subkey_type)
+                        .can_encrypt(true)
+                        .version(version)
+                        .build()
+                        .unwrap(),
+                )
+                .build()
+                .unwrap()
+                .generate()
+                .unwrap()
+                .sign(String::new)
+                .unwrap();
+            let public = SignedPublicKey::from(secret.clone());
+            (secret, public)
+        };
+        // without AEAD preferences, so no Features are announced
+        let (v6, v6_public) = generate(KeyVersion::V6, &[]);
+        let (v4, v4_public) = generate(
+            KeyVersion::V4,
+            &[(SymmetricKeyAlgorithm::AES128, AeadAlgorithm::Ocb)],
+        );
+        let (_, v4_v1_only) = generate(KeyVersion::V4, &[]);
+
+        let now = chrono::Utc::now();
+        assert!(v6_public.supports_seipd_v2(&now));
+
+        let lit_msg = Message::new_literal("hello.txt", "hello world\n");
+        let encrypt = |recipients: &[&SignedPublicKey]| {
+            lit_msg.encrypt_to_recipients(
+                &mut thread_rng(),
+                SymmetricKeyAlgorithm::AES128,
+                AeadAlgorithm::Ocb,
+                recipients,
+                SeipdVersionPolicy::Negotiate,
+            )
+        };
+
+        let (msg, version) = encrypt(&[&v6_public, &v4_public]).unwrap();
+        assert_eq!(
+            version,
+            SeipdVersion::V2(SymmetricKeyAlgorithm::AES128, AeadAlgorithm::Ocb)
+        );
+        let Message::Encrypted { ref edata, .. } = msg else {
+            panic!("not encrypted");
+        };
+        assert_eq!(edata.version(), Some(2));
+        for key in [&v6, &v4] {
+            let (decrypted, _) = msg.decrypt(String::new, &[key]).unwrap();
+            assert_eq!(decrypted, lit_msg);
+        }
+
+        // there is no SEIPD version both can decrypt
+        assert!(encrypt(&[&v6_public, &v4_v1_only]).is_err());
+    }
+
+    #[test]
+    fn test_from_reader_partial_lengths() {
+        use crate::test_util::ChunkedReader;
+
+        let content: Vec<u8> = (0..1900u32).map(|i| (i % 251) as u8).collect();
+        // binary literal data: format, empty file name, date
+        let mut body = vec![b'b', 0, 0, 0, 0, 0];
+        body.extend_from_slice(&content);
+
+        // a literal data packet split into partial body chunks of 1024, 512 and 256 bytes
+        let mut bytes = vec![0xc0 | 11];
+        let mut rest = &body[..];
+        for len_octet in [0xea, 0xe9, 0xe8] {
+            let len = 1 << (len_octet & 0x1f);
+            bytes.push(len_octet);
+            bytes.extend_from_slice(&rest[..len]);
+            rest = &rest[len..];
+        }
+        assert!(rest.len() < 192);
+        bytes.push(rest.len() as u8);
+        bytes.extend_from_slice(rest);
+
+        let expected = Message::new_literal_bytes("", &content);
+        for chunk in [1, 7, 500, 4096] {
+            let reader = ChunkedReader::new(&bytes, chunk);
+            let (msg, headers) = Message::from_reader_single(reader).unwrap();
+            assert!(headers.is_none());
+            let Message::Literal(ref data) = msg else {
+                panic!("unexpected message {msg:?}");
+            };
+            assert_eq!(data.data(), &content[..]);
+            assert_eq!(msg.get_content().unwrap(), expected.get_content().unwrap());
+        }
+
+        let armored = expected.to_armored_bytes(None.into()).unwrap();
+        let reader = ChunkedReader::new(&armored, 64);
+        let (msg, headers) = Message::from_reader_single(reader).unwrap();
+        assert_eq!(msg, expected);
+        assert!(headers.is_some());
+    }
+
+    #[test]
+    fn test_password_encryption() {
+        let _ = pretty_env_logger::try_init();
+
+        let mut rng = thread_rng();
+
+        let lit_msg = Message::new_literal("hello.txt", "hello world\n");
+        let compressed_msg = lit_msg.compress(CompressionAlgorithm::ZLIB).unwrap();
+
+        let s2k = StringToKey::new_default(&mut rng);
+
+        let encrypted = compressed_msg
+            .encrypt_with_password(&mut rng, s2k, SymmetricKeyAlgorithm::AES128, || {
+                "secret".into()
+            })
+            .unwrap();
+
+        let armored = encrypted.to_armored_bytes(None.into()).unwrap();
+        fs::write("./message-password.asc", &armored).unwrap();
+
+        let parsed = Message::from_armor_single(&armored[..]).unwrap().0;
+
+        let decrypted = parsed.decrypt_with_password(|| "secret".into()).unwrap();
+
+        assert_eq!(compressed_msg, decrypted);
+    }
+
+    #[test]
+    fn test_multiple_password_decryption() {
+        let mut rng = thread_rng();
+
+        let lit_msg = Message::new_literal("hello.txt", "hello world\n");
+        let alg = SymmetricKeyAlgorithm::AES128;
+        let session_key = alg.new_session_key(&mut rng);
+        let esk = ["alice", "bob"]
+            .into_iter()
+            .map(|pw| {
+                let s2k = StringToKey::new_default(&mut rng);
+                let skesk =
+                    SymKeyEncryptedSessionKey::encrypt(|| pw.into(), &session_key, s2k, alg)
+                        .unwrap();
+                Esk::SymKeyEncryptedSessionKey(skesk)
+            })
+            .collect();
+        let encrypted = lit_msg
+            .encrypt_symmetric(&mut rng, esk, alg, session_key, None)
+            .unwrap();
+
+        let armored = encrypted.to_armored_bytes(None.into()).unwrap();
+        let parsed = Message::from_armor_single(&armored[..]).unwrap().0;
+
+        for pw in ["alice", "bob"] {
+            let decrypted = parsed.decrypt_with_password(|| pw.into()).unwrap();
+            assert_eq!(lit_msg, decrypted);
+        }
+        assert!(parsed.decrypt_with_password(|| "carol".into()).is_err());
+    }
+
+    #[test]
+    fn test_no_plaintext_decryption() {
+        // Invalid message "encrypted" with plaintext algorithm.
+        // Generated with the Python script below.
+        let msg_raw = b"\xc3\x04\x04\x00\x00\x08\xd2-\x01\x00\x00\xcb\x12b\x00\x00\x00\x00\x00Hello world!\xd3\x14\xc3\xadw\x022\x05\x0ek'k\x8d\x12\xaa8\r'\x8d\xc0\x82)";
+        /*
+                import hashlib
+                import sys
+                data = (
+                    b"\xc3"  # PTag = 11000011, new packet format, tag 3 = SKESK
+                    b"\x04"  # Packet length, 4
+                    b"\x04"  # Version number, 4
+                    b"\x00"  # Algorithm, plaintext
+                    b"\x00\x08"  # S2K specifier, Simple S2K, SHA256
+                    b"\xd2"  # PTag = 1101 0010, new packet format, tag 18 = SEIPD
+                    b"\x2d"  # Packet length, 45
+                    b"\x01"  # Version number, 1
+                )
+                inner_data = (
+                    b"\x00\x00"  # IV
+                    b"\xcb"  # PTag = 11001011, new packet format, tag 11 = literal data packet
+                    b"\x12"  # Packet length, 18
+                    b"\x62"  # Binary data ('b')
+                    b"\x00"  # No filename, empty filename length
+                    b"\x00\x00\x00\x00"  # Date
+                    b"Hello world!"
+                )
+                data += inner_data
+                data += (
+                    b"\xd3"  # Modification Detection Code packet, tag 19
+                    b"\x14"  # MDC packet length, 20 bytes
+                )
+                data += hashlib.new("SHA1", inner_data + b"\xd3\x14").digest()
+                print(data)
+        */
+
+        let msg = Message::from_bytes(&msg_raw[..]).unwrap();
+
+        // Before the fix message eventually decrypted to
+        //   Literal(LiteralData { packet_version: New, mode: Binary, created: 1970-01-01T00:00:00Z, file_name: "", data: "48656c6c6f20776f726c6421" })
+        // where "48656c6c6f20776f726c6421" is an encoded "Hello world!" string.
+        assert!(msg
+            .decrypt_with_password(|| "foobarbaz".into())
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("plaintext"));
+    }
+
+    #[test]
+    fn test_x25519_signing_string() {
+        let (skey, _headers) = SignedSecretKey::from_armor_single(
+            fs::File::open("./tests/autocrypt/alice@autocrypt.example.sec.asc").unwrap(),
+        )
+        .unwrap();
+
+        let pkey = skey.public_key();
+
+        let lit_msg = Message::new_literal("hello.txt", "hello world\n");
+        assert!(lit_msg.verify(&pkey).is_err()); // Unsigned message shouldn't verify
+
+        let signed_msg = lit_msg
+            .sign(&skey, || "".into(), HashAlgorithm::SHA2_256)
+            .unwrap();
+
+        let armored = signed_msg.to_armored_bytes(None.into()).unwrap();
+        fs::write("./message-string-signed-x25519.asc", &armored).unwrap();
+
+        signed_msg.verify(&pkey).unwrap();
+
+        let parsed = Message::from_armor_single(&armored[..]).unwrap().0;
+        parsed.verify(&pkey).unwrap();
+    }
+
+    #[test]
+    fn test_sign_with_hashed_subpackets() {
+        let (skey, _headers) = SignedSecretKey::from_armor_single(
+            fs::File::open("./tests/autocrypt/alice@autocrypt.example.sec.asc").unwrap(),
+        )
+        .unwrap();
+        let pkey = skey.public_key();
+        let lit_msg = Message::new_literal("hello.txt", "hello world\n");
+
+        let created = Utc.with_ymd_and_hms(2020, 1, 2, 3, 4, 5).unwrap();
+        let policy = Subpacket::regular(SubpacketData::PolicyURI("https://example.org".into()));
+        let hashed = vec![
+            policy.clone(),
+            Subpacket::critical(SubpacketData::SignatureCreationTime(created)),
+        ];
+
+        let sign = |hashed: Vec<Subpacket>, strict| {
+            lit_msg.clone().sign_with_hashed_subpackets(
+                &skey,
+                String::new,
+                HashAlgorithm::SHA2_256,
+                hashed,
+                strict,
+            )
+        };
+        let signed_msg = sign(hashed.clone(), true).unwrap();
+        signed_msg.verify(&pkey).unwrap();
+        let Message::Signed { ref signature, .. } = signed_msg else {
+            panic!("not signed");
+        };
+        assert_eq!(signature.config.hashed_subpackets, hashed);
+
+        // the creation time is appended if missing, or rejected in strict mode
+        let signed_msg = sign(vec![policy.clone()], false).unwrap();
+        signed_msg.verify(&pkey).unwrap();
+        let Message::Signed { ref signature, .. } = signed_msg else {
+            panic!("not signed");
+        };
+        assert_eq!(signature.config.hashed_subpackets.len(), 2);
+        assert_eq!(signature.config.hashed_subpackets[0], policy);
+        assert!(signature.created().is_some());
+
+        assert!(sign(vec![policy], true).is_err());
+    }
+
+    #[test]
+    fn test_verify_with_key_block() {
+        let (skey, _headers) = SignedSecretKey::from_armor_single(
+            fs::File::open("./tests/autocrypt/alice@autocrypt.example.sec.asc").unwrap(),
+        )
+        .unwrap();
+        let pkey = SignedPublicKey::from(skey.clone());
+        let lit_msg = Message::new_literal("hello.txt", "hello world\n");
+
+        let sign = |key_block: &SignedPublicKey| {
+            let hashed = vec![
+                Subpacket::regular(SubpacketData::SignatureCreationTime(
+                    Utc::now().trunc_subsecs(0),
+                )),
+                Subpacket::regular(SubpacketData::Issuer(skey.key_id())),
+                Subpacket::regular(SubpacketData::KeyBlock(key_block.to_bytes().unwrap())),
+            ];
+            lit_msg
+                .clone()
+                .sign_with_hashed_subpackets(
+                    &skey,
+                    String::new,
+                    HashAlgorithm::SHA2_256,
+                    hashed,
+                    true,
+                )
+                .unwrap()
+        };
+
+        let signed_msg = sign(&pkey);
+        let signed_msg = Message::from_bytes(&signed_msg.to_bytes().unwrap()[..]).unwrap();
+        let key = signed_msg.verify_with_key_block().unwrap();
+        assert_eq!(key.inner(), &pkey);
+
+        let compressed = signed_msg.compress(CompressionAlgorithm::ZLIB).unwrap();
+        assert_eq!(compressed.verify_with_key_block().unwrap(), key);
+
+        let Message::Signed { ref signature, .. } = signed_msg else {
+            panic!("not signed");
+        };
+        let standalone = StandaloneSignature::new(signature.clone());
+        assert_eq!(
+            standalone
+                .verify_with_key_block(b"hello world\n")
+                .unwrap()
+                .into_inner(),
+            pkey
+        );
+        assert!(standalone.verify_with_key_block(b"tampered").is_err());
+
+        // the embedded key must have made the signature
+        let (other, _headers) = SignedPublicKey::from_armor_single(
+            fs::File::open("./tests/autocrypt/bob@autocrypt.example.pub.asc").unwrap(),
+        )
+        .unwrap();
+        assert!(sign(&other).verify_with_key_block().is_err());
+
+        // and must be self-consistent
+        let mut broken = pkey.clone();
+        broken.details.users[0].id =
+            crate::packet::UserId::from_str(Default::default(), "Mallory <mallory@example.org>");
+        assert!(sign(&broken).verify_with_key_block().is_err());
+
+        let unsigned_key_block = lit_msg
+            .clone()
+            .sign(&skey, String::new, HashAlgorithm::SHA2_256)
+            .unwrap();
+        assert!(unsigned_key_block.verify_with_key_block().is_err());
+    }
+
+    #[test]
+    fn test_verify_message() {
+        use crate::composed::SubkeyParamsBuilder;
+        use crate::composed::{verify_message_with_keyring, KeyType, SecretKeyParamsBuilder};
+
+        let generate = |name: &str| {
+            SecretKeyParamsBuilder::default()
+                .key_type(KeyType::EdDSA)
+                .can_certify(true)
+                .can_sign(true)
+                .primary_user_id(name.into())
+                .subkeys(vec![SubkeyParamsBuilder::default()
+                    .key_type(KeyType::EdDSA)
+                    .can_sign(true)
+                    .build()
+                    .unwrap()])
+                .build()
+                .unwrap()
+                .generate()
+                .unwrap()
+                .sign(String::new)
+                .unwrap()
+        };
+        let skey = generate("Me <me@example.com>");
+        let pkey = SignedPublicKey::from(skey.clone());
+        let other = SignedPublicKey::from(generate("Other <other@example.com>"));
+
+        let lit_msg = Message::new_literal_bytes("hello.txt", &b"hello world\n"[..]);
+        let signed_msg = lit_msg
+            .clone()
+            .sign(&skey, String::new, HashAlgorithm::SHA2_256)
+            .unwrap();
+        let compressed_msg = signed_msg.compress(CompressionAlgorithm::ZLIB).unwrap();
+
+        for msg in [&signed_msg, &compressed_msg] {
+            let armored = msg.to_armored_bytes(None.into()).unwrap();
+            let parsed = Message::from_armor_single(&armored[..]).unwrap().0;
+            assert_eq!(
+                pkey.verify_message(&parsed).unwrap(),
+                (b"hello world\n".to_vec(), pkey.key_id())
+            );
+            assert!(other.verify_message(&parsed).is_err());
+            assert_eq!(
+                verify_message_with_keyring(&[&other, &pkey], &parsed)
+                    .unwrap()
+                    .1,
+                pkey.key_id()
+            );
+            assert!(verify_message_with_keyring(&[&other], &parsed).is_err());
+        }
+
+        // signed by the signing subkey
+        let subkey_msg = lit_msg
+            .clone()
+            .sign(
+                &skey.secret_subkeys[0],
+                String::new,
+                HashAlgorithm::SHA2_256,
+            )
+            .unwrap();
+        assert_eq!(
+            pkey.verify_message(&subkey_msg).unwrap().1,
+            pkey.public_subkeys[0].key.key_id()
+        );
+
+        assert!(pkey.verify_message(&lit_msg).is_err());
+    }
+
+    #[test]
+    fn test_x25519_signing_bytes() {
+        let (skey, _headers) = SignedSecretKey::from_armor_single(
+            fs::File::open("./tests/autocrypt/alice@autocrypt.example.sec.asc").unwrap(),
+        )
+        .unwrap();
+
+        let pkey = skey.public_key();
+
+        let lit_msg = Message::new_literal_bytes("hello.txt", &b"hello world\n"[..]);
+        let signed_msg = lit_msg
+            .sign(&skey, || "".into(), HashAlgorithm::SHA2_256)
+            .unwrap();
+
+        let armored = signed_msg.to_armored_bytes(None.into()).unwrap();
+        fs::write("./message-bytes-signed-x25519.asc", &armored).unwrap();
+
+        signed_msg.verify(&pkey).unwrap();
+
+        let parsed = Message::from_armor_single(&armored[..]).unwrap().0;
+        parsed.verify(&pkey).unwrap();
+    }
+
+    #[test]
+    fn test_x25519_signing_bytes_compressed() {
+        let (skey, _headers) = SignedSecretKey::from_armor_single(
+            fs::File::open("./tests/autocrypt/alice@autocrypt.example.sec.asc").unwrap(),
+        )
+        .unwrap();
+
+        let pkey = skey.public_key();
+
+        let lit_msg = Message::new_literal_bytes("hello.txt", &b"hello world\n"[..]);
+        let signed_msg = lit_msg
+            .sign(&skey, || "".into(), HashAlgorithm::SHA2_256)
+            .unwrap();
+        let compressed_msg = signed_msg.compress(CompressionAlgorithm::ZLIB).unwrap();
+
+        let armored = compressed_msg.to_armored_bytes(None.into()).unwrap();
+        fs::write("./message-bytes-compressed-signed-x25519.asc", &armored).unwrap();
+
+        signed_msg.verify(&pkey).unwrap();
+
+        let parsed = Message::from_armor_single(&armored[..]).unwrap().0;
+        parsed.verify(&pkey).unwrap();
+    }
+
+    #[test]
+    fn test_encrypt_with_session_key() {
+        let mut rng = thread_rng();
+        let (skey, _headers) = SignedSecretKey::from_armor_single(
+            fs::File::open("./tests/autocrypt/alice@autocrypt.example.sec.asc").unwrap(),
+        )
+        .unwrap();
+        // subkey[0] is the encryption key
+        let pkey = skey.secret_subkeys[0].public_key();
+        let alg = SymmetricKeyAlgorithm::AES256;
+        let session_key = alg.new_session_key(&mut rng);
+
+        let pkesk =
+            PublicKeyEncryptedSessionKey::from_session_key(&mut rng, &session_key, alg, &pkey)
+                .unwrap();
+        assert_eq!(
+            pkesk
+                .decrypt(&skey.secret_subkeys[0], || "".into())
+                .unwrap(),
+            PlainSessionKey::V4 {
+                sym_alg: alg,
+                key: session_key.clone()
+            }
+        );
+        // the primary key is not a recipient
+        assert!(pkesk.decrypt(&skey.primary_key, || "".into()).is_err());
+
+        let skesk = SymKeyEncryptedSessionKey::encrypt(
+            || "secret".into(),
+            &session_key,
+            StringToKey::new_default(&mut rng),
+            alg,
+        )
+        .unwrap();
+
+        let lit_msg = Message::new_literal("hello.txt", "hello world\n");
+        let encrypted = lit_msg
+            .encrypt_with_session_key(
+                &mut rng,
+                vec![
+                    Esk::SymKeyEncryptedSessionKey(skesk),
+                    Esk::PublicKeyEncryptedSessionKey(pkesk),
+                ],
+                alg,
+                session_key.clone(),
+            )
+            .unwrap();
+        let parsed = Message::from_bytes(&encrypted.to_bytes().unwrap()[..]).unwrap();
+
+        let (decrypted, ids) = parsed.decrypt(|| "".into(), &[&skey]).unwrap();
+        assert_eq!(decrypted, lit_msg);
+        assert_eq!(ids, vec![pkey.key_id()]);
+        let decrypted = parsed.decrypt_with_password(|| "secret".into()).unwrap();
+        assert_eq!(decrypted, lit_msg);
+
+        assert!(lit_msg
+            .encrypt_with_session_key(
+                &mut rng,
+                Vec::new(),
+                SymmetricKeyAlgorithm::AES128,
+                session_key
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_encrypt_seipdv2_pkesk_v6() {
+        let mut rng = thread_rng();
+        let (skey, _headers) = SignedSecretKey::from_armor_single(
+            fs::File::open("./tests/autocrypt/alice@autocrypt.example.sec.asc").unwrap(),
+        )
+        .unwrap();
+        // subkey[0] is the encryption key
+        let pkey = skey.secret_subkeys[0].public_key();
+
+        let lit_msg = Message::new_literal("hello.txt", "hello world\n");
+        let encrypted = lit_msg
+            .encrypt_to_keys_seipdv2(
+                &mut rng,
+                SymmetricKeyAlgorithm::AES128,
+                AeadAlgorithm::Ocb,
+                6,
+                &[&pkey],
+            )
+            .unwrap();
+        let armored = encrypted.to_armored_bytes(None.into()).unwrap();
+        let parsed = Message::from_armor_single(&armored[..]).unwrap().0;
+        assert_eq!(parsed, encrypted);
+
+        let Message::Encrypted { ref esk, .. } = parsed else {
+            panic!("not encrypted");
+        };
+        let Esk::PublicKeyEncryptedSessionKey(ref pkesk) = esk[0] else {
+            panic!("no pkesk");
+        };
+        assert_eq!(pkesk.version(), 6);
+        assert_eq!(
+            pkesk.fingerprint(),
+            Some((KeyVersion::V4, &pkey.fingerprint()[..]))
+        );
+        assert_eq!(pkesk.id(), &pkey.key_id());
+        assert!(pkesk.is_recipient(&pkey));
+        assert!(!pkesk.is_recipient(&skey.primary_key));
+        assert!(matches!(
+            pkesk.decrypt(&skey.secret_subkeys[0], || "".into()),
+            Ok(PlainSessionKey::V6 { .. })
+        ));
+
+        let (decrypted, ids) = parsed.decrypt(|| "".into(), &[&skey]).unwrap();
+        assert_eq!(decrypted, lit_msg);
+        assert_eq!(ids, vec![pkey.key_id()]);
+    }
+
+    #[test]
+    fn test_decrypt_missing_key_error() {
+        let mut rng = thread_rng();
+        let (skey, _headers) = SignedSecretKey::from_armor_single(
+            fs::File::open("./tests/autocrypt/alice@autocrypt.example.sec.asc").unwrap(),
+        )
+        .unwrap();
+        let (other, _headers) = SignedSecretKey::from_armor_single(
+            fs::File::open("./tests/autocrypt/bob@autocrypt.example.sec.asc").unwrap(),
+        )
+        .unwrap();
+        // subkey[0] is the encryption key
+        let pkey = skey.secret_subkeys[0].public_key();
+
+        let lit_msg = Message::new_literal("hello.txt", "hello world\n");
+        let encrypted = lit_msg
+            .encrypt_to_keys(&mut rng, SymmetricKeyAlgorithm::AES128, &[&pkey])
+            .unwrap();
+        let err = encrypted.decrypt(|| "".into(), &[&other]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("no secret key for 0x{}", hex::encode_upper(pkey.key_id()))
+        );
+
+        let encrypted = lit_msg
+            .encrypt_to_keys_seipdv2(
+                &mut rng,
+                SymmetricKeyAlgorithm::AES128,
+                AeadAlgorithm::Ocb,
+                6,
+                &[&pkey],
+            )
+            .unwrap();
+        let err = encrypted.decrypt(|| "".into(), &[&other]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "no secret key for 0x{}",
+                hex::encode_upper(pkey.fingerprint())
+            )
+        );
+    }
+
+    #[test]
+    fn test_encrypt_to_recipients_seipd_negotiation() {
+        use crate::composed::{KeyType, SecretKeyParamsBuilder, SubkeyParamsBuilder};
+
+        let generate = |name: &str, aead: &[(SymmetricKeyAlgorithm, AeadAlgorithm)]| {
+            let secret = SecretKeyParamsBuilder::default()
+                .key_type(KeyType::EdDSA)
+                .can_certify(true)
+                .can_sign(true)
+                .primary_user_id(name.into())
+                .preferred_aead_algorithms(SmallVec::from_slice(aead))
+                .subkey(
+                    SubkeyParamsBuilder::default()
+                        .key_type(KeyType::ECDH)
+                        .can_encrypt(true)
+                        .build()
+                        .unwrap(),
+                )
+                .build()
+                .unwrap()
+                .generate()
+                .unwrap()
+                .sign(String::new)
+                .unwrap();
+            let public = secret.public_key().sign(&secret, String::new).unwrap();
+            (secret, public)
+        };
+        // without AEAD preferences no Features are announced
+        let (_, alice) = generate("Alice <alice@example.com>", &[]);
+        let (bob, bob_public) = generate(
+            "Bob <bob@example.com>",
+            &[(SymmetricKeyAlgorithm::AES128, AeadAlgorithm::Ocb)],
+        );
+
+        let now = chrono::Utc::now();
+        assert!(!alice.supports_seipd_v2(&now));
+        assert!(bob_public.supports_seipd_v2(&now));
+
+        let lit_msg = Message::new_literal("hello.txt", "hello world\n");
+        let encrypt = |recipients: &[&SignedPublicKey], policy| {
+            lit_msg.encrypt_to_recipients(
+                &mut thread_rng(),
+                SymmetricKeyAlgorithm::AES128,
+                AeadAlgorithm::Ocb,
+                recipients,
+                policy,
+            )
+        };
+
+        let (msg, version) = encrypt(&[&bob_public], SeipdVersionPolicy::Negotiate).unwrap();
+        assert_eq!(
+            version,
+            SeipdVersion::V2(SymmetricKeyAlgorithm::AES128, AeadAlgorithm::Ocb)
+        );
+        let (decrypted, _) = msg.decrypt(String::new, &[&bob]).unwrap();
+        assert_eq!(decrypted, lit_msg);
+
+        // alice only supports version 1
+        let (msg, version) =
+            encrypt(&[&alice, &bob_public], SeipdVersionPolicy::Negotiate).unwrap();
+        assert_eq!(version, SeipdVersion::V1);
+        let Message::Encrypted { ref esk, ref edata } = msg else {
+            panic!("not encrypted");
+        };
+        assert_eq!(esk.len(), 2);
+        assert_eq!(edata.version(), Some(1));
+        let (decrypted, _) = msg.decrypt(String::new, &[&bob]).unwrap();
+        assert_eq!(decrypted, lit_msg);
+
+        // forcing version 2 drops alice
+        let (msg, version) = encrypt(&[&alice, &bob_public], SeipdVersionPolicy::ForceV2).unwrap();
+        assert_eq!(
+            version,
+            SeipdVersion::V2(SymmetricKeyAlgorithm::AES128, AeadAlgorithm::Ocb)
+        );
+        let Message::Encrypted { ref esk, .. } = msg else {
+            panic!("not encrypted");
+        };
+        assert_eq!(esk.len(), 1);
+        assert!(encrypt(&[&alice], SeipdVersionPolicy::ForceV2).is_err());
+    }
+
+    #[test]
+    fn test_encrypt_to_recipients_negotiated() {
+        use crate::composed::{
+            KeyType, SecretKeyParamsBuilder, SubkeyParamsBuilder, DEFAULT_AEAD_CIPHERSUITE,
+        };
+
+        let generate = |name: &str, aead: &[(SymmetricKeyAlgorithm, AeadAlgorithm)]| {
+            let secret = SecretKeyParamsBuilder::default()
+                .key_type(KeyType::EdDSA)
+                .can_certify(true)
+                .can_sign(true)
+                .primary_user_id(name.into())
+                .preferred_aead_algorithms(SmallVec::from_slice(aead))
+                .subkey(
+                    SubkeyParamsBuilder::default()
+                        .key_type(KeyType::ECDH)
+                        .can_encrypt(true)
+                        .build()
+                        .unwrap(),
+                )
+                .build()
+                .unwrap()
+                .generate()
+                .unwrap()
+                .sign(String::new)
+                .unwrap();
+            let public = secret.public_key().sign(&secret, String::new).unwrap();
+            (secret, public)
+        };
+        let (bob, bob_public) = generate(
+            "Bob <bob@example.com>",
+            &[
+                (SymmetricKeyAlgorithm::AES256, AeadAlgorithm::Gcm),
+                (SymmetricKeyAlgorithm::AES128, AeadAlgorithm::Ocb),
+            ],
+        );
+        let (carol, carol_public) = generate(
+            "Carol <carol@example.com>",
+            &[
+                (SymmetricKeyAlgorithm::AES128, AeadAlgorithm::Ocb),
+                (SymmetricKeyAlgorithm::AES256, AeadAlgorithm::Gcm),
+            ],
+        );
+        let (_, dave) = generate(
+            "Dave <dave@example.com>",
+            &[(SymmetricKeyAlgorithm::AES128, AeadAlgorithm::Eax)],
+        );
+        let (_, erin) = generate(
+            "Erin <erin@example.com>",
+            &[(SymmetricKeyAlgorithm::Camellia256, AeadAlgorithm::Ocb)],
+        );
+        let (_, alice) = generate("Alice <alice@example.com>", &[]);
+
+        let lit_msg = Message::new_literal("hello.txt", "hello world\n");
+        let encrypt = |recipients: &[&SignedPublicKey]| {
+            lit_msg.encrypt_to_recipients_negotiated(
+                &mut thread_rng(),
+                SymmetricKeyAlgorithm::AES128,
+                recipients,
+                SeipdVersionPolicy::Negotiate,
+            )
+        };
+
+        // the preference order of the first recipient wins
+        let (msg, version) = encrypt(&[&bob_public, &carol_public]).unwrap();
+        assert_eq!(
+            version,
+            SeipdVersion::V2(SymmetricKeyAlgorithm::AES256, AeadAlgorithm::Gcm)
+        );
+        let Message::Encrypted { ref edata, .. } = msg else {
+            panic!("not encrypted");
+        };
+        assert_eq!(edata.version(), Some(2));
+        for key in [&bob, &carol] {
+            let (decrypted, _) = msg.decrypt(String::new, &[key]).unwrap();
+            assert_eq!(decrypted, lit_msg);
+        }
+        let (_, version) = encrypt(&[&carol_public, &bob_public]).unwrap();
+        assert_eq!(
+            version,
+            SeipdVersion::V2(SymmetricKeyAlgorithm::AES128, AeadAlgorithm::Ocb)
+        );
+
+        // no common preference
+        let (msg, version) = encrypt(&[&bob_public, &dave]).unwrap();
+        let (sym_alg, aead) = DEFAULT_AEAD_CIPHERSUITE;
+        assert_eq!(version, SeipdVersion::V2(sym_alg, aead));
+        let (decrypted, _) = msg.decrypt(String::new, &[&bob]).unwrap();
+        assert_eq!(decrypted, lit_msg);
+
+        // the only preference is not supported
+        assert!(matches!(encrypt(&[&erin]), Err(Error::Unsupported(_))));
+
+        // version 1
+        let (_, version) = encrypt(&[&bob_public, &alice]).unwrap();
+        assert_eq!(version, SeipdVersion::V1);
+    }
+
+    #[test]
+    fn test_encrypt_to_recipients_with_adsk() {
+        use crate::composed::{KeyType, SecretKeyParamsBuilder, SubkeyParamsBuilder};
+        use crate::packet::{KeyFlags, SubpacketData};
+
+        let encryption_subkey = || {
+            SubkeyParamsBuilder::default()
+                .key_type(KeyType::ECDH)
+                .can_encrypt(true)
+                .build()
+                .unwrap()
+        };
+        let secret = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::EdDSA)
+            .can_certify(true)
+            .primary_user_id("Me <me@example.com>".into())
+            .subkeys(vec![encryption_subkey(), encryption_subkey()])
+            .build()
+            .unwrap()
+            .generate()
+            .unwrap()
+            .sign(String::new)
+            .unwrap();
+        let mut public = SignedPublicKey::from(secret.clone());
+
+        // turn the second subkey into an ADSK
+        let adsk = &mut public.public_subkeys[1];
+        let mut config = adsk.signatures[0].config.clone();
+        let mut flags = KeyFlags::default();
+        flags.set_adsk(true);
+        for p in &mut config.hashed_subpackets {
+            if let SubpacketData::KeyFlags(ref mut f) = p.data {
+                *f = flags.into();
+            }
+        }
+        adsk.signatures[0] = config
+            .sign_key_binding(&secret, String::new, &adsk.key)
+            .unwrap();
+        public.verify().unwrap();
+
+        let now = chrono::Utc::now();
+        let adsk_id = public.public_subkeys[1].key_id();
+        assert_eq!(public.encryption_subkeys(now).len(), 1);
+        let adsk_subkeys = public.adsk_subkeys(now);
+        assert_eq!(adsk_subkeys.len(), 1);
+        assert_eq!(adsk_subkeys[0].key_id(), adsk_id);
+        assert!(adsk_subkeys[0].signatures[0].key_flags().adsk());
+
+        let lit_msg = Message::new_literal("hello.txt", "hello world\n");
+        let recipient_ids = |msg: &Message| {
+            let Message::Encrypted { esk, .. } = msg else {
+                panic!("not encrypted");
+            };
+            esk.iter()
+                .map(|esk| match esk {
+                    Esk::PublicKeyEncryptedSessionKey(pkesk) => pkesk.id().clone(),
+                    _ => panic!("unexpected esk"),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let (msg, _) = lit_msg
+            .encrypt_to_recipients(
+                &mut thread_rng(),
+                SymmetricKeyAlgorithm::AES128,
+                AeadAlgorithm::Ocb,
+                &[&public],
+                SeipdVersionPolicy::Negotiate,
+            )
+            .unwrap();
+        assert_eq!(recipient_ids(&msg), vec![public.public_subkeys[0].key_id()]);
+
+        let (msg, _) = lit_msg
+            .encrypt_to_recipients_with_adsk(
+                &mut thread_rng(),
+                SymmetricKeyAlgorithm::AES128,
+                AeadAlgorithm::Ocb,
+                &[&public],
+                SeipdVersionPolicy::Negotiate,
+            )
+            .unwrap();
+        assert_eq!(
+            recipient_ids(&msg),
+            vec![public.public_subkeys[0].key_id(), adsk_id]
+        );
+
+        // only the ADSK can decrypt
+        let mut escrow = secret.clone();
+        escrow.secret_subkeys.remove(0);
+        let (decrypted, _) = msg.decrypt(String::new, &[&escrow]).unwrap();
+        assert_eq!(decrypted, lit_msg);
+    }
+
+    #[test]
+    fn test_compress_with_policy() {
+        let text = Message::new_literal("hello.txt", &"hello world\n".repeat(100));
+        let png = Message::new_literal_bytes("image.png", b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR");
+        let zip = Message::new_literal_bytes("archive.zip", b"PK\x03\x04\x14\0");
+
+        let (compressed, done) = text
+            .compress_with_policy(CompressionPolicy::Auto(CompressionAlgorithm::ZLIB))
+            .unwrap();
+        assert!(done);
+        assert!(matches!(compressed, Message::Compressed(_)));
+        assert_eq!(compressed.decompress().unwrap(), text);
+
+        for msg in [&png, &zip] {
+            let (skipped, done) = msg
+                .compress_with_policy(CompressionPolicy::Auto(CompressionAlgorithm::ZLIB))
+                .unwrap();
+            assert!(!done);
+            assert_eq!(&skipped, msg);
+
+            let (compressed, done) = msg
+                .compress_with_policy(CompressionPolicy::Always(CompressionAlgorithm::ZLIB))
+                .unwrap();
+            assert!(done);
+            assert!(matches!(compressed, Message::Compressed(_)));
+        }
+    }
+
+    #[test]
+    fn test_padding_packet_sizes() {
+        let mut rng = thread_rng();
+        for policy in [PaddingPolicy::PowerOfTwo, PaddingPolicy::Multiple(512)] {
+            for len in (0..9000).step_by(7) {
+                let packet = padding_packet(&mut rng, len, policy).unwrap();
+                let padded = len + packet.to_bytes().unwrap().len();
+                match policy {
+                    PaddingPolicy::PowerOfTwo => assert!(padded.is_power_of_two(), "{len}"),
+                    PaddingPolicy::Multiple(size) => assert_eq!(padded % size, 0, "{len}"),
+                }
+                assert!(padded < 2 * (len + 6).max(512), "{len}");
+            }
+        }
+        assert!(padding_packet(&mut rng, 10, PaddingPolicy::Multiple(0)).is_err());
+    }
+
+    #[test]
+    fn test_encrypt_legacy_sed() {
+        let mut rng = thread_rng();
+        let (skey, _headers) = SignedSecretKey::from_armor_single(
+            fs::File::open("./tests/autocrypt/alice@autocrypt.example.sec.asc").unwrap(),
+        )
+        .unwrap();
+        // subkey[0] is the encryption key
+        let pkey = skey.secret_subkeys[0].public_key();
+
+        let msg = Message::new_literal("hello.txt", "hello world\n");
+        let encrypted = msg
+            .encrypt_to_keys_legacy_sed(&mut rng, SymmetricKeyAlgorithm::AES128, &[&pkey])
+            .unwrap();
+        let Message::Encrypted { ref edata, .. } = encrypted else {
+            panic!("not encrypted");
+        };
+        assert_eq!(edata.tag(), Tag::SymEncryptedData);
+
+        let armored = encrypted.to_armored_bytes(None.into()).unwrap();
+        let parsed = Message::from_armor_single(&armored[..]).unwrap().0;
+        assert_eq!(parsed, encrypted);
+        let (decrypted, _) = parsed.decrypt_legacy_sed(|| "".into(), &[&skey]).unwrap();
+        assert_eq!(decrypted, msg);
+
+        let Message::Encrypted { edata, .. } = msg
+            .encrypt_to_keys(&mut rng, SymmetricKeyAlgorithm::AES128, &[&pkey])
+            .unwrap()
+        else {
+            panic!("not encrypted");
+        };
+        assert_eq!(edata.tag(), Tag::SymEncryptedProtectedData);
+    }
+
+    #[test]
+    fn test_decrypt_rejects_legacy_sed() {
+        let mut rng = thread_rng();
+        let (skey, _headers) = SignedSecretKey::from_armor_single(
+            fs::File::open("./tests/autocrypt/alice@autocrypt.example.sec.asc").unwrap(),
+        )
+        .unwrap();
+        let pkey = skey.secret_subkeys[0].public_key();
+
+        let encrypted = Message::new_literal("hello.txt", "hello world\n")
+            .encrypt_to_keys_legacy_sed(&mut rng, SymmetricKeyAlgorithm::AES128, &[&pkey])
+            .unwrap();
+        let err = encrypted.decrypt(|| "".into(), &[&skey]).unwrap_err();
+        assert!(err.to_string().contains("Symmetrically Encrypted Data"));
+
+        // also when wrapped in another layer
+        let compressed = Message::Compressed(CompressedData::from_compressed(
+            CompressionAlgorithm::Uncompressed,
+            encrypted.to_bytes().unwrap(),
+        ));
+        assert!(compressed.decrypt(|| "".into(), &[&skey]).is_err());
+        compressed
+            .decrypt_legacy_sed(|| "".into(), &[&skey])
+            .unwrap();
+    }
+
+    #[test]
+    fn test_encrypt_with_padding() {
+        let mut rng = thread_rng();
+        let (skey, _headers) = SignedSecretKey::from_armor_single(
+            fs::File::open("./tests/autocrypt/alice@autocrypt.example.sec.asc").unwrap(),
+        )
+        .unwrap();
+        // subkey[0] is the encryption key
+        let pkey = skey.secret_subkeys[0].public_key();
+
+        let short = Message::new_literal_bytes("hello.txt", &b"hello"[..]);
+        let long = Message::new_literal_bytes("hello.txt", &[b'a'; 200][..]);
+
+        let mut sizes = Vec::new();
+        for msg in [&short, &long] {
+            let encrypted = msg
+                .encrypt_to_keys_with_padding(
+                    &mut rng,
+                    SymmetricKeyAlgorithm::AES128,
+                    &[&pkey],
+                    PaddingPolicy::Multiple(256),
+                )
+                .unwrap();
+            let Message::Encrypted { ref edata, .. } = encrypted else {
+                panic!("not encrypted");
+            };
+            sizes.push(edata.data().len());
+
+            let armored = encrypted.to_armored_bytes(None.into()).unwrap();
+            let parsed = Message::from_armor_single(&armored[..]).unwrap().0;
+            let (decrypted, _) = parsed.decrypt(|| "".into(), &[&skey]).unwrap();
+            assert_eq!(&decrypted, msg);
+
+            let s2k = StringToKey::new_default(&mut rng);
+            let encrypted = msg
+                .encrypt_with_password_with_padding(
+                    &mut rng,
+                    s2k,
+                    SymmetricKeyAlgorithm::AES128,
+                    || "secret".into(),
+                    PaddingPolicy::PowerOfTwo,
+                )
+                .unwrap();
+            let decrypted = encrypted.decrypt_with_password(|| "secret".into()).unwrap();
+            assert_eq!(&decrypted, msg);
+        }
+        assert_eq!(sizes[0], sizes[1]);
+    }
+
+    #[test]
+    fn test_outermost_compression() {
+        let mut rng = thread_rng();
+        let (skey, _headers) = SignedSecretKey::from_armor_single(
+            fs::File::open("./tests/autocrypt/alice@autocrypt.example.sec.asc").unwrap(),
+        )
+        .unwrap();
+        let pkey = skey.public_key();
+
+        let lit_msg = Message::new_literal_bytes("hello.txt", &b"hello world\n"[..]);
+
+        // compressed, then signed
+        let compressed_signed = lit_msg
+            .clone()
+            .sign(&skey, || "".into(), HashAlgorithm::SHA2_256)
+            .unwrap()
+            .compress(CompressionAlgorithm::ZLIB)
+            .unwrap();
+        let parsed = Message::from_bytes(&compressed_signed.to_bytes().unwrap()[..]).unwrap();
+        assert!(matches!(parsed, Message::Compressed(_)));
+        parsed.verify(&pkey).unwrap();
+        assert_eq!(
+            parsed.get_content().unwrap().unwrap(),
+            b"hello world\n".to_vec()
+        );
+        assert!(matches!(
+            parsed.decompress().unwrap(),
+            Message::Signed { .. }
+        ));
+
+        // a signature over compressed data
+        let signed_compressed = lit_msg
+            .compress(CompressionAlgorithm::ZIP)
+            .unwrap()
+            .sign(&skey, || "".into(), HashAlgorithm::SHA2_256)
+            .unwrap();
+        let parsed = Message::from_bytes(&signed_compressed.to_bytes().unwrap()[..]).unwrap();
+        parsed.verify(&pkey).unwrap();
+        assert_eq!(
+            parsed.get_content().unwrap().unwrap(),
+            b"hello world\n".to_vec()
+        );
+
+        // encrypted data inside compressed data
+        let s2k = StringToKey::new_default(&mut rng);
+        let encrypted = lit_msg
+            .encrypt_with_password(&mut rng, s2k, SymmetricKeyAlgorithm::AES128, || {
+                "secret".into()
+            })
+            .unwrap();
+        let compressed_encrypted = encrypted.compress(CompressionAlgorithm::ZLIB).unwrap();
+        let parsed = Message::from_bytes(&compressed_encrypted.to_bytes().unwrap()[..]).unwrap();
+        let decrypted = parsed.decrypt_with_password(|| "secret".into()).unwrap();
+        assert_eq!(decrypted, lit_msg);
+
+        // only a single layer is decompressed
+        let twice = compressed_encrypted
+            .compress(CompressionAlgorithm::ZLIB)
+            .unwrap();
+        assert!(twice.decrypt_with_password(|| "secret".into()).is_err());
+    }
+
+    #[test]
+    fn test_max_nesting_depth() {
+        let mut rng = thread_rng();
+        let (skey, _headers) = SignedSecretKey::from_armor_single(
+            fs::File::open("./tests/autocrypt/alice@autocrypt.example.sec.asc").unwrap(),
+        )
+        .unwrap();
+        let pkey = skey.public_key();
+
+        let lit_msg = Message::new_literal("hello.txt", "hello world\n");
+
+        let mut nested = lit_msg.clone();
+        for _ in 0..DEFAULT_MAX_NESTING_DEPTH + 4 {
+            nested = nested
+                .sign(&skey, || "".into(), HashAlgorithm::SHA2_256)
+                .unwrap();
+        }
+        let bytes = nested.to_bytes().unwrap();
+        assert!(Message::from_bytes(&bytes[..]).is_err());
+        assert!(Message::from_bytes_with_max_depth(&bytes[..], DEFAULT_MAX_NESTING_DEPTH).is_err());
+        let parsed =
+            Message::from_bytes_with_max_depth(&bytes[..], DEFAULT_MAX_NESTING_DEPTH + 4).unwrap();
+        parsed.verify(&pkey).unwrap();
+
+        // compressed data nested in compressed data
+        let mut compressed = lit_msg.clone();
+        for _ in 0..DEFAULT_MAX_NESTING_DEPTH + 4 {
+            compressed = compressed.compress(CompressionAlgorithm::ZLIB).unwrap();
+        }
+        let parsed = Message::from_bytes(&compressed.to_bytes().unwrap()[..]).unwrap();
+        assert!(parsed.get_content().is_err());
+        assert!(parsed.decrypt_with_password(|| "secret".into()).is_err());
+
+        // compressed data nested in signed layers, nested in compressed data
+        let s2k = StringToKey::new_default(&mut rng);
+        let mut layered = lit_msg
+            .encrypt_with_password(&mut rng, s2k, SymmetricKeyAlgorithm::AES128, || {
+                "secret".into()
+            })
+            .unwrap();
+        for _ in 0..4 {
+            layered = layered
+                .compress(CompressionAlgorithm::ZLIB)
+                .unwrap()
+                .sign(&skey, || "".into(), HashAlgorithm::SHA2_256)
+                .unwrap();
+        }
+        let parsed = Message::from_bytes(&layered.to_bytes().unwrap()[..]).unwrap();
+        assert!(parsed.decrypt_with_password(|| "secret".into()).is_err());
+    }
+
     #[test]
     fn test_rsa_signing_string() {
         for _ in 0..100 {
@@ -4,12 +4,14 @@ pub mod message;
 pub mod signed_key;
 
 mod any;
+mod preserved;
 mod shared;
 mod signature;
 
 pub use self::any::Any;
 pub use self::key::*;
 pub use self::message::*;
+pub use self::preserved::Preserved;
 pub use self::shared::Deserializable;
 pub use self::signature::*;
 pub use self::signed_key::*;
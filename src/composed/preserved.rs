@@ -0,0 +1,237 @@
+use std::borrow::Cow;
+use std::io::{self, BufRead, Read};
+
+use buffer_redux::BufReader;
+
+use crate::armor;
+use crate::composed::shared::{filter_parsed_packet_results, Deserializable};
+use crate::errors::{Error, Result};
+use crate::packet::single;
+use crate::ser::Serialize;
+use crate::types::{PacketLength, Tag, Version};
+
+/// A composition, such as a [`SignedPublicKey`](crate::composed::SignedPublicKey), parsed
+/// together with the exact bytes of the packets it was parsed from.
+///
+/// Serializing writes back the original packets unchanged, including packets that were not
+/// understood or are not represented in the parsed value, and details like old style packet
+/// headers. This matters for signatures made by other implementations, which may depend on
+/// bytes that a regular parse and serialize round trip normalizes away.
+///
+/// The parsed value is read-only, use [`Preserved::into_inner`] to modify it, which drops the
+/// original bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Preserved<T> {
+    inner: T,
+    packets: Vec<Vec<u8>>,
+}
+
+impl<T: Deserializable> Preserved<T> {
+    /// Parses a single byte encoded composition, keeping the bytes of all packets.
+    pub fn from_bytes(mut bytes: impl Read) -> Result<Self> {
+        let mut data = Vec::new();
+        bytes.read_to_end(&mut data)?;
+
+        let raw_packets = split_packets(&data)?;
+        let packets = raw_packets
+            .iter()
+            .map(RawPacket::parse)
+            .filter_map(filter_parsed_packet_results);
+        let mut el = T::from_packets(packets.peekable());
+        let inner = el.next().ok_or(Error::NoMatchingPacket)??;
+        if el.next().is_some() {
+            return Err(Error::TooManyPackets);
+        }
+
+        Ok(Preserved {
+            inner,
+            packets: raw_packets.iter().map(|p| p.bytes.to_vec()).collect(),
+        })
+    }
+
+    /// Parses a single armor encoded composition, keeping the bytes of all packets.
+    pub fn from_armor_single<R: Read>(input: R) -> Result<(Self, armor::Headers)> {
+        Self::from_armor_single_buf(BufReader::new(input))
+    }
+
+    /// Parses a single armor encoded composition, keeping the bytes of all packets.
+    pub fn from_armor_single_buf<R: BufRead>(input: R) -> Result<(Self, armor::Headers)> {
+        let mut dearmor = armor::Dearmor::new(input);
+        dearmor.read_header()?;
+        let typ = dearmor
+            .typ
+            .ok_or_else(|| format_err!("dearmor failed to retrieve armor type"))?;
+        if !T::matches_block_type(typ) {
+            bail!("unexpected block type: {}", typ);
+        }
+        let headers = dearmor.headers.clone();
+
+        Ok((Self::from_bytes(dearmor)?, headers))
+    }
+}
+
+impl<T> Preserved<T> {
+    /// The parsed value.
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns the parsed value, dropping the original bytes.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// The original bytes of each packet, including its header.
+    pub fn packets(&self) -> impl Iterator<Item = &[u8]> {
+        self.packets.iter().map(|p| &p[..])
+    }
+}
+
+impl<T> AsRef<T> for Preserved<T> {
+    fn as_ref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T> Serialize for Preserved<T> {
+    fn to_writer<W: io::Write>(&self, writer: &mut W) -> Result<()> {
+        for packet in &self.packets {
+            writer.write_all(packet)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A packet as found in the input.
+struct RawPacket<'a> {
+    version: Version,
+    tag: Tag,
+    /// The complete packet, including the header.
+    bytes: &'a [u8],
+    /// The packet body, reassembled if it used partial body lengths.
+    body: Cow<'a, [u8]>,
+}
+
+impl RawPacket<'_> {
+    fn parse(&self) -> Result<crate::packet::Packet> {
+        match single::body_parser(self.version, self.tag, &self.body) {
+            Err(Error::Incomplete(_)) => Err(Error::PacketIncomplete),
+            res => res,
+        }
+    }
+}
+
+/// Splits `data` into packets, without parsing their bodies.
+fn split_packets(data: &[u8]) -> Result<Vec<RawPacket<'_>>> {
+    let mut packets = Vec::new();
+    let mut rest = data;
+
+    while !rest.is_empty() {
+        let (after_header, (version, tag, len)) =
+            single::parser(rest).map_err(|err| match err {
+                nom::Err::Incomplete(_) => Error::PacketIncomplete,
+                err => err.into(),
+            })?;
+        let header_len = rest.len() - after_header.len();
+
+        let (packet_len, body) = match len {
+            PacketLength::Fixed(len) => {
+                ensure!(after_header.len() >= len, "packet body is incomplete");
+                (header_len + len, Cow::Borrowed(&after_header[..len]))
+            }
+            PacketLength::Indeterminate => (rest.len(), Cow::Borrowed(after_header)),
+            PacketLength::Partial(len) => {
+                let mut body = Vec::new();
+                let mut chunk_len = len;
+                let mut i = after_header;
+                loop {
+                    ensure!(i.len() >= chunk_len, "packet body is incomplete");
+                    body.extend_from_slice(&i[..chunk_len]);
+                    let (next, len) = single::read_packet_len(&i[chunk_len..])?;
+                    i = next;
+                    match len {
+                        PacketLength::Partial(len) => chunk_len = len,
+                        PacketLength::Fixed(len) => {
+                            ensure!(i.len() >= len, "packet body is incomplete");
+                            body.extend_from_slice(&i[..len]);
+                            i = &i[len..];
+                            break;
+                        }
+                        PacketLength::Indeterminate => return Err(Error::InvalidInput),
+                    }
+                }
+                (rest.len() - i.len(), Cow::Owned(body))
+            }
+        };
+
+        packets.push(RawPacket {
+            version,
+            tag,
+            bytes: &rest[..packet_len],
+            body,
+        });
+        rest = &rest[packet_len..];
+    }
+
+    Ok(packets)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    use crate::composed::SignedPublicKey;
+    use crate::packet::PacketParser;
+
+    #[test]
+    fn test_preserved_roundtrip() {
+        let (key, _headers) = SignedPublicKey::from_armor_single(
+            std::fs::File::open("./tests/autocrypt/alice@autocrypt.example.pub.asc").unwrap(),
+        )
+        .unwrap();
+
+        // old style headers and an unsupported packet, which a regular round trip normalizes away
+        let mut bytes = Vec::new();
+        for (i, packet) in PacketParser::new(&key.to_bytes().unwrap()[..]).enumerate() {
+            packet
+                .unwrap()
+                .to_writer_with_version(&mut bytes, Version::Old)
+                .unwrap();
+            if i == 0 {
+                // a signature with an unknown version after the primary key, which is skipped
+                bytes.extend_from_slice(&[0xc2, 3, 42, 0, 0]);
+            }
+        }
+        let normalized = SignedPublicKey::from_bytes(&bytes[..])
+            .unwrap()
+            .to_bytes()
+            .unwrap();
+        assert_ne!(normalized, bytes);
+
+        let preserved = Preserved::<SignedPublicKey>::from_bytes(&bytes[..]).unwrap();
+        assert_eq!(preserved.inner(), &key);
+        assert_eq!(preserved.to_bytes().unwrap(), bytes);
+        assert_eq!(
+            preserved.packets().count(),
+            PacketParser::new(&bytes[..]).count()
+        );
+
+        let mut armored = Vec::new();
+        armor::write(
+            &preserved,
+            armor::BlockType::PublicKey,
+            &mut armored,
+            None,
+            true,
+        )
+        .unwrap();
+        let (from_armor, _headers) =
+            Preserved::<SignedPublicKey>::from_armor_single(&armored[..]).unwrap();
+        assert_eq!(from_armor, preserved);
+
+        assert!(Preserved::<SignedPublicKey>::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
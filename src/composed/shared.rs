@@ -82,6 +82,9 @@ pub trait Deserializable: Sized {
             | BlockType::PrivateKeyOpenssh => {
                 unimplemented_err!("key format {:?}", typ);
             }
+            BlockType::Custom => {
+                bail!("unexpected block type: {}", typ)
+            }
         }
     }
 
@@ -104,6 +107,10 @@ pub trait Deserializable: Sized {
     ///
     /// Returns a composition and a BTreeMap containing armor headers
     /// (None, if the data was unarmored)
+    ///
+    /// Packets are parsed one after the other as they are read, so `input` can be a socket
+    /// that doesn't provide all data up front. The body of the packet currently being parsed,
+    /// including all chunks of partial body lengths, is held in memory.
     #[allow(clippy::type_complexity)]
     fn from_reader_single<'a, R: Read + 'a>(input: R) -> Result<(Self, Option<armor::Headers>)> {
         Self::from_reader_single_buf(BufReader::new(input))
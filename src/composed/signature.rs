@@ -1,11 +1,12 @@
+use std::io::Read;
 use std::iter::Peekable;
 
-use crate::composed::Deserializable;
+use crate::composed::{Deserializable, SignedPublicKey};
 use crate::errors::Result;
-use crate::packet::{Packet, Signature};
+use crate::packet::{self, Packet, PacketTrait, Signature};
 use crate::ser::Serialize;
-use crate::types::PublicKeyTrait;
 use crate::types::Tag;
+use crate::types::{KeyTrait, PublicKeyTrait};
 use crate::{armor, ArmorOptions};
 
 /// Standalone signature as defined by the cleartext framework.
@@ -24,12 +25,13 @@ impl StandaloneSignature {
         writer: &mut impl std::io::Write,
         opts: ArmorOptions<'_>,
     ) -> Result<()> {
-        armor::write(
+        armor::write_with_line_width(
             self,
             armor::BlockType::Signature,
             writer,
             opts.headers,
             opts.include_checksum,
+            opts.line_width,
         )
     }
 
@@ -50,6 +52,83 @@ impl StandaloneSignature {
     pub fn verify(&self, key: &impl PublicKeyTrait, content: &[u8]) -> Result<()> {
         self.signature.verify(key, content)
     }
+
+    /// Verify this signature against content read from `content`.
+    ///
+    /// The data is streamed through the hasher, so arbitrarily large inputs can be
+    /// verified with bounded memory. Line endings are normalized on the fly for text
+    /// signatures.
+    pub fn verify_reader(&self, key: &impl PublicKeyTrait, content: impl Read) -> Result<()> {
+        self.signature.verify(key, content)
+    }
+
+    /// Verify this signature with the signer's key embedded in its key block subpacket,
+    /// see [`UnauthenticatedKey`].
+    pub fn verify_with_key_block(&self, content: &[u8]) -> Result<UnauthenticatedKey> {
+        let key = UnauthenticatedKey::from_signature(&self.signature)?;
+        self.verify(&key.signer(&self.signature)?, content)?;
+
+        Ok(key)
+    }
+}
+
+/// A key taken from the key block subpacket of a signature, which was verified with it.
+///
+/// The key is self-consistent and made the signature, but the signature can't vouch for the
+/// key that verifies it: nothing establishes that the key belongs to whom its User IDs claim.
+/// At most it can be trusted on first use (TOFU), like keys discovered through Autocrypt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnauthenticatedKey(SignedPublicKey);
+
+impl UnauthenticatedKey {
+    /// Parses the key from the key block subpacket of `signature` and checks its
+    /// self-signatures.
+    pub(crate) fn from_signature(signature: &Signature) -> Result<Self> {
+        let key_block = signature
+            .key_block()
+            .ok_or_else(|| format_err!("signature has no key block"))?;
+        let key = SignedPublicKey::from_bytes(key_block)?;
+        key.verify()?;
+
+        Ok(UnauthenticatedKey(key))
+    }
+
+    /// The primary key or subkey of this key that issued `signature`.
+    pub(crate) fn signer(&self, signature: &Signature) -> Result<packet::PublicKey> {
+        if Signature::match_identity(signature, &self.0.primary_key) {
+            return Ok(self.0.primary_key.clone());
+        }
+
+        let subkey = self
+            .0
+            .public_subkeys
+            .iter()
+            .find(|subkey| Signature::match_identity(signature, &subkey.key))
+            .ok_or_else(|| {
+                format_err!(
+                    "signature was not issued by the key {} from its key block",
+                    self.0.key_id()
+                )
+            })?;
+        packet::PublicKey::new(
+            subkey.key.packet_version(),
+            subkey.key.version(),
+            subkey.key.algorithm(),
+            *subkey.key.created_at(),
+            subkey.key.expiration(),
+            subkey.key.public_params().clone(),
+        )
+    }
+
+    /// The key, whose authenticity is not established.
+    pub fn inner(&self) -> &SignedPublicKey {
+        &self.0
+    }
+
+    /// Returns the key, whose authenticity is not established.
+    pub fn into_inner(self) -> SignedPublicKey {
+        self.0
+    }
 }
 
 impl Serialize for StandaloneSignature {
@@ -95,3 +174,94 @@ fn next<I: Iterator<Item = Result<Packet>>>(
         None => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    use chrono::SubsecRound;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use crate::composed::{KeyType, SecretKeyParamsBuilder};
+    use crate::packet::{SignatureConfig, SignatureType, Subpacket, SubpacketData};
+    use crate::test_util::ChunkedReader;
+    use crate::types::{KeyTrait, SecretKeyTrait};
+
+    /// A reader failing after handing out `data`.
+    struct FailingReader<'a> {
+        data: &'a [u8],
+    }
+
+    impl Read for FailingReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.data.is_empty() {
+                return Err(std::io::Error::other("read failed"));
+            }
+            let n = self.data.read(buf)?;
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn verify_reader() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let key = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::EdDSA)
+            .can_sign(true)
+            .primary_user_id("Me <me@mail.com>".into())
+            .build()
+            .unwrap()
+            .generate_with_rng(&mut rng)
+            .unwrap()
+            .sign(String::new)
+            .unwrap();
+
+        let sign = |typ, data: &[u8]| {
+            let config = SignatureConfig::new_v4(
+                Default::default(),
+                typ,
+                key.algorithm(),
+                key.hash_alg(),
+                vec![Subpacket::regular(SubpacketData::SignatureCreationTime(
+                    chrono::Utc::now().trunc_subsecs(0),
+                ))],
+                vec![Subpacket::regular(SubpacketData::Issuer(key.key_id()))],
+            );
+            StandaloneSignature::new(config.sign(&key, String::new, data).unwrap())
+        };
+
+        let data: Vec<u8> = (0..10_000u32).flat_map(|i| i.to_be_bytes()).collect();
+        let sig = sign(SignatureType::Binary, &data);
+        sig.verify_reader(&key, ChunkedReader::new(&data, 3))
+            .unwrap();
+        assert!(sig
+            .verify_reader(&key, ChunkedReader::new(&data[1..], 3))
+            .is_err());
+
+        // text signatures are made over CRLF normalized data
+        let sig = sign(SignatureType::Text, b"hello\r\nworld\r\n");
+        sig.verify_reader(&key, ChunkedReader::new(b"hello\nworld\n", 3))
+            .unwrap();
+        sig.verify_reader(&key, ChunkedReader::new(b"hello\r\nworld\r\n", 3))
+            .unwrap();
+        assert!(sig
+            .verify_reader(&key, ChunkedReader::new(b"hello\nworld", 3))
+            .is_err());
+
+        // read errors are returned, not treated as the end of the data
+        for (typ, data) in [
+            (SignatureType::Binary, &b"hello world"[..]),
+            (SignatureType::Text, &b"hello\r\nworld\r\n"[..]),
+        ] {
+            let sig = sign(typ, data);
+            let err = sig.verify_reader(&key, FailingReader { data }).unwrap_err();
+            assert!(
+                matches!(err, crate::errors::Error::IOError { .. }),
+                "{err:?}"
+            );
+        }
+    }
+}
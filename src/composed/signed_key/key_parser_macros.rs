@@ -60,7 +60,7 @@ macro_rules! key_parser {
                 debug!("primary key: {:?}", primary_key.key_id());
 
                 // -- Zero or more revocation signatures
-                // -- followed by zero or more direct signatures in V4 keys
+                // -- followed by zero or more direct signatures in V4 and V6 keys
                 debug!("  signatures");
                 let mut revocation_signatures = Vec::new();
                 let mut direct_signatures = Vec::new();
@@ -77,7 +77,7 @@ macro_rules! key_parser {
                             if typ == SignatureType::KeyRevocation {
                                 revocation_signatures.push(sig);
                             } else {
-                                if primary_key.version() != KeyVersion::V4 {
+                                if !matches!(primary_key.version(), KeyVersion::V4 | KeyVersion::V6) {
                                     // no direct signatures on V2|V3 keys
                                     warn!("unexpected signature: {:?}", typ);
                                 }
@@ -170,9 +170,9 @@ macro_rules! key_parser {
                         $( p.tag() == Tag::$subkey_tag || )* false
                     }
                 )) {
-                    // -- Only V4 keys should have sub keys
-                    if primary_key.version() != KeyVersion::V4 {
-                        return Some(Err(format_err!("only V4 keys can have subkeys")));
+                    // -- Only V4 and V6 keys should have sub keys
+                    if !matches!(primary_key.version(), KeyVersion::V4 | KeyVersion::V6) {
+                        return Some(Err(format_err!("only V4 and V6 keys can have subkeys")));
                     }
 
                     let packet = match res {
@@ -2,13 +2,86 @@ use std::{io, iter};
 
 use buffer_redux::BufReader;
 
+use chrono::{DateTime, Utc};
+
 use crate::armor::{self, BlockType};
 use crate::composed::signed_key::{
     PublicOrSecret, SignedPublicKey, SignedPublicKeyParser, SignedSecretKey, SignedSecretKeyParser,
 };
+use crate::crypto::public_key::PublicKeyAlgorithm;
 use crate::errors::{Error, Result};
-use crate::packet::{Packet, PacketParser};
-use crate::types::Tag;
+use crate::packet::{Packet, PacketParser, UserId};
+use crate::types::{KeyId, KeyTrait, KeyVersion, Tag};
+
+/// Basic information about a transferable key, see [`peek_key_metadata`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyMetadata {
+    pub fingerprint: Vec<u8>,
+    pub key_id: KeyId,
+    pub algorithm: PublicKeyAlgorithm,
+    pub version: KeyVersion,
+    pub created_at: DateTime<Utc>,
+    /// `true` if the primary key is a secret key.
+    pub is_secret: bool,
+    /// The first User ID of the key, which is usually the primary one.
+    pub user_id: Option<UserId>,
+}
+
+/// Reads basic metadata of the first key in the given ASCII-armored or binary data.
+///
+/// Only the primary key packet and the packets up to the first User ID are parsed,
+/// and no signatures are verified. This is much cheaper than parsing the full key,
+/// e.g. for indexing large numbers of keys, but the returned data is not validated.
+pub fn peek_key_metadata(input: &[u8]) -> Result<KeyMetadata> {
+    let mut input = input;
+    if crate::composed::shared::is_binary(&mut input)? {
+        peek_key_metadata_packets(PacketParser::new(input))
+    } else {
+        let mut dearmor = armor::Dearmor::new(input);
+        dearmor.read_header()?;
+        peek_key_metadata_packets(PacketParser::new(dearmor))
+    }
+}
+
+fn peek_key_metadata_packets(packets: impl Iterator<Item = Result<Packet>>) -> Result<KeyMetadata> {
+    let mut packets = packets.filter_map(crate::composed::shared::filter_parsed_packet_results);
+
+    let mut metadata = match packets.next().ok_or(Error::NoMatchingPacket)?? {
+        Packet::PublicKey(key) => KeyMetadata {
+            fingerprint: key.fingerprint(),
+            key_id: key.key_id(),
+            algorithm: key.algorithm(),
+            version: key.version(),
+            created_at: *key.created_at(),
+            is_secret: false,
+            user_id: None,
+        },
+        Packet::SecretKey(key) => KeyMetadata {
+            fingerprint: key.fingerprint(),
+            key_id: key.key_id(),
+            algorithm: key.algorithm(),
+            version: key.version(),
+            created_at: *key.created_at(),
+            is_secret: true,
+            user_id: None,
+        },
+        packet => bail!("expected a primary key packet, found {:?}", packet.tag()),
+    };
+
+    for packet in packets {
+        match packet? {
+            Packet::UserId(id) => {
+                metadata.user_id = Some(id);
+                break;
+            }
+            Packet::Signature(_) | Packet::UserAttribute(_) | Packet::Trust(_) => {}
+            // subkeys or the next key, so there is no user id
+            _ => break,
+        }
+    }
+
+    Ok(metadata)
+}
 
 /// Parses a list of secret and public keys, from either ASCII-armored or binary OpenPGP data.
 ///
@@ -76,7 +149,8 @@ pub fn from_armor_many_buf<'a, R: io::BufRead + 'a>(
         BlockType::Message
         | BlockType::MultiPartMessage(_, _)
         | BlockType::Signature
-        | BlockType::CleartextMessage => {
+        | BlockType::CleartextMessage
+        | BlockType::Custom => {
             bail!("unexpected block type: {}", typ)
         }
         BlockType::PublicKeyPKCS1(_)
@@ -1,16 +1,25 @@
-use std::io;
+use std::{fmt, io};
 
-use chrono::{DateTime, Utc};
+use base64::engine::Engine as _;
+use chrono::{DateTime, Duration, Utc};
+use md5::{Digest, Md5};
 use rand::{CryptoRng, Rng};
 
 use crate::composed::key::{PublicKey, PublicSubkey};
+use crate::composed::message::Message;
 use crate::composed::signed_key::SignedKeyDetails;
+use crate::composed::Deserializable;
+use crate::crypto::aead::AeadAlgorithm;
+use crate::crypto::ecc_curve::ECCCurve;
 use crate::crypto::hash::HashAlgorithm;
 use crate::crypto::public_key::PublicKeyAlgorithm;
-use crate::errors::Result;
-use crate::packet::{self, write_packet, SignatureType};
+use crate::crypto::sym::SymmetricKeyAlgorithm;
+use crate::errors::{Error, Result};
+use crate::packet::{
+    self, write_packet, KeyFlags, PacketTrait, Signature, SignatureType, UserAttribute, UserId,
+};
 use crate::ser::Serialize;
-use crate::types::{KeyId, KeyTrait, Mpi, PublicKeyTrait};
+use crate::types::{KeyId, KeyTrait, KeyVersion, Mpi, PublicKeyTrait, PublicParams, Tag};
 use crate::{armor, ArmorOptions};
 
 /// Represents a Public PGP key, which is signed and either received or ready to be transferred.
@@ -35,6 +44,141 @@ key_parser!(
     )
 );
 
+/// What a primary key or subkey can be used for, see [`SignedPublicKey::key_capabilities`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyCapabilities {
+    pub key_id: KeyId,
+    pub fingerprint: Vec<u8>,
+    pub is_primary: bool,
+    pub algorithm: PublicKeyAlgorithm,
+    /// The curve of elliptic curve keys.
+    pub curve: Option<ECCCurve>,
+    /// The key flags, limited to the operations the algorithm can perform.
+    pub flags: KeyFlags,
+}
+
+impl KeyCapabilities {
+    fn new(
+        key: &impl KeyTrait,
+        public_params: &PublicParams,
+        is_primary: bool,
+        mut flags: KeyFlags,
+    ) -> Self {
+        if !key.is_signing_key() {
+            flags.set_certify(false);
+            flags.set_sign(false);
+            flags.set_authentication(false);
+        }
+        if !key.is_encryption_key() {
+            flags.set_encrypt_comms(false);
+            flags.set_encrypt_storage(false);
+            flags.set_adsk(false);
+        }
+
+        KeyCapabilities {
+            key_id: key.key_id(),
+            fingerprint: key.fingerprint(),
+            is_primary,
+            algorithm: key.algorithm(),
+            curve: public_params.curve(),
+            flags,
+        }
+    }
+}
+
+/// A defect of a key, found by [`SignedPublicKey::check`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyDefect {
+    /// The key is flagged for certification, signing or authentication, but its algorithm
+    /// can't make signatures.
+    CannotSign {
+        key_id: KeyId,
+        algorithm: PublicKeyAlgorithm,
+    },
+    /// The key is flagged for encryption, but its algorithm can't encrypt.
+    CannotEncrypt {
+        key_id: KeyId,
+        algorithm: PublicKeyAlgorithm,
+    },
+}
+
+impl KeyDefect {
+    /// The defects of `key`, whose self-signatures or binding signatures set `flags`.
+    fn flag_contradictions(key: &impl KeyTrait, flags: KeyFlags) -> Vec<KeyDefect> {
+        let mut defects = Vec::new();
+        if (flags.certify() || flags.sign() || flags.authentication()) && !key.is_signing_key() {
+            defects.push(KeyDefect::CannotSign {
+                key_id: key.key_id(),
+                algorithm: key.algorithm(),
+            });
+        }
+        if (flags.encrypt_comms() || flags.encrypt_storage() || flags.adsk())
+            && !key.is_encryption_key()
+        {
+            defects.push(KeyDefect::CannotEncrypt {
+                key_id: key.key_id(),
+                algorithm: key.algorithm(),
+            });
+        }
+
+        defects
+    }
+}
+
+impl fmt::Display for KeyDefect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyDefect::CannotSign { key_id, algorithm } => write!(
+                f,
+                "key {} is flagged for signing, but {:?} can't sign",
+                key_id, algorithm
+            ),
+            KeyDefect::CannotEncrypt { key_id, algorithm } => write!(
+                f,
+                "key {} is flagged for encryption, but {:?} can't encrypt",
+                key_id, algorithm
+            ),
+        }
+    }
+}
+
+/// The algorithms that [`SignedPublicKey::weak_preferences`] reports as weak.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlgorithmPolicy {
+    pub weak_symmetric_algs: Vec<SymmetricKeyAlgorithm>,
+    pub weak_hash_algs: Vec<HashAlgorithm>,
+}
+
+impl Default for AlgorithmPolicy {
+    /// Treats IDEA, TripleDES, CAST5, MD5 and SHA1 as weak.
+    fn default() -> Self {
+        AlgorithmPolicy {
+            weak_symmetric_algs: vec![
+                SymmetricKeyAlgorithm::IDEA,
+                SymmetricKeyAlgorithm::TripleDES,
+                SymmetricKeyAlgorithm::CAST5,
+            ],
+            weak_hash_algs: vec![HashAlgorithm::MD5, HashAlgorithm::SHA1],
+        }
+    }
+}
+
+/// A weak algorithm in the preferences of a key, found by [`SignedPublicKey::weak_preferences`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeakPref {
+    Symmetric(SymmetricKeyAlgorithm),
+    Hash(HashAlgorithm),
+}
+
+impl fmt::Display for WeakPref {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WeakPref::Symmetric(alg) => write!(f, "weak symmetric algorithm {:?}", alg),
+            WeakPref::Hash(alg) => write!(f, "weak hash algorithm {:?}", alg),
+        }
+    }
+}
+
 impl SignedPublicKey {
     pub fn new(
         primary_key: packet::PublicKey,
@@ -57,12 +201,382 @@ impl SignedPublicKey {
         }
     }
 
+    /// Parses a single transferable public key from base64 encoded binary packets,
+    /// without armor header, footer or checksum (e.g. a key embedded in a JSON field).
+    ///
+    /// Whitespace in `input` is ignored.
+    pub fn from_base64(input: &str) -> Result<Self> {
+        let input: String = input.split_ascii_whitespace().collect();
+        let bytes = base64::engine::general_purpose::STANDARD.decode(input)?;
+
+        let mut keys = Self::from_bytes_many(&bytes[..]);
+        let key = keys.next().ok_or(Error::NoMatchingPacket)??;
+        if keys.next().is_some() {
+            return Err(Error::TooManyPackets);
+        }
+
+        Ok(key)
+    }
+
+    /// Get the creation time of the primary key.
+    pub fn created_at(&self) -> &DateTime<Utc> {
+        self.primary_key.created_at()
+    }
+
+    /// Get the creation time of the primary key, as stored in the key packet, in seconds since
+    /// the Unix epoch.
+    pub fn created_at_unix(&self) -> u32 {
+        self.primary_key.created_at_unix()
+    }
+
+    /// Get the creation time of the newest self-signature of the primary key.
+    ///
+    /// This is when the preferences or expiration of the key were last updated, which can
+    /// be much later than [`Self::created_at`]. Signatures are not verified.
+    pub fn last_self_signed_at(&self) -> Option<&DateTime<Utc>> {
+        self.details
+            .last_self_signature_created_at(&self.primary_key)
+    }
+
+    /// Returns the User Attributes of this key, such as photos, see
+    /// [`UserAttribute::as_image`].
+    ///
+    /// Their signatures are available through `details.user_attributes`, and are verified
+    /// like those of User IDs, with [`SignedUserAttribute::verify`](crate::types::SignedUserAttribute::verify).
+    pub fn user_attributes(&self) -> impl Iterator<Item = &UserAttribute> {
+        self.details.user_attributes.iter().map(|attr| &attr.attr)
+    }
+
     /// Get the public key expiration as a date.
     pub fn expires_at(&self) -> Option<DateTime<Utc>> {
         let expiration = self.details.key_expiration_time()?;
         Some(*self.primary_key.created_at() + expiration)
     }
 
+    /// Returns `true` if `other` has the same primary key, by fingerprint.
+    ///
+    /// Unlike `==`, this ignores User IDs, signatures and subkeys, and can be used to
+    /// compare public and secret keys.
+    pub fn same_key_as(&self, other: &impl KeyTrait) -> bool {
+        self.primary_key.fingerprint() == other.fingerprint()
+    }
+
+    /// Computes the digest SKS keyservers use to identify keys when reconciling their key sets.
+    ///
+    /// **This is not a security feature.** The digest uses MD5, which is broken, and must only
+    /// serve as an identifier when talking to SKS compatible keyservers, never to check the
+    /// integrity or identity of a key, use [`KeyTrait::fingerprint`] and signature
+    /// verification for that.
+    ///
+    /// All packets are sorted by tag and then by their body, duplicates are removed, and each is
+    /// hashed as its tag and body length as big endian 32 bit integers, followed by the body.
+    /// The order of packets in the key doesn't matter, but the keyserver's copy must have the
+    /// same packets.
+    pub fn sks_digest(&self) -> Result<[u8; 16]> {
+        fn body(packet: &impl PacketTrait) -> Result<(u8, Vec<u8>)> {
+            Ok((packet.tag().into(), packet.to_bytes()?))
+        }
+
+        let details = &self.details;
+        let mut packets = vec![body(&self.primary_key)?];
+        for sig in details
+            .revocation_signatures
+            .iter()
+            .chain(&details.direct_signatures)
+        {
+            packets.push(body(sig)?);
+        }
+        for user in &details.users {
+            packets.push(body(&user.id)?);
+            for sig in &user.signatures {
+                packets.push(body(sig)?);
+            }
+        }
+        for attr in &details.user_attributes {
+            packets.push(body(&attr.attr)?);
+            for sig in &attr.signatures {
+                packets.push(body(sig)?);
+            }
+        }
+        for subkey in &self.public_subkeys {
+            packets.push(body(&subkey.key)?);
+            for sig in &subkey.signatures {
+                packets.push(body(sig)?);
+            }
+        }
+        packets.sort();
+        packets.dedup();
+
+        let mut hasher = Md5::new();
+        for (tag, body) in packets {
+            hasher.update(u32::from(tag).to_be_bytes());
+            hasher.update(u32::try_from(body.len())?.to_be_bytes());
+            hasher.update(&body);
+        }
+
+        Ok(hasher.finalize().into())
+    }
+
+    /// Returns the combined key flags of the primary key and all subkeys that are valid at `at`.
+    ///
+    /// A (sub)key contributes its flags if it has a valid self-signature (or binding signature)
+    /// made before `at`, and is neither expired nor revoked at that time. Signing subkeys
+    /// additionally need a valid back signature. If the primary key is not valid, no
+    /// capabilities are returned at all.
+    pub fn effective_capabilities(&self, at: DateTime<Utc>) -> KeyFlags {
+        let Some(mut flags) = self.primary_key_flags(&at) else {
+            return KeyFlags::default();
+        };
+
+        for subkey in &self.public_subkeys {
+            if let Some(subkey_flags) = subkey.key_flags_at(&self.primary_key, &at) {
+                flags |= subkey_flags;
+            }
+        }
+
+        flags
+    }
+
+    /// Lists the primary key and the subkeys that are valid at `at`, with the operations each
+    /// of them can perform.
+    ///
+    /// Unlike the raw key flags, operations the key's algorithm can't perform are left out,
+    /// as are signing operations of subkeys without a valid back signature.
+    pub fn key_capabilities(&self, at: DateTime<Utc>) -> Vec<KeyCapabilities> {
+        let Some(flags) = self.primary_key_flags(&at) else {
+            return Vec::new();
+        };
+
+        let primary = KeyCapabilities::new(
+            &self.primary_key,
+            self.primary_key.public_params(),
+            true,
+            flags,
+        );
+        let subkeys = self.public_subkeys.iter().filter_map(|subkey| {
+            let flags = subkey.key_flags_at(&self.primary_key, &at)?;
+            Some(KeyCapabilities::new(
+                &subkey.key,
+                subkey.key.public_params(),
+                false,
+                flags,
+            ))
+        });
+
+        std::iter::once(primary).chain(subkeys).collect()
+    }
+
+    /// Checks the key for defects, like key flags that contradict the algorithm of the key
+    /// they are set on, for example an ECDH subkey flagged for signing.
+    ///
+    /// Such flags are ignored elsewhere, see [`Self::key_capabilities`], but tooling may want to
+    /// reject malformed or maliciously crafted keys outright. Only the flags of validly made
+    /// self-signatures and binding signatures are considered, regardless of when they were made.
+    pub fn check(&self) -> Vec<KeyDefect> {
+        let key = &self.primary_key;
+        let user_sigs = self.details.users.iter().flat_map(|user| {
+            user.signatures
+                .iter()
+                .filter(move |sig| sig.verify_certification(key, Tag::UserId, &user.id).is_ok())
+        });
+        let direct_sigs = self
+            .details
+            .direct_signatures
+            .iter()
+            .filter(|sig| sig.verify_key(key).is_ok());
+        let primary_flags = user_sigs
+            .chain(direct_sigs)
+            .fold(KeyFlags::default(), |flags, sig| flags | sig.key_flags());
+
+        let mut defects = KeyDefect::flag_contradictions(key, primary_flags);
+        for subkey in &self.public_subkeys {
+            let flags = subkey
+                .signatures
+                .iter()
+                .filter(|sig| {
+                    sig.typ() == SignatureType::SubkeyBinding
+                        && sig.verify_key_binding(key, &subkey.key).is_ok()
+                })
+                .fold(KeyFlags::default(), |flags, sig| flags | sig.key_flags());
+            defects.extend(KeyDefect::flag_contradictions(&subkey.key, flags));
+        }
+
+        defects
+    }
+
+    /// Returns the subkeys that can be used for encryption at `at`.
+    ///
+    /// Both the subkey and the primary key must be valid at that time, see
+    /// [`Self::effective_capabilities`]. An expired or revoked primary key invalidates all of
+    /// its subkeys, even if they have not expired themselves.
+    pub fn encryption_subkeys(&self, at: DateTime<Utc>) -> Vec<&SignedPublicSubKey> {
+        self.valid_subkeys(at, |flags| flags.encrypt_comms() || flags.encrypt_storage())
+    }
+
+    /// Returns the additional decryption subkeys (ADSK) that are valid at `at`.
+    ///
+    /// These are not regular encryption subkeys, but are meant to be added as recipients when
+    /// encrypting to this key, see
+    /// [`crate::Message::encrypt_to_recipients_with_adsk`].
+    pub fn adsk_subkeys(&self, at: DateTime<Utc>) -> Vec<&SignedPublicSubKey> {
+        self.valid_subkeys(at, |flags| flags.adsk())
+    }
+
+    /// Returns the subkeys that can be used for signing at `at`.
+    ///
+    /// Like [`Self::encryption_subkeys`], the primary key must be valid at that time as well.
+    pub fn signing_subkeys(&self, at: DateTime<Utc>) -> Vec<&SignedPublicSubKey> {
+        self.valid_subkeys(at, KeyFlags::sign)
+    }
+
+    /// Returns the subkeys that are flagged for signing, but lack a valid back signature.
+    ///
+    /// See [`SignedPublicSubKey::requires_backsig`] and
+    /// [`SignedPublicSubKey::has_valid_backsig`].
+    pub fn subkeys_missing_backsig(&self) -> Vec<&SignedPublicSubKey> {
+        self.public_subkeys
+            .iter()
+            .filter(|subkey| {
+                subkey.requires_backsig(&self.primary_key)
+                    && !subkey.has_valid_backsig(&self.primary_key)
+            })
+            .collect()
+    }
+
+    fn valid_subkeys(
+        &self,
+        at: DateTime<Utc>,
+        usable: impl Fn(&KeyFlags) -> bool,
+    ) -> Vec<&SignedPublicSubKey> {
+        if self.primary_key_flags(&at).is_none() {
+            return Vec::new();
+        }
+
+        self.public_subkeys
+            .iter()
+            .filter(|subkey| {
+                subkey
+                    .key_flags_at(&self.primary_key, &at)
+                    .is_some_and(|flags| usable(&flags))
+            })
+            .collect()
+    }
+
+    /// Returns true if the key announces support for version 2 SEIPD packets at `at`, in the
+    /// Features subpacket of its self-signature.
+    ///
+    /// Version 6 keys always support them, as they require version 6 PKESK packets.
+    pub fn supports_seipd_v2(&self, at: &DateTime<Utc>) -> bool {
+        if self.primary_key.version() == KeyVersion::V6 {
+            return true;
+        }
+        self.primary_self_signature(at)
+            .and_then(|sig| sig.features().first())
+            .is_some_and(|features| features & FEATURE_SEIPD_V2 != 0)
+    }
+
+    /// The preferred AEAD ciphersuites of the self-signature that is current at `at`, empty if
+    /// there are none or the key is not valid at that time.
+    pub fn preferred_aead_ciphersuites(
+        &self,
+        at: &DateTime<Utc>,
+    ) -> &[(SymmetricKeyAlgorithm, AeadAlgorithm)] {
+        self.primary_self_signature(at)
+            .map(Signature::preferred_aead_ciphersuites)
+            .unwrap_or_default()
+    }
+
+    /// Returns true if the owner set the "No-modify" key server preference in the
+    /// self-signature that is current at `at`.
+    ///
+    /// Tools that upload keys to keyservers should only upload such keys with the owner's
+    /// consent.
+    pub fn key_server_no_modify(&self, at: &DateTime<Utc>) -> bool {
+        self.primary_self_signature(at)
+            .is_some_and(|sig| sig.key_server_no_modify())
+    }
+
+    /// Lists the algorithms in the preferences of the self-signature that is current at `at`
+    /// that `policy` considers weak, in the order they are preferred.
+    ///
+    /// Symmetric algorithms are taken from both the preferred symmetric algorithms and the
+    /// preferred AEAD ciphersuites, each is reported once. Keys that are not valid at `at`
+    /// have no current self-signature, so nothing is reported for them.
+    pub fn weak_preferences(&self, policy: &AlgorithmPolicy, at: &DateTime<Utc>) -> Vec<WeakPref> {
+        let Some(sig) = self.primary_self_signature(at) else {
+            return Vec::new();
+        };
+
+        let symmetric = sig
+            .preferred_symmetric_algs()
+            .iter()
+            .chain(sig.preferred_aead_ciphersuites().iter().map(|(alg, _)| alg))
+            .filter(|alg| policy.weak_symmetric_algs.contains(alg))
+            .map(|alg| WeakPref::Symmetric(*alg));
+        let hash = sig
+            .preferred_hash_algs()
+            .iter()
+            .filter(|alg| policy.weak_hash_algs.contains(alg))
+            .map(|alg| WeakPref::Hash(*alg));
+
+        let mut weak = Vec::new();
+        for pref in symmetric.chain(hash) {
+            if !weak.contains(&pref) {
+                weak.push(pref);
+            }
+        }
+
+        weak
+    }
+
+    /// Key flags of the primary key at `at`, or `None` if it is not valid at that time.
+    pub(crate) fn primary_key_flags(&self, at: &DateTime<Utc>) -> Option<KeyFlags> {
+        self.primary_self_signature(at).map(Signature::key_flags)
+    }
+
+    /// The self-signature that applies to the primary key at `at`, or `None` if the key is not
+    /// valid at that time.
+    fn primary_self_signature(&self, at: &DateTime<Utc>) -> Option<&Signature> {
+        let key = &self.primary_key;
+        if key.created_at() > at {
+            return None;
+        }
+
+        let revoked = self
+            .details
+            .revocation_signatures
+            .iter()
+            .any(|sig| is_alive_at(sig, at) && sig.verify_key(key).is_ok());
+        if revoked {
+            return None;
+        }
+
+        // Prefer the newest self-signature on a primary user id, then on any user id,
+        // then direct key signatures.
+        let user_sigs = self.details.users.iter().flat_map(|user| {
+            user.signatures.iter().filter(move |sig| {
+                is_alive_at(sig, at) && sig.verify_certification(key, Tag::UserId, &user.id).is_ok()
+            })
+        });
+        let self_sig = user_sigs
+            .max_by_key(|sig| (sig.is_primary(), sig.created().copied()))
+            .or_else(|| {
+                self.details
+                    .direct_signatures
+                    .iter()
+                    .filter(|sig| is_alive_at(sig, at) && sig.verify_key(key).is_ok())
+                    .max_by_key(|sig| sig.created().copied())
+            })?;
+
+        if let Some(expiration) = self_sig.key_expiration_time() {
+            if *expiration > Duration::zero() && *key.created_at() + *expiration <= *at {
+                return None;
+            }
+        }
+
+        Some(self_sig)
+    }
+
     fn verify_public_subkeys(&self) -> Result<()> {
         for subkey in &self.public_subkeys {
             subkey.verify(&self.primary_key)?;
@@ -78,17 +592,187 @@ impl SignedPublicKey {
         Ok(())
     }
 
+    /// Verifies the inline signature of `message` against this key and returns the signed
+    /// literal data, together with the id of the primary key or subkey that made the signature.
+    ///
+    /// The signing key must be valid and allowed to sign at the creation time of the signature.
+    /// Decompresses up to one layer of compressed data; the content is only returned if the
+    /// signature verifies.
+    pub fn verify_message(&self, message: &Message) -> Result<(Vec<u8>, KeyId)> {
+        let signature = message.signature()?;
+        let created = *signature
+            .created()
+            .ok_or_else(|| format_err!("signature without creation time"))?;
+
+        let signs = self
+            .primary_key_flags(&created)
+            .is_some_and(|flags| flags.sign());
+        let signer = if signs && verifies(message, &self.primary_key) {
+            Some(self.primary_key.key_id())
+        } else {
+            self.signing_subkeys(created)
+                .into_iter()
+                .find(|subkey| verifies(message, &subkey.key))
+                .map(|subkey| subkey.key.key_id())
+        };
+        let Some(signer) = signer else {
+            bail!(
+                "message is not signed by {}",
+                hex::encode_upper(self.primary_key.fingerprint())
+            );
+        };
+
+        let content = message
+            .get_content()?
+            .ok_or_else(|| format_err!("signed message has no literal data"))?;
+
+        Ok((content, signer))
+    }
+
+    /// Removes unhashed subpackets, except for issuer key IDs and embedded signatures, from
+    /// all signatures of this key. The key still verifies afterwards.
+    pub fn strip_unhashed_subpackets(&mut self) {
+        self.details.strip_unhashed_subpackets();
+        for sig in self
+            .public_subkeys
+            .iter_mut()
+            .flat_map(|k| k.signatures.iter_mut())
+        {
+            sig.strip_unhashed_subpackets();
+        }
+    }
+
+    /// Verifies the binding of the User ID `id` to the primary key on its own, without
+    /// verifying the rest of the key.
+    ///
+    /// Uses the newest self-certification that verifies, and fails if there is none or if
+    /// it was revoked by a later self-signed certification revocation.
+    pub fn verify_user_id_binding(&self, id: &str) -> Result<UserIdBindingInfo<'_>> {
+        let key = &self.primary_key;
+        let user = self
+            .details
+            .users
+            .iter()
+            .find(|user| user.id.id() == id)
+            .ok_or_else(|| format_err!("unknown user id {:?}", id))?;
+
+        let self_signatures = user.signatures.iter().filter(|sig| {
+            Signature::match_identity(sig, key)
+                && sig.verify_certification(key, Tag::UserId, &user.id).is_ok()
+        });
+        let (revocations, certifications): (Vec<_>, Vec<_>) =
+            self_signatures.partition(|sig| sig.typ() == SignatureType::CertRevocation);
+
+        let signature = certifications
+            .into_iter()
+            .max_by_key(|sig| sig.created().copied())
+            .ok_or_else(|| format_err!("no valid self-signature for user id {:?}", id))?;
+        let created_at = *signature
+            .created()
+            .ok_or_else(|| format_err!("self-signature without creation time"))?;
+        ensure!(
+            !revocations
+                .iter()
+                .any(|sig| sig.created().is_some_and(|revoked| *revoked >= created_at)),
+            "user id {:?} is revoked",
+            id
+        );
+
+        Ok(UserIdBindingInfo {
+            user_id: &user.id,
+            signature,
+            is_primary: signature.is_primary(),
+            created_at,
+            key_expires_at: signature
+                .key_expiration_time()
+                .filter(|expiration| **expiration > Duration::zero())
+                .map(|expiration| *key.created_at() + *expiration),
+            signature_expires_at: signature
+                .signature_expiration_time()
+                .filter(|expiration| **expiration > Duration::zero())
+                .map(|expiration| created_at + *expiration),
+        })
+    }
+
+    /// Verifies the third-party certifications on all User IDs against the keys in `keyring`.
+    ///
+    /// Self-signatures are skipped. Each remaining certification is checked against the
+    /// primary keys in `keyring` that match its issuer; certifications without a matching
+    /// key are reported as [`CertificationValidity::Unverifiable`] instead of failing.
+    pub fn verify_certifications(
+        &self,
+        keyring: &[&SignedPublicKey],
+    ) -> Vec<CertificationStatus<'_>> {
+        let key = &self.primary_key;
+        let mut statuses = Vec::new();
+
+        for user in &self.details.users {
+            for signature in &user.signatures {
+                let has_issuer =
+                    !signature.issuer().is_empty() || !signature.issuer_fingerprint().is_empty();
+                let is_self_signature = if has_issuer {
+                    Signature::match_identity(signature, key)
+                } else {
+                    signature
+                        .verify_certification(key, Tag::UserId, &user.id)
+                        .is_ok()
+                };
+                if is_self_signature {
+                    continue;
+                }
+
+                let mut validity = CertificationValidity::Unverifiable;
+                for certifier in keyring
+                    .iter()
+                    .filter(|k| Signature::match_identity(signature, &k.primary_key))
+                {
+                    match signature.verify_third_party_certification(
+                        &certifier.primary_key,
+                        key,
+                        Tag::UserId,
+                        &user.id,
+                    ) {
+                        Ok(()) => {
+                            validity = CertificationValidity::Valid(certifier.key_id());
+                            break;
+                        }
+                        Err(err) => {
+                            debug!(
+                                "certification by {:?} failed to verify: {:?}",
+                                certifier.key_id(),
+                                err
+                            );
+                            // without issuer information any key is only a guess
+                            if has_issuer {
+                                validity = CertificationValidity::Invalid(certifier.key_id());
+                            }
+                        }
+                    }
+                }
+
+                statuses.push(CertificationStatus {
+                    user_id: &user.id,
+                    signature,
+                    validity,
+                });
+            }
+        }
+
+        statuses
+    }
+
     pub fn to_armored_writer(
         &self,
         writer: &mut impl io::Write,
         opts: ArmorOptions<'_>,
     ) -> Result<()> {
-        armor::write(
+        armor::write_with_line_width(
             self,
             armor::BlockType::PublicKey,
             writer,
             opts.headers,
             opts.include_checksum,
+            opts.line_width,
         )
     }
 
@@ -117,6 +801,40 @@ impl SignedPublicKey {
     }
 }
 
+/// A verified User ID self-signature, see [`SignedPublicKey::verify_user_id_binding`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserIdBindingInfo<'a> {
+    pub user_id: &'a UserId,
+    /// The self-signature, which carries the preferences and key flags for this User ID.
+    pub signature: &'a Signature,
+    pub is_primary: bool,
+    pub created_at: DateTime<Utc>,
+    /// When the key expires according to this self-signature.
+    pub key_expires_at: Option<DateTime<Utc>>,
+    pub signature_expires_at: Option<DateTime<Utc>>,
+}
+
+/// The result of checking a third-party certification, see
+/// [`SignedPublicKey::verify_certifications`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CertificationStatus<'a> {
+    /// The certified User ID.
+    pub user_id: &'a UserId,
+    /// The certification signature.
+    pub signature: &'a Signature,
+    pub validity: CertificationValidity,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CertificationValidity {
+    /// The certification was verified against the key with the given id.
+    Valid(KeyId),
+    /// The certification does not verify against the issuing key with the given id.
+    Invalid(KeyId),
+    /// None of the given keys issued this certification.
+    Unverifiable,
+}
+
 impl KeyTrait for SignedPublicKey {
     fn fingerprint(&self) -> Vec<u8> {
         self.primary_key.fingerprint()
@@ -129,6 +847,10 @@ impl KeyTrait for SignedPublicKey {
     fn algorithm(&self) -> PublicKeyAlgorithm {
         self.primary_key.algorithm()
     }
+
+    fn version(&self) -> KeyVersion {
+        self.primary_key.version()
+    }
 }
 
 impl PublicKeyTrait for SignedPublicKey {
@@ -145,6 +867,12 @@ impl PublicKeyTrait for SignedPublicKey {
     }
 }
 
+impl AsRef<PublicParams> for SignedPublicKey {
+    fn as_ref(&self) -> &PublicParams {
+        self.primary_key.public_params()
+    }
+}
+
 impl Serialize for SignedPublicKey {
     fn to_writer<W: io::Write>(&self, writer: &mut W) -> Result<()> {
         write_packet(writer, &self.primary_key)?;
@@ -157,6 +885,113 @@ impl Serialize for SignedPublicKey {
     }
 }
 
+/// The bit in the first octet of the Features subpacket, that announces support for version 2
+/// SEIPD packets.
+const FEATURE_SEIPD_V2: u8 = 0x08;
+
+fn verifies(message: &Message, key: &impl PublicKeyTrait) -> bool {
+    match message.verify(key) {
+        Ok(()) => true,
+        Err(err) => {
+            debug!(
+                "message signature failed to verify with {:?}: {:?}",
+                key.key_id(),
+                err
+            );
+            false
+        }
+    }
+}
+
+/// The ciphersuite that is used for version 2 SEIPD packets when no common one is preferred, and
+/// that all implementations must support.
+pub const DEFAULT_AEAD_CIPHERSUITE: (SymmetricKeyAlgorithm, AeadAlgorithm) =
+    (SymmetricKeyAlgorithm::AES128, AeadAlgorithm::Ocb);
+
+/// Picks the AEAD ciphersuite to encrypt to all `recipients` with, from the preferred AEAD
+/// ciphersuites their self-signatures list at `at`.
+///
+/// The first ciphersuite preferred by the first recipient that all others prefer as well is
+/// used, [`DEFAULT_AEAD_CIPHERSUITE`] if there is none. Fails if the recipients have common
+/// ciphersuites, but none of them is supported by this build.
+pub fn negotiate_aead_ciphersuite(
+    recipients: &[&SignedPublicKey],
+    at: &DateTime<Utc>,
+) -> Result<(SymmetricKeyAlgorithm, AeadAlgorithm)> {
+    let Some((first, others)) = recipients.split_first() else {
+        return Ok(DEFAULT_AEAD_CIPHERSUITE);
+    };
+
+    let common: Vec<_> = first
+        .preferred_aead_ciphersuites(at)
+        .iter()
+        .filter(|suite| {
+            others
+                .iter()
+                .all(|other| other.preferred_aead_ciphersuites(at).contains(suite))
+        })
+        .collect();
+    if common.is_empty() {
+        return Ok(DEFAULT_AEAD_CIPHERSUITE);
+    }
+
+    match common
+        .iter()
+        .find(|(sym_alg, aead)| aead.is_supported(sym_alg))
+    {
+        Some(suite) => Ok(**suite),
+        None => unsupported_err!(
+            "none of the common AEAD ciphersuites {:?} is supported",
+            common
+        ),
+    }
+}
+
+/// Resolves a key id to the fingerprint of the matching primary key or subkey in `keyring`.
+///
+/// `id` is either a long, 8 byte, key id, or a short, 4 byte, one, see [`KeyId::matches`].
+pub fn resolve_key_id(keyring: &[&SignedPublicKey], id: &[u8]) -> Option<Vec<u8>> {
+    keyring.iter().find_map(|key| {
+        let subkeys = key.public_subkeys.iter().map(|k| &k.key as &dyn KeyTrait);
+        std::iter::once(&key.primary_key as &dyn KeyTrait)
+            .chain(subkeys)
+            .find(|k| k.key_id().matches(id))
+            .map(|k| k.fingerprint())
+    })
+}
+
+/// Verifies the inline signature of `message` against the keys in `keyring`, see
+/// [`SignedPublicKey::verify_message`].
+pub fn verify_message_with_keyring(
+    keyring: &[&SignedPublicKey],
+    message: &Message,
+) -> Result<(Vec<u8>, KeyId)> {
+    for key in keyring {
+        match key.verify_message(message) {
+            Ok(verified) => return Ok(verified),
+            Err(err) => debug!("{:?}", err),
+        }
+    }
+
+    let signature = message.signature()?;
+    match signature.issuer().first() {
+        Some(issuer) => bail!(
+            "no key in the keyring verifies the message signed by {}",
+            describe_key_id(keyring, issuer)
+        ),
+        None => bail!("no key in the keyring verifies the message"),
+    }
+}
+
+/// Describes the key with the given key id for error messages and logs, by its fingerprint if
+/// it is found in `keyring`, and by the key id otherwise.
+pub fn describe_key_id(keyring: &[&SignedPublicKey], id: &KeyId) -> String {
+    match resolve_key_id(keyring, id.as_ref()) {
+        Some(fingerprint) => format!("0x{}", hex::encode_upper(fingerprint)),
+        None => id.to_string(),
+    }
+}
+
 /// Represents a Public PGP SubKey.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct SignedPublicSubKey {
@@ -192,6 +1027,77 @@ impl SignedPublicSubKey {
         Ok(())
     }
 
+    /// Key flags of this subkey at `at`, or `None` if it is not validly bound to `primary`
+    /// at that time.
+    fn key_flags_at(&self, primary: &impl PublicKeyTrait, at: &DateTime<Utc>) -> Option<KeyFlags> {
+        if self.key.created_at() > at {
+            return None;
+        }
+
+        let revoked = self.signatures.iter().any(|sig| {
+            sig.typ() == SignatureType::SubkeyRevocation
+                && is_alive_at(sig, at)
+                && sig.verify_key_binding(primary, &self.key).is_ok()
+        });
+        if revoked {
+            return None;
+        }
+
+        let binding = self.newest_binding(primary, |sig| is_alive_at(sig, at))?;
+
+        if let Some(expiration) = binding.key_expiration_time() {
+            if *expiration > Duration::zero() && *self.key.created_at() + *expiration <= *at {
+                return None;
+            }
+        }
+
+        let mut flags = binding.key_flags();
+        if flags.sign() {
+            flags.set_sign(self.has_backsig(binding, primary));
+        }
+
+        Some(flags)
+    }
+
+    /// Whether this subkey needs a primary key binding signature ("back signature"), which is
+    /// the case for subkeys that are flagged for signing by their newest binding signature.
+    pub fn requires_backsig(&self, primary: &impl PublicKeyTrait) -> bool {
+        self.newest_binding(primary, |_| true)
+            .is_some_and(|binding| binding.key_flags().sign())
+    }
+
+    /// Whether the newest binding signature of this subkey embeds a valid back signature.
+    ///
+    /// Signing subkeys without one can't be used for signing.
+    pub fn has_valid_backsig(&self, primary: &impl PublicKeyTrait) -> bool {
+        self.newest_binding(primary, |_| true)
+            .is_some_and(|binding| self.has_backsig(binding, primary))
+    }
+
+    /// The newest binding signature made by `primary` that satisfies `filter`.
+    fn newest_binding(
+        &self,
+        primary: &impl PublicKeyTrait,
+        filter: impl Fn(&Signature) -> bool,
+    ) -> Option<&Signature> {
+        self.signatures
+            .iter()
+            .filter(|sig| {
+                sig.typ() == SignatureType::SubkeyBinding
+                    && filter(sig)
+                    && sig.verify_key_binding(primary, &self.key).is_ok()
+            })
+            .max_by_key(|sig| sig.created().copied())
+    }
+
+    fn has_backsig(&self, binding: &Signature, primary: &impl PublicKeyTrait) -> bool {
+        binding.embedded_signature().is_some_and(|backsig| {
+            backsig
+                .verify_backwards_key_binding(&self.key, primary)
+                .is_ok()
+        })
+    }
+
     pub fn as_unsigned(&self) -> PublicSubkey {
         let keyflags = self
             .signatures
@@ -217,6 +1123,10 @@ impl KeyTrait for SignedPublicSubKey {
     fn algorithm(&self) -> PublicKeyAlgorithm {
         self.key.algorithm()
     }
+
+    fn version(&self) -> KeyVersion {
+        self.key.version()
+    }
 }
 
 impl PublicKeyTrait for SignedPublicSubKey {
@@ -233,6 +1143,12 @@ impl PublicKeyTrait for SignedPublicSubKey {
     }
 }
 
+impl AsRef<PublicParams> for SignedPublicSubKey {
+    fn as_ref(&self) -> &PublicParams {
+        self.key.public_params()
+    }
+}
+
 impl Serialize for SignedPublicSubKey {
     fn to_writer<W: io::Write>(&self, writer: &mut W) -> Result<()> {
         write_packet(writer, &self.key)?;
@@ -243,3 +1159,361 @@ impl Serialize for SignedPublicSubKey {
         Ok(())
     }
 }
+
+/// Checks that `sig` was created no later than `at` and has not expired by then.
+fn is_alive_at(sig: &Signature, at: &DateTime<Utc>) -> bool {
+    let Some(created) = sig.created() else {
+        return false;
+    };
+    if created > at {
+        return false;
+    }
+
+    match sig.signature_expiration_time() {
+        Some(expiration) if *expiration > Duration::zero() => *created + *expiration > *at,
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    use crate::composed::{KeyType, SecretKeyParamsBuilder, SignedSecretKey};
+    use crate::packet::SignatureConfigBuilder;
+    use crate::test_util::signed_key;
+
+    use chrono::SubsecRound;
+    use smallvec::SmallVec;
+
+    #[test]
+    fn key_effective_capabilities() {
+        let key = signed_key(
+            SecretKeyParamsBuilder::certify_only_with_subkeys()
+                .primary_user_id("Me <me@mail.com>".into()),
+            "",
+        );
+        let public_key = SignedPublicKey::from(key.clone());
+
+        let now = chrono::Utc::now();
+        let flags = public_key.effective_capabilities(now);
+        assert!(flags.certify());
+        assert!(flags.sign());
+        assert!(flags.encrypt_comms());
+        assert!(flags.authentication());
+
+        // nothing is valid before the key was created
+        let before = *public_key.primary_key.created_at() - chrono::Duration::days(1);
+        assert_eq!(
+            public_key.effective_capabilities(before),
+            KeyFlags::default()
+        );
+
+        // without the encryption subkey, nothing can encrypt
+        let mut signing_only = public_key.clone();
+        signing_only.public_subkeys.remove(1);
+        let flags = signing_only.effective_capabilities(now);
+        assert!(flags.sign());
+        assert!(!flags.encrypt_comms());
+
+        // a signing subkey without back signature is not usable for signing
+        let mut no_backsig = public_key.clone();
+        no_backsig.public_subkeys.truncate(1);
+        let subkey = &mut no_backsig.public_subkeys[0];
+        let mut config = subkey.signatures[0].config.clone();
+        config
+            .hashed_subpackets
+            .retain(|p| !matches!(p.data, packet::SubpacketData::EmbeddedSignature(_)));
+        subkey.signatures[0] = config
+            .sign_key_binding(&key, String::new, &subkey.key)
+            .unwrap();
+        assert!(!no_backsig.effective_capabilities(now).sign());
+    }
+
+    #[test]
+    fn key_subkey_backsig() {
+        let key = signed_key(
+            SecretKeyParamsBuilder::certify_only_with_subkeys()
+                .primary_user_id("Me <me@mail.com>".into()),
+            "",
+        );
+        let public_key = SignedPublicKey::from(key.clone());
+        let primary = &public_key.primary_key;
+
+        // signing, encryption and authentication subkeys
+        let required: Vec<_> = public_key
+            .public_subkeys
+            .iter()
+            .map(|subkey| subkey.requires_backsig(primary))
+            .collect();
+        assert_eq!(required, vec![true, false, false]);
+        assert!(public_key.public_subkeys[0].has_valid_backsig(primary));
+        assert!(public_key.subkeys_missing_backsig().is_empty());
+
+        let mut no_backsig = public_key.clone();
+        let subkey = &mut no_backsig.public_subkeys[0];
+        let mut config = subkey.signatures[0].config.clone();
+        config
+            .hashed_subpackets
+            .retain(|p| !matches!(p.data, packet::SubpacketData::EmbeddedSignature(_)));
+        subkey.signatures[0] = config
+            .sign_key_binding(&key, String::new, &subkey.key)
+            .unwrap();
+        let subkey = &no_backsig.public_subkeys[0];
+        assert!(subkey.requires_backsig(primary));
+        assert!(!subkey.has_valid_backsig(primary));
+        assert_eq!(no_backsig.subkeys_missing_backsig(), vec![subkey]);
+    }
+
+    #[test]
+    fn key_check_flag_contradictions() {
+        let key = signed_key(
+            SecretKeyParamsBuilder::certify_only_with_subkeys()
+                .primary_user_id("Me <me@mail.com>".into()),
+            "",
+        );
+        let mut public_key = SignedPublicKey::from(key.clone());
+        assert!(public_key.check().is_empty());
+
+        // flag the X25519 subkey for signing, and the Ed25519 subkey for encryption
+        let set_flags = |subkey: &mut SignedPublicSubKey, flags: KeyFlags| {
+            let mut config = subkey.signatures[0].config.clone();
+            for p in &mut config.hashed_subpackets {
+                if let packet::SubpacketData::KeyFlags(_) = p.data {
+                    p.data = packet::SubpacketData::KeyFlags(flags.into());
+                }
+            }
+            subkey.signatures[0] = config
+                .sign_key_binding(&key, String::new, &subkey.key)
+                .unwrap();
+        };
+        let mut flags = KeyFlags::default();
+        flags.set_sign(true);
+        flags.set_encrypt_comms(true);
+        set_flags(&mut public_key.public_subkeys[1], flags);
+        let mut flags = KeyFlags::default();
+        flags.set_authentication(true);
+        flags.set_encrypt_storage(true);
+        set_flags(&mut public_key.public_subkeys[2], flags);
+
+        let defects = public_key.check();
+        assert_eq!(
+            defects,
+            vec![
+                KeyDefect::CannotSign {
+                    key_id: public_key.public_subkeys[1].key_id(),
+                    algorithm: PublicKeyAlgorithm::X25519,
+                },
+                KeyDefect::CannotEncrypt {
+                    key_id: public_key.public_subkeys[2].key_id(),
+                    algorithm: PublicKeyAlgorithm::Ed25519,
+                },
+            ]
+        );
+        assert!(defects[0].to_string().contains("can't sign"));
+    }
+
+    #[test]
+    fn key_verify_user_id_binding() {
+        let key = signed_key(
+            SecretKeyParamsBuilder::default()
+                .key_type(KeyType::EdDSA)
+                .can_certify(true)
+                .can_sign(true)
+                .primary_user_id("Me <me@mail.com>".into())
+                .user_ids(vec!["Other <other@mail.com>".into()])
+                .preferred_hash_algorithms(smallvec![HashAlgorithm::SHA2_512]),
+            "",
+        );
+        let mut public_key = SignedPublicKey::from(key.clone());
+
+        let info = public_key
+            .verify_user_id_binding("Me <me@mail.com>")
+            .unwrap();
+        assert!(info.is_primary);
+        assert_eq!(
+            info.signature.preferred_hash_algs(),
+            &[HashAlgorithm::SHA2_512]
+        );
+        assert_eq!(info.key_expires_at, None);
+
+        let info = public_key
+            .verify_user_id_binding("Other <other@mail.com>")
+            .unwrap();
+        assert!(!info.is_primary);
+        assert_eq!(info.user_id.id(), "Other <other@mail.com>");
+
+        assert!(public_key.verify_user_id_binding("Unknown").is_err());
+
+        // a signature over a different User ID does not bind this one
+        let mut swapped = public_key.clone();
+        let other_sigs = swapped.details.users[1].signatures.clone();
+        swapped.details.users[0].signatures = other_sigs;
+        assert!(swapped.verify_user_id_binding("Me <me@mail.com>").is_err());
+        assert!(swapped
+            .verify_user_id_binding("Other <other@mail.com>")
+            .is_ok());
+
+        // revoked
+        let user = &public_key.details.users[1];
+        let revocation = SignatureConfigBuilder::default()
+            .typ(SignatureType::CertRevocation)
+            .pub_alg(key.algorithm())
+            .hash_alg(HashAlgorithm::SHA2_256)
+            .hashed_subpackets(vec![
+                packet::Subpacket::regular(packet::SubpacketData::SignatureCreationTime(
+                    chrono::Utc::now().trunc_subsecs(0),
+                )),
+                packet::Subpacket::regular(packet::SubpacketData::IssuerFingerprint(
+                    Default::default(),
+                    SmallVec::from_slice(&key.fingerprint()),
+                )),
+            ])
+            .unhashed_subpackets(vec![])
+            .build()
+            .unwrap()
+            .sign_certification(&key, String::new, Tag::UserId, &user.id)
+            .unwrap();
+        public_key.details.users[1].signatures.push(revocation);
+        assert!(public_key
+            .verify_user_id_binding("Other <other@mail.com>")
+            .is_err());
+        assert!(public_key
+            .verify_user_id_binding("Me <me@mail.com>")
+            .is_ok());
+    }
+
+    #[test]
+    fn key_verify_certifications() {
+        use crate::composed::signed_key::CertificationValidity;
+
+        // the key material is the same, the creation time makes the fingerprints differ
+        let now = chrono::Utc::now().trunc_subsecs(0);
+        let gen_key = |name: &str, days: i64| {
+            signed_key(
+                SecretKeyParamsBuilder::modern_defaults()
+                    .primary_user_id(format!("{name} <{name}@mail.com>"))
+                    .created_at(now - chrono::Duration::days(days)),
+                "",
+            )
+        };
+        let alice = gen_key("alice", 0);
+        let bob = gen_key("bob", 1);
+        let carol = gen_key("carol", 2);
+
+        // bob and carol certify alice's user id
+        let mut alice_pub = SignedPublicKey::from(alice);
+        for certifier in [&bob, &carol] {
+            let config = packet::SignatureConfigBuilder::default()
+                .typ(packet::SignatureType::CertGeneric)
+                .pub_alg(certifier.algorithm())
+                .hash_alg(HashAlgorithm::SHA2_256)
+                .hashed_subpackets(vec![
+                    packet::Subpacket::regular(packet::SubpacketData::SignatureCreationTime(
+                        chrono::Utc::now().trunc_subsecs(0),
+                    )),
+                    packet::Subpacket::regular(packet::SubpacketData::IssuerFingerprint(
+                        Default::default(),
+                        SmallVec::from_slice(&certifier.fingerprint()),
+                    )),
+                ])
+                .unhashed_subpackets(vec![packet::Subpacket::regular(
+                    packet::SubpacketData::Issuer(certifier.key_id()),
+                )])
+                .build()
+                .unwrap();
+            let user = &mut alice_pub.details.users[0];
+            let sig = config
+                .sign_third_party_certification(
+                    &certifier.primary_key,
+                    String::new,
+                    &alice_pub.primary_key,
+                    Tag::UserId,
+                    &user.id,
+                )
+                .unwrap();
+            user.signatures.push(sig);
+        }
+
+        let bob_pub = SignedPublicKey::from(bob.clone());
+        let statuses = alice_pub.verify_certifications(&[&bob_pub]);
+        assert_eq!(statuses.len(), 2);
+        assert_eq!(
+            statuses[0].validity,
+            CertificationValidity::Valid(bob.key_id())
+        );
+        assert_eq!(statuses[1].validity, CertificationValidity::Unverifiable);
+
+        // a tampered certification is reported as invalid
+        let mut tampered = alice_pub.clone();
+        tampered.details.users[0].id = UserId::from_str(Default::default(), "mallory");
+        let statuses = tampered.verify_certifications(&[&bob_pub]);
+        assert!(statuses
+            .iter()
+            .any(|s| s.validity == CertificationValidity::Invalid(bob.key_id())));
+    }
+
+    #[test]
+    fn key_last_self_signed_at() {
+        let date = |year: i32| {
+            chrono::DateTime::parse_from_rfc3339(&format!("{year}-01-01T00:00:00Z"))
+                .unwrap()
+                .with_timezone(&chrono::Utc)
+        };
+        // the key material is the same, the creation time makes the fingerprints differ
+        let gen_key = |name: &str, year: i32| {
+            signed_key(
+                SecretKeyParamsBuilder::modern_defaults()
+                    .primary_user_id(format!("{name} <{name}@mail.com>"))
+                    .created_at(date(year)),
+                "",
+            )
+        };
+        let alice = gen_key("alice", 2019);
+        let bob = gen_key("bob", 2020);
+
+        let mut alice_pub = SignedPublicKey::from(alice.clone());
+        assert_eq!(alice_pub.created_at(), &date(2019));
+        let self_signed_at = *alice_pub.last_self_signed_at().unwrap();
+        assert!(self_signed_at > date(2019));
+
+        let certify = |signer: &SignedSecretKey, created: chrono::DateTime<chrono::Utc>| {
+            let config = packet::SignatureConfigBuilder::default()
+                .typ(packet::SignatureType::CertPositive)
+                .pub_alg(signer.algorithm())
+                .hash_alg(HashAlgorithm::SHA2_256)
+                .hashed_subpackets(vec![
+                    packet::Subpacket::regular(packet::SubpacketData::SignatureCreationTime(
+                        created,
+                    )),
+                    packet::Subpacket::regular(packet::SubpacketData::IssuerFingerprint(
+                        Default::default(),
+                        SmallVec::from_slice(&signer.fingerprint()),
+                    )),
+                ])
+                .unhashed_subpackets(vec![])
+                .build()
+                .unwrap();
+            config
+                .sign_third_party_certification(
+                    &signer.primary_key,
+                    String::new,
+                    &alice.primary_key,
+                    Tag::UserId,
+                    &alice.details.users[0].id,
+                )
+                .unwrap()
+        };
+
+        // third party certifications don't count
+        let sig = certify(&bob, date(2100));
+        alice_pub.details.users[0].signatures.push(sig);
+        assert_eq!(alice_pub.last_self_signed_at(), Some(&self_signed_at));
+
+        let sig = certify(&alice, date(2090));
+        alice_pub.details.users[0].signatures.push(sig);
+        assert_eq!(alice_pub.last_self_signed_at(), Some(&date(2090)));
+    }
+}
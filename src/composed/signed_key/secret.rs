@@ -1,19 +1,29 @@
 use std::io;
 
-use chrono::{DateTime, Utc};
-use rand::{CryptoRng, Rng};
+use chrono::{DateTime, Duration, SubsecRound, Utc};
+use rand::{thread_rng, CryptoRng, Rng};
+use zeroize::Zeroizing;
 
-use crate::composed::key::{PublicKey, PublicSubkey};
-use crate::composed::signed_key::{SignedKeyDetails, SignedPublicSubKey};
+use crate::composed::key::{
+    issuer_fingerprint_subpacket, issuer_subpackets, preferred_signing_hash, signature_version,
+    PublicKey, PublicSubkey, SecretKey, SecretSubkey,
+};
+use crate::composed::signed_key::{KeyCapabilities, SignedKeyDetails, SignedPublicSubKey};
+use crate::crypto::ecc_curve::ECCCurve;
 use crate::crypto::hash::HashAlgorithm;
 use crate::crypto::public_key::PublicKeyAlgorithm;
+use crate::crypto::sym::SymmetricKeyAlgorithm;
 use crate::errors::Result;
-use crate::packet::{self, write_packet, SignatureType};
+use crate::packet::{
+    self, write_packet, PacketTrait, RevocationCode, SignatureConfigBuilder, SignatureType,
+    Subpacket, SubpacketData,
+};
 use crate::ser::Serialize;
 use crate::types::{
-    KeyId, KeyTrait, Mpi, PublicKeyTrait, PublicParams, SecretKeyRepr, SecretKeyTrait,
+    KeyId, KeyTrait, KeyVersion, Mpi, PlainSecretParams, PublicKeyTrait, PublicParams, S2kParams,
+    SecretKeyRepr, SecretKeyTrait, SecretParams,
 };
-use crate::{armor, ArmorOptions, SignedPublicKey};
+use crate::{armor, ArmorOptions, SignedPublicKey, StandaloneSignature};
 
 /// Represents a secret signed PGP key.
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -22,6 +32,8 @@ pub struct SignedSecretKey {
     pub details: SignedKeyDetails,
     pub public_subkeys: Vec<SignedPublicSubKey>,
     pub secret_subkeys: Vec<SignedSecretSubKey>,
+    /// See [`SignedSecretKey::pregenerated_revocation`].
+    pub(crate) pregenerated_revocation: Option<StandaloneSignature>,
 }
 
 key_parser!(
@@ -75,15 +87,336 @@ impl SignedSecretKey {
             details,
             public_subkeys,
             secret_subkeys,
+            pregenerated_revocation: None,
         }
     }
 
+    /// The revocation certificate created when signing a key generated with
+    /// `pregenerate_revocation` set.
+    ///
+    /// It is not part of the serialized key, and is `None` for parsed keys.
+    pub fn pregenerated_revocation(&self) -> Option<&StandaloneSignature> {
+        self.pregenerated_revocation.as_ref()
+    }
+
     /// Get the secret key expiration as a date.
     pub fn expires_at(&self) -> Option<DateTime<Utc>> {
         let expiration = self.details.key_expiration_time()?;
         Some(*self.primary_key.created_at() + expiration)
     }
 
+    /// Returns `true` if `other` has the same primary key, by fingerprint.
+    ///
+    /// Unlike `==`, this ignores User IDs, signatures and subkeys, and can be used to
+    /// compare public and secret keys.
+    pub fn same_key_as(&self, other: &impl KeyTrait) -> bool {
+        self.primary_key.fingerprint() == other.fingerprint()
+    }
+
+    /// Lists the primary key and the subkeys that are valid at `at`, with the operations each
+    /// of them can perform, see [`SignedPublicKey::key_capabilities`].
+    pub fn key_capabilities(&self, at: DateTime<Utc>) -> Vec<KeyCapabilities> {
+        SignedPublicKey::from(self.clone()).key_capabilities(at)
+    }
+
+    /// Adds a new User ID, bound to the primary key with a positive certification
+    /// self-signature.
+    ///
+    /// The self-signature carries the same preferences as the existing primary User ID.
+    pub fn add_user_id<F>(&mut self, id: &str, key_pw: F) -> Result<()>
+    where
+        F: FnOnce() -> String,
+    {
+        let id = packet::UserId::from_str(Default::default(), id);
+        let sig = self
+            .details
+            .self_certify(&self.primary_key, key_pw, &id, false)?;
+        self.details.users.push(id.into_signed(sig));
+
+        Ok(())
+    }
+
+    /// Adds a new User Attribute, such as a photo, bound to the primary key with a positive
+    /// certification self-signature, like [`Self::add_user_id`].
+    pub fn add_user_attribute<F>(&mut self, attr: packet::UserAttribute, key_pw: F) -> Result<()>
+    where
+        F: FnOnce() -> String,
+    {
+        let sig = self
+            .details
+            .self_certify(&self.primary_key, key_pw, &attr, false)?;
+        self.details.user_attributes.push(attr.into_signed(sig));
+
+        Ok(())
+    }
+
+    /// Makes the given User ID the primary one.
+    ///
+    /// All User IDs get fresh self-signatures, with the primary User ID flag set only
+    /// on the chosen one. These replace the previous self-certifications, while
+    /// third-party certifications and revocations are kept.
+    pub fn set_primary_user_id<F>(&mut self, id: &str, key_pw: F) -> Result<()>
+    where
+        F: (FnOnce() -> String) + Clone,
+    {
+        ensure!(
+            self.details.users.iter().any(|user| user.id.id() == id),
+            "unknown user id {:?}",
+            id
+        );
+
+        // Create all signatures first, so that the key stays untouched on failure.
+        let signatures = self
+            .details
+            .users
+            .iter()
+            .map(|user| {
+                self.details.self_certify(
+                    &self.primary_key,
+                    key_pw.clone(),
+                    &user.id,
+                    user.id.id() == id,
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let key_id = self.primary_key.key_id();
+        let fingerprint = self.primary_key.fingerprint();
+        for (user, sig) in self.details.users.iter_mut().zip(signatures) {
+            user.signatures.retain(|sig| {
+                let is_self_signature = sig.issuer().into_iter().any(|id| id == &key_id)
+                    || sig
+                        .issuer_fingerprint()
+                        .into_iter()
+                        .any(|fp| fp == fingerprint);
+                !(is_self_signature
+                    && sig.is_certification()
+                    && sig.typ() != SignatureType::CertRevocation)
+            });
+            user.signatures.insert(0, sig);
+        }
+
+        Ok(())
+    }
+
+    /// Re-issues the self-signatures of this key with `hash_alg`, for example to replace SHA-1
+    /// self-signatures of an old key.
+    ///
+    /// For the direct key signature, each User ID and User Attribute, and each subkey binding,
+    /// the newest valid self-signature is copied with all its subpackets, such as preferences,
+    /// key flags and expirations, and signed again. Back signatures of signing subkeys are
+    /// renewed as well, if the secret subkey is available. The new signatures are created later
+    /// than the ones they are based on and so supersede them, the old signatures are kept.
+    ///
+    /// Revoked and expired bindings are left alone.
+    pub fn upgrade_signatures<F>(&mut self, hash_alg: HashAlgorithm, key_pw: F) -> Result<()>
+    where
+        F: (FnOnce() -> String) + Clone,
+    {
+        let key = &self.primary_key;
+        let now = Utc::now().trunc_subsecs(0);
+
+        // Key revocations are kept apart from the direct key signatures, a revoked key does not
+        // get a new direct key signature.
+        let revoked = self.details.revocation_signatures.iter().any(|sig| {
+            sig.typ() == SignatureType::KeyRevocation
+                && packet::Signature::match_identity(sig, key)
+                && sig.verify_key(key).is_ok()
+        });
+
+        // Create all signatures first, so that the key stays untouched on failure.
+        let direct_signature = newest_self_signature(
+            &self.details.direct_signatures,
+            key,
+            SignatureType::KeyRevocation,
+            |sig| sig.typ() == SignatureType::Key && sig.verify_key(key).is_ok(),
+        )
+        .filter(|_| !revoked)
+        .and_then(|sig| upgraded_config(sig, hash_alg, now))
+        .map(|config| config.sign_key(key, key_pw.clone(), key))
+        .transpose()?;
+
+        let mut user_signatures = Vec::new();
+        for (i, user) in self.details.users.iter().enumerate() {
+            let sig = newest_self_signature(
+                &user.signatures,
+                key,
+                SignatureType::CertRevocation,
+                |sig| {
+                    sig.is_certification()
+                        && sig
+                            .verify_certification(key, user.id.tag(), &user.id)
+                            .is_ok()
+                },
+            );
+            if let Some(config) = sig.and_then(|sig| upgraded_config(sig, hash_alg, now)) {
+                let sig =
+                    config.sign_certification(key, key_pw.clone(), user.id.tag(), &user.id)?;
+                user_signatures.push((i, sig));
+            }
+        }
+
+        let mut attribute_signatures = Vec::new();
+        for (i, attr) in self.details.user_attributes.iter().enumerate() {
+            let sig = newest_self_signature(
+                &attr.signatures,
+                key,
+                SignatureType::CertRevocation,
+                |sig| {
+                    sig.is_certification()
+                        && sig
+                            .verify_certification(key, attr.attr.tag(), &attr.attr)
+                            .is_ok()
+                },
+            );
+            if let Some(config) = sig.and_then(|sig| upgraded_config(sig, hash_alg, now)) {
+                let sig =
+                    config.sign_certification(key, key_pw.clone(), attr.attr.tag(), &attr.attr)?;
+                attribute_signatures.push((i, sig));
+            }
+        }
+
+        let mut public_subkey_signatures = Vec::new();
+        for (i, subkey) in self.public_subkeys.iter().enumerate() {
+            let sig = newest_self_signature(
+                &subkey.signatures,
+                key,
+                SignatureType::SubkeyRevocation,
+                |sig| {
+                    sig.typ() == SignatureType::SubkeyBinding
+                        && sig.verify_key_binding(key, &subkey.key).is_ok()
+                },
+            );
+            // Without the secret subkey the existing back signature is kept, it only covers
+            // the keys and stays valid.
+            if let Some(config) = sig.and_then(|sig| upgraded_config(sig, hash_alg, now)) {
+                let sig = config.sign_key_binding(key, key_pw.clone(), &subkey.key)?;
+                public_subkey_signatures.push((i, sig));
+            }
+        }
+
+        let mut secret_subkey_signatures = Vec::new();
+        for (i, subkey) in self.secret_subkeys.iter().enumerate() {
+            let sig = newest_self_signature(
+                &subkey.signatures,
+                key,
+                SignatureType::SubkeyRevocation,
+                |sig| {
+                    sig.typ() == SignatureType::SubkeyBinding
+                        && sig.verify_key_binding(key, &subkey.key).is_ok()
+                },
+            );
+            let Some(mut config) = sig.and_then(|sig| upgraded_config(sig, hash_alg, now)) else {
+                continue;
+            };
+
+            let backsig = sig
+                .and_then(|sig| sig.embedded_signature())
+                .and_then(|backsig| upgraded_config(backsig, hash_alg, now))
+                .map(|backsig| backsig.sign_primary_key_binding(&subkey.key, key_pw.clone(), key))
+                .transpose()?;
+            if let Some(backsig) = backsig {
+                for subpacket in config
+                    .hashed_subpackets
+                    .iter_mut()
+                    .chain(config.unhashed_subpackets.iter_mut())
+                {
+                    if let SubpacketData::EmbeddedSignature(ref mut sig) = subpacket.data {
+                        **sig = backsig.clone();
+                    }
+                }
+            }
+
+            let sig = config.sign_key_binding(key, key_pw.clone(), &subkey.key)?;
+            secret_subkey_signatures.push((i, sig));
+        }
+
+        self.details.direct_signatures.extend(direct_signature);
+        for (i, sig) in user_signatures {
+            self.details.users[i].signatures.push(sig);
+        }
+        for (i, sig) in attribute_signatures {
+            self.details.user_attributes[i].signatures.push(sig);
+        }
+        for (i, sig) in public_subkey_signatures {
+            self.public_subkeys[i].signatures.push(sig);
+        }
+        for (i, sig) in secret_subkey_signatures {
+            self.secret_subkeys[i].signatures.push(sig);
+        }
+
+        Ok(())
+    }
+
+    /// Creates an armored detached signature over `data`, like `gpg --detach-sign --armor`.
+    ///
+    /// The signature is made by the primary key if it is marked for signing, otherwise by
+    /// the first signing subkey. Keys that are expired or revoked are not used, an expired
+    /// primary key invalidates all subkeys.
+    pub fn detach_sign_armored<F>(
+        &self,
+        data: impl io::Read,
+        hash_algorithm: HashAlgorithm,
+        key_pw: F,
+    ) -> Result<String>
+    where
+        F: FnOnce() -> String,
+    {
+        let public_key = SignedPublicKey::from(self.clone());
+        let now = Utc::now();
+        let primary_can_sign = public_key
+            .primary_key_flags(&now)
+            .is_some_and(|flags| flags.sign());
+
+        let signature = if primary_can_sign {
+            detached_signature(&self.primary_key, key_pw, hash_algorithm, data)?
+        } else {
+            let signing_subkeys = public_key.signing_subkeys(now);
+            let subkey = self
+                .secret_subkeys
+                .iter()
+                .find(|subkey| {
+                    signing_subkeys
+                        .iter()
+                        .any(|s| s.key.fingerprint() == subkey.key.fingerprint())
+                })
+                .ok_or_else(|| format_err!("no signing key found"))?;
+            detached_signature(&subkey.key, key_pw, hash_algorithm, data)?
+        };
+
+        StandaloneSignature::new(signature).to_armored_string(ArmorOptions::default())
+    }
+
+    /// Creates a revocation certificate for the primary key, a key revocation signature that
+    /// can be published later to revoke the key.
+    pub fn revocation_certificate<F>(
+        &self,
+        key_pw: F,
+        code: RevocationCode,
+        reason: &str,
+    ) -> Result<StandaloneSignature>
+    where
+        F: FnOnce() -> String,
+    {
+        let config = SignatureConfigBuilder::default()
+            .version(signature_version(self))
+            .typ(SignatureType::KeyRevocation)
+            .pub_alg(self.algorithm())
+            .hash_alg(self.hash_alg())
+            .hashed_subpackets(vec![
+                Subpacket::regular(SubpacketData::SignatureCreationTime(
+                    Utc::now().trunc_subsecs(0),
+                )),
+                issuer_fingerprint_subpacket(self),
+                Subpacket::regular(SubpacketData::RevocationReason(code, reason.into())),
+            ])
+            .unhashed_subpackets(issuer_subpackets(self))
+            .build()?;
+        let signature = config.sign_key(&self.primary_key, key_pw, &self.primary_key)?;
+
+        Ok(StandaloneSignature::new(signature))
+    }
+
     fn verify_public_subkeys(&self) -> Result<()> {
         for subkey in &self.public_subkeys {
             subkey.verify(&self.primary_key)?;
@@ -100,6 +433,149 @@ impl SignedSecretKey {
         Ok(())
     }
 
+    /// Changes the passphrase the secret key material of the primary key and all secret
+    /// subkeys is protected with, leaving the public key material and all signatures untouched.
+    ///
+    /// `old` is ignored for unencrypted keys. The primary key is protected with `s2k`, subkeys
+    /// with copies of it using their own random salt, IV and nonce. Use
+    /// [`S2kParams::Unprotected`] to remove the passphrase.
+    pub fn reencrypt<F, G>(&self, old: F, new: G, s2k: S2kParams) -> Result<SignedSecretKey>
+    where
+        F: FnOnce() -> String + Clone,
+        G: FnOnce() -> String + Clone,
+    {
+        self.reencrypt_with_rng(thread_rng(), old, new, s2k)
+    }
+
+    /// Like [`Self::reencrypt`], using the given rng for the salts, IVs and nonces of the
+    /// subkeys.
+    pub fn reencrypt_with_rng<R, F, G>(
+        &self,
+        mut rng: R,
+        old: F,
+        new: G,
+        s2k: S2kParams,
+    ) -> Result<SignedSecretKey>
+    where
+        R: Rng + CryptoRng,
+        F: FnOnce() -> String + Clone,
+        G: FnOnce() -> String + Clone,
+    {
+        let mut key = self.clone();
+        let new = Zeroizing::new(new());
+        let protect = !matches!(s2k, S2kParams::Unprotected);
+
+        key.primary_key.remove_password(old.clone())?;
+        if protect {
+            key.primary_key.set_password_with_s2k(&new, s2k.clone())?;
+        }
+        for subkey in &mut key.secret_subkeys {
+            subkey.key.remove_password(old.clone())?;
+            if protect {
+                subkey
+                    .key
+                    .set_password_with_s2k(&new, s2k.with_new_randomness(&mut rng))?;
+            }
+        }
+
+        Ok(key)
+    }
+
+    /// Re-expresses this version 6 key as a version 4 key, for correspondents whose software
+    /// can not parse version 6 keys.
+    ///
+    /// Key material is converted to its version 4 encoding: Ed25519 keys become EdDSA keys and
+    /// X25519 keys become ECDH keys on Curve25519, other algorithms are kept as they are, and
+    /// algorithms without a version 4 encoding fail. The User IDs, User Attributes and the
+    /// subkeys that are not revoked are signed again with version 4 signatures, using the key
+    /// flags, preferences and expiration of the current self-signatures.
+    ///
+    /// Re-signing needs the secret keys, so this is done on the secret key, the public key to
+    /// share is [`SignedPublicKey::from`] the result. It has a different fingerprint and key
+    /// ID than this key, and its secret key material is unprotected, use [`Self::reencrypt`]
+    /// to protect it again. Public subkeys that can sign can not be bound without their
+    /// secret key, and fail.
+    pub fn downgrade_to_v4<F>(&self, key_pw: F) -> Result<SignedSecretKey>
+    where
+        F: FnOnce() -> String + Clone,
+    {
+        let key = &self.primary_key;
+        ensure_eq!(
+            key.version(),
+            KeyVersion::V6,
+            "only version 6 keys can be downgraded"
+        );
+
+        let mut unlocked = key.clone();
+        unlocked.remove_password(key_pw.clone())?;
+        let (algorithm, public_params) = v4_public_params(key.algorithm(), key.public_params())?;
+        let primary_key = packet::SecretKey {
+            details: packet::PublicKey::new(
+                key.packet_version(),
+                KeyVersion::V4,
+                algorithm,
+                *key.created_at(),
+                expiration_secs(self.details.key_expiration_time().as_ref())?,
+                public_params,
+            )?,
+            secret_params: v4_secret_params(unlocked.secret_params()),
+        };
+
+        let mut public_subkeys = Vec::new();
+        for subkey in &self.public_subkeys {
+            let Some(sig) = subkey_binding(&subkey.signatures, key, &subkey.key) else {
+                continue;
+            };
+            ensure!(
+                !sig.key_flags().sign(),
+                "signing subkey {:?} can not be bound without its secret key",
+                subkey.key.key_id()
+            );
+            let (algorithm, public_params) =
+                v4_public_params(subkey.key.algorithm(), subkey.key.public_params())?;
+            let details = packet::PublicSubkey::new(
+                subkey.key.packet_version(),
+                KeyVersion::V4,
+                algorithm,
+                *subkey.key.created_at(),
+                expiration_secs(sig.key_expiration_time())?,
+                public_params,
+            )?;
+            public_subkeys.push(PublicSubkey::new(details, sig.key_flags()));
+        }
+
+        let mut secret_subkeys = Vec::new();
+        for subkey in &self.secret_subkeys {
+            let Some(sig) = subkey_binding(&subkey.signatures, key, &subkey.key.details) else {
+                continue;
+            };
+            let mut unlocked = subkey.key.clone();
+            unlocked.remove_password(key_pw.clone())?;
+            let (algorithm, public_params) =
+                v4_public_params(subkey.key.algorithm(), subkey.key.public_params())?;
+            let packet = packet::SecretSubkey {
+                details: packet::PublicSubkey::new(
+                    subkey.key.packet_version(),
+                    KeyVersion::V4,
+                    algorithm,
+                    *subkey.key.created_at(),
+                    expiration_secs(sig.key_expiration_time())?,
+                    public_params,
+                )?,
+                secret_params: v4_secret_params(unlocked.secret_params()),
+            };
+            secret_subkeys.push(SecretSubkey::new(packet, sig.key_flags()));
+        }
+
+        SecretKey::new(
+            primary_key,
+            self.details.as_unsigned(),
+            public_subkeys,
+            secret_subkeys,
+        )
+        .sign(String::new)
+    }
+
     pub fn verify(&self) -> Result<()> {
         self.details.verify(&self.primary_key)?;
         self.verify_public_subkeys()?;
@@ -108,17 +584,36 @@ impl SignedSecretKey {
         Ok(())
     }
 
+    /// Removes unhashed subpackets, except for issuer key IDs and embedded signatures, from
+    /// all signatures of this key. The key still verifies afterwards.
+    pub fn strip_unhashed_subpackets(&mut self) {
+        self.details.strip_unhashed_subpackets();
+        let subkey_signatures = self
+            .public_subkeys
+            .iter_mut()
+            .flat_map(|k| k.signatures.iter_mut())
+            .chain(
+                self.secret_subkeys
+                    .iter_mut()
+                    .flat_map(|k| k.signatures.iter_mut()),
+            );
+        for sig in subkey_signatures {
+            sig.strip_unhashed_subpackets();
+        }
+    }
+
     pub fn to_armored_writer(
         &self,
         writer: &mut impl io::Write,
         opts: ArmorOptions<'_>,
     ) -> Result<()> {
-        armor::write(
+        armor::write_with_line_width(
             self,
             armor::BlockType::PrivateKey,
             writer,
             opts.headers,
             opts.include_checksum,
+            opts.line_width,
         )
     }
 
@@ -136,6 +631,164 @@ impl SignedSecretKey {
     }
 }
 
+/// The newest signature in `signatures` issued by `key` that passes `is_valid`, or `None` if it
+/// is superseded by a revocation of type `revocation`.
+fn newest_self_signature<'a>(
+    signatures: &'a [packet::Signature],
+    key: &impl PublicKeyTrait,
+    revocation: SignatureType,
+    is_valid: impl Fn(&packet::Signature) -> bool,
+) -> Option<&'a packet::Signature> {
+    let newest = signatures
+        .iter()
+        .filter(|sig| packet::Signature::match_identity(sig, key))
+        .filter(|sig| sig.typ() == revocation || is_valid(sig))
+        .max_by_key(|sig| sig.created().copied())?;
+
+    (newest.typ() != revocation).then_some(newest)
+}
+
+/// The version 4 encoding of the public key material `params`, and its algorithm, see
+/// [`SignedSecretKey::downgrade_to_v4`].
+fn v4_public_params(
+    algorithm: PublicKeyAlgorithm,
+    params: &PublicParams,
+) -> Result<(PublicKeyAlgorithm, PublicParams)> {
+    match params {
+        PublicParams::Ed25519 { public } => {
+            let mut q = vec![0x40];
+            q.extend_from_slice(public);
+            Ok((
+                PublicKeyAlgorithm::EdDSA,
+                PublicParams::EdDSA {
+                    curve: ECCCurve::Ed25519,
+                    q: q.into(),
+                },
+            ))
+        }
+        PublicParams::X25519 { public } => {
+            let mut p = vec![0x40];
+            p.extend_from_slice(public);
+            Ok((
+                PublicKeyAlgorithm::ECDH,
+                PublicParams::ECDH {
+                    curve: ECCCurve::Curve25519,
+                    p: p.into(),
+                    hash: HashAlgorithm::SHA2_256,
+                    alg_sym: SymmetricKeyAlgorithm::AES128,
+                },
+            ))
+        }
+        PublicParams::Unknown { .. } => {
+            unsupported_err!("{:?} keys have no version 4 encoding", algorithm)
+        }
+        params => Ok((algorithm, params.clone())),
+    }
+}
+
+/// The key packet expiration, in seconds after the creation of the key.
+fn expiration_secs(expiration: Option<&Duration>) -> Result<Option<u32>> {
+    Ok(expiration
+        .map(|expiration| u32::try_from(expiration.num_seconds()))
+        .transpose()?)
+}
+
+/// The version 4 encoding of the unlocked secret key material `params`.
+fn v4_secret_params(params: &SecretParams) -> SecretParams {
+    let plain = match params {
+        SecretParams::Plain(PlainSecretParams::Ed25519(secret)) => {
+            PlainSecretParams::EdDSA(Mpi::from_raw_slice(&secret[..]))
+        }
+        SecretParams::Plain(PlainSecretParams::X25519(secret)) => {
+            // the native secret is little endian, the ECDH one big endian
+            PlainSecretParams::ECDH(Mpi::from_raw(secret.iter().rev().copied().collect()))
+        }
+        params => return params.clone(),
+    };
+
+    SecretParams::Plain(plain)
+}
+
+/// The newest valid binding signature of `subkey` made by `key`, `None` if it is revoked.
+fn subkey_binding<'a>(
+    signatures: &'a [packet::Signature],
+    key: &impl PublicKeyTrait,
+    subkey: &packet::PublicSubkey,
+) -> Option<&'a packet::Signature> {
+    newest_self_signature(signatures, key, SignatureType::SubkeyRevocation, |sig| {
+        sig.typ() == SignatureType::SubkeyBinding && sig.verify_key_binding(key, subkey).is_ok()
+    })
+}
+
+/// A copy of the configuration of `sig` using `hash_alg`, created at `now`, or a second after
+/// `sig` if that is later, so that the new signature supersedes it.
+///
+/// A signature expiration time is adjusted to keep the signature expiring at the same time,
+/// `None` is returned if it has already expired.
+fn upgraded_config(
+    sig: &packet::Signature,
+    hash_alg: HashAlgorithm,
+    now: DateTime<Utc>,
+) -> Option<packet::SignatureConfig> {
+    let old_created = sig.created().copied();
+    let created = old_created.map_or(now, |old| now.max(old + Duration::seconds(1)));
+
+    let mut config = sig.config.clone();
+    config.hash_alg = hash_alg;
+    // every version 6 signature needs a fresh salt, of the size `hash_alg` requires
+    config.salt = None;
+    if config.created.is_some() {
+        // v2 and v3 signatures have no subpackets
+        config.created = Some(created);
+        return Some(config);
+    }
+
+    for subpacket in &mut config.hashed_subpackets {
+        match subpacket.data {
+            SubpacketData::SignatureCreationTime(ref mut time) => *time = created,
+            SubpacketData::SignatureExpirationTime(ref mut expiration)
+                if *expiration > Duration::zero() =>
+            {
+                let expires_at = old_created? + *expiration;
+                if expires_at <= created {
+                    return None;
+                }
+                *expiration = expires_at - created;
+            }
+            _ => {}
+        }
+    }
+
+    Some(config)
+}
+
+/// Creates a binary signature over `data` with the subpackets used by GnuPG.
+fn detached_signature<F>(
+    key: &impl SecretKeyTrait,
+    key_pw: F,
+    hash_algorithm: HashAlgorithm,
+    data: impl io::Read,
+) -> Result<packet::Signature>
+where
+    F: FnOnce() -> String,
+{
+    let config = packet::SignatureConfig::new_v4(
+        signature_version(key),
+        SignatureType::Binary,
+        key.algorithm(),
+        hash_algorithm,
+        vec![
+            issuer_fingerprint_subpacket(key),
+            packet::Subpacket::regular(packet::SubpacketData::SignatureCreationTime(
+                Utc::now().trunc_subsecs(0),
+            )),
+        ],
+        issuer_subpackets(key),
+    );
+
+    config.sign(key, key_pw, data)
+}
+
 impl KeyTrait for SignedSecretKey {
     /// Returns the fingerprint of the associated primary key.
     fn fingerprint(&self) -> Vec<u8> {
@@ -150,6 +803,10 @@ impl KeyTrait for SignedSecretKey {
     fn algorithm(&self) -> PublicKeyAlgorithm {
         self.primary_key.algorithm()
     }
+
+    fn version(&self) -> KeyVersion {
+        self.primary_key.version()
+    }
 }
 
 impl Serialize for SignedSecretKey {
@@ -207,6 +864,17 @@ impl SecretKeyTrait for SignedSecretKey {
     fn public_params(&self) -> &PublicParams {
         self.primary_key.public_params()
     }
+
+    /// The first hash algorithm from the key's own preferences that is strong enough for the
+    /// primary key, or the default of the primary key if there is none.
+    fn hash_alg(&self) -> HashAlgorithm {
+        let preferred = self
+            .details
+            .preferences_signature()
+            .map(|sig| sig.preferred_hash_algs())
+            .unwrap_or_default();
+        preferred_signing_hash(self.primary_key.hash_alg(), preferred)
+    }
 }
 
 impl PublicKeyTrait for SignedSecretKey {
@@ -274,6 +942,10 @@ impl KeyTrait for SignedSecretSubKey {
     fn algorithm(&self) -> PublicKeyAlgorithm {
         self.key.algorithm()
     }
+
+    fn version(&self) -> KeyVersion {
+        self.key.version()
+    }
 }
 
 impl Serialize for SignedSecretSubKey {
@@ -356,3 +1028,449 @@ impl From<SignedSecretSubKey> for SignedPublicSubKey {
         SignedPublicSubKey::new(value.key.public_key(), value.signatures)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use crate::composed::{Deserializable, KeyType, SecretKeyParamsBuilder, SubkeyParamsBuilder};
+    use crate::crypto::aead::AeadAlgorithm;
+    use crate::packet::KeyFlags;
+    use crate::test_util::signed_key;
+    use crate::types::Tag;
+
+    #[test]
+    fn key_add_user_id() {
+        let mut signed_key = signed_key(
+            SecretKeyParamsBuilder::modern_defaults()
+                .primary_user_id("Me <me@mail.com>".into())
+                .passphrase(Some("hello".into())),
+            "hello",
+        );
+
+        signed_key
+            .add_user_id("Me at work <me@work.com>", || "hello".into())
+            .expect("failed to add user id");
+        assert!(signed_key
+            .add_user_id("Me again <me@mail.com>", || "wrong".into())
+            .is_err());
+
+        let armor = signed_key
+            .to_armored_string(None.into())
+            .expect("failed to serialize key");
+        let (signed_key2, _headers) =
+            SignedSecretKey::from_string(&armor).expect("failed to parse key");
+        signed_key2.verify().expect("invalid key");
+
+        let users = &signed_key2.details.users;
+        assert_eq!(users.len(), 2);
+        assert_eq!(users[1].id.id(), "Me at work <me@work.com>");
+        assert!(users[0].is_primary());
+        assert!(!users[1].is_primary());
+
+        let primary_sig = &users[0].signatures[0];
+        let new_sig = &users[1].signatures[0];
+        assert_eq!(new_sig.typ(), packet::SignatureType::CertPositive);
+        assert_eq!(new_sig.key_flags(), primary_sig.key_flags());
+        assert_eq!(
+            new_sig.preferred_symmetric_algs(),
+            primary_sig.preferred_symmetric_algs()
+        );
+        assert_eq!(
+            new_sig.preferred_hash_algs(),
+            primary_sig.preferred_hash_algs()
+        );
+        assert_eq!(
+            new_sig.preferred_aead_ciphersuites(),
+            primary_sig.preferred_aead_ciphersuites()
+        );
+    }
+
+    #[test]
+    fn key_set_primary_user_id() {
+        let mut signed_key = signed_key(
+            SecretKeyParamsBuilder::modern_defaults()
+                .primary_user_id("Me <me@mail.com>".into())
+                .user_id("Me at work <me@work.com>"),
+            "",
+        );
+        assert!(signed_key.details.users[0].is_primary());
+        assert!(!signed_key.details.users[1].is_primary());
+
+        assert!(signed_key
+            .set_primary_user_id("Someone else <other@mail.com>", String::new)
+            .is_err());
+
+        signed_key
+            .set_primary_user_id("Me at work <me@work.com>", String::new)
+            .expect("failed to set primary user id");
+
+        let armor = signed_key
+            .to_armored_string(None.into())
+            .expect("failed to serialize key");
+        let (signed_key2, _headers) =
+            SignedSecretKey::from_string(&armor).expect("failed to parse key");
+        signed_key2.verify().expect("invalid key");
+
+        let users = &signed_key2.details.users;
+        assert!(!users[0].is_primary());
+        assert!(users[1].is_primary());
+        for user in users {
+            assert_eq!(user.signatures.len(), 1);
+            assert_eq!(
+                user.signatures[0].typ(),
+                packet::SignatureType::CertPositive
+            );
+        }
+
+        let public_key = signed_key2.public_key();
+        let signed_public_key = public_key
+            .sign(&signed_key2, String::new)
+            .expect("failed to sign public key");
+        let primary = signed_public_key
+            .details
+            .users
+            .iter()
+            .find(|u| u.is_primary())
+            .unwrap();
+        assert_eq!(primary.id.id(), "Me at work <me@work.com>");
+    }
+
+    #[test]
+    fn key_upgrade_signatures() {
+        let key = signed_key(
+            SecretKeyParamsBuilder::certify_only_with_subkeys()
+                .primary_user_id("Me <me@mail.com>".into())
+                .expiration(Some(std::time::Duration::from_secs(365 * 24 * 60 * 60))),
+            "",
+        );
+
+        // re-sign all self-signatures with SHA-1
+        let mut old = key.clone();
+        let user = &mut old.details.users[0];
+        let mut config = user.signatures[0].config.clone();
+        config.hash_alg = HashAlgorithm::SHA1;
+        user.signatures[0] = config
+            .sign_certification(&key.primary_key, String::new, Tag::UserId, &user.id)
+            .unwrap();
+        for subkey in &mut old.secret_subkeys {
+            let mut config = subkey.signatures[0].config.clone();
+            config.hash_alg = HashAlgorithm::SHA1;
+            subkey.signatures[0] = config
+                .sign_key_binding(&key.primary_key, String::new, &subkey.key)
+                .unwrap();
+        }
+        old.verify().expect("invalid key");
+
+        let mut upgraded = old.clone();
+        upgraded
+            .upgrade_signatures(HashAlgorithm::SHA2_256, String::new)
+            .unwrap();
+        upgraded.verify().expect("invalid key");
+
+        let newest = |sigs: &[packet::Signature]| {
+            sigs.iter()
+                .max_by_key(|sig| sig.created().copied())
+                .cloned()
+                .unwrap()
+        };
+        let subpackets = |sig: &packet::Signature| {
+            sig.config
+                .hashed_subpackets()
+                .filter(|p| {
+                    !matches!(
+                        p.data,
+                        packet::SubpacketData::SignatureCreationTime(_)
+                            | packet::SubpacketData::EmbeddedSignature(_)
+                    )
+                })
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        let old_sig = &old.details.users[0].signatures[0];
+        let user = &upgraded.details.users[0];
+        assert_eq!(user.signatures.len(), 2);
+        let new_sig = newest(&user.signatures);
+        assert_eq!(new_sig.hash_alg(), HashAlgorithm::SHA2_256);
+        assert!(new_sig.created() > old_sig.created());
+        assert_eq!(subpackets(&new_sig), subpackets(old_sig));
+        assert_eq!(upgraded.expires_at(), old.expires_at());
+
+        let public_key = SignedPublicKey::from(upgraded.clone());
+        let binding = public_key
+            .verify_user_id_binding("Me <me@mail.com>")
+            .unwrap();
+        assert_eq!(binding.signature, &new_sig);
+
+        for (old_subkey, subkey) in old.secret_subkeys.iter().zip(&upgraded.secret_subkeys) {
+            assert_eq!(subkey.signatures.len(), 2);
+            let new_sig = newest(&subkey.signatures);
+            assert_eq!(new_sig.hash_alg(), HashAlgorithm::SHA2_256);
+            assert_eq!(subpackets(&new_sig), subpackets(&old_subkey.signatures[0]));
+            if let Some(backsig) = new_sig.embedded_signature() {
+                assert_eq!(backsig.hash_alg(), HashAlgorithm::SHA2_256);
+                backsig
+                    .verify_backwards_key_binding(&subkey.key, &upgraded.primary_key)
+                    .unwrap();
+            }
+        }
+        assert_eq!(
+            public_key.key_capabilities(chrono::Utc::now()),
+            SignedPublicKey::from(old).key_capabilities(chrono::Utc::now())
+        );
+    }
+
+    #[test]
+    fn key_upgrade_signatures_v6_salt() {
+        let key = signed_key(
+            SecretKeyParamsBuilder::signing_only_ed25519()
+                .primary_user_id("Me <me@mail.com>".into())
+                .version(KeyVersion::V6),
+            "",
+        );
+        let old_sig = key.details.users[0].signatures[0].clone();
+        assert_eq!(old_sig.hash_alg(), HashAlgorithm::SHA2_256);
+
+        // the same hash gets a new salt, a different one a salt of its own size
+        for (hash_alg, salt_size) in [(HashAlgorithm::SHA2_256, 16), (HashAlgorithm::SHA2_512, 32)]
+        {
+            let mut upgraded = key.clone();
+            upgraded.upgrade_signatures(hash_alg, String::new).unwrap();
+            upgraded.verify().expect("invalid key");
+
+            let sigs = &upgraded.details.users[0].signatures;
+            assert_eq!(sigs.len(), 2);
+            let new_sig = sigs.iter().find(|sig| **sig != old_sig).unwrap();
+            let salt = new_sig.config.salt.as_deref().unwrap();
+            assert_eq!(salt.len(), salt_size);
+            assert_ne!(Some(salt), old_sig.config.salt.as_deref());
+        }
+    }
+
+    #[test]
+    fn key_upgrade_signatures_revoked() {
+        // without a User ID, the preferences are stored in a direct key signature
+        let mut key = signed_key(&SecretKeyParamsBuilder::signing_only_ed25519(), "");
+        assert_eq!(key.details.direct_signatures.len(), 1);
+
+        let revocation = key
+            .revocation_certificate(String::new, RevocationCode::KeyCompromised, "lost")
+            .unwrap();
+        key.details.revocation_signatures.push(revocation.signature);
+
+        let mut upgraded = key.clone();
+        upgraded
+            .upgrade_signatures(HashAlgorithm::SHA2_512, String::new)
+            .unwrap();
+        assert_eq!(upgraded, key);
+    }
+
+    #[test]
+    fn key_capabilities() {
+        let mut key = signed_key(
+            SecretKeyParamsBuilder::certify_only_with_subkeys()
+                .primary_user_id("Me <me@mail.com>".into()),
+            "",
+        );
+        let now = chrono::Utc::now();
+
+        let summary = |key: &SignedSecretKey| {
+            key.key_capabilities(now)
+                .into_iter()
+                .map(|caps| (caps.is_primary, caps.algorithm, caps.curve, caps.flags))
+                .collect::<Vec<_>>()
+        };
+        let flags = |f: fn(&mut KeyFlags)| {
+            let mut flags = KeyFlags::default();
+            f(&mut flags);
+            flags
+        };
+
+        let expected = vec![
+            (
+                true,
+                PublicKeyAlgorithm::Ed25519,
+                Some(ECCCurve::Ed25519),
+                flags(|f| f.set_certify(true)),
+            ),
+            (
+                false,
+                PublicKeyAlgorithm::Ed25519,
+                Some(ECCCurve::Ed25519),
+                flags(|f| f.set_sign(true)),
+            ),
+            (
+                false,
+                PublicKeyAlgorithm::X25519,
+                Some(ECCCurve::Curve25519),
+                flags(|f| {
+                    f.set_encrypt_comms(true);
+                    f.set_encrypt_storage(true);
+                }),
+            ),
+            (
+                false,
+                PublicKeyAlgorithm::Ed25519,
+                Some(ECCCurve::Ed25519),
+                flags(|f| f.set_authentication(true)),
+            ),
+        ];
+        assert_eq!(summary(&key), expected);
+        let caps = &key.key_capabilities(now)[2];
+        assert_eq!(caps.key_id, key.secret_subkeys[1].key_id());
+
+        // an encryption flag on the Ed25519 signing subkey is not listed
+        let subkey = &key.secret_subkeys[0];
+        let mut config = subkey.signatures[0].config.clone();
+        for p in &mut config.hashed_subpackets {
+            if let packet::SubpacketData::KeyFlags(_) = p.data {
+                let mut flags = KeyFlags::default();
+                flags.set_sign(true);
+                flags.set_encrypt_comms(true);
+                p.data = packet::SubpacketData::KeyFlags(flags.into());
+            }
+        }
+        let sig = config
+            .sign_key_binding(&key, String::new, &subkey.key)
+            .unwrap();
+        key.secret_subkeys[0].signatures = vec![sig];
+        assert_eq!(summary(&key), expected);
+    }
+
+    #[test]
+    fn key_subkey_selection_expired_primary() {
+        let now = chrono::Utc::now().trunc_subsecs(0);
+
+        let mut key = signed_key(
+            SecretKeyParamsBuilder::certify_only_with_subkeys()
+                .primary_user_id("Me <me@mail.com>".into())
+                .created_at(now - chrono::Duration::days(10)),
+            "",
+        );
+
+        let public_key = SignedPublicKey::from(key.clone());
+        assert_eq!(public_key.encryption_subkeys(now).len(), 1);
+        assert_eq!(public_key.signing_subkeys(now).len(), 1);
+        key.detach_sign_armored(&b"hello"[..], HashAlgorithm::SHA2_256, String::new)
+            .unwrap();
+
+        // the primary key expired after a day, the subkeys themselves don't expire
+        let user = &key.details.users[0];
+        let mut config = user.signatures[0].config.clone();
+        config.hashed_subpackets.push(packet::Subpacket::regular(
+            packet::SubpacketData::KeyExpirationTime(chrono::Duration::days(1)),
+        ));
+        let sig = config
+            .sign_certification(&key, String::new, Tag::UserId, &user.id)
+            .unwrap();
+        key.details.users[0].signatures = vec![sig];
+
+        let public_key = SignedPublicKey::from(key.clone());
+        assert!(public_key
+            .public_subkeys
+            .iter()
+            .flat_map(|subkey| &subkey.signatures)
+            .all(|sig| sig.key_expiration_time().is_none()));
+        assert!(public_key.encryption_subkeys(now).is_empty());
+        assert!(public_key.signing_subkeys(now).is_empty());
+        assert!(key
+            .detach_sign_armored(&b"hello"[..], HashAlgorithm::SHA2_256, String::new)
+            .is_err());
+    }
+
+    #[test]
+    fn key_reencrypt() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let s2k = S2kParams::new_argon2(&mut rng, AeadAlgorithm::Ocb, 1, 1, 10);
+        let passphrase = Some("hello".to_string());
+        let key = signed_key(
+            SecretKeyParamsBuilder::default()
+                .key_type(KeyType::ECDSA(ECCCurve::P256))
+                .can_certify(true)
+                .can_sign(true)
+                .primary_user_id("Me <me@mail.com>".into())
+                .passphrase(passphrase.clone())
+                .s2k(Some(s2k.clone()))
+                .subkey(
+                    SubkeyParamsBuilder::default()
+                        .key_type(KeyType::ECDH)
+                        .can_encrypt(true)
+                        .passphrase(passphrase)
+                        .s2k(Some(s2k))
+                        .build()
+                        .unwrap(),
+                ),
+            "hello",
+        );
+
+        let unlocks = |key: &SignedSecretKey, pw: &'static str| {
+            key.unlock(|| pw.into(), |_| Ok(())).is_ok()
+                && key
+                    .secret_subkeys
+                    .iter()
+                    .all(|sub| sub.unlock(|| pw.into(), |_| Ok(())).is_ok())
+        };
+
+        let new_s2k = S2kParams::new_argon2(&mut rng, AeadAlgorithm::Ocb, 1, 1, 10);
+        let changed = key
+            .reencrypt(|| "hello".into(), || "world".into(), new_s2k.clone())
+            .unwrap();
+        changed.verify().unwrap();
+        assert!(unlocks(&changed, "world"));
+        assert!(!unlocks(&changed, "hello"));
+        assert_eq!(changed.details, key.details);
+        assert_eq!(
+            changed.primary_key.public_key(),
+            key.primary_key.public_key()
+        );
+        assert_eq!(
+            changed.secret_subkeys[0].signatures,
+            key.secret_subkeys[0].signatures
+        );
+
+        // subkeys don't reuse the salt and nonce of the primary key
+        let SecretParams::Encrypted(primary) = changed.primary_key.secret_params() else {
+            panic!("not encrypted");
+        };
+        let SecretParams::Encrypted(sub) = changed.secret_subkeys[0].key.secret_params() else {
+            panic!("not encrypted");
+        };
+        assert_eq!(primary.string_to_key_params(), &new_s2k);
+        assert_ne!(primary.string_to_key_params(), sub.string_to_key_params());
+
+        // their salt and nonce are drawn from the given rng
+        let reencrypt = |seed| {
+            key.reencrypt_with_rng(
+                ChaCha8Rng::seed_from_u64(seed),
+                || "hello".into(),
+                || "world".into(),
+                new_s2k.clone(),
+            )
+            .unwrap()
+        };
+        assert_eq!(reencrypt(1), reencrypt(1));
+        assert_ne!(reencrypt(1), reencrypt(2));
+
+        // the wrong old passphrase is rejected
+        assert!(key
+            .reencrypt(|| "wrong".into(), || "world".into(), new_s2k.clone())
+            .is_err());
+
+        // removing the passphrase, and protecting an unencrypted key again
+        let plain = changed
+            .reencrypt(|| "world".into(), String::new, S2kParams::Unprotected)
+            .unwrap();
+        assert!(!plain.primary_key.secret_params().is_encrypted());
+        let protected = plain
+            .reencrypt(|| "ignored".into(), || "again".into(), new_s2k)
+            .unwrap();
+        assert!(unlocks(&protected, "again"));
+        assert!(!unlocks(&protected, "world"));
+    }
+}
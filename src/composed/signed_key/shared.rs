@@ -1,14 +1,19 @@
 use std::io;
 
-use chrono::Duration;
+use chrono::{DateTime, Duration, SubsecRound, Utc};
 use smallvec::SmallVec;
 
-use crate::composed::key::KeyDetails;
+use crate::composed::key::{
+    issuer_fingerprint_subpacket, issuer_subpackets, signature_version, KeyDetails,
+};
 use crate::composed::signed_key::{SignedPublicKey, SignedSecretKey};
 use crate::crypto::public_key::PublicKeyAlgorithm;
 use crate::errors::Result;
+use crate::packet::{PacketTrait, SignatureConfigBuilder, SignatureType, Subpacket, SubpacketData};
 use crate::ser::Serialize;
-use crate::types::{KeyId, KeyTrait, PublicKeyTrait, SignedUser, SignedUserAttribute};
+use crate::types::{
+    KeyId, KeyTrait, KeyVersion, PublicKeyTrait, SecretKeyTrait, SignedUser, SignedUserAttribute,
+};
 use crate::{packet, ArmorOptions};
 
 /// Shared details between secret and public keys.
@@ -70,6 +75,42 @@ impl SignedKeyDetails {
             .cloned()
     }
 
+    /// Removes unhashed subpackets from all signatures, see
+    /// [`packet::Signature::strip_unhashed_subpackets`].
+    pub fn strip_unhashed_subpackets(&mut self) {
+        let signatures = self
+            .revocation_signatures
+            .iter_mut()
+            .chain(self.direct_signatures.iter_mut())
+            .chain(self.users.iter_mut().flat_map(|u| u.signatures.iter_mut()))
+            .chain(
+                self.user_attributes
+                    .iter_mut()
+                    .flat_map(|u| u.signatures.iter_mut()),
+            );
+        for sig in signatures {
+            sig.strip_unhashed_subpackets();
+        }
+    }
+
+    /// Returns the creation time of the newest self-signature of `key`, considering direct key
+    /// signatures and certifications of User IDs and User Attributes.
+    ///
+    /// Revocations are not considered and signatures are not verified.
+    pub fn last_self_signature_created_at(
+        &self,
+        key: &impl PublicKeyTrait,
+    ) -> Option<&DateTime<Utc>> {
+        self.direct_signatures
+            .iter()
+            .chain(self.users.iter().flat_map(|u| &u.signatures))
+            .chain(self.user_attributes.iter().flat_map(|u| &u.signatures))
+            .filter(|sig| sig.typ() == SignatureType::Key || sig.is_certification())
+            .filter(|sig| packet::Signature::match_identity(sig, key))
+            .filter_map(|sig| sig.created())
+            .max()
+    }
+
     fn verify_users(&self, key: &impl PublicKeyTrait) -> Result<()> {
         for user in &self.users {
             user.verify(key)?;
@@ -111,17 +152,85 @@ impl SignedKeyDetails {
         Ok(())
     }
 
+    /// Returns the self-signature carrying the key preferences: the one on the
+    /// primary User ID, or the first direct key signature for keys without User IDs.
+    pub(crate) fn preferences_signature(&self) -> Option<&packet::Signature> {
+        self.users
+            .iter()
+            .find(|u| u.is_primary())
+            .or_else(|| self.users.first())
+            .map_or_else(
+                || self.direct_signatures.first(),
+                |user| user.signatures.first(),
+            )
+    }
+
+    /// Creates a positive certification self-signature for the given User ID or User
+    /// Attribute, carrying the same preferences as the current primary User ID.
+    pub(crate) fn self_certify<F>(
+        &self,
+        key: &impl SecretKeyTrait,
+        key_pw: F,
+        id: &impl PacketTrait,
+        is_primary: bool,
+    ) -> Result<packet::Signature>
+    where
+        F: FnOnce() -> String,
+    {
+        let mut hashed_subpackets = vec![Subpacket::regular(SubpacketData::SignatureCreationTime(
+            chrono::Utc::now().trunc_subsecs(0),
+        ))];
+        if is_primary {
+            hashed_subpackets.push(Subpacket::regular(SubpacketData::IsPrimary(true)));
+        }
+        if let Some(sig) = self.preferences_signature() {
+            hashed_subpackets.extend(
+                sig.config
+                    .hashed_subpackets()
+                    .filter(|p| {
+                        matches!(
+                            p.data,
+                            SubpacketData::KeyFlags(_)
+                                | SubpacketData::KeyExpirationTime(_)
+                                | SubpacketData::PreferredSymmetricAlgorithms(_)
+                                | SubpacketData::PreferredHashAlgorithms(_)
+                                | SubpacketData::PreferredCompressionAlgorithms(_)
+                                | SubpacketData::PreferredAeadAlgorithms(_)
+                                | SubpacketData::PreferredAeadCiphersuites(_)
+                                | SubpacketData::Features(_)
+                                | SubpacketData::KeyServerPreferences(_)
+                                | SubpacketData::PreferredKeyServer(_)
+                                | SubpacketData::RevocationKey(_)
+                        )
+                    })
+                    .cloned(),
+            );
+        }
+        hashed_subpackets.push(issuer_fingerprint_subpacket(&key));
+
+        let config = SignatureConfigBuilder::default()
+            .version(signature_version(&key))
+            .typ(SignatureType::CertPositive)
+            .pub_alg(key.algorithm())
+            .hash_alg(key.hash_alg())
+            .hashed_subpackets(hashed_subpackets)
+            .unhashed_subpackets(issuer_subpackets(&key))
+            .build()?;
+
+        config.sign_certification(key, key_pw, id.tag(), id)
+    }
+
     pub fn as_unsigned(&self) -> KeyDetails {
-        let primary_user = self.users.iter().find(|u| u.is_primary()).map_or_else(
-            || self.users.first().expect("missing user ids"),
-            |user| user,
-        );
-
-        let primary_user_id = primary_user.id.clone();
-        let primary_sig = primary_user
-            .signatures
-            .first()
-            .expect("invalid primary user");
+        let primary_user = self
+            .users
+            .iter()
+            .find(|u| u.is_primary())
+            .or_else(|| self.users.first());
+
+        let primary_user_id = primary_user.map(|user| user.id.clone());
+        let primary_sig = self
+            .preferences_signature()
+            .expect("missing user ids and direct signatures");
         let keyflags = primary_sig.key_flags();
 
         let preferred_symmetric_algorithms =
@@ -129,6 +238,8 @@ impl SignedKeyDetails {
         let preferred_hash_algorithms = SmallVec::from_slice(primary_sig.preferred_hash_algs());
         let preferred_compression_algorithms =
             SmallVec::from_slice(primary_sig.preferred_compression_algs());
+        let preferred_aead_algorithms =
+            SmallVec::from_slice(primary_sig.preferred_aead_ciphersuites());
         let revocation_key = primary_sig.revocation_key().cloned();
 
         KeyDetails::new(
@@ -146,6 +257,7 @@ impl SignedKeyDetails {
             preferred_symmetric_algorithms,
             preferred_hash_algorithms,
             preferred_compression_algorithms,
+            preferred_aead_algorithms,
             revocation_key,
         )
     }
@@ -276,4 +388,11 @@ impl KeyTrait for PublicOrSecret {
             PublicOrSecret::Secret(k) => k.algorithm(),
         }
     }
+
+    fn version(&self) -> KeyVersion {
+        match self {
+            PublicOrSecret::Public(k) => k.version(),
+            PublicOrSecret::Secret(k) => k.version(),
+        }
+    }
 }
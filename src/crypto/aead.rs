@@ -45,6 +45,17 @@ pub enum AeadAlgorithm {
 }
 
 impl AeadAlgorithm {
+    /// Returns true if this AEAD algorithm can be used with `sym_algorithm` in this build.
+    pub fn is_supported(&self, sym_algorithm: &SymmetricKeyAlgorithm) -> bool {
+        matches!(
+            (sym_algorithm, self),
+            (
+                SymmetricKeyAlgorithm::AES128 | SymmetricKeyAlgorithm::AES256,
+                AeadAlgorithm::Eax | AeadAlgorithm::Ocb | AeadAlgorithm::Gcm
+            )
+        )
+    }
+
     /// Nonce size used for this AEAD algorithm.
     pub fn nonce_size(&self) -> usize {
         match self {
@@ -0,0 +1,310 @@
+//! Brainpool curves (RFC 5639) for ECDSA and ECDH, backed by OpenSSL.
+//!
+//! There is no RustCrypto implementation of these curves, so they are only available with
+//! the `openssl` feature. Without it, all operations fail with [`Error::Unsupported`].
+//!
+//! [`Error::Unsupported`]: crate::errors::Error::Unsupported
+
+use rand::{CryptoRng, Rng};
+use zeroize::Zeroizing;
+
+use crate::crypto::ecc_curve::ECCCurve;
+use crate::errors::Result;
+
+pub(crate) use backend::{derive_shared_secret, generate_key, sign, verify};
+
+/// The size of the secret scalar and of the coordinates, in bytes.
+pub(crate) fn field_size(curve: &ECCCurve) -> Result<usize> {
+    match curve {
+        ECCCurve::BrainpoolP256r1 => Ok(32),
+        ECCCurve::BrainpoolP384r1 => Ok(48),
+        ECCCurve::BrainpoolP512r1 => Ok(64),
+        _ => bail!("{} is not a Brainpool curve", curve.name()),
+    }
+}
+
+/// Generates an ephemeral key pair on `curve` and derives the shared secret with `their_public`.
+///
+/// Returns the encoded ephemeral public point and the shared secret.
+pub(crate) fn ephemeral_shared_secret<R: CryptoRng + Rng>(
+    rng: &mut R,
+    curve: &ECCCurve,
+    their_public: &[u8],
+) -> Result<(Vec<u8>, Zeroizing<Vec<u8>>)> {
+    let (secret, public) = generate_key(rng, curve)?;
+    let shared_secret = derive_shared_secret(curve, &secret, their_public)?;
+
+    Ok((public, shared_secret))
+}
+
+#[cfg(feature = "openssl")]
+mod backend {
+    use std::cmp::Ordering;
+
+    use openssl::bn::{BigNum, BigNumContext};
+    use openssl::ec::{EcGroup, EcKey, EcPoint, PointConversionForm};
+    use openssl::ecdsa::EcdsaSig;
+    use openssl::nid::Nid;
+    use rand::{CryptoRng, Rng};
+    use zeroize::Zeroizing;
+
+    use super::field_size;
+    use crate::crypto::ecc_curve::ECCCurve;
+    use crate::errors::{Error, Result};
+
+    fn group(curve: &ECCCurve) -> Result<EcGroup> {
+        let nid = match curve {
+            ECCCurve::BrainpoolP256r1 => Nid::BRAINPOOL_P256R1,
+            ECCCurve::BrainpoolP384r1 => Nid::BRAINPOOL_P384R1,
+            ECCCurve::BrainpoolP512r1 => Nid::BRAINPOOL_P512R1,
+            _ => bail!("{} is not a Brainpool curve", curve.name()),
+        };
+
+        Ok(EcGroup::from_curve_name(nid)?)
+    }
+
+    fn public_point(group: &EcGroup, public: &[u8], ctx: &mut BigNumContext) -> Result<EcPoint> {
+        let point = EcPoint::from_bytes(group, public, ctx)?;
+        // rejects points which are not on the curve
+        EcKey::from_public_key(group, &point)?.check_key()?;
+
+        Ok(point)
+    }
+
+    /// Generates a key pair, returning the secret scalar and the uncompressed public point.
+    ///
+    /// The scalar is sampled from `rng`, rejecting values outside of `[1, n - 1]`.
+    pub(crate) fn generate_key<R: CryptoRng + Rng>(
+        rng: &mut R,
+        curve: &ECCCurve,
+    ) -> Result<(Zeroizing<Vec<u8>>, Vec<u8>)> {
+        let group = group(curve)?;
+        let size = field_size(curve)?;
+        let mut ctx = BigNumContext::new()?;
+        let mut order = BigNum::new()?;
+        group.order(&mut order, &mut ctx)?;
+
+        let mut bytes = Zeroizing::new(vec![0u8; size]);
+        let secret = loop {
+            rng.fill_bytes(&mut bytes);
+            let candidate = BigNum::from_slice(&bytes)?;
+            if candidate.num_bits() > 0 && candidate.ucmp(&order) == Ordering::Less {
+                break candidate;
+            }
+        };
+
+        let mut public = EcPoint::new(&group)?;
+        public.mul_generator2(&group, &secret, &mut ctx)?;
+        let public = public.to_bytes(&group, PointConversionForm::UNCOMPRESSED, &mut ctx)?;
+
+        Ok((Zeroizing::new(secret.to_vec_padded(size as i32)?), public))
+    }
+
+    /// Signs the `digest`, returning `r` and `s`.
+    pub(crate) fn sign(
+        curve: &ECCCurve,
+        secret: &[u8],
+        digest: &[u8],
+    ) -> Result<(Vec<u8>, Vec<u8>)> {
+        let group = group(curve)?;
+        let mut ctx = BigNumContext::new()?;
+        let secret = BigNum::from_slice(secret)?;
+
+        let mut public = EcPoint::new(&group)?;
+        public.mul_generator2(&group, &secret, &mut ctx)?;
+        let key = EcKey::from_private_components(&group, &secret, &public)?;
+
+        let signature = EcdsaSig::sign(digest, &key)?;
+
+        Ok((signature.r().to_vec(), signature.s().to_vec()))
+    }
+
+    /// Verifies the signature `r`, `s` of the `digest`.
+    pub(crate) fn verify(
+        curve: &ECCCurve,
+        public: &[u8],
+        digest: &[u8],
+        r: &[u8],
+        s: &[u8],
+    ) -> Result<()> {
+        let group = group(curve)?;
+        let mut ctx = BigNumContext::new()?;
+        let point = public_point(&group, public, &mut ctx)?;
+        let key = EcKey::from_public_key(&group, &point)?;
+
+        let signature =
+            EcdsaSig::from_private_components(BigNum::from_slice(r)?, BigNum::from_slice(s)?)?;
+        if signature.verify(digest, &key)? {
+            Ok(())
+        } else {
+            Err(Error::SignatureError(signature::Error::new()))
+        }
+    }
+
+    /// Derives the ECDH shared secret, the x coordinate of `secret * their_public`.
+    pub(crate) fn derive_shared_secret(
+        curve: &ECCCurve,
+        secret: &[u8],
+        their_public: &[u8],
+    ) -> Result<Zeroizing<Vec<u8>>> {
+        let group = group(curve)?;
+        let size = field_size(curve)?;
+        let mut ctx = BigNumContext::new()?;
+        let their_public = public_point(&group, their_public, &mut ctx)?;
+        let secret = BigNum::from_slice(secret)?;
+
+        let mut shared = EcPoint::new(&group)?;
+        shared.mul2(&group, &their_public, &secret, &mut ctx)?;
+        // uncompressed points are encoded as 0x04 || x || y
+        let shared =
+            Zeroizing::new(shared.to_bytes(&group, PointConversionForm::UNCOMPRESSED, &mut ctx)?);
+        ensure_eq!(shared.len(), 1 + 2 * size, "invalid shared point");
+
+        Ok(Zeroizing::new(shared[1..=size].to_vec()))
+    }
+}
+
+#[cfg(not(feature = "openssl"))]
+mod backend {
+    use rand::{CryptoRng, Rng};
+    use zeroize::Zeroizing;
+
+    use crate::crypto::ecc_curve::ECCCurve;
+    use crate::errors::Result;
+
+    pub(crate) fn generate_key<R: CryptoRng + Rng>(
+        _rng: &mut R,
+        curve: &ECCCurve,
+    ) -> Result<(Zeroizing<Vec<u8>>, Vec<u8>)> {
+        unsupported_err!("curve {} requires the openssl feature", curve.name())
+    }
+
+    pub(crate) fn sign(
+        curve: &ECCCurve,
+        _secret: &[u8],
+        _digest: &[u8],
+    ) -> Result<(Vec<u8>, Vec<u8>)> {
+        unsupported_err!("curve {} requires the openssl feature", curve.name())
+    }
+
+    pub(crate) fn verify(
+        curve: &ECCCurve,
+        _public: &[u8],
+        _digest: &[u8],
+        _r: &[u8],
+        _s: &[u8],
+    ) -> Result<()> {
+        unsupported_err!("curve {} requires the openssl feature", curve.name())
+    }
+
+    pub(crate) fn derive_shared_secret(
+        curve: &ECCCurve,
+        _secret: &[u8],
+        _their_public: &[u8],
+    ) -> Result<Zeroizing<Vec<u8>>> {
+        unsupported_err!("curve {} requires the openssl feature", curve.name())
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "openssl")]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use rand::{RngCore, SeedableRng};
+    use rand_chacha::ChaChaRng;
+
+    use super::*;
+    use crate::crypto::hash::HashAlgorithm;
+    use crate::crypto::{ecdh, ecdsa, Decryptor, Signer};
+    use crate::types::{Mpi, PublicParams, SecretKeyRepr};
+
+    const CURVES: [ECCCurve; 3] = [
+        ECCCurve::BrainpoolP256r1,
+        ECCCurve::BrainpoolP384r1,
+        ECCCurve::BrainpoolP512r1,
+    ];
+
+    #[test]
+    fn test_sign_verify() {
+        let mut rng = ChaChaRng::from_seed([0u8; 32]);
+
+        for curve in CURVES {
+            let (pkey, skey) = ecdsa::generate_key(&mut rng, &curve).unwrap();
+            let PublicParams::ECDSA(ref params) = pkey else {
+                panic!("invalid key generated");
+            };
+            let SecretKeyRepr::ECDSA(ref skey) = skey.as_ref().as_repr(&pkey).unwrap() else {
+                panic!("invalid key generated");
+            };
+
+            let hash = HashAlgorithm::SHA2_512;
+            let digest = hash.digest(b"hello world").unwrap();
+            let sig = skey.sign(hash, &digest, &pkey).unwrap();
+            let sig = sig.into_iter().map(Mpi::from_raw).collect::<Vec<_>>();
+            ecdsa::verify(params, hash, &digest, &sig).unwrap();
+
+            let other = hash.digest(b"hello world!").unwrap();
+            assert!(
+                ecdsa::verify(params, hash, &other, &sig).is_err(),
+                "{curve:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let mut rng = ChaChaRng::from_seed([0u8; 32]);
+
+        for curve in CURVES {
+            let (pkey, skey) = ecdh::generate_brainpool_key(&mut rng, &curve).unwrap();
+            let PublicParams::ECDH {
+                ref p,
+                hash,
+                alg_sym,
+                ..
+            } = pkey
+            else {
+                panic!("invalid key generated");
+            };
+
+            let mut fingerprint = vec![0u8; 20];
+            rng.fill_bytes(&mut fingerprint);
+            let mut plain = vec![0u8; 35];
+            rng.fill_bytes(&mut plain);
+
+            let mpis = ecdh::encrypt(
+                &mut rng,
+                &curve,
+                alg_sym,
+                hash,
+                &fingerprint,
+                p.as_bytes(),
+                &plain,
+            )
+            .unwrap();
+            // the ephemeral key is an uncompressed point
+            assert_eq!(mpis[0].len(), 1 + 2 * field_size(&curve).unwrap());
+            let mpis = mpis.into_iter().map(Into::into).collect::<Vec<Mpi>>();
+
+            let SecretKeyRepr::ECDHBrainpool(ref skey) = skey.as_ref().as_repr(&pkey).unwrap()
+            else {
+                panic!("invalid key generated");
+            };
+            assert_eq!(skey.decrypt(&mpis, &fingerprint).unwrap(), plain);
+        }
+    }
+
+    #[test]
+    fn test_rejects_invalid_points() {
+        let mut rng = ChaChaRng::from_seed([0u8; 32]);
+
+        for curve in CURVES {
+            let (secret, mut public) = generate_key(&mut rng, &curve).unwrap();
+            let last = public.len() - 1;
+            public[last] ^= 1;
+
+            assert!(derive_shared_secret(&curve, &secret, &public).is_err());
+        }
+    }
+}
@@ -1,12 +1,15 @@
 use std::fmt;
 
 use dsa::{Components, Signature, SigningKey, VerifyingKey};
-use num_bigint::BigUint;
+use num_bigint::prime::probably_prime;
+use num_bigint::{BigUint, RandBigInt};
+use num_traits::One;
 use rand::{CryptoRng, Rng};
 use signature::hazmat::PrehashVerifier;
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
 use crate::crypto::hash::HashAlgorithm;
+use crate::crypto::prime::{random_prime, Attempt, MR_ROUNDS};
 use crate::crypto::Signer;
 use crate::errors::Result;
 use crate::types::{PlainSecretParams, PublicParams};
@@ -35,7 +38,17 @@ impl Signer for SecretKey {
         let PublicParams::DSA { p, q, g, y } = pub_params else {
             bail!("invalid public params");
         };
-        let components = Components::from_components(p.into(), q.into(), g.into())?;
+        let q: BigUint = q.into();
+        // The hash must be at least as long as the subgroup order, as shorter hashes
+        // would be padded instead of truncated, reducing the security of the signature.
+        ensure!(
+            digest.len() * 8 >= q.bits(),
+            "hash {:?} is too short for DSA with a {} bit subgroup",
+            hash_algorithm,
+            q.bits()
+        );
+
+        let components = Components::from_components(p.into(), q, g.into())?;
         let verifying_key = VerifyingKey::from_components(components, y.into())?;
         let signing_key = SigningKey::from_components(verifying_key, self.x.clone())?;
 
@@ -86,6 +99,57 @@ pub fn generate_key<R: Rng + CryptoRng>(
     key_size: KeySize,
 ) -> Result<(PublicParams, PlainSecretParams)> {
     let components = Components::generate(&mut rng, key_size);
+    Ok(key_from_components(rng, components))
+}
+
+/// Generates a DSA key with an `l` bit `p` and an `n` bit `q`, calling `attempt` before every
+/// prime candidate that is tested.
+///
+/// The parameters are generated like [`Components::generate`] does: `q` is a random prime,
+/// and `p` is searched among the numbers of the form `k * 2q + 1`. `g` is found using the
+/// unverifiable method of FIPS 186-4, Appendix A.2.1.
+pub(crate) fn generate_key_with_attempts<R: Rng + CryptoRng>(
+    mut rng: R,
+    l: usize,
+    n: usize,
+    attempt: Attempt<'_>,
+) -> Result<(PublicParams, PlainSecretParams)> {
+    let p_min = BigUint::one() << (l - 1);
+    let (p, q) = 'search: loop {
+        let q = random_prime(&mut rng, n, attempt)?;
+        let two_q = &q << 1;
+
+        // give up on `q` after a while, like `Components::generate`
+        for _ in 0..4096 {
+            attempt()?;
+
+            let m = rng.gen_biguint(l - 1) | &p_min;
+            let p = &m - (&m % &two_q) + 1u32;
+            if p.bits() == l && probably_prime(&p, MR_ROUNDS) {
+                break 'search (p, q);
+            }
+        }
+    };
+
+    let e = (&p - 1u32) / &q;
+    let mut h = BigUint::from(2u32);
+    let g = loop {
+        let g = h.modpow(&e, &p);
+        if !g.is_one() {
+            break g;
+        }
+        h += 1u32;
+    };
+
+    let components = Components::from_components(p, q, g)?;
+    Ok(key_from_components(rng, components))
+}
+
+/// Generates the secret `x` for the given parameters.
+fn key_from_components<R: Rng + CryptoRng>(
+    mut rng: R,
+    components: Components,
+) -> (PublicParams, PlainSecretParams) {
     let signing_key = SigningKey::generate(&mut rng, components);
     let verifying_key = signing_key.verifying_key();
     let p = verifying_key.components().p();
@@ -101,7 +165,7 @@ pub fn generate_key<R: Rng + CryptoRng>(
         y: y.into(),
     };
     let secret_params = PlainSecretParams::DSA(x.into());
-    Ok((public_params, secret_params))
+    (public_params, secret_params)
 }
 
 #[cfg(test)]
@@ -294,12 +358,17 @@ mod test {
                     g: g.clone().into(),
                     y: y.clone().into(),
                 };
-                let res = key
-                    .sign(hash_algorithm, &hashed, &params)
-                    .expect("failed to sign");
-                let new_r = res[0].clone();
-                let new_s = res[1].clone();
-                assert_eq!((new_r, new_s), (r.to_bytes_be(), s.to_bytes_be()));
+                if hashed.len() * 8 < q.bits() {
+                    // hashes shorter than the 256 bit subgroup are only accepted for verification
+                    assert!(key.sign(hash_algorithm, &hashed, &params).is_err());
+                } else {
+                    let res = key
+                        .sign(hash_algorithm, &hashed, &params)
+                        .expect("failed to sign");
+                    let new_r = res[0].clone();
+                    let new_s = res[1].clone();
+                    assert_eq!((new_r, new_s), (r.to_bytes_be(), s.to_bytes_be()));
+                }
                 verify(p.clone(), q.clone(), g.clone(), y.clone(), &hashed, r, s)
                     .expect("failed to verify");
             };
@@ -374,5 +443,21 @@ mod test {
             hex_num("89EC4BB1400ECCFF8E7D9AA515CD1DE7803F2DAFF09693EE7FD1353E90A68307"),
             hex_num("C9F0BDABCC0D880BB137A994CC7F3980CE91CC10FAF529FC46565B15CEA854E1"),
         );
+
+        // DSA-2048-256 refuses to sign with SHA-1
+        let params = PublicParams::DSA {
+            p: p.into(),
+            q: q.into(),
+            g: g.into(),
+            y: y.into(),
+        };
+        let err = SecretKey { x }
+            .sign(
+                HashAlgorithm::SHA1,
+                &hash(HashAlgorithm::SHA1, "sample"),
+                &params,
+            )
+            .expect_err("SHA-1 hash accepted for a 256 bit q");
+        assert!(err.to_string().contains("too short"), "{err}");
     }
 }
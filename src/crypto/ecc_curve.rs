@@ -197,6 +197,18 @@ mod tests {
             ECCCurve::Secp256k1.oid(),
             vec![0x2B, 0x81, 0x04, 0x00, 0x0A]
         );
+        assert_eq!(
+            ECCCurve::BrainpoolP256r1.oid(),
+            vec![0x2B, 0x24, 0x03, 0x03, 0x02, 0x08, 0x01, 0x01, 0x07]
+        );
+        assert_eq!(
+            ECCCurve::BrainpoolP384r1.oid(),
+            vec![0x2B, 0x24, 0x03, 0x03, 0x02, 0x08, 0x01, 0x01, 0x0B]
+        );
+        assert_eq!(
+            ECCCurve::BrainpoolP512r1.oid(),
+            vec![0x2B, 0x24, 0x03, 0x03, 0x02, 0x08, 0x01, 0x01, 0x0D]
+        );
     }
 
     #[test]
@@ -5,7 +5,10 @@ use x25519_dalek::{PublicKey, StaticSecret};
 use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
 use crate::crypto::{
-    aes_kw, ecc_curve::ECCCurve, public_key::PublicKeyAlgorithm, sym::SymmetricKeyAlgorithm,
+    aes_kw, brainpool,
+    ecc_curve::{ecc_curve_from_oid, ECCCurve},
+    public_key::PublicKeyAlgorithm,
+    sym::SymmetricKeyAlgorithm,
     Decryptor, KeyParams,
 };
 use crate::errors::{Error, Result};
@@ -21,7 +24,7 @@ const ANON_SENDER: [u8; 20] = [
 
 const SECRET_KEY_LENGTH: usize = 32;
 
-/// Secret key for ECDH with Curve25519, the only combination we currently support.
+/// Secret key for ECDH with Curve25519.
 #[derive(Clone, PartialEq, Eq, Zeroize, ZeroizeOnDrop)]
 pub struct SecretKey {
     /// The secret point.
@@ -31,6 +34,16 @@ pub struct SecretKey {
     pub alg_sym: SymmetricKeyAlgorithm,
 }
 
+/// Secret key for ECDH with one of the Brainpool curves, which requires the `openssl` feature.
+#[derive(Clone, PartialEq, Eq, Zeroize, ZeroizeOnDrop)]
+pub struct BrainpoolSecretKey {
+    /// The secret scalar, big endian and padded to the field size of the curve.
+    pub secret: Vec<u8>,
+    pub hash: HashAlgorithm,
+    pub oid: Vec<u8>,
+    pub alg_sym: SymmetricKeyAlgorithm,
+}
+
 impl fmt::Debug for SecretKey {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("EcdhSecretKey")
@@ -105,6 +118,56 @@ impl Decryptor for SecretKey {
     }
 }
 
+impl fmt::Debug for BrainpoolSecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EcdhBrainpoolSecretKey")
+            .field("secret", &"[..]")
+            .field("hash", &self.hash)
+            .field("oid", &hex::encode(&self.oid))
+            .field("alg_sym", &self.alg_sym)
+            .finish()
+    }
+}
+
+impl KeyParams for BrainpoolSecretKey {
+    type KeyParams = (Vec<u8>, SymmetricKeyAlgorithm, HashAlgorithm);
+
+    fn key_params(&self) -> Self::KeyParams {
+        (self.oid.clone(), self.alg_sym, self.hash)
+    }
+}
+
+impl Decryptor for BrainpoolSecretKey {
+    fn decrypt(&self, mpis: &[Mpi], fingerprint: &[u8]) -> Result<Vec<u8>> {
+        debug!("ECDH decrypt");
+
+        ensure_eq!(mpis.len(), 3);
+        let Some(curve) = ecc_curve_from_oid(&self.oid) else {
+            unsupported_err!("curve {} for ECDH", hex::encode(&self.oid));
+        };
+
+        // encrypted and wrapped value derived from the session key
+        let encrypted_session_key = mpis[2].as_bytes();
+
+        let shared_secret =
+            brainpool::derive_shared_secret(&curve, &self.secret, mpis[0].as_bytes())?;
+
+        // obtain the session key from the shared secret
+        let encrypted_key_len: usize = match mpis[1].first() {
+            Some(l) => *l as usize,
+            None => 0,
+        };
+
+        unwrap_session_key(
+            &shared_secret,
+            encrypted_session_key,
+            encrypted_key_len,
+            &(self.oid.clone(), self.alg_sym, self.hash),
+            fingerprint,
+        )
+    }
+}
+
 /// Obtain the OpenPGP session key for a DH shared secret.
 ///
 /// This helper function performs the key derivation and unwrapping steps
@@ -115,13 +178,30 @@ pub fn derive_session_key(
     encrypted_key_len: usize,
     key_params: &<SecretKey as KeyParams>::KeyParams,
     fingerprint: &[u8],
+) -> Result<Vec<u8>> {
+    unwrap_session_key(
+        &shared_secret,
+        encrypted_session_key,
+        encrypted_key_len,
+        key_params,
+        fingerprint,
+    )
+}
+
+/// Like [`derive_session_key`], for shared secrets of any curve.
+fn unwrap_session_key(
+    shared_secret: &[u8],
+    encrypted_session_key: &[u8],
+    encrypted_key_len: usize,
+    key_params: &<SecretKey as KeyParams>::KeyParams,
+    fingerprint: &[u8],
 ) -> Result<Vec<u8>> {
     let (oid, alg_sym, hash) = key_params;
 
     let param = build_ecdh_param(oid, *alg_sym, *hash, fingerprint);
 
     // Perform key derivation
-    let z = kdf(*hash, &shared_secret, alg_sym.key_size(), &param)?;
+    let z = kdf_inner(*hash, shared_secret, alg_sym.key_size(), &param)?;
 
     // Perform AES Key Unwrap
     let mut encrypted_session_key_vec = vec![0; encrypted_key_len];
@@ -179,8 +259,39 @@ pub fn derive_session_key(
 pub fn generate_key<R: Rng + CryptoRng>(mut rng: R) -> (PublicParams, PlainSecretParams) {
     let mut secret_key_bytes = Zeroizing::new([0u8; SECRET_KEY_LENGTH]);
     rng.fill_bytes(&mut *secret_key_bytes);
+    key_from_seed(&secret_key_bytes)
+}
 
-    let secret = StaticSecret::from(*secret_key_bytes);
+/// Generate an ECDH KeyPair on one of the Brainpool curves, requires the `openssl` feature.
+pub fn generate_brainpool_key<R: Rng + CryptoRng>(
+    mut rng: R,
+    curve: &ECCCurve,
+) -> Result<(PublicParams, PlainSecretParams)> {
+    // The KDF parameters recommended by RFC 6637, Section 12.2 and RFC 9580, Section 9.2, for
+    // curves of the same size.
+    let (hash, alg_sym) = match curve {
+        ECCCurve::BrainpoolP256r1 => (HashAlgorithm::SHA2_256, SymmetricKeyAlgorithm::AES128),
+        ECCCurve::BrainpoolP384r1 => (HashAlgorithm::SHA2_384, SymmetricKeyAlgorithm::AES192),
+        ECCCurve::BrainpoolP512r1 => (HashAlgorithm::SHA2_512, SymmetricKeyAlgorithm::AES256),
+        _ => unsupported_err!("curve {:?} for Brainpool ECDH", curve),
+    };
+    let (secret, public) = brainpool::generate_key(&mut rng, curve)?;
+
+    Ok((
+        PublicParams::ECDH {
+            curve: curve.clone(),
+            p: Mpi::from_raw_slice(&public),
+            hash,
+            alg_sym,
+        },
+        PlainSecretParams::ECDH(Mpi::from_raw_slice(&secret)),
+    ))
+}
+
+/// Derive an ECDH Curve25519 keypair from the 32 byte seed, which is the (unclamped) X25519
+/// secret key.
+pub fn key_from_seed(seed: &[u8; SECRET_KEY_LENGTH]) -> (PublicParams, PlainSecretParams) {
+    let secret = StaticSecret::from(*seed);
     let public = PublicKey::from(&secret);
 
     // public key
@@ -244,6 +355,11 @@ pub fn build_ecdh_param(
 /// Key Derivation Function for ECDH (as defined in RFC 6637).
 /// https://tools.ietf.org/html/rfc6637#section-7
 pub fn kdf(hash: HashAlgorithm, x: &[u8; 32], length: usize, param: &[u8]) -> Result<Vec<u8>> {
+    kdf_inner(hash, x, length, param)
+}
+
+/// Like [`kdf`], for shared secrets of any curve.
+fn kdf_inner(hash: HashAlgorithm, x: &[u8], length: usize, param: &[u8]) -> Result<Vec<u8>> {
     let prefix = vec![0, 0, 0, 1];
 
     let values: Vec<&[u8]> = vec![&prefix, x, param];
@@ -277,28 +393,46 @@ pub fn encrypt<R: CryptoRng + Rng>(
 
     let param = build_ecdh_param(&curve.oid(), alg_sym, hash, fingerprint);
 
-    ensure_eq!(q.len(), 33, "invalid public key");
+    let (encoded_public, shared_secret) = match curve {
+        ECCCurve::Curve25519 => {
+            ensure_eq!(q.len(), 33, "invalid public key");
 
-    let their_public = {
-        // public part of the ephemeral key (removes 0x40 prefix)
-        let public_key = &q[1..];
+            let their_public = {
+                // public part of the ephemeral key (removes 0x40 prefix)
+                let public_key = &q[1..];
 
-        // create montgomery point
-        let mut public_key_arr = [0u8; 32];
-        public_key_arr[..].copy_from_slice(public_key);
+                // create montgomery point
+                let mut public_key_arr = [0u8; 32];
+                public_key_arr[..].copy_from_slice(public_key);
 
-        x25519_dalek::PublicKey::from(public_key_arr)
-    };
+                x25519_dalek::PublicKey::from(public_key_arr)
+            };
 
-    let mut our_secret_key_bytes = Zeroizing::new([0u8; SECRET_KEY_LENGTH]);
-    rng.fill_bytes(&mut *our_secret_key_bytes);
-    let our_secret = StaticSecret::from(*our_secret_key_bytes);
+            let mut our_secret_key_bytes = Zeroizing::new([0u8; SECRET_KEY_LENGTH]);
+            rng.fill_bytes(&mut *our_secret_key_bytes);
+            let our_secret = StaticSecret::from(*our_secret_key_bytes);
 
-    // derive shared secret
-    let shared_secret = our_secret.diffie_hellman(&their_public);
+            // derive shared secret
+            let shared_secret = our_secret.diffie_hellman(&their_public);
+
+            // Encode public point: prefix with 0x40
+            let mut encoded_public = Vec::with_capacity(33);
+            encoded_public.push(0x40);
+            encoded_public.extend(x25519_dalek::PublicKey::from(&our_secret).as_bytes().iter());
+
+            (
+                encoded_public,
+                Zeroizing::new(shared_secret.as_bytes().to_vec()),
+            )
+        }
+        ECCCurve::BrainpoolP256r1 | ECCCurve::BrainpoolP384r1 | ECCCurve::BrainpoolP512r1 => {
+            brainpool::ephemeral_shared_secret(rng, curve, q)?
+        }
+        _ => unsupported_err!("curve {:?} for ECDH", curve),
+    };
 
     // Perform key derivation
-    let z = kdf(hash, shared_secret.as_bytes(), alg_sym.key_size(), &param)?;
+    let z = kdf_inner(hash, &shared_secret, alg_sym.key_size(), &param)?;
 
     // PKCS5-style padding, with a blocksize of 8.
     // However, the padding may exceed the length one block, to obfuscate key size.
@@ -336,11 +470,6 @@ pub fn encrypt<R: CryptoRng + Rng>(
     // Perform AES Key Wrap
     let encrypted_key = aes_kw::wrap(&z, &plain_padded)?;
 
-    // Encode public point: prefix with 0x40
-    let mut encoded_public = Vec::with_capacity(33);
-    encoded_public.push(0x40);
-    encoded_public.extend(x25519_dalek::PublicKey::from(&our_secret).as_bytes().iter());
-
     let encrypted_key_len = vec![u8::try_from(encrypted_key.len())?];
 
     Ok(vec![encoded_public, encrypted_key_len, encrypted_key])
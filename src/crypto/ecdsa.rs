@@ -20,6 +20,12 @@ pub enum SecretKey {
     P384(p384::SecretKey),
     P521(p521::SecretKey),
     Secp256k1(k256::SecretKey),
+    Brainpool {
+        /// The secret scalar.
+        secret: Mpi,
+        #[zeroize(skip)]
+        curve: ECCCurve,
+    },
     Unsupported {
         /// The secret point.
         x: Mpi,
@@ -35,6 +41,11 @@ impl fmt::Debug for SecretKey {
             Self::P384(_) => write!(f, "ECDSASecretKey::P384([..])"),
             Self::P521(_) => write!(f, "ECDSASecretKey::P521([..])"),
             Self::Secp256k1(_) => write!(f, "ECDSASecretKey::Secp256k1([..])"),
+            Self::Brainpool { curve, .. } => f
+                .debug_struct("ECDSASecretKey::Brainpool")
+                .field("secret", &"[..]")
+                .field("curve", &curve)
+                .finish(),
             Self::Unsupported { curve, .. } => f
                 .debug_struct("ECDSASecretKey::Unsupported")
                 .field("x", &"[..]")
@@ -99,6 +110,9 @@ impl Signer for SecretKey {
                 let (r, s) = signature.split_bytes();
                 (r.to_vec(), s.to_vec())
             }
+            Self::Brainpool { secret, curve } => {
+                super::brainpool::sign(curve, secret.as_bytes(), digest)?
+            }
             Self::Unsupported { curve, .. } => {
                 unsupported_err!("curve {:?} for ECDSA", curve)
             }
@@ -115,6 +129,7 @@ impl SecretKey {
             Self::P384 { .. } => Some(48),
             Self::P521 { .. } => Some(66),
             Self::Secp256k1 { .. } => Some(32),
+            Self::Brainpool { curve, .. } => super::brainpool::field_size(curve).ok(),
             Self::Unsupported { .. } => None,
         }
     }
@@ -181,6 +196,18 @@ pub fn generate_key<R: Rng + CryptoRng>(
             ))
         }
 
+        ECCCurve::BrainpoolP256r1 | ECCCurve::BrainpoolP384r1 | ECCCurve::BrainpoolP512r1 => {
+            let (secret, public) = super::brainpool::generate_key(&mut rng, curve)?;
+
+            Ok((
+                PublicParams::ECDSA(EcdsaPublicParams::Brainpool {
+                    curve: curve.clone(),
+                    p: Mpi::from_raw_slice(&public),
+                }),
+                PlainSecretParams::ECDSA(Mpi::from_raw_slice(&secret)),
+            ))
+        }
+
         _ => unsupported_err!("curve {:?} for ECDSA", curve),
     }
 }
@@ -289,6 +316,17 @@ pub fn verify(
 
             Ok(())
         }
+        EcdsaPublicParams::Brainpool { curve, p } => {
+            ensure_eq!(sig.len(), 2);
+
+            super::brainpool::verify(
+                curve,
+                p.as_bytes(),
+                hashed,
+                sig[0].as_bytes(),
+                sig[1].as_bytes(),
+            )
+        }
         EcdsaPublicParams::Unsupported { curve, .. } => {
             unsupported_err!("curve {:?} for ECDSA", curve.to_string())
         }
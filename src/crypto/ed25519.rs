@@ -0,0 +1,114 @@
+use std::fmt;
+
+use rand::{CryptoRng, Rng};
+use signature::{Signer as _, Verifier};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+use crate::crypto::hash::HashAlgorithm;
+use crate::crypto::Signer;
+use crate::errors::Result;
+use crate::types::{Mpi, PlainSecretParams, PublicParams};
+
+/// Secret key for Ed25519, as defined in RFC 9580.
+#[derive(Clone, PartialEq, Eq, Zeroize, ZeroizeOnDrop)]
+pub struct SecretKey {
+    /// The native secret key.
+    pub secret: [u8; 32],
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Ed25519SecretKey")
+            .field("secret", &"[..]")
+            .finish()
+    }
+}
+
+impl Signer for SecretKey {
+    /// Returns the native 64 octet signature, as a single value.
+    fn sign(
+        &self,
+        _hash: HashAlgorithm,
+        digest: &[u8],
+        pub_params: &PublicParams,
+    ) -> Result<Vec<Vec<u8>>> {
+        let PublicParams::Ed25519 { public } = pub_params else {
+            bail!("invalid public params");
+        };
+
+        let key = ed25519_dalek::SigningKey::from_bytes(&self.secret);
+        ensure_eq!(
+            &key.verifying_key().to_bytes(),
+            public,
+            "public key does not match the secret key"
+        );
+
+        Ok(vec![key.sign(digest).to_bytes().to_vec()])
+    }
+}
+
+/// Generate an Ed25519 KeyPair, as defined in RFC 9580.
+pub fn generate_key<R: Rng + CryptoRng>(mut rng: R) -> (PublicParams, PlainSecretParams) {
+    let mut bytes = Zeroizing::new([0u8; ed25519_dalek::SECRET_KEY_LENGTH]);
+    rng.fill_bytes(&mut *bytes);
+    key_from_seed(&bytes)
+}
+
+/// Derive an Ed25519 keypair from the 32 byte seed, which is the secret key.
+pub fn key_from_seed(seed: &[u8; 32]) -> (PublicParams, PlainSecretParams) {
+    let secret = ed25519_dalek::SigningKey::from_bytes(seed);
+    let public = ed25519_dalek::VerifyingKey::from(&secret);
+
+    (
+        PublicParams::Ed25519 {
+            public: public.to_bytes(),
+        },
+        PlainSecretParams::Ed25519(secret.to_bytes()),
+    )
+}
+
+/// Verify a native Ed25519 signature.
+pub fn verify(public: &[u8; 32], hashed: &[u8], sig: &[Mpi]) -> Result<()> {
+    let sig_bytes = signature_bytes(sig)?;
+
+    let pk = ed25519_dalek::VerifyingKey::from_bytes(public)?;
+    let sig = ed25519_dalek::Signature::from_bytes(&sig_bytes);
+    pk.verify(hashed, &sig)?;
+
+    Ok(())
+}
+
+/// Verify a batch of Ed25519 signatures, given as raw `(public, hashed, sig)` triples, using
+/// `ed25519-dalek`'s batch verification.
+///
+/// This is considerably faster than verifying each signature on its own, but only reports
+/// whether all signatures are valid, not which ones are not.
+pub fn verify_batch(items: &[([u8; 32], &[u8], [u8; 64])]) -> Result<()> {
+    if items.is_empty() {
+        return Ok(());
+    }
+
+    let mut messages = Vec::with_capacity(items.len());
+    let mut signatures = Vec::with_capacity(items.len());
+    let mut keys = Vec::with_capacity(items.len());
+    for (public, hashed, sig) in items {
+        messages.push(*hashed);
+        signatures.push(ed25519_dalek::Signature::from_bytes(sig));
+        keys.push(ed25519_dalek::VerifyingKey::from_bytes(public)?);
+    }
+
+    ed25519_dalek::verify_batch(&messages, &signatures, &keys)?;
+
+    Ok(())
+}
+
+/// Extracts the raw signature from its native encoding, which is kept as a single `Mpi`.
+pub(crate) fn signature_bytes(sig: &[Mpi]) -> Result<[u8; 64]> {
+    ensure_eq!(sig.len(), 1, "invalid signature");
+    let sig_bytes = sig[0]
+        .as_bytes()
+        .try_into()
+        .map_err(|_| format_err!("invalid signature (len)"))?;
+
+    Ok(sig_bytes)
+}
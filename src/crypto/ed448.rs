@@ -0,0 +1,192 @@
+use std::fmt;
+
+use rand::{CryptoRng, Rng};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+use crate::crypto::hash::HashAlgorithm;
+use crate::crypto::Signer;
+use crate::errors::Result;
+use crate::types::{Mpi, PlainSecretParams, PublicParams};
+
+const SECRET_KEY_LENGTH: usize = 57;
+
+/// Secret key for Ed448, as defined in RFC 9580.
+///
+/// Ed448 is implemented by OpenSSL, using it requires the `openssl` feature.
+#[derive(Clone, PartialEq, Eq, Zeroize, ZeroizeOnDrop)]
+pub struct SecretKey {
+    /// The native secret key.
+    pub secret: [u8; 57],
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Ed448SecretKey")
+            .field("secret", &"[..]")
+            .finish()
+    }
+}
+
+impl Signer for SecretKey {
+    /// Returns the native 114 octet signature, as a single value.
+    fn sign(
+        &self,
+        _hash: HashAlgorithm,
+        digest: &[u8],
+        pub_params: &PublicParams,
+    ) -> Result<Vec<Vec<u8>>> {
+        let PublicParams::Ed448 { public } = pub_params else {
+            bail!("invalid public params");
+        };
+
+        ensure_eq!(
+            &public_key(&self.secret)?,
+            public,
+            "public key does not match the secret key"
+        );
+
+        Ok(vec![sign(&self.secret, digest)?])
+    }
+}
+
+/// Generate an Ed448 KeyPair, as defined in RFC 9580.
+pub fn generate_key<R: Rng + CryptoRng>(mut rng: R) -> Result<(PublicParams, PlainSecretParams)> {
+    let mut secret = Zeroizing::new([0u8; SECRET_KEY_LENGTH]);
+    rng.fill_bytes(&mut *secret);
+
+    let public = public_key(&secret)?;
+
+    Ok((
+        PublicParams::Ed448 { public },
+        PlainSecretParams::Ed448(*secret),
+    ))
+}
+
+/// Verify a native Ed448 signature.
+///
+/// The signature is made over the digest, with an empty context.
+pub fn verify(public: &[u8; 57], hashed: &[u8], sig: &[Mpi]) -> Result<()> {
+    ensure_eq!(sig.len(), 1, "invalid signature");
+    let sig_bytes: &[u8; 114] = sig[0]
+        .as_bytes()
+        .try_into()
+        .map_err(|_| format_err!("invalid signature (len)"))?;
+
+    verify_raw(public, hashed, sig_bytes)
+}
+
+#[cfg(feature = "openssl")]
+fn public_key(secret: &[u8; 57]) -> Result<[u8; 57]> {
+    use openssl::pkey::{Id, PKey};
+
+    let secret = PKey::private_key_from_raw_bytes(secret, Id::ED448)?;
+    let public = secret.raw_public_key()?;
+
+    public
+        .try_into()
+        .map_err(|_| format_err!("invalid Ed448 public key"))
+}
+
+#[cfg(feature = "openssl")]
+fn sign(secret: &[u8; 57], data: &[u8]) -> Result<Vec<u8>> {
+    use openssl::pkey::{Id, PKey};
+
+    let secret = PKey::private_key_from_raw_bytes(secret, Id::ED448)?;
+    let signature =
+        openssl::sign::Signer::new_without_digest(&secret)?.sign_oneshot_to_vec(data)?;
+    ensure_eq!(signature.len(), 114, "invalid Ed448 signature");
+
+    Ok(signature)
+}
+
+#[cfg(feature = "openssl")]
+fn verify_raw(public: &[u8; 57], data: &[u8], sig: &[u8; 114]) -> Result<()> {
+    use openssl::pkey::{Id, PKey};
+
+    let public = PKey::public_key_from_raw_bytes(public, Id::ED448)?;
+    if openssl::sign::Verifier::new_without_digest(&public)?.verify_oneshot(sig, data)? {
+        Ok(())
+    } else {
+        Err(crate::errors::Error::SignatureError(signature::Error::new()))
+    }
+}
+
+#[cfg(not(feature = "openssl"))]
+fn public_key(_secret: &[u8; 57]) -> Result<[u8; 57]> {
+    unsupported_err!("Ed448 requires the openssl feature")
+}
+
+#[cfg(not(feature = "openssl"))]
+fn sign(_secret: &[u8; 57], _data: &[u8]) -> Result<Vec<u8>> {
+    unsupported_err!("Ed448 requires the openssl feature")
+}
+
+#[cfg(not(feature = "openssl"))]
+fn verify_raw(_public: &[u8; 57], _data: &[u8], _sig: &[u8; 114]) -> Result<()> {
+    unsupported_err!("Ed448 requires the openssl feature")
+}
+
+#[cfg(test)]
+#[cfg(feature = "openssl")]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    #[test]
+    fn test_rfc8032_vector() {
+        // https://www.rfc-editor.org/rfc/rfc8032#section-7.4, "1 octet"
+        let secret = hex::decode(
+            "c4eab05d357007c632f3dbb48489924d552b08fe0c353a0d4a1f00acda2c463a\
+             fbea67c5e8d2877c5e3bc397a659949ef8021e954e0a12274e",
+        )
+        .unwrap();
+        let public = hex::decode(
+            "43ba28f430cdff456ae531545f7ecd0ac834a55d9358c0372bfa0c6c6798c086\
+             6aea01eb00742802b8438ea4cb82169c235160627b4c3a9480",
+        )
+        .unwrap();
+        let sig = hex::decode(
+            "26b8f91727bd62897af15e41eb43c377efb9c610d48f2335cb0bd0087810f435\
+             2541b143c4b981b7e18f62de8ccdf633fc1bf037ab7cd779805e0dbcc0aae1cb\
+             cee1afb2e027df36bc04dcecbf154336c19f0af7e0a6472905e799f1953d2a0f\
+             f3348ab21aa4adafd1d234441cf807c03a00",
+        )
+        .unwrap();
+
+        let secret: [u8; 57] = secret.try_into().unwrap();
+        let public: [u8; 57] = public.try_into().unwrap();
+        assert_eq!(public_key(&secret).unwrap(), public);
+        assert_eq!(sign(&secret, &[0x03]).unwrap(), sig);
+        verify(&public, &[0x03], &[Mpi::from_slice(&sig)]).unwrap();
+        assert!(verify(&public, &[0x04], &[Mpi::from_slice(&sig)]).is_err());
+    }
+
+    #[test]
+    fn test_sign_verify() {
+        let mut rng = ChaChaRng::seed_from_u64(0);
+        let (pub_params, PlainSecretParams::Ed448(secret)) = generate_key(&mut rng).unwrap() else {
+            panic!("unexpected params");
+        };
+        let PublicParams::Ed448 { public } = pub_params else {
+            panic!("unexpected params");
+        };
+
+        let hash = HashAlgorithm::SHA2_512;
+        let digest = hash.digest(b"hello world").unwrap();
+        let sig = SecretKey { secret }
+            .sign(hash, &digest, &pub_params)
+            .unwrap();
+        assert_eq!(sig.len(), 1);
+        assert_eq!(sig[0].len(), 114);
+
+        let sig = [Mpi::from_slice(&sig[0])];
+        verify(&public, &digest, &sig).unwrap();
+
+        let other = hash.digest(b"hello world!").unwrap();
+        assert!(verify(&public, &other, &sig).is_err());
+    }
+}
@@ -60,7 +60,12 @@ impl Signer for SecretKey {
 pub fn generate_key<R: Rng + CryptoRng>(mut rng: R) -> (PublicParams, PlainSecretParams) {
     let mut bytes = Zeroizing::new([0u8; ed25519_dalek::SECRET_KEY_LENGTH]);
     rng.fill_bytes(&mut *bytes);
-    let secret = ed25519_dalek::SigningKey::from_bytes(&bytes);
+    key_from_seed(&bytes)
+}
+
+/// Derive an EdDSA keypair from the 32 byte seed, which is the Ed25519 secret key.
+pub fn key_from_seed(seed: &[u8; 32]) -> (PublicParams, PlainSecretParams) {
+    let secret = ed25519_dalek::SigningKey::from_bytes(seed);
     let public = ed25519_dalek::VerifyingKey::from(&secret);
 
     // public key
@@ -69,8 +74,7 @@ pub fn generate_key<R: Rng + CryptoRng>(mut rng: R) -> (PublicParams, PlainSecre
     q.extend_from_slice(&public.to_bytes());
 
     // secret key
-    let p = Mpi::from_raw_slice(&secret.to_bytes());
-    bytes.zeroize();
+    let p = Mpi::from_raw_slice(&Zeroizing::new(secret.to_bytes())[..]);
 
     (
         PublicParams::EdDSA {
@@ -91,24 +95,10 @@ pub fn verify(
 ) -> Result<()> {
     match *curve {
         ECCCurve::Ed25519 => {
-            ensure_eq!(sig.len(), 2);
-
-            let r = sig[0].as_bytes();
-            let s = sig[1].as_bytes();
+            let (pk_bytes, sig_bytes) = ed25519_parts(q, sig)?;
 
-            ensure!(r.len() < 33, "invalid R (len)");
-            ensure!(s.len() < 33, "invalid S (len)");
-            ensure_eq!(q.len(), 33, "invalid Q (len)");
-            ensure_eq!(q[0], 0x40, "invalid Q (prefix)");
-
-            let pk =
-                ed25519_dalek::VerifyingKey::from_bytes(&q[1..].try_into().expect("pre verified"))?;
-            let mut sig_bytes = vec![0u8; 64];
-            // add padding if the values were encoded short
-            sig_bytes[(32 - r.len())..32].copy_from_slice(r);
-            sig_bytes[32 + (32 - s.len())..].copy_from_slice(s);
-
-            let sig = ed25519_dalek::Signature::from_slice(&sig_bytes)?;
+            let pk = ed25519_dalek::VerifyingKey::from_bytes(&pk_bytes)?;
+            let sig = ed25519_dalek::Signature::from_bytes(&sig_bytes);
 
             pk.verify(hashed, &sig)?;
 
@@ -117,3 +107,24 @@ pub fn verify(
         _ => unsupported_err!("curve {:?} for EdDSA", curve.to_string()),
     }
 }
+
+/// Extracts the raw Ed25519 public key and signature from their OpenPGP encoding.
+pub(crate) fn ed25519_parts(q: &[u8], sig: &[Mpi]) -> Result<([u8; 32], [u8; 64])> {
+    ensure_eq!(sig.len(), 2);
+
+    let r = sig[0].as_bytes();
+    let s = sig[1].as_bytes();
+
+    ensure!(r.len() < 33, "invalid R (len)");
+    ensure!(s.len() < 33, "invalid S (len)");
+    ensure_eq!(q.len(), 33, "invalid Q (len)");
+    ensure_eq!(q[0], 0x40, "invalid Q (prefix)");
+
+    let pk_bytes = q[1..].try_into().expect("pre verified");
+    let mut sig_bytes = [0u8; 64];
+    // add padding if the values were encoded short
+    sig_bytes[(32 - r.len())..32].copy_from_slice(r);
+    sig_bytes[32 + (32 - s.len())..].copy_from_slice(s);
+
+    Ok((pk_bytes, sig_bytes))
+}
@@ -1,6 +1,7 @@
 use std::{fmt::Display, str::FromStr};
 
-use digest::Digest;
+use const_oid::ObjectIdentifier;
+use digest::{const_oid::AssociatedOid, Digest};
 use md5::Md5;
 use num_enum::{FromPrimitive, IntoPrimitive};
 use ripemd::Ripemd160;
@@ -172,7 +173,8 @@ impl HashAlgorithm {
         })
     }
 
-    /// Returns the expected digest size for the given algorithm.
+    /// Returns the expected digest size in bytes for the given algorithm, or 0 if it is not
+    /// supported.
     pub fn digest_size(self) -> usize {
         match self {
             HashAlgorithm::MD5 => Md5::output_size(),
@@ -187,4 +189,34 @@ impl HashAlgorithm {
             _ => 0,
         }
     }
+
+    /// Returns the size in bytes of the salt of version 6 signatures made with this algorithm,
+    /// or `None` if it can't be used for them.
+    pub fn salt_size(self) -> Option<usize> {
+        match self {
+            HashAlgorithm::SHA2_224 | HashAlgorithm::SHA2_256 | HashAlgorithm::SHA3_256 => Some(16),
+            HashAlgorithm::SHA2_384 => Some(24),
+            HashAlgorithm::SHA2_512 | HashAlgorithm::SHA3_512 => Some(32),
+            _ => None,
+        }
+    }
+
+    /// Returns the object identifier of the algorithm, as used in the `DigestInfo` of
+    /// PKCS#1 v1.5 signatures.
+    pub fn oid(self) -> Result<ObjectIdentifier> {
+        Ok(match self {
+            HashAlgorithm::MD5 => Md5::OID,
+            HashAlgorithm::SHA1 => Sha1::OID,
+            HashAlgorithm::RIPEMD160 => Ripemd160::OID,
+            HashAlgorithm::SHA2_256 => sha2::Sha256::OID,
+            HashAlgorithm::SHA2_384 => sha2::Sha384::OID,
+            HashAlgorithm::SHA2_512 => sha2::Sha512::OID,
+            HashAlgorithm::SHA2_224 => sha2::Sha224::OID,
+            HashAlgorithm::SHA3_256 => sha3::Sha3_256::OID,
+            HashAlgorithm::SHA3_512 => sha3::Sha3_512::OID,
+
+            HashAlgorithm::Private10 => unsupported_err!("Private10 should not be used"),
+            _ => unimplemented_err!("hasher: {:?}", self),
+        })
+    }
 }
@@ -1,21 +1,97 @@
 //! # Cryptography module
 
-use crate::types::{Mpi, PublicParams};
+use crate::types::{CompressionAlgorithm, Mpi, PublicParams};
 
+use self::aead::AeadAlgorithm;
 use self::hash::HashAlgorithm;
+use self::public_key::PublicKeyAlgorithm;
+use self::sym::SymmetricKeyAlgorithm;
 
 pub mod aead;
 pub mod aes_kw;
+pub mod brainpool;
 pub mod checksum;
 pub mod dsa;
 pub mod ecc_curve;
 pub mod ecdh;
 pub mod ecdsa;
+pub mod ed25519;
+pub mod ed448;
 pub mod eddsa;
 pub mod hash;
+pub(crate) mod prime;
 pub mod public_key;
 pub mod rsa;
 pub mod sym;
+pub mod x25519;
+pub mod x448;
+
+/// Public key algorithms this build can use for signing, verifying, encrypting or decrypting.
+pub fn supported_public_key_algorithms() -> &'static [PublicKeyAlgorithm] {
+    &[
+        PublicKeyAlgorithm::RSA,
+        PublicKeyAlgorithm::RSAEncrypt,
+        PublicKeyAlgorithm::RSASign,
+        PublicKeyAlgorithm::DSA,
+        PublicKeyAlgorithm::ECDH,
+        PublicKeyAlgorithm::ECDSA,
+        PublicKeyAlgorithm::EdDSA,
+        PublicKeyAlgorithm::Ed25519,
+        PublicKeyAlgorithm::X25519,
+        #[cfg(feature = "openssl")]
+        PublicKeyAlgorithm::X448,
+        #[cfg(feature = "openssl")]
+        PublicKeyAlgorithm::Ed448,
+    ]
+}
+
+/// Symmetric key algorithms this build can encrypt and decrypt with.
+pub fn supported_symmetric_algorithms() -> &'static [SymmetricKeyAlgorithm] {
+    &[
+        SymmetricKeyAlgorithm::IDEA,
+        SymmetricKeyAlgorithm::TripleDES,
+        SymmetricKeyAlgorithm::CAST5,
+        SymmetricKeyAlgorithm::Blowfish,
+        SymmetricKeyAlgorithm::AES128,
+        SymmetricKeyAlgorithm::AES192,
+        SymmetricKeyAlgorithm::AES256,
+        SymmetricKeyAlgorithm::Twofish,
+        SymmetricKeyAlgorithm::Camellia128,
+        SymmetricKeyAlgorithm::Camellia192,
+        SymmetricKeyAlgorithm::Camellia256,
+    ]
+}
+
+/// Hash algorithms this build can compute.
+pub fn supported_hash_algorithms() -> &'static [HashAlgorithm] {
+    &[
+        HashAlgorithm::MD5,
+        HashAlgorithm::SHA1,
+        HashAlgorithm::RIPEMD160,
+        HashAlgorithm::SHA2_256,
+        HashAlgorithm::SHA2_384,
+        HashAlgorithm::SHA2_512,
+        HashAlgorithm::SHA2_224,
+        HashAlgorithm::SHA3_256,
+        HashAlgorithm::SHA3_512,
+    ]
+}
+
+/// Compression algorithms this build can compress and decompress with.
+pub fn supported_compression_algorithms() -> &'static [CompressionAlgorithm] {
+    &[
+        CompressionAlgorithm::Uncompressed,
+        CompressionAlgorithm::ZIP,
+        CompressionAlgorithm::ZLIB,
+    ]
+}
+
+/// AEAD algorithms this build can encrypt and decrypt with.
+///
+/// These are only available in combination with AES-128 and AES-256.
+pub fn supported_aead_algorithms() -> &'static [AeadAlgorithm] {
+    &[AeadAlgorithm::Eax, AeadAlgorithm::Ocb, AeadAlgorithm::Gcm]
+}
 
 pub trait Decryptor {
     fn decrypt(&self, mpis: &[Mpi], fingerprint: &[u8]) -> crate::errors::Result<Vec<u8>>;
@@ -36,3 +112,58 @@ pub trait KeyParams {
 
     fn key_params(&self) -> Self::KeyParams;
 }
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    #[test]
+    fn test_supported_hash_algorithms() {
+        for alg in supported_hash_algorithms() {
+            let digest = alg.digest(b"hello").unwrap();
+            assert_eq!(digest.len(), alg.digest_size());
+            alg.oid().unwrap();
+        }
+
+        assert_eq!(
+            HashAlgorithm::SHA2_256.oid().unwrap().to_string(),
+            "2.16.840.1.101.3.4.2.1"
+        );
+        assert!(HashAlgorithm::Other(99).oid().is_err());
+        assert_eq!(HashAlgorithm::Other(99).digest_size(), 0);
+    }
+
+    #[test]
+    fn test_supported_symmetric_algorithms() {
+        for alg in supported_symmetric_algorithms() {
+            let key = vec![1u8; alg.key_size()];
+            let mut ciphertext = alg.encrypt_protected(&key, b"hello").unwrap();
+            let plaintext = alg.decrypt_protected(&key, &mut ciphertext).unwrap();
+            assert_eq!(plaintext, b"hello");
+        }
+    }
+
+    #[test]
+    fn test_supported_aead_algorithms() {
+        let key = [1u8; 16];
+        for alg in supported_aead_algorithms() {
+            let nonce = vec![2u8; alg.nonce_size()];
+            let mut buf = b"hello".to_vec();
+            let tag = alg
+                .encrypt_in_place(&SymmetricKeyAlgorithm::AES128, &key, &nonce, b"", &mut buf)
+                .unwrap();
+            alg.decrypt_in_place(
+                &SymmetricKeyAlgorithm::AES128,
+                &key,
+                &nonce,
+                b"",
+                &tag,
+                &mut buf,
+            )
+            .unwrap();
+            assert_eq!(buf, b"hello");
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use num_bigint::prime::probably_prime;
+use num_bigint::BigUint;
+use rand::{CryptoRng, Rng};
+use zeroize::Zeroizing;
+
+use crate::errors::Result;
+
+/// Number of Miller-Rabin rounds used to test candidates, like `num-bigint-dig`'s `gen_prime`.
+pub(crate) const MR_ROUNDS: usize = 20;
+
+/// Called before every prime candidate that RSA and DSA key generation tests. Returning an
+/// error stops the generation with that error.
+pub(crate) type Attempt<'a> = &'a mut dyn FnMut() -> Result<()>;
+
+/// Draws random `bits` sized candidates until one is probably prime, calling `attempt` before
+/// each of them.
+///
+/// The top two bits of the candidates are set, so the product of two of them is never one bit
+/// short, and the lowest bit is set, as even numbers aren't prime.
+pub(crate) fn random_prime<R: Rng + CryptoRng>(
+    rng: &mut R,
+    bits: usize,
+    attempt: Attempt<'_>,
+) -> Result<BigUint> {
+    ensure!(bits >= 8, "primes must be at least 8 bits");
+
+    let mut bytes = Zeroizing::new(vec![0u8; bits.div_ceil(8)]);
+    let top_bits = match bits % 8 {
+        0 => 8,
+        b => b,
+    };
+    loop {
+        attempt()?;
+
+        rng.fill_bytes(&mut bytes);
+        if top_bits == 1 {
+            bytes[0] = 1;
+            bytes[1] |= 0x80;
+        } else {
+            bytes[0] &= ((1u16 << top_bits) - 1) as u8;
+            bytes[0] |= 0b11 << (top_bits - 2);
+        }
+        let last = bytes.len() - 1;
+        bytes[last] |= 1;
+
+        let candidate = BigUint::from_bytes_be(&bytes[..]);
+        if probably_prime(&candidate, MR_ROUNDS) {
+            return Ok(candidate);
+        }
+    }
+}
@@ -23,6 +23,14 @@ pub enum PublicKeyAlgorithm {
     DiffieHellman = 21,
     /// EdDSA (not yet assigned)
     EdDSA = 22,
+    /// X25519: RFC 9580
+    X25519 = 25,
+    /// X448: RFC 9580
+    X448 = 26,
+    /// Ed25519: RFC 9580
+    Ed25519 = 27,
+    /// Ed448: RFC 9580
+    Ed448 = 28,
     /// Private experimental range (from OpenGPG)
     Private100 = 100,
     Private101 = 101,
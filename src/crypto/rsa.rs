@@ -18,11 +18,16 @@ use signature::hazmat::{PrehashSigner, PrehashVerifier};
 use signature::SignatureEncoding;
 use zeroize::ZeroizeOnDrop;
 
+use crate::crypto::prime::{random_prime, Attempt};
 use crate::crypto::{hash::HashAlgorithm, Decryptor, KeyParams, Signer};
 use crate::errors::Result;
 use crate::types::{Mpi, PlainSecretParams, PublicParams};
 
-const MAX_KEY_SIZE: usize = 16384;
+/// The largest RSA keys, in bits, that are accepted.
+pub(crate) const MAX_KEY_SIZE: usize = 16384;
+
+/// The public exponent of generated keys.
+const RSA_EXPONENT: u32 = 65537;
 
 /// Private Key for RSA.
 #[derive(Debug, ZeroizeOnDrop)]
@@ -109,10 +114,36 @@ pub fn encrypt<R: CryptoRng + Rng>(
 
 /// Generate an RSA KeyPair.
 pub fn generate_key<R: Rng + CryptoRng>(
+    rng: R,
+    bit_size: usize,
+) -> Result<(PublicParams, PlainSecretParams)> {
+    generate_key_with_attempts(rng, bit_size, &mut || Ok(()))
+}
+
+/// Generates an RSA key like [`generate_key`], calling `attempt` before every prime candidate
+/// that is tested.
+pub(crate) fn generate_key_with_attempts<R: Rng + CryptoRng>(
     mut rng: R,
     bit_size: usize,
+    attempt: Attempt<'_>,
 ) -> Result<(PublicParams, PlainSecretParams)> {
-    let key = RsaPrivateKey::new(&mut rng, bit_size)?;
+    let e = BigUint::from(RSA_EXPONENT);
+    let key = loop {
+        // the top two bits of both primes are set, so `n` has exactly `bit_size` bits
+        let p = random_prime(&mut rng, bit_size / 2, attempt)?;
+        let q = random_prime(&mut rng, bit_size - bit_size / 2, attempt)?;
+        if p == q {
+            continue;
+        }
+
+        let n = &p * &q;
+        let totient = (&p - 1u32) * (&q - 1u32);
+        // `e` must be coprime to the totient
+        let Some(d) = e.clone().mod_inverse(&totient).and_then(|d| d.to_biguint()) else {
+            continue;
+        };
+        break RsaPrivateKey::from_components(n, e, d, vec![p, q])?;
+    };
 
     let p = &key.primes()[0];
     let q = &key.primes()[1];
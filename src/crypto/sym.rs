@@ -16,6 +16,9 @@ use crate::errors::{Error, Result};
 
 macro_rules! decrypt {
     ($mode:ident, $key:expr, $iv:expr, $prefix:expr, $data:expr, $bs:expr, $resync:expr) => {{
+        // the resync IV is taken from the ciphertext, before it is overwritten
+        let resync_iv = $resync.then(|| $prefix[2..$bs + 2].to_vec());
+
         let mut mode = BufDecryptor::<$mode>::new_from_slices($key, $iv)?;
         mode.decrypt($prefix);
 
@@ -25,11 +28,10 @@ macro_rules! decrypt {
         // and paper <https://eprint.iacr.org/2005/033>
         // for details.
 
-        if $resync {
-            unimplemented!("CFB resync is not here");
-        // debug!("resync {}", hex::encode(&$prefix[2..$bs + 2]));
-        // let mut mode = Cfb::<$mode>::new_from_slices($key, &$prefix[2..$bs + 2])?;
-        // mode.decrypt($data);
+        if let Some(resync_iv) = resync_iv {
+            debug!("resync {}", hex::encode(&resync_iv));
+            let mut mode = BufDecryptor::<$mode>::new_from_slices($key, &resync_iv)?;
+            mode.decrypt($data);
         } else {
             mode.decrypt($data);
         }
@@ -42,10 +44,9 @@ macro_rules! encrypt {
         mode.encrypt($prefix);
 
         if $resync {
-            unimplemented!("CFB resync is not here");
-        // debug!("resync {}", hex::encode(&$prefix[2..$bs + 2]));
-        // let mut mode = Cfb::<$mode>::new_var($key, &$prefix[2..$bs + 2])?;
-        // mode.encrypt($data);
+            debug!("resync {}", hex::encode(&$prefix[2..$bs + 2]));
+            let mut mode = BufEncryptor::<$mode>::new_from_slices($key, &$prefix[2..$bs + 2])?;
+            mode.encrypt($data);
         } else {
             mode.encrypt($data);
         }
@@ -129,7 +130,7 @@ impl SymmetricKeyAlgorithm {
         }
     }
 
-    /// The size of a single block in bytes.
+    /// The size of a key in bytes.
     /// Based on https://github.com/gpg/libgcrypt/blob/master/cipher
     pub fn key_size(self) -> usize {
         match self {
@@ -137,8 +138,8 @@ impl SymmetricKeyAlgorithm {
             SymmetricKeyAlgorithm::IDEA => 16,
             SymmetricKeyAlgorithm::TripleDES => 24,
             SymmetricKeyAlgorithm::CAST5 => 16,
-            // TODO: Validate this is the right key size.
-            SymmetricKeyAlgorithm::Blowfish => 16, //56,
+            // Blowfish supports keys of up to 56 bytes, OpenPGP uses 128 bit keys.
+            SymmetricKeyAlgorithm::Blowfish => 16,
             SymmetricKeyAlgorithm::AES128 => 16,
             SymmetricKeyAlgorithm::AES192 => 24,
             SymmetricKeyAlgorithm::AES256 => 32,
@@ -569,6 +570,38 @@ impl SymmetricKeyAlgorithm {
     }
 }
 
+/// Reports if AES is hardware accelerated on the current CPU.
+///
+/// The `aes` crate detects AES-NI (x86/x86-64) at runtime and uses it automatically, falling
+/// back to a constant time software implementation otherwise. The ARMv8 crypto extensions are
+/// only used when building with `--cfg aes_armv8`, and `--cfg aes_force_soft` always selects the
+/// software implementation. On platforms without acceleration, AES is considerably slower.
+/// OpenPGP does not define a ChaCha20 based AEAD, so on these platforms
+/// [`SymmetricKeyAlgorithm::Twofish`] or Camellia may be preferable when the recipients support
+/// them.
+pub fn aes_hardware_accelerated() -> bool {
+    #[allow(unknown_lints, unexpected_cfgs)]
+    let force_soft = cfg!(aes_force_soft);
+    if force_soft {
+        return false;
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        std::is_x86_feature_detected!("aes")
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        #[allow(unknown_lints, unexpected_cfgs)]
+        let armv8 = cfg!(aes_armv8);
+        armv8 && std::arch::is_aarch64_feature_detected!("aes")
+    }
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+    {
+        false
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -624,6 +657,45 @@ mod tests {
     roundtrip!(roundtrip_camellia192, SymmetricKeyAlgorithm::Camellia192);
     roundtrip!(roundtrip_camellia256, SymmetricKeyAlgorithm::Camellia256);
 
+    #[test]
+    fn sizes_match_ciphers() {
+        use cipher::{BlockSizeUser, KeySizeUser};
+
+        macro_rules! check {
+            ($alg:path, $cipher:ty) => {
+                assert_eq!($alg.key_size(), <$cipher>::key_size(), "{:?}", $alg);
+                assert_eq!($alg.block_size(), <$cipher>::block_size(), "{:?}", $alg);
+            };
+        }
+
+        check!(SymmetricKeyAlgorithm::IDEA, Idea);
+        check!(SymmetricKeyAlgorithm::TripleDES, TdesEde3);
+        check!(SymmetricKeyAlgorithm::CAST5, Cast5);
+        check!(SymmetricKeyAlgorithm::AES128, Aes128);
+        check!(SymmetricKeyAlgorithm::AES192, Aes192);
+        check!(SymmetricKeyAlgorithm::AES256, Aes256);
+        check!(SymmetricKeyAlgorithm::Twofish, Twofish);
+        check!(SymmetricKeyAlgorithm::Camellia128, Camellia128);
+        check!(SymmetricKeyAlgorithm::Camellia192, Camellia192);
+        check!(SymmetricKeyAlgorithm::Camellia256, Camellia256);
+
+        // variable key size, OpenPGP fixes it to 128 bit
+        assert_eq!(SymmetricKeyAlgorithm::Blowfish.key_size(), 16);
+        assert_eq!(
+            SymmetricKeyAlgorithm::Blowfish.block_size(),
+            <Blowfish>::block_size()
+        );
+
+        for alg in [
+            SymmetricKeyAlgorithm::Plaintext,
+            SymmetricKeyAlgorithm::Private10,
+            SymmetricKeyAlgorithm::Other(99),
+        ] {
+            assert_eq!(alg.key_size(), 0);
+            assert_eq!(alg.block_size(), 0);
+        }
+    }
+
     #[test]
     pub fn decrypt_without_enough_ciphertext() {
         let key: [u8; 0] = [];
@@ -0,0 +1,172 @@
+use std::fmt;
+
+use rand::{CryptoRng, Rng};
+use sha2::Sha256;
+use x25519_dalek::{PublicKey, StaticSecret};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+use crate::crypto::{aes_kw, Decryptor};
+use crate::errors::Result;
+use crate::types::{Mpi, PlainSecretParams, PublicParams};
+
+const SECRET_KEY_LENGTH: usize = 32;
+
+/// Secret key for X25519, as defined in RFC 9580.
+#[derive(Clone, PartialEq, Eq, Zeroize, ZeroizeOnDrop)]
+pub struct SecretKey {
+    /// The native secret key.
+    pub secret: [u8; 32],
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("X25519SecretKey")
+            .field("secret", &"[..]")
+            .finish()
+    }
+}
+
+impl Decryptor for SecretKey {
+    /// Unwraps the session key, from the ephemeral public key and the wrapped key.
+    ///
+    /// Unlike ECDH, the result carries neither the symmetric algorithm nor a checksum.
+    fn decrypt(&self, mpis: &[Mpi], _fingerprint: &[u8]) -> Result<Vec<u8>> {
+        debug!("X25519 decrypt");
+
+        ensure!(mpis.len() >= 2, "missing values");
+        let ephemeral: [u8; 32] = mpis[0]
+            .as_bytes()
+            .try_into()
+            .map_err(|_| format_err!("invalid ephemeral public key"))?;
+        let wrapped = mpis[1].as_bytes();
+
+        let our_secret = StaticSecret::from(self.secret);
+        let our_public = PublicKey::from(&our_secret);
+        let shared_secret = our_secret.diffie_hellman(&PublicKey::from(ephemeral));
+
+        let key = derive_wrapping_key(&ephemeral, our_public.as_bytes(), shared_secret.as_bytes())?;
+
+        aes_kw::unwrap(&key[..], wrapped)
+    }
+}
+
+/// Derives the AES-128 key wrapping key, see
+/// https://www.rfc-editor.org/rfc/rfc9580.html#name-x25519
+fn derive_wrapping_key(
+    ephemeral: &[u8; 32],
+    public: &[u8; 32],
+    shared_secret: &[u8; 32],
+) -> Result<Zeroizing<[u8; 16]>> {
+    let mut ikm = Zeroizing::new([0u8; 96]);
+    ikm[..32].copy_from_slice(ephemeral);
+    ikm[32..64].copy_from_slice(public);
+    ikm[64..].copy_from_slice(shared_secret);
+
+    let hk = hkdf::Hkdf::<Sha256>::new(None, &ikm[..]);
+    let mut okm = Zeroizing::new([0u8; 16]);
+    hk.expand(b"OpenPGP X25519", &mut okm[..])
+        .map_err(|_| format_err!("hkdf expand"))?;
+
+    Ok(okm)
+}
+
+/// X25519 encryption of a session key, as defined in RFC 9580.
+///
+/// Returns the ephemeral public key and the wrapped session key. The session key is wrapped
+/// as is, without the symmetric algorithm, a checksum or padding.
+pub fn encrypt<R: CryptoRng + Rng>(
+    rng: &mut R,
+    public: &[u8; 32],
+    plain: &[u8],
+) -> Result<([u8; 32], Vec<u8>)> {
+    debug!("X25519 encrypt");
+
+    ensure!(
+        matches!(plain.len(), 16 | 24 | 32),
+        "invalid session key length {}",
+        plain.len()
+    );
+
+    let mut our_secret_key_bytes = Zeroizing::new([0u8; SECRET_KEY_LENGTH]);
+    rng.fill_bytes(&mut *our_secret_key_bytes);
+    let our_secret = StaticSecret::from(*our_secret_key_bytes);
+    let ephemeral = PublicKey::from(&our_secret).to_bytes();
+
+    let shared_secret = our_secret.diffie_hellman(&PublicKey::from(*public));
+
+    let key = derive_wrapping_key(&ephemeral, public, shared_secret.as_bytes())?;
+    let wrapped = aes_kw::wrap(&key[..], plain)?;
+
+    Ok((ephemeral, wrapped))
+}
+
+/// Generate an X25519 KeyPair, as defined in RFC 9580.
+pub fn generate_key<R: Rng + CryptoRng>(mut rng: R) -> (PublicParams, PlainSecretParams) {
+    let mut secret_key_bytes = Zeroizing::new([0u8; SECRET_KEY_LENGTH]);
+    rng.fill_bytes(&mut *secret_key_bytes);
+    key_from_seed(&secret_key_bytes)
+}
+
+/// Derive an X25519 keypair from the 32 byte seed, which is the (unclamped) secret key.
+pub fn key_from_seed(seed: &[u8; SECRET_KEY_LENGTH]) -> (PublicParams, PlainSecretParams) {
+    let secret = StaticSecret::from(*seed);
+    let public = PublicKey::from(&secret);
+
+    // Unlike the legacy ECDH format, both keys are stored in their native little endian form.
+    // Clamp, as `to_bytes` does not clamp.
+    let secret = curve25519_dalek::scalar::clamp_integer(secret.to_bytes());
+
+    (
+        PublicParams::X25519 {
+            public: public.to_bytes(),
+        },
+        PlainSecretParams::X25519(secret),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    use rand::{RngCore, SeedableRng};
+    use rand_chacha::ChaChaRng;
+
+    #[test]
+    fn test_generate_key() {
+        let mut rng = ChaChaRng::seed_from_u64(0);
+        let (PublicParams::X25519 { public }, PlainSecretParams::X25519(secret)) =
+            generate_key(&mut rng)
+        else {
+            panic!("unexpected params");
+        };
+
+        let expected = PublicKey::from(&StaticSecret::from(secret));
+        assert_eq!(public, expected.to_bytes());
+        assert_eq!(secret[0] & 0b111, 0);
+        assert_eq!(secret[31] & 0b1100_0000, 0b0100_0000);
+    }
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let mut rng = ChaChaRng::seed_from_u64(0);
+        let (PublicParams::X25519 { public }, PlainSecretParams::X25519(secret)) =
+            generate_key(&mut rng)
+        else {
+            panic!("unexpected params");
+        };
+
+        for size in [16, 24, 32] {
+            let mut plain = vec![0u8; size];
+            rng.fill_bytes(&mut plain);
+
+            let (ephemeral, wrapped) = encrypt(&mut rng, &public, &plain).unwrap();
+            assert_eq!(wrapped.len(), size + 8);
+
+            let mpis = [Mpi::from_slice(&ephemeral), Mpi::from_slice(&wrapped)];
+            let decrypted = SecretKey { secret }.decrypt(&mpis, &[]).unwrap();
+            assert_eq!(decrypted, plain);
+        }
+    }
+}
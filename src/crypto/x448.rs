@@ -0,0 +1,224 @@
+use std::fmt;
+
+use rand::{CryptoRng, Rng};
+use sha2::Sha512;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+use crate::crypto::{aes_kw, Decryptor};
+use crate::errors::Result;
+use crate::types::{Mpi, PlainSecretParams, PublicParams};
+
+const SECRET_KEY_LENGTH: usize = 56;
+
+/// Secret key for X448, as defined in RFC 9580.
+///
+/// X448 is implemented by OpenSSL, using it requires the `openssl` feature.
+#[derive(Clone, PartialEq, Eq, Zeroize, ZeroizeOnDrop)]
+pub struct SecretKey {
+    /// The native secret key.
+    pub secret: [u8; 56],
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("X448SecretKey")
+            .field("secret", &"[..]")
+            .finish()
+    }
+}
+
+impl Decryptor for SecretKey {
+    /// Unwraps the session key, from the ephemeral public key and the wrapped key.
+    ///
+    /// Unlike ECDH, the result carries neither the symmetric algorithm nor a checksum.
+    fn decrypt(&self, mpis: &[Mpi], _fingerprint: &[u8]) -> Result<Vec<u8>> {
+        debug!("X448 decrypt");
+
+        ensure!(mpis.len() >= 2, "missing values");
+        let ephemeral: [u8; 56] = mpis[0]
+            .as_bytes()
+            .try_into()
+            .map_err(|_| format_err!("invalid ephemeral public key"))?;
+        let wrapped = mpis[1].as_bytes();
+
+        let our_public = public_key(&self.secret)?;
+        let shared_secret = diffie_hellman(&self.secret, &ephemeral)?;
+
+        let key = derive_wrapping_key(&ephemeral, &our_public, &shared_secret)?;
+
+        aes_kw::unwrap(&key[..], wrapped)
+    }
+}
+
+/// Derives the AES-256 key wrapping key, see
+/// https://www.rfc-editor.org/rfc/rfc9580.html#name-x448
+fn derive_wrapping_key(
+    ephemeral: &[u8; 56],
+    public: &[u8; 56],
+    shared_secret: &[u8; 56],
+) -> Result<Zeroizing<[u8; 32]>> {
+    let mut ikm = Zeroizing::new([0u8; 168]);
+    ikm[..56].copy_from_slice(ephemeral);
+    ikm[56..112].copy_from_slice(public);
+    ikm[112..].copy_from_slice(shared_secret);
+
+    let hk = hkdf::Hkdf::<Sha512>::new(None, &ikm[..]);
+    let mut okm = Zeroizing::new([0u8; 32]);
+    hk.expand(b"OpenPGP X448", &mut okm[..])
+        .map_err(|_| format_err!("hkdf expand"))?;
+
+    Ok(okm)
+}
+
+/// X448 encryption of a session key, as defined in RFC 9580.
+///
+/// Returns the ephemeral public key and the wrapped session key. The session key is wrapped
+/// as is, without the symmetric algorithm, a checksum or padding.
+pub fn encrypt<R: CryptoRng + Rng>(
+    rng: &mut R,
+    public: &[u8; 56],
+    plain: &[u8],
+) -> Result<([u8; 56], Vec<u8>)> {
+    debug!("X448 encrypt");
+
+    ensure!(
+        matches!(plain.len(), 16 | 24 | 32),
+        "invalid session key length {}",
+        plain.len()
+    );
+
+    let mut our_secret = Zeroizing::new([0u8; SECRET_KEY_LENGTH]);
+    rng.fill_bytes(&mut *our_secret);
+    let ephemeral = public_key(&our_secret)?;
+
+    let shared_secret = diffie_hellman(&our_secret, public)?;
+
+    let key = derive_wrapping_key(&ephemeral, public, &shared_secret)?;
+    let wrapped = aes_kw::wrap(&key[..], plain)?;
+
+    Ok((ephemeral, wrapped))
+}
+
+/// Generate an X448 KeyPair, as defined in RFC 9580.
+pub fn generate_key<R: Rng + CryptoRng>(mut rng: R) -> Result<(PublicParams, PlainSecretParams)> {
+    let mut secret = Zeroizing::new([0u8; SECRET_KEY_LENGTH]);
+    rng.fill_bytes(&mut *secret);
+
+    // Clamp, so the stored key matches the scalar that is used (RFC 7748).
+    secret[0] &= 252;
+    secret[55] |= 128;
+
+    let public = public_key(&secret)?;
+
+    Ok((
+        PublicParams::X448 { public },
+        PlainSecretParams::X448(*secret),
+    ))
+}
+
+#[cfg(feature = "openssl")]
+fn public_key(secret: &[u8; 56]) -> Result<[u8; 56]> {
+    use openssl::pkey::{Id, PKey};
+
+    let secret = PKey::private_key_from_raw_bytes(secret, Id::X448)?;
+    let public = secret.raw_public_key()?;
+
+    public
+        .try_into()
+        .map_err(|_| format_err!("invalid X448 public key"))
+}
+
+#[cfg(feature = "openssl")]
+fn diffie_hellman(secret: &[u8; 56], their_public: &[u8; 56]) -> Result<Zeroizing<[u8; 56]>> {
+    use openssl::derive::Deriver;
+    use openssl::pkey::{Id, PKey};
+
+    let secret = PKey::private_key_from_raw_bytes(secret, Id::X448)?;
+    let their_public = PKey::public_key_from_raw_bytes(their_public, Id::X448)?;
+
+    let mut deriver = Deriver::new(&secret)?;
+    deriver.set_peer(&their_public)?;
+    let mut shared_secret = Zeroizing::new([0u8; 56]);
+    let len = deriver.derive(&mut shared_secret[..])?;
+    ensure_eq!(len, 56, "invalid X448 shared secret");
+
+    Ok(shared_secret)
+}
+
+#[cfg(not(feature = "openssl"))]
+fn public_key(_secret: &[u8; 56]) -> Result<[u8; 56]> {
+    unsupported_err!("X448 requires the openssl feature")
+}
+
+#[cfg(not(feature = "openssl"))]
+fn diffie_hellman(_secret: &[u8; 56], _their_public: &[u8; 56]) -> Result<Zeroizing<[u8; 56]>> {
+    unsupported_err!("X448 requires the openssl feature")
+}
+
+#[cfg(test)]
+#[cfg(feature = "openssl")]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    use rand::{RngCore, SeedableRng};
+    use rand_chacha::ChaChaRng;
+
+    #[test]
+    fn test_rfc7748_vector() {
+        // https://www.rfc-editor.org/rfc/rfc7748#section-6.2
+        let alice = hex::decode(
+            "9a8f4925d1519f5775cf46b04b5800d4ee9ee8bae8bc5565d498c28dd9c9baf5\
+             74a9419744897391006382a6f127ab1d9ac2d8c0a598726b",
+        )
+        .unwrap();
+        let alice_public = hex::decode(
+            "9b08f7cc31b7e3e67d22d5aea121074a273bd2b83de09c63faa73d2c22c5d9bb\
+             c836647241d953d40c5b12da88120d53177f80e532c41fa0",
+        )
+        .unwrap();
+        let bob_public = hex::decode(
+            "3eb7a829b0cd20f5bcfc0b599b6feccf6da4627107bdb0d4f345b43027d8b972\
+             fc3e34fb4232a13ca706dcb57aec3dae07bdc1c67bf33609",
+        )
+        .unwrap();
+        let shared = hex::decode(
+            "07fff4181ac6cc95ec1c16a94a0f74d12da232ce40a77552281d282bb60c0b56\
+             fd2464c335543936521c24403085d59a449a5037514a879d",
+        )
+        .unwrap();
+
+        let alice: [u8; 56] = alice.try_into().unwrap();
+        let bob_public: [u8; 56] = bob_public.try_into().unwrap();
+        assert_eq!(&public_key(&alice).unwrap()[..], &alice_public[..]);
+        assert_eq!(
+            &diffie_hellman(&alice, &bob_public).unwrap()[..],
+            &shared[..]
+        );
+    }
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let mut rng = ChaChaRng::seed_from_u64(0);
+        let (PublicParams::X448 { public }, PlainSecretParams::X448(secret)) =
+            generate_key(&mut rng).unwrap()
+        else {
+            panic!("unexpected params");
+        };
+        assert_eq!(secret[0] & 0b11, 0);
+        assert_eq!(secret[55] & 0b1000_0000, 0b1000_0000);
+
+        for size in [16, 24, 32] {
+            let mut plain = vec![0u8; size];
+            rng.fill_bytes(&mut plain);
+
+            let (ephemeral, wrapped) = encrypt(&mut rng, &public, &plain).unwrap();
+            assert_eq!(wrapped.len(), size + 8);
+
+            let mpis = [Mpi::from_slice(&ephemeral), Mpi::from_slice(&wrapped)];
+            let decrypted = SecretKey { secret }.decrypt(&mpis, &[]).unwrap();
+            assert_eq!(decrypted, plain);
+        }
+    }
+}
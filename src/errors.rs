@@ -85,6 +85,15 @@ pub enum Error {
     Eax,
     #[error("OCB")]
     Ocb,
+    #[error("the decryption key is not an intended recipient of the signature")]
+    IntendedRecipientMismatch,
+    #[error("the operation was cancelled")]
+    Cancelled,
+    #[error("no secret key for {0}")]
+    MissingSecretKey(String),
+    #[cfg(feature = "openssl")]
+    #[error("openssl error: {0:?}")]
+    OpenSsl(#[from] openssl::error::ErrorStack),
 }
 
 impl Error {
@@ -123,6 +132,11 @@ impl Error {
             Error::Gcm => 30,
             Error::Eax => 31,
             Error::Ocb => 32,
+            Error::IntendedRecipientMismatch => 33,
+            Error::Cancelled => 34,
+            Error::MissingSecretKey(_) => 35,
+            #[cfg(feature = "openssl")]
+            Error::OpenSsl(_) => 36,
         }
     }
 }
@@ -23,6 +23,19 @@
 // Enable backtraces for thiserror.
 #![cfg_attr(feature = "nightly", feature(error_generic_member_access))]
 
+#[cfg(all(feature = "pure-rust", feature = "asm"))]
+compile_error!(
+    "feature \"pure-rust\" can not be combined with \"asm\", which uses assembly backends"
+);
+
+#[cfg(all(feature = "pure-rust", feature = "profile"))]
+compile_error!(
+    "feature \"pure-rust\" can not be combined with \"profile\", which links gperftools"
+);
+
+#[cfg(all(feature = "pure-rust", feature = "openssl"))]
+compile_error!("feature \"pure-rust\" can not be combined with \"openssl\", which links OpenSSL");
+
 #[macro_use]
 extern crate nom;
 #[macro_use]
@@ -58,6 +71,9 @@ pub mod packet;
 pub mod ser;
 pub mod types;
 
+#[cfg(test)]
+mod test_util;
+
 // reexports for easier use
 #[allow(unused_imports)]
 pub use self::composed::key::*;
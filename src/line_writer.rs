@@ -5,6 +5,8 @@ use std::io;
 use generic_array::typenum::{Sum, Unsigned, U2};
 use generic_array::{ArrayLength, GenericArray};
 
+use crate::errors::Result;
+
 const CRLF: [u8; 2] = [b'\r', b'\n'];
 const CR: [u8; 1] = [b'\r'];
 const LF: [u8; 1] = [b'\n'];
@@ -47,8 +49,10 @@ where
     /// Holds a partial chunk, if any, after the last `write()`, so that we may then fill the chunk
     /// with the next `write()`, write it, then proceed with the rest of the input normally.
     extra: GenericArray<u8, N>,
-    /// How much of `extra` is occupied, in `[0, N]`.
+    /// How much of `extra` is occupied, in `[0, line_length]`.
     extra_len: usize,
+    /// Length of a line, at most `N`.
+    line_length: usize,
     buffer: GenericArray<u8, Sum<N, U2>>,
     /// True iff partial last chunk has been written.
     finished: bool,
@@ -65,12 +69,35 @@ where
 {
     /// Creates a new encoder around an existing writer.
     pub fn new(w: &'a mut W, line_break: LineBreak) -> Self {
+        Self::new_unchecked(w, line_break, N::to_usize())
+    }
+
+    /// Creates a new encoder around an existing writer, breaking lines after `line_length`
+    /// bytes instead of `N`.
+    ///
+    /// Fails if `line_length` is zero or larger than `N`.
+    pub fn with_line_length(
+        w: &'a mut W,
+        line_break: LineBreak,
+        line_length: usize,
+    ) -> Result<Self> {
+        ensure!(
+            line_length > 0 && line_length <= N::to_usize(),
+            "invalid line length {}",
+            line_length
+        );
+
+        Ok(Self::new_unchecked(w, line_break, line_length))
+    }
+
+    fn new_unchecked(w: &'a mut W, line_break: LineBreak, line_length: usize) -> Self {
         LineWriter {
             line_break,
             w,
             extra: Default::default(),
             buffer: Default::default(),
             extra_len: 0,
+            line_length,
             finished: false,
             panicked: false,
         }
@@ -125,7 +152,7 @@ where
         // - Errors mean that "no bytes were written to this writer", so we need to reset the
         // internal state to what it was before the error occurred
 
-        let sl = N::to_usize();
+        let sl = self.line_length;
         let line_break = self.line_break.as_ref();
 
         let orig_extra_len = self.extra_len;
@@ -279,6 +306,28 @@ mod tests {
         };
     }
 
+    #[test]
+    fn shorter_line_length() {
+        let mut buf = Vec::new();
+
+        {
+            let mut w = LineWriter::<_, U10>::with_line_length(&mut buf, LineBreak::Lf, 4).unwrap();
+            w.write_all(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]).unwrap();
+        }
+
+        assert_eq!(
+            &buf[..],
+            &[0, 1, 2, 3, b'\n', 4, 5, 6, 7, b'\n', 8, 9, b'\n'][..]
+        );
+    }
+
+    #[test]
+    fn invalid_line_length() {
+        let mut buf = Vec::new();
+        assert!(LineWriter::<_, U10>::with_line_length(&mut buf, LineBreak::Lf, 0).is_err());
+        assert!(LineWriter::<_, U10>::with_line_length(&mut buf, LineBreak::Lf, 11).is_err());
+    }
+
     test_len!(test_break_line_len_1, typenum::U1);
     test_len!(test_break_line_len_2, typenum::U2);
     test_len!(test_break_line_len_10, typenum::U10);
@@ -79,7 +79,7 @@
 
 mod many;
 mod packet_sum;
-mod single;
+pub(crate) mod single;
 
 #[macro_use]
 mod secret_key_macro;
@@ -1,12 +1,20 @@
 use std::io;
 
-use crate::errors::Result;
+use crate::crypto::checksum;
+use crate::errors::{Error, Result};
 use crate::packet::PacketTrait;
 use crate::ser::Serialize;
 use crate::types::{Tag, Version};
 
+/// The packet header, which is included in the hash.
+const HEADER: [u8; 2] = [0xD3, 0x14];
+
 /// Modification Detection Code Packet
 /// https://tools.ietf.org/html/rfc4880.html#section-5.14
+///
+/// A version 1 Symmetrically Encrypted Integrity Protected Data packet embeds this as the
+/// last packet of its plaintext. The hash covers the random prefix, the preceeding
+/// plaintext and the header of this packet (`0xD3 0x14`).
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ModDetectionCode {
     packet_version: Version,
@@ -27,6 +35,38 @@ impl ModDetectionCode {
             hash,
         })
     }
+
+    /// Creates a new `ModDetectionCode` packet from the given hash.
+    pub fn new(hash: [u8; 20]) -> Self {
+        ModDetectionCode {
+            packet_version: Default::default(),
+            hash,
+        }
+    }
+
+    /// Creates the `ModDetectionCode` packet for `data`, the random prefix followed by the
+    /// plaintext that precedes this packet.
+    pub fn from_data(data: &[u8]) -> Self {
+        let mut hash = [0u8; 20];
+        hash.copy_from_slice(&checksum::calculate_sha1([data, &HEADER[..]]));
+
+        Self::new(hash)
+    }
+
+    /// The SHA-1 hash.
+    pub fn hash(&self) -> &[u8; 20] {
+        &self.hash
+    }
+
+    /// Validates this packet against `data`, the random prefix followed by the plaintext that
+    /// precedes this packet.
+    pub fn validate(&self, data: &[u8]) -> Result<()> {
+        if Self::from_data(data).hash != self.hash {
+            return Err(Error::MdcError);
+        }
+
+        Ok(())
+    }
 }
 
 impl Serialize for ModDetectionCode {
@@ -45,3 +85,28 @@ impl PacketTrait for ModDetectionCode {
         Tag::ModDetectionCode
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::packet::write_packet;
+
+    #[test]
+    fn test_mdc_validate() {
+        let data = b"prefix and plaintext";
+        let mdc = ModDetectionCode::from_data(data);
+        assert!(mdc.validate(data).is_ok());
+        assert!(matches!(
+            mdc.validate(b"prefix and plaintexT"),
+            Err(Error::MdcError)
+        ));
+
+        // the header that goes into the hash matches the serialized packet
+        let mut encoded = Vec::new();
+        write_packet(&mut encoded, &mdc).expect("encode");
+        assert_eq!(&encoded[..2], &HEADER[..]);
+        assert_eq!(&encoded[2..], &mdc.hash()[..]);
+        assert_eq!(ModDetectionCode::new(*mdc.hash()), mdc);
+    }
+}
@@ -104,29 +104,46 @@ impl_try_from_into!(
     Padding => Padding
 );
 
+impl Packet {
+    /// Serializes this packet using the given packet header format, instead of its own.
+    pub fn to_writer_with_version<W: io::Write>(
+        &self,
+        writer: &mut W,
+        packet_version: Version,
+    ) -> Result<()> {
+        match self {
+            Packet::CompressedData(p) => write_packet_with_version(writer, &p, packet_version),
+            Packet::PublicKey(p) => write_packet_with_version(writer, &p, packet_version),
+            Packet::PublicSubkey(p) => write_packet_with_version(writer, &p, packet_version),
+            Packet::SecretKey(p) => write_packet_with_version(writer, &p, packet_version),
+            Packet::SecretSubkey(p) => write_packet_with_version(writer, &p, packet_version),
+            Packet::LiteralData(p) => write_packet_with_version(writer, &p, packet_version),
+            Packet::Marker(p) => write_packet_with_version(writer, &p, packet_version),
+            Packet::ModDetectionCode(p) => write_packet_with_version(writer, &p, packet_version),
+            Packet::OnePassSignature(p) => write_packet_with_version(writer, &p, packet_version),
+            Packet::PublicKeyEncryptedSessionKey(p) => {
+                write_packet_with_version(writer, &p, packet_version)
+            }
+            Packet::Signature(p) => write_packet_with_version(writer, &p, packet_version),
+            Packet::SymEncryptedData(p) => write_packet_with_version(writer, &p, packet_version),
+            Packet::SymEncryptedProtectedData(p) => {
+                write_packet_with_version(writer, &p, packet_version)
+            }
+            Packet::SymKeyEncryptedSessionKey(p) => {
+                write_packet_with_version(writer, &p, packet_version)
+            }
+            Packet::Trust(p) => write_packet_with_version(writer, &p, packet_version),
+            Packet::UserAttribute(p) => write_packet_with_version(writer, &p, packet_version),
+            Packet::UserId(p) => write_packet_with_version(writer, &p, packet_version),
+            Packet::Padding(p) => write_packet_with_version(writer, &p, packet_version),
+        }
+    }
+}
+
 // TODO: move to its own file
 impl Serialize for Packet {
     fn to_writer<W: io::Write>(&self, writer: &mut W) -> Result<()> {
-        match self {
-            Packet::CompressedData(p) => write_packet(writer, &p),
-            Packet::PublicKey(p) => write_packet(writer, &p),
-            Packet::PublicSubkey(p) => write_packet(writer, &p),
-            Packet::SecretKey(p) => write_packet(writer, &p),
-            Packet::SecretSubkey(p) => write_packet(writer, &p),
-            Packet::LiteralData(p) => write_packet(writer, &p),
-            Packet::Marker(p) => write_packet(writer, &p),
-            Packet::ModDetectionCode(p) => write_packet(writer, &p),
-            Packet::OnePassSignature(p) => write_packet(writer, &p),
-            Packet::PublicKeyEncryptedSessionKey(p) => write_packet(writer, &p),
-            Packet::Signature(p) => write_packet(writer, &p),
-            Packet::SymEncryptedData(p) => write_packet(writer, &p),
-            Packet::SymEncryptedProtectedData(p) => write_packet(writer, &p),
-            Packet::SymKeyEncryptedSessionKey(p) => write_packet(writer, &p),
-            Packet::Trust(p) => write_packet(writer, &p),
-            Packet::UserAttribute(p) => write_packet(writer, &p),
-            Packet::UserId(p) => write_packet(writer, &p),
-            Packet::Padding(p) => write_packet(writer, &p),
-        }
+        self.to_writer_with_version(writer, self.packet_version())
     }
 }
 
@@ -146,7 +163,16 @@ impl<'a, T: 'a + PacketTrait> PacketTrait for &'a T {
 }
 
 pub fn write_packet(writer: &mut impl io::Write, packet: &impl PacketTrait) -> Result<()> {
-    let packet_version = packet.packet_version();
+    write_packet_with_version(writer, packet, packet.packet_version())
+}
+
+/// Like [`write_packet`], but uses the given packet header format, instead of the one of the
+/// packet. This allows forcing old format headers, e.g. for interoperability tests.
+pub fn write_packet_with_version(
+    writer: &mut impl io::Write,
+    packet: &impl PacketTrait,
+    packet_version: Version,
+) -> Result<()> {
     let mut buf = Vec::new();
     packet.to_writer(&mut buf)?;
     debug!(
@@ -13,15 +13,26 @@ use crate::crypto::sym::SymmetricKeyAlgorithm;
 use crate::errors::{IResult, Result};
 use crate::packet::PacketTrait;
 use crate::ser::Serialize;
-use crate::types::{mpi, KeyId, Mpi, PublicKeyTrait, Tag, Version};
+use crate::types::{
+    mpi, KeyId, KeyTrait, KeyVersion, Mpi, PublicKeyTrait, SecretKeyRepr, SecretKeyTrait, Tag,
+    Version,
+};
+use crate::PlainSessionKey;
 
 /// Public Key Encrypted Session Key Packet
 /// https://tools.ietf.org/html/rfc4880.html#section-5.1
+///
+/// Version 3 packets name the recipient by key id, version 6 packets by key version and
+/// fingerprint, see
+/// https://www.ietf.org/archive/id/draft-ietf-openpgp-crypto-refresh-13.html#name-version-6-public-key-encryp
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PublicKeyEncryptedSessionKey {
     packet_version: Version,
     version: u8,
+    /// For version 6 packets, this is derived from the fingerprint.
     id: KeyId,
+    /// The recipient of a version 6 packet, `None` for anonymous recipients.
+    fingerprint: Option<(KeyVersion, Vec<u8>)>,
     algorithm: PublicKeyAlgorithm,
     mpis: Vec<Mpi>,
 }
@@ -31,7 +42,7 @@ impl PublicKeyEncryptedSessionKey {
     pub fn from_slice(version: Version, input: &[u8]) -> Result<Self> {
         let (_, pk) = parse(version)(input)?;
 
-        if pk.version != 3 {
+        if pk.version != 3 && pk.version != 6 {
             unsupported_err!("unsupported PKESK version {}", pk.version);
         }
 
@@ -39,12 +50,46 @@ impl PublicKeyEncryptedSessionKey {
     }
 
     /// Encrypts the given session key to the passed in public key.
+    ///
+    /// Builds a single packet, see [`Message::encrypt_with_session_key`] for encrypting a
+    /// message to a list of them.
+    ///
+    /// [`Message::encrypt_with_session_key`]: crate::composed::Message::encrypt_with_session_key
     pub fn from_session_key<R: CryptoRng + Rng>(
         rng: &mut R,
         session_key: &[u8],
         alg: SymmetricKeyAlgorithm,
         pkey: &impl PublicKeyTrait,
     ) -> Result<Self> {
+        ensure!(
+            alg != SymmetricKeyAlgorithm::Plaintext,
+            "session key algorithm cannot be plaintext"
+        );
+        ensure_eq!(
+            session_key.len(),
+            alg.key_size(),
+            "invalid session key length for {:?}",
+            alg
+        );
+
+        if matches!(
+            pkey.algorithm(),
+            PublicKeyAlgorithm::X25519 | PublicKeyAlgorithm::X448
+        ) {
+            // the session key is wrapped as is, the symmetric key algorithm is stored in the clear
+            let mut mpis = pkey.encrypt(rng, session_key)?;
+            mpis.push(Mpi::from_slice(&[u8::from(alg)]));
+
+            return Ok(PublicKeyEncryptedSessionKey {
+                packet_version: Default::default(),
+                version: 3,
+                id: pkey.key_id(),
+                fingerprint: None,
+                algorithm: pkey.algorithm(),
+                mpis,
+            });
+        }
+
         // the session key is prefixed with symmetric key algorithm
         let len = session_key.len();
         let mut data = vec![0u8; len + 3];
@@ -63,15 +108,124 @@ impl PublicKeyEncryptedSessionKey {
             packet_version: Default::default(),
             version: 3,
             id: pkey.key_id(),
+            fingerprint: None,
             algorithm: pkey.algorithm(),
             mpis,
         })
     }
 
+    /// Encrypts the given session key to the passed in public key, into a version 6 packet.
+    ///
+    /// Version 6 packets must be used together with version 2 SEIPD packets, which carry the
+    /// symmetric algorithm instead of the session key.
+    pub fn from_session_key_v6<R: CryptoRng + Rng>(
+        rng: &mut R,
+        session_key: &[u8],
+        pkey: &impl PublicKeyTrait,
+    ) -> Result<Self> {
+        let fingerprint = pkey.fingerprint();
+        let key_version = match fingerprint.len() {
+            20 => KeyVersion::V4,
+            32 => KeyVersion::V6,
+            len => unsupported_err!("fingerprint of length {}", len),
+        };
+
+        let mpis = if matches!(
+            pkey.algorithm(),
+            PublicKeyAlgorithm::X25519 | PublicKeyAlgorithm::X448
+        ) {
+            // the session key is wrapped as is
+            pkey.encrypt(rng, session_key)?
+        } else {
+            // the session key is appended a checksum, without the symmetric key algorithm
+            let mut data = session_key.to_vec();
+            data.extend_from_slice(&checksum::calculate_simple(session_key).to_be_bytes());
+
+            pkey.encrypt(rng, &data)?
+        };
+
+        Ok(PublicKeyEncryptedSessionKey {
+            packet_version: Default::default(),
+            version: 6,
+            id: pkey.key_id(),
+            fingerprint: Some((key_version, fingerprint)),
+            algorithm: pkey.algorithm(),
+            mpis,
+        })
+    }
+
+    /// Returns true if this packet names `key` as its recipient, by fingerprint for version 6
+    /// packets, and by key id otherwise.
+    ///
+    /// Anonymous recipients never match.
+    pub fn is_recipient(&self, key: &impl KeyTrait) -> bool {
+        match self.fingerprint {
+            Some((_, ref fingerprint)) => fingerprint == &key.fingerprint(),
+            None => !self.id.is_wildcard() && self.id == key.key_id(),
+        }
+    }
+
+    /// Decrypts the session key with the given secret key.
+    ///
+    /// Version 3 packets contain the symmetric algorithm, and result in
+    /// [`PlainSessionKey::V4`], version 6 packets in [`PlainSessionKey::V6`].
+    ///
+    /// Fails if the packet is addressed to a different key.
+    pub fn decrypt<F, L>(&self, key: &L, key_pw: F) -> Result<PlainSessionKey>
+    where
+        F: FnOnce() -> String,
+        L: SecretKeyTrait<Unlocked = SecretKeyRepr> + KeyTrait,
+    {
+        ensure!(
+            self.is_anonymous() || self.is_recipient(key),
+            "session key is encrypted to {:?}, not {:?}",
+            self.id,
+            key.key_id()
+        );
+        ensure_eq!(
+            self.algorithm,
+            key.algorithm(),
+            "session key is encrypted with a different algorithm"
+        );
+
+        key.unlock(key_pw, |priv_key| {
+            if self.version == 6 {
+                let key = priv_key.decrypt_v6(&self.mpis, &key.fingerprint())?;
+                Ok(PlainSessionKey::V6 { key })
+            } else {
+                let (key, sym_alg) = priv_key.decrypt(&self.mpis, &key.fingerprint())?;
+                Ok(PlainSessionKey::V4 { sym_alg, key })
+            }
+        })
+    }
+
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
     pub fn id(&self) -> &KeyId {
         &self.id
     }
 
+    /// The key version and fingerprint of the recipient of a version 6 packet.
+    pub fn fingerprint(&self) -> Option<(KeyVersion, &[u8])> {
+        self.fingerprint
+            .as_ref()
+            .map(|(version, fingerprint)| (*version, &fingerprint[..]))
+    }
+
+    /// Returns true if the recipient is not named.
+    pub fn is_anonymous(&self) -> bool {
+        match self.fingerprint {
+            Some(_) => false,
+            None => self.id.is_wildcard(),
+        }
+    }
+
+    pub fn algorithm(&self) -> PublicKeyAlgorithm {
+        self.algorithm
+    }
+
     pub fn mpis(&self) -> &[Mpi] {
         &self.mpis
     }
@@ -81,7 +235,11 @@ impl PublicKeyEncryptedSessionKey {
     }
 }
 
-fn parse_mpis<'i>(alg: &PublicKeyAlgorithm, i: &'i [u8]) -> IResult<&'i [u8], Vec<Mpi>> {
+fn parse_mpis<'i>(
+    version: u8,
+    alg: &PublicKeyAlgorithm,
+    i: &'i [u8],
+) -> IResult<&'i [u8], Vec<Mpi>> {
     match alg {
         PublicKeyAlgorithm::RSA | PublicKeyAlgorithm::RSASign | PublicKeyAlgorithm::RSAEncrypt => {
             map(mpi, |v| vec![v.to_owned()])(i)
@@ -101,6 +259,33 @@ fn parse_mpis<'i>(alg: &PublicKeyAlgorithm, i: &'i [u8]) -> IResult<&'i [u8], Ve
             let v: [u8; 1] = [blen];
             Ok((i, vec![a.to_owned(), (&v[..]).into(), b.into()]))
         }
+        PublicKeyAlgorithm::X25519 | PublicKeyAlgorithm::X448 => {
+            // the ephemeral public key, followed by the length prefixed wrapped session key,
+            // which version 3 packets prefix with the symmetric key algorithm
+            let ephemeral_len = if *alg == PublicKeyAlgorithm::X25519 {
+                32usize
+            } else {
+                56
+            };
+            let (i, ephemeral) = take(ephemeral_len)(i)?;
+            let (i, len) = be_u8(i)?;
+            let (i, rest) = take(len)(i)?;
+            if version == 6 {
+                Ok((i, vec![Mpi::from_slice(ephemeral), Mpi::from_slice(rest)]))
+            } else {
+                let Some((alg, wrapped)) = rest.split_first() else {
+                    return Err(nom::Err::Error(crate::errors::Error::InvalidInput));
+                };
+                Ok((
+                    i,
+                    vec![
+                        Mpi::from_slice(ephemeral),
+                        Mpi::from_slice(wrapped),
+                        Mpi::from_slice(&[*alg]),
+                    ],
+                ))
+            }
+        }
         PublicKeyAlgorithm::Unknown(_) => Ok((i, vec![])), // we don't know the format of this data
         _ => Err(nom::Err::Error(crate::errors::Error::ParsingError(
             nom::error::ErrorKind::Switch,
@@ -113,15 +298,42 @@ fn parse(
     packet_version: Version,
 ) -> impl Fn(&[u8]) -> IResult<&[u8], PublicKeyEncryptedSessionKey> {
     move |i: &[u8]| {
-        // version, only 3 is allowed
+        // version, 3 or 6
         let (i, version) = be_u8(i)?;
-        // the key id this maps to
-        let (i, id) = map_res(take(8u8), KeyId::from_slice)(i)?;
-        // the symmetric key algorithm
+        let (i, id, fingerprint) = if version == 6 {
+            // the key version and fingerprint this maps to, or nothing for anonymous recipients
+            let (i, len) = be_u8(i)?;
+            if len == 0 {
+                (i, KeyId::from_slice(&[0; 8])?, None)
+            } else {
+                let (i, key_version) = map(be_u8, KeyVersion::from)(i)?;
+                let (i, fingerprint) = take(len - 1)(i)?;
+                if fingerprint.len() < 8 {
+                    return Err(nom::Err::Error(crate::errors::Error::InvalidInput));
+                }
+                // v4 key ids are the end of the fingerprint, newer ones the start
+                let id = match key_version {
+                    KeyVersion::V2 | KeyVersion::V3 | KeyVersion::V4 => {
+                        &fingerprint[fingerprint.len() - 8..]
+                    }
+                    _ => &fingerprint[..8],
+                };
+                (
+                    i,
+                    KeyId::from_slice(id)?,
+                    Some((key_version, fingerprint.to_vec())),
+                )
+            }
+        } else {
+            // the key id this maps to
+            let (i, id) = map_res(take(8u8), KeyId::from_slice)(i)?;
+            (i, id, None)
+        };
+        // the public key algorithm
         let (i, alg) = map(be_u8, PublicKeyAlgorithm::from)(i)?;
 
         // key algorithm specific data
-        let (i, mpis) = parse_mpis(&alg, i)?;
+        let (i, mpis) = parse_mpis(version, &alg, i)?;
 
         Ok((
             i,
@@ -129,6 +341,7 @@ fn parse(
                 packet_version,
                 version,
                 id,
+                fingerprint,
                 algorithm: alg,
                 mpis,
             },
@@ -139,7 +352,17 @@ fn parse(
 impl Serialize for PublicKeyEncryptedSessionKey {
     fn to_writer<W: io::Write>(&self, writer: &mut W) -> Result<()> {
         writer.write_all(&[self.version])?;
-        writer.write_all(self.id.as_ref())?;
+        if self.version == 6 {
+            match self.fingerprint {
+                Some((key_version, ref fingerprint)) => {
+                    writer.write_all(&[fingerprint.len() as u8 + 1, key_version.into()])?;
+                    writer.write_all(fingerprint)?;
+                }
+                None => writer.write_all(&[0])?,
+            }
+        } else {
+            writer.write_all(self.id.as_ref())?;
+        }
         writer.write_all(&[self.algorithm.into()])?;
 
         match self.algorithm {
@@ -167,6 +390,20 @@ impl Serialize for PublicKeyEncryptedSessionKey {
                 }
                 writer.write_all(self.mpis[2].as_bytes())?;
             }
+            PublicKeyAlgorithm::X25519 | PublicKeyAlgorithm::X448 => {
+                ensure!(self.mpis.len() >= 2, "missing values");
+                writer.write_all(self.mpis[0].as_bytes())?;
+                let wrapped = self.mpis[1].as_bytes();
+                if self.version == 6 {
+                    writer.write_all(&[u8::try_from(wrapped.len())?])?;
+                } else {
+                    ensure_eq!(self.mpis.len(), 3, "missing symmetric algorithm");
+                    let alg = self.mpis[2].as_bytes();
+                    writer.write_all(&[u8::try_from(alg.len() + wrapped.len())?])?;
+                    writer.write_all(alg)?;
+                }
+                writer.write_all(wrapped)?;
+            }
             _ => {
                 unimplemented_err!("writing {:?}", self.algorithm);
             }
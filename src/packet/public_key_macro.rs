@@ -7,7 +7,7 @@ macro_rules! impl_public_key {
             pub(crate) version: $crate::types::KeyVersion,
             pub(crate) algorithm: $crate::crypto::public_key::PublicKeyAlgorithm,
             pub(crate) created_at: chrono::DateTime<chrono::Utc>,
-            pub(crate) expiration: Option<u16>,
+            pub(crate) expiration: Option<u32>,
             pub(crate) public_params: $crate::types::PublicParams,
         }
 
@@ -18,7 +18,7 @@ macro_rules! impl_public_key {
                 version: $crate::types::KeyVersion,
                 algorithm: $crate::crypto::public_key::PublicKeyAlgorithm,
                 created_at: chrono::DateTime<chrono::Utc>,
-                expiration: Option<u16>,
+                expiration: Option<u32>,
                 public_params: $crate::types::PublicParams,
             ) -> $crate::errors::Result<Self> {
                 use $crate::crypto::public_key::PublicKeyAlgorithm;
@@ -36,6 +36,14 @@ macro_rules! impl_public_key {
                             version,
                         );
                     }
+                    if let Some(expiration) = expiration {
+                        ensure!(
+                            u16::try_from(expiration).is_ok(),
+                            "expiration {} does not fit into version {:?} keys",
+                            expiration,
+                            version
+                        );
+                    }
                 }
 
                 Ok($name {
@@ -74,7 +82,19 @@ macro_rules! impl_public_key {
                 &self.created_at
             }
 
-            pub fn expiration(&self) -> Option<u16> {
+            /// The creation time as stored in the packet, in seconds since the Unix epoch.
+            ///
+            /// The creation time is part of the fingerprint, so it must be kept exactly when
+            /// re-encoding the key.
+            pub fn created_at_unix(&self) -> u32 {
+                self.created_at.timestamp() as u32
+            }
+
+            /// The validity period stored in the packet, in days for version 2 and 3 keys.
+            ///
+            /// Newer keys store their expiration in self-signatures instead, this is only set
+            /// for keys that were just generated, in seconds.
+            pub fn expiration(&self) -> Option<u32> {
                 self.expiration
             }
 
@@ -93,11 +113,11 @@ macro_rules! impl_public_key {
                 use byteorder::{BigEndian, WriteBytesExt};
                 use $crate::ser::Serialize;
 
-                writer.write_u32::<BigEndian>(self.created_at.timestamp() as u32)?;
-                writer.write_u16::<BigEndian>(
-                    self.expiration
-                        .expect("old key versions have an expiration"),
-                )?;
+                writer.write_u32::<BigEndian>(self.created_at_unix())?;
+                let expiration = self
+                    .expiration
+                    .expect("old key versions have an expiration");
+                writer.write_u16::<BigEndian>(u16::try_from(expiration)?)?;
                 writer.write_all(&[self.algorithm.into()])?;
                 self.public_params.to_writer(writer)?;
 
@@ -111,13 +131,31 @@ macro_rules! impl_public_key {
                 use byteorder::{BigEndian, WriteBytesExt};
                 use $crate::ser::Serialize;
 
-                writer.write_u32::<BigEndian>(self.created_at.timestamp() as u32)?;
+                writer.write_u32::<BigEndian>(self.created_at_unix())?;
                 writer.write_all(&[self.algorithm.into()])?;
                 self.public_params.to_writer(writer)?;
 
                 Ok(())
             }
 
+            fn to_writer_v6<W: std::io::Write>(
+                &self,
+                writer: &mut W,
+            ) -> $crate::errors::Result<()> {
+                use byteorder::{BigEndian, WriteBytesExt};
+                use $crate::ser::Serialize;
+
+                writer.write_u32::<BigEndian>(self.created_at_unix())?;
+                writer.write_all(&[self.algorithm.into()])?;
+
+                // the key material is prefixed with its length
+                let params = self.public_params.to_bytes()?;
+                writer.write_u32::<BigEndian>(params.len().try_into()?)?;
+                writer.write_all(&params)?;
+
+                Ok(())
+            }
+
             pub fn sign<F>(
                 &self,
                 key: &impl $crate::types::SecretKeyTrait,
@@ -155,6 +193,12 @@ macro_rules! impl_public_key {
             }
         }
 
+        impl AsRef<$crate::types::PublicParams> for $name {
+            fn as_ref(&self) -> &$crate::types::PublicParams {
+                &self.public_params
+            }
+        }
+
         impl $crate::ser::Serialize for $name {
             fn to_writer<W: std::io::Write>(&self, writer: &mut W) -> $crate::errors::Result<()> {
                 writer.write_all(&[u8::from(self.version)])?;
@@ -164,6 +208,7 @@ macro_rules! impl_public_key {
                         self.to_writer_old(writer)
                     }
                     $crate::types::KeyVersion::V4 => self.to_writer_new(writer),
+                    $crate::types::KeyVersion::V6 => self.to_writer_v6(writer),
                     $crate::types::KeyVersion::V5 => unimplemented_err!("V5 keys"),
                     $crate::types::KeyVersion::Other(v) => {
                         unimplemented_err!("Unsupported key version {}", v)
@@ -188,6 +233,7 @@ macro_rules! impl_public_key {
                 use byteorder::{BigEndian, ByteOrder, WriteBytesExt};
                 use md5::Md5;
                 use sha1::{Digest, Sha1};
+                use sha2::Sha256;
 
                 use $crate::ser::Serialize;
                 use $crate::types::KeyVersion;
@@ -205,10 +251,7 @@ macro_rules! impl_public_key {
                         let mut packet = vec![4, 0, 0, 0, 0];
 
                         // A four-octet number denoting the time that the key was created.
-                        BigEndian::write_u32(
-                            &mut packet[1..5],
-                            self.created_at().timestamp() as u32,
-                        );
+                        BigEndian::write_u32(&mut packet[1..5], self.created_at_unix());
 
                         // A one-octet number denoting the public-key algorithm of this key.
                         packet.push(self.algorithm().into());
@@ -224,6 +267,18 @@ macro_rules! impl_public_key {
 
                         h.finalize().to_vec()
                     }
+                    KeyVersion::V6 => {
+                        let mut packet = vec![6];
+                        self.to_writer_v6(&mut packet).expect("write to vec");
+
+                        let mut h = Sha256::new();
+                        h.update(&[0x9B]);
+                        h.write_u32::<BigEndian>(packet.len() as u32)
+                            .expect("write to hasher");
+                        h.update(&packet);
+
+                        h.finalize().to_vec()
+                    }
                     KeyVersion::V5 => unimplemented!("V5 keys"),
                     KeyVersion::Other(v) => unimplemented!("Unsupported key version {}", v),
                 }
@@ -248,6 +303,10 @@ macro_rules! impl_public_key {
 
                         KeyId::from_slice(&f[offset..]).expect("fixed size slice")
                     }
+                    KeyVersion::V6 => {
+                        // High 64 bits
+                        KeyId::from_slice(&self.fingerprint()[..8]).expect("fixed size slice")
+                    }
                     KeyVersion::V5 => unimplemented!("V5 keys"),
                     KeyVersion::Other(v) => unimplemented!("Unsupported key version {}", v),
                 }
@@ -256,6 +315,10 @@ macro_rules! impl_public_key {
             fn algorithm(&self) -> $crate::crypto::public_key::PublicKeyAlgorithm {
                 self.algorithm
             }
+
+            fn version(&self) -> $crate::types::KeyVersion {
+                self.version
+            }
         }
 
         impl $crate::types::PublicKeyTrait for $name {
@@ -295,6 +358,18 @@ macro_rules! impl_public_key {
                     PublicParams::Elgamal { .. } => {
                         unimplemented_err!("verify Elgamal");
                     }
+                    PublicParams::Ed25519 { ref public } => {
+                        $crate::crypto::ed25519::verify(public, hashed, sig)
+                    }
+                    PublicParams::X25519 { .. } => {
+                        bail!("X25519 is only used for encryption");
+                    }
+                    PublicParams::X448 { .. } => {
+                        bail!("X448 is only used for encryption");
+                    }
+                    PublicParams::Ed448 { ref public } => {
+                        $crate::crypto::ed448::verify(public, hashed, sig)
+                    }
                     PublicParams::DSA {
                         ref p,
                         ref q,
@@ -331,6 +406,8 @@ macro_rules! impl_public_key {
                         $crate::crypto::rsa::encrypt(rng, n.as_bytes(), e.as_bytes(), plain)
                     }
                     PublicParams::EdDSA { .. } => bail!("EdDSA is only used for signing"),
+                    PublicParams::Ed25519 { .. } => bail!("Ed25519 is only used for signing"),
+                    PublicParams::Ed448 { .. } => bail!("Ed448 is only used for signing"),
                     PublicParams::ECDSA { .. } => bail!("ECDSA is only used for signing"),
                     PublicParams::ECDH {
                         ref curve,
@@ -347,6 +424,24 @@ macro_rules! impl_public_key {
                         plain,
                     ),
                     PublicParams::Elgamal { .. } => unimplemented_err!("encryption with Elgamal"),
+                    PublicParams::X25519 { ref public } => {
+                        // native values, which are not encoded as MPIs
+                        let (ephemeral, wrapped) =
+                            $crate::crypto::x25519::encrypt(rng, public, plain)?;
+                        return Ok(vec![
+                            $crate::types::Mpi::from_slice(&ephemeral),
+                            $crate::types::Mpi::from_slice(&wrapped),
+                        ]);
+                    }
+                    PublicParams::X448 { ref public } => {
+                        // native values, which are not encoded as MPIs
+                        let (ephemeral, wrapped) =
+                            $crate::crypto::x448::encrypt(rng, public, plain)?;
+                        return Ok(vec![
+                            $crate::types::Mpi::from_slice(&ephemeral),
+                            $crate::types::Mpi::from_slice(&wrapped),
+                        ]);
+                    }
                     PublicParams::DSA { .. } => bail!("DSA is only used for signing"),
                     PublicParams::Unknown { .. } => bail!("Unknown algorithm"),
                 }?;
@@ -366,8 +461,14 @@ macro_rules! impl_public_key {
                 let mut key_buf = Vec::new();
                 self.to_writer(&mut key_buf)?;
 
-                // old style packet header for the key
-                writer.write_all(&[0x99, (key_buf.len() >> 8) as u8, key_buf.len() as u8])?;
+                if self.version == $crate::types::KeyVersion::V6 {
+                    // v6 keys are hashed with a four-octet length
+                    writer.write_all(&[0x9B])?;
+                    writer.write_all(&u32::try_from(key_buf.len())?.to_be_bytes())?;
+                } else {
+                    // old style packet header for the key
+                    writer.write_all(&[0x99, (key_buf.len() >> 8) as u8, key_buf.len() as u8])?;
+                }
                 writer.write_all(&key_buf)?;
 
                 Ok(())
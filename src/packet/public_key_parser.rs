@@ -1,6 +1,6 @@
 use chrono::{DateTime, TimeZone, Utc};
-use nom::bytes::streaming::tag;
-use nom::combinator::{map, map_opt, map_res};
+use nom::bytes::streaming::{tag, take};
+use nom::combinator::{all_consuming, map, map_opt, map_parser, map_res};
 use nom::multi::length_data;
 use nom::number::streaming::{be_u16, be_u32, be_u8};
 use nom::sequence::{pair, tuple};
@@ -81,6 +81,38 @@ fn ecdh(i: &[u8]) -> IResult<&[u8], PublicParams> {
     )(i)
 }
 
+/// Ref: https://www.rfc-editor.org/rfc/rfc9580.html#name-algorithm-specific-part-for-x
+fn x25519(i: &[u8]) -> IResult<&[u8], PublicParams> {
+    // 32 octets of the native public key
+    map(take(32usize), |public: &[u8]| PublicParams::X25519 {
+        public: public.try_into().expect("size checked"),
+    })(i)
+}
+
+/// Ref: https://www.rfc-editor.org/rfc/rfc9580.html#name-algorithm-specific-part-for-ed
+fn ed25519(i: &[u8]) -> IResult<&[u8], PublicParams> {
+    // 32 octets of the native public key
+    map(take(32usize), |public: &[u8]| PublicParams::Ed25519 {
+        public: public.try_into().expect("size checked"),
+    })(i)
+}
+
+/// Ref: https://www.rfc-editor.org/rfc/rfc9580.html#name-algorithm-specific-part-for-x4
+fn x448(i: &[u8]) -> IResult<&[u8], PublicParams> {
+    // 56 octets of the native public key
+    map(take(56usize), |public: &[u8]| PublicParams::X448 {
+        public: public.try_into().expect("size checked"),
+    })(i)
+}
+
+/// Ref: https://www.rfc-editor.org/rfc/rfc9580.html#name-algorithm-specific-part-for-ed4
+fn ed448(i: &[u8]) -> IResult<&[u8], PublicParams> {
+    // 57 octets of the native public key
+    map(take(57usize), |public: &[u8]| PublicParams::Ed448 {
+        public: public.try_into().expect("size checked"),
+    })(i)
+}
+
 fn elgamal(i: &[u8]) -> IResult<&[u8], PublicParams> {
     map(
         tuple((
@@ -128,6 +160,10 @@ pub fn parse_pub_fields(typ: PublicKeyAlgorithm) -> impl Fn(&[u8]) -> IResult<&[
         PublicKeyAlgorithm::ECDH => ecdh(i),
         PublicKeyAlgorithm::Elgamal | PublicKeyAlgorithm::ElgamalSign => elgamal(i),
         PublicKeyAlgorithm::EdDSA => eddsa(i),
+        PublicKeyAlgorithm::X25519 => x25519(i),
+        PublicKeyAlgorithm::Ed25519 => ed25519(i),
+        PublicKeyAlgorithm::X448 => x448(i),
+        PublicKeyAlgorithm::Ed448 => ed448(i),
 
         PublicKeyAlgorithm::DiffieHellman
         | PublicKeyAlgorithm::Private100
@@ -155,14 +191,20 @@ fn new_public_key_parser(
         KeyVersion,
         PublicKeyAlgorithm,
         DateTime<Utc>,
-        Option<u16>,
+        Option<u32>,
         PublicParams,
     ),
 > + '_ {
     |i: &[u8]| {
         let (i, created_at) = map_opt(be_u32, |v| Utc.timestamp_opt(i64::from(v), 0).single())(i)?;
         let (i, alg) = map(be_u8, PublicKeyAlgorithm::from)(i)?;
-        let (i, params) = parse_pub_fields(alg)(i)?;
+        let (i, params) = match key_ver {
+            // v6 keys prefix the key material with its length
+            KeyVersion::V6 => {
+                map_parser(length_data(be_u32), all_consuming(parse_pub_fields(alg)))(i)?
+            }
+            _ => parse_pub_fields(alg)(i)?,
+        };
         Ok((i, (*key_ver, alg, created_at, None, params)))
     }
 }
@@ -177,7 +219,7 @@ fn old_public_key_parser(
         KeyVersion,
         PublicKeyAlgorithm,
         DateTime<Utc>,
-        Option<u16>,
+        Option<u32>,
         PublicParams,
     ),
 > + '_ {
@@ -187,7 +229,7 @@ fn old_public_key_parser(
         let (i, alg) = map(be_u8, PublicKeyAlgorithm::from)(i)?;
         let (i, params) = parse_pub_fields(alg)(i)?;
 
-        Ok((i, (*key_ver, alg, created_at, Some(exp), params)))
+        Ok((i, (*key_ver, alg, created_at, Some(u32::from(exp)), params)))
     }
 }
 
@@ -202,14 +244,14 @@ pub(crate) fn parse(
         KeyVersion,
         PublicKeyAlgorithm,
         DateTime<Utc>,
-        Option<u16>,
+        Option<u32>,
         PublicParams,
     ),
 > {
     let (i, key_ver) = map(be_u8, KeyVersion::from)(i)?;
     let (i, key) = match &key_ver {
         &KeyVersion::V2 | &KeyVersion::V3 => old_public_key_parser(&key_ver)(i)?,
-        &KeyVersion::V4 => new_public_key_parser(&key_ver)(i)?,
+        &KeyVersion::V4 | &KeyVersion::V6 => new_public_key_parser(&key_ver)(i)?,
         KeyVersion::V5 | KeyVersion::Other(_) => {
             return Err(nom::Err::Error(crate::errors::Error::Unsupported(format!(
                 "Unsupported key version {}",
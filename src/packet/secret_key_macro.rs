@@ -52,7 +52,13 @@ macro_rules! impl_secret_key {
                 &self.details.created_at()
             }
 
-            pub fn expiration(&self) -> Option<u16> {
+            /// The creation time as stored in the packet, in seconds since the Unix epoch.
+            pub fn created_at_unix(&self) -> u32 {
+                self.details.created_at_unix()
+            }
+
+            /// See [`PublicKey::expiration`](crate::packet::PublicKey::expiration).
+            pub fn expiration(&self) -> Option<u32> {
                 self.details.expiration()
             }
 
@@ -72,7 +78,14 @@ macro_rules! impl_secret_key {
             where
                 F: FnOnce() -> String,
             {
-                let plain = ciphertext.unlock(pw, self.details.algorithm, self.public_params())?;
+                let plain = ciphertext.unlock_with_public_key(
+                    pw,
+                    self.details.algorithm,
+                    self.public_params(),
+                    self.details.version,
+                    &self.details,
+                    $tag,
+                )?;
                 self.repr_from_plaintext(&plain)
             }
 
@@ -87,6 +100,53 @@ macro_rules! impl_secret_key {
                 &self.secret_params
             }
 
+            /// Encrypts the currently unencrypted secret parameters, using the given
+            /// passphrase and s2k parameters.
+            pub fn set_password_with_s2k(
+                &mut self,
+                passphrase: &str,
+                s2k_params: $crate::types::S2kParams,
+            ) -> $crate::errors::Result<()> {
+                let $crate::types::SecretParams::Plain(ref plain) = self.secret_params else {
+                    bail!("secret key is already encrypted");
+                };
+
+                let encrypted = plain.clone().encrypt_with_public_key(
+                    passphrase,
+                    s2k_params,
+                    self.details.version,
+                    &self.details,
+                    $tag,
+                )?;
+                self.secret_params = $crate::types::SecretParams::Encrypted(encrypted);
+
+                Ok(())
+            }
+
+            /// Decrypts the secret parameters with the given passphrase, and stores them
+            /// unencrypted. Does nothing if they are not encrypted.
+            pub fn remove_password<F>(&mut self, pw: F) -> $crate::errors::Result<()>
+            where
+                F: FnOnce() -> String,
+            {
+                let $crate::types::SecretParams::Encrypted(ref encrypted) = self.secret_params
+                else {
+                    return Ok(());
+                };
+
+                let plain = encrypted.unlock_with_public_key(
+                    pw,
+                    self.details.algorithm,
+                    self.public_params(),
+                    self.details.version,
+                    &self.details,
+                    $tag,
+                )?;
+                self.secret_params = $crate::types::SecretParams::Plain(plain);
+
+                Ok(())
+            }
+
             /// Checks if we should expect a SHA1 checksum in the encrypted part.
             pub fn has_sha1_checksum(&self) -> bool {
                 self.secret_params.string_to_key_id() == 254
@@ -116,6 +176,16 @@ macro_rules! impl_secret_key {
                 Ok(())
             }
 
+            fn to_writer_v6<W: std::io::Write>(
+                &self,
+                writer: &mut W,
+            ) -> $crate::errors::Result<()> {
+                self.details.to_writer_v6(writer)?;
+                self.secret_params.to_writer_v6(writer)?;
+
+                Ok(())
+            }
+
             pub fn sign<F>(
                 &self,
                 key: &impl $crate::types::SecretKeyTrait,
@@ -196,12 +266,38 @@ macro_rules! impl_secret_key {
                         SecretKeyRepr::DSA(ref priv_key) => {
                             priv_key.sign(hash, data, self.public_params())
                         }
-                        SecretKeyRepr::ECDH(_) => {
+                        SecretKeyRepr::ECDH(_) | SecretKeyRepr::ECDHBrainpool(_) => {
                             bail!("ECDH can not be used to for signing operations")
                         }
+                        SecretKeyRepr::X25519(_) => {
+                            bail!("X25519 can not be used to for signing operations")
+                        }
+                        SecretKeyRepr::X448(_) => {
+                            bail!("X448 can not be used to for signing operations")
+                        }
                         SecretKeyRepr::EdDSA(ref priv_key) => {
                             priv_key.sign(hash, data, self.public_params())
                         }
+                        SecretKeyRepr::Ed25519(ref priv_key) => {
+                            // a native signature, which is not encoded as MPIs
+                            let sig = priv_key.sign(hash, data, self.public_params())?;
+                            signature = Some(
+                                sig.iter()
+                                    .map(|v| $crate::types::Mpi::from_slice(&v[..]))
+                                    .collect(),
+                            );
+                            return Ok(());
+                        }
+                        SecretKeyRepr::Ed448(ref priv_key) => {
+                            // a native signature, which is not encoded as MPIs
+                            let sig = priv_key.sign(hash, data, self.public_params())?;
+                            signature = Some(
+                                sig.iter()
+                                    .map(|v| $crate::types::Mpi::from_slice(&v[..]))
+                                    .collect(),
+                            );
+                            return Ok(());
+                        }
                     }?;
 
                     // strip leading zeros, to match parse results from MPIs
@@ -234,6 +330,7 @@ macro_rules! impl_secret_key {
                         self.to_writer_old(writer)
                     }
                     $crate::types::KeyVersion::V4 => self.to_writer_new(writer),
+                    $crate::types::KeyVersion::V6 => self.to_writer_v6(writer),
                     $crate::types::KeyVersion::V5 => unimplemented_err!("V5 keys"),
                     $crate::types::KeyVersion::Other(v) => {
                         unimplemented_err!("Unsupported key version {}", v)
@@ -265,6 +362,10 @@ macro_rules! impl_secret_key {
             fn algorithm(&self) -> $crate::crypto::public_key::PublicKeyAlgorithm {
                 self.details.algorithm()
             }
+
+            fn version(&self) -> $crate::types::KeyVersion {
+                self.details.version()
+            }
         }
 
         impl $crate::types::PublicKeyTrait for $name {
@@ -289,16 +390,8 @@ macro_rules! impl_secret_key {
                 &self,
                 writer: &mut impl std::io::Write,
             ) -> $crate::errors::Result<()> {
-                use $crate::ser::Serialize;
-
-                let mut key_buf = Vec::new();
-                self.details.to_writer(&mut key_buf)?;
-
-                // old style packet header for the key
-                writer.write_all(&[0x99, (key_buf.len() >> 8) as u8, key_buf.len() as u8])?;
-                writer.write_all(&key_buf)?;
-
-                Ok(())
+                // the trait method, not the v2/v3 serialization of the key packet
+                $crate::types::PublicKeyTrait::to_writer_old(&self.details, writer)
             }
         }
     };
@@ -1,5 +1,6 @@
 use chrono::{DateTime, TimeZone, Utc};
-use nom::combinator::{map, map_opt, map_res, rest};
+use nom::combinator::{all_consuming, map, map_opt, map_parser, map_res, rest};
+use nom::multi::length_data;
 use nom::number::streaming::{be_u16, be_u32, be_u8};
 use nom::sequence::tuple;
 
@@ -10,16 +11,35 @@ use crate::types::{KeyVersion, PublicParams, SecretParams};
 
 /// Parse the whole private key, both public and private fields.
 fn parse_pub_priv_fields(
+    key_ver: KeyVersion,
     typ: PublicKeyAlgorithm,
 ) -> impl Fn(&[u8]) -> IResult<&[u8], (PublicParams, SecretParams)> {
     move |i| {
         map_res(tuple((parse_pub_fields(typ), rest)), |(pub_params, v)| {
-            let secret_params = SecretParams::from_slice(v, typ, &pub_params)?;
+            let secret_params = SecretParams::from_slice(v, key_ver, typ, &pub_params)?;
             Ok::<_, Error>((pub_params, secret_params))
         })(i)
     }
 }
 
+/// Parse the fields of a v6 private key, where the public fields are prefixed with their length.
+fn parse_pub_priv_fields_v6(
+    typ: PublicKeyAlgorithm,
+) -> impl Fn(&[u8]) -> IResult<&[u8], (PublicParams, SecretParams)> {
+    move |i| {
+        map_res(
+            tuple((
+                map_parser(length_data(be_u32), all_consuming(parse_pub_fields(typ))),
+                rest,
+            )),
+            |(pub_params, v)| {
+                let secret_params = SecretParams::from_slice(v, KeyVersion::V6, typ, &pub_params)?;
+                Ok::<_, Error>((pub_params, secret_params))
+            },
+        )(i)
+    }
+}
+
 fn new_private_key_parser(
     key_ver: &KeyVersion,
 ) -> impl Fn(
@@ -30,7 +50,7 @@ fn new_private_key_parser(
         KeyVersion,
         PublicKeyAlgorithm,
         DateTime<Utc>,
-        Option<u16>,
+        Option<u32>,
         PublicParams,
         SecretParams,
     ),
@@ -38,7 +58,10 @@ fn new_private_key_parser(
     |i: &[u8]| {
         let (i, created_at) = map_opt(be_u32, |v| Utc.timestamp_opt(i64::from(v), 0).single())(i)?;
         let (i, alg) = map(be_u8, PublicKeyAlgorithm::from)(i)?;
-        let (i, params) = parse_pub_priv_fields(alg)(i)?;
+        let (i, params) = match key_ver {
+            KeyVersion::V6 => parse_pub_priv_fields_v6(alg)(i)?,
+            _ => parse_pub_priv_fields(*key_ver, alg)(i)?,
+        };
         Ok((i, (*key_ver, alg, created_at, None, params.0, params.1)))
     }
 }
@@ -53,7 +76,7 @@ fn old_private_key_parser(
         KeyVersion,
         PublicKeyAlgorithm,
         DateTime<Utc>,
-        Option<u16>,
+        Option<u32>,
         PublicParams,
         SecretParams,
     ),
@@ -62,10 +85,17 @@ fn old_private_key_parser(
         let (i, created_at) = map_opt(be_u32, |v| Utc.timestamp_opt(i64::from(v), 0).single())(i)?;
         let (i, exp) = be_u16(i)?;
         let (i, alg) = map(be_u8, PublicKeyAlgorithm::from)(i)?;
-        let (i, params) = parse_pub_priv_fields(alg)(i)?;
+        let (i, params) = parse_pub_priv_fields(*key_ver, alg)(i)?;
         Ok((
             i,
-            (*key_ver, alg, created_at, Some(exp), params.0, params.1),
+            (
+                *key_ver,
+                alg,
+                created_at,
+                Some(u32::from(exp)),
+                params.0,
+                params.1,
+            ),
         ))
     }
 }
@@ -81,7 +111,7 @@ pub(crate) fn parse(
         KeyVersion,
         PublicKeyAlgorithm,
         DateTime<Utc>,
-        Option<u16>,
+        Option<u32>,
         PublicParams,
         SecretParams,
     ),
@@ -89,7 +119,7 @@ pub(crate) fn parse(
     let (i, key_ver) = map(be_u8, KeyVersion::from)(i)?;
     let (i, key) = match &key_ver {
         &KeyVersion::V2 | &KeyVersion::V3 => old_private_key_parser(&key_ver)(i)?,
-        &KeyVersion::V4 => new_private_key_parser(&key_ver)(i)?,
+        &KeyVersion::V4 | &KeyVersion::V6 => new_private_key_parser(&key_ver)(i)?,
         KeyVersion::V5 | KeyVersion::Other(_) => {
             return Err(nom::Err::Error(Error::Unsupported(format!(
                 "Unsupported key version {}",
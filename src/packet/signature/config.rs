@@ -2,7 +2,8 @@ use std::fmt;
 use std::io::Read;
 
 use byteorder::{BigEndian, ByteOrder};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, SubsecRound, Utc};
+use rand::{thread_rng, RngCore};
 
 use crate::crypto::hash::{HashAlgorithm, Hasher};
 use crate::crypto::public_key::PublicKeyAlgorithm;
@@ -28,6 +29,10 @@ pub struct SignatureConfig {
     pub created: Option<DateTime<Utc>>,
     #[builder(default)]
     pub issuer: Option<KeyId>,
+
+    // only set on V6 signatures, a random salt is generated when signing if it is missing
+    #[builder(default)]
+    pub salt: Option<Vec<u8>>,
 }
 
 impl SignatureConfig {
@@ -48,16 +53,49 @@ impl SignatureConfig {
             unhashed_subpackets,
             issuer: None,
             created: None,
+            salt: None,
+        }
+    }
+
+    /// Creates a v4 config that hashes exactly the given subpackets, in the given order.
+    ///
+    /// The signature creation time is mandatory. If `hashed_subpackets` doesn't contain it,
+    /// the current time is appended, or an error returned if `strict` is set.
+    pub fn new_v4_with_hashed_subpackets(
+        typ: SignatureType,
+        pub_alg: PublicKeyAlgorithm,
+        hash_alg: HashAlgorithm,
+        mut hashed_subpackets: Vec<Subpacket>,
+        unhashed_subpackets: Vec<Subpacket>,
+        strict: bool,
+    ) -> Result<Self> {
+        let has_creation_time = hashed_subpackets
+            .iter()
+            .any(|p| matches!(p.data, SubpacketData::SignatureCreationTime(_)));
+        if !has_creation_time {
+            ensure!(!strict, "missing signature creation time subpacket");
+            hashed_subpackets.push(Subpacket::regular(SubpacketData::SignatureCreationTime(
+                Utc::now().trunc_subsecs(0),
+            )));
         }
+
+        Ok(Self::new_v4(
+            Default::default(),
+            typ,
+            pub_alg,
+            hash_alg,
+            hashed_subpackets,
+            unhashed_subpackets,
+        ))
     }
 
     /// Sign the given data.
-    pub fn sign<F, R>(self, key: &impl SecretKeyTrait, key_pw: F, data: R) -> Result<Signature>
+    pub fn sign<F, R>(mut self, key: &impl SecretKeyTrait, key_pw: F, data: R) -> Result<Signature>
     where
         F: FnOnce() -> String,
         R: Read,
     {
-        let mut hasher = self.hash_alg.new_hasher()?;
+        let mut hasher = self.new_signing_hasher()?;
 
         self.hash_data_to_sign(&mut *hasher, data)?;
         let len = self.hash_signature_data(&mut *hasher)?;
@@ -79,6 +117,21 @@ impl SignatureConfig {
         tag: Tag,
         id: &impl Serialize,
     ) -> Result<Signature>
+    where
+        F: FnOnce() -> String,
+    {
+        self.sign_third_party_certification(key, key_pw, key, tag, id)
+    }
+
+    /// Create a certification signature by `signing_key` over the identity `id` of `key`.
+    pub fn sign_third_party_certification<F>(
+        mut self,
+        signing_key: &impl SecretKeyTrait,
+        key_pw: F,
+        key: &impl PublicKeyTrait,
+        tag: Tag,
+        id: &impl Serialize,
+    ) -> Result<Signature>
     where
         F: FnOnce() -> String,
     {
@@ -88,7 +141,7 @@ impl SignatureConfig {
         );
         debug!("signing certification {:#?}", self.typ);
 
-        let mut hasher = self.hash_alg.new_hasher()?;
+        let mut hasher = self.new_signing_hasher()?;
 
         key.to_writer_old(&mut hasher)?;
 
@@ -99,7 +152,7 @@ impl SignatureConfig {
             SignatureVersion::V2 | SignatureVersion::V3 => {
                 // Nothing to do
             }
-            SignatureVersion::V4 | SignatureVersion::V5 => {
+            SignatureVersion::V4 | SignatureVersion::V5 | SignatureVersion::V6 => {
                 let prefix = match tag {
                     Tag::UserId => 0xB4,
                     Tag::UserAttribute => 0xD1,
@@ -126,14 +179,14 @@ impl SignatureConfig {
         let hash = &hasher.finish()[..];
 
         let signed_hash_value = [hash[0], hash[1]];
-        let signature = key.create_signature(key_pw, self.hash_alg, hash)?;
+        let signature = signing_key.create_signature(key_pw, self.hash_alg, hash)?;
 
         Ok(Signature::from_config(self, signed_hash_value, signature))
     }
 
     /// Sign a key binding.
     pub fn sign_key_binding<F>(
-        self,
+        mut self,
         signing_key: &impl SecretKeyTrait,
         key_pw: F,
         key: &impl PublicKeyTrait,
@@ -146,7 +199,7 @@ impl SignatureConfig {
             self, signing_key, key
         );
 
-        let mut hasher = self.hash_alg.new_hasher()?;
+        let mut hasher = self.new_signing_hasher()?;
 
         // Signing Key
         signing_key.to_writer_old(&mut hasher)?;
@@ -164,9 +217,43 @@ impl SignatureConfig {
         Ok(Signature::from_config(self, signed_hash_value, signature))
     }
 
+    /// Sign a primary key binding ("back signature"), made by a signing subkey
+    /// over the primary key it is bound to.
+    pub fn sign_primary_key_binding<F>(
+        mut self,
+        signing_key: &impl SecretKeyTrait,
+        key_pw: F,
+        key: &impl PublicKeyTrait,
+    ) -> Result<Signature>
+    where
+        F: FnOnce() -> String,
+    {
+        debug!(
+            "signing primary key binding: {:#?} - {:#?} - {:#?}",
+            self, signing_key, key
+        );
+
+        let mut hasher = self.new_signing_hasher()?;
+
+        // Primary Key
+        key.to_writer_old(&mut hasher)?;
+
+        // Signing Subkey
+        signing_key.to_writer_old(&mut hasher)?;
+
+        let len = self.hash_signature_data(&mut *hasher)?;
+        hasher.update(&self.trailer(len)?);
+
+        let hash = &hasher.finish()[..];
+        let signed_hash_value = [hash[0], hash[1]];
+        let signature = signing_key.create_signature(key_pw, self.hash_alg, hash)?;
+
+        Ok(Signature::from_config(self, signed_hash_value, signature))
+    }
+
     /// Signs a direct key signature or a revocation.
     pub fn sign_key<F>(
-        self,
+        mut self,
         signing_key: &impl SecretKeyTrait,
         key_pw: F,
         key: &impl PublicKeyTrait,
@@ -176,7 +263,7 @@ impl SignatureConfig {
     {
         debug!("signing key (revocation): {:#?} - {:#?}", self, key);
 
-        let mut hasher = self.hash_alg.new_hasher()?;
+        let mut hasher = self.new_signing_hasher()?;
 
         key.to_writer_old(&mut hasher)?;
 
@@ -190,6 +277,75 @@ impl SignatureConfig {
         Ok(Signature::from_config(self, signed_hash_value, signature))
     }
 
+    /// Creates the hasher for signing, see [`Self::new_hasher`].
+    ///
+    /// Version 6 signatures without a salt get a random one, of the size their hash
+    /// algorithm requires.
+    fn new_signing_hasher(&mut self) -> Result<Box<dyn Hasher>> {
+        if self.version == SignatureVersion::V6 && self.salt.is_none() {
+            let size = self.hash_alg.salt_size().ok_or_else(|| {
+                format_err!("{:?} can not be used for v6 signatures", self.hash_alg)
+            })?;
+            let mut salt = vec![0u8; size];
+            thread_rng().fill_bytes(&mut salt);
+            self.salt = Some(salt);
+        }
+
+        self.new_hasher()
+    }
+
+    /// Creates the hasher for this signature. For version 6 signatures the salt is hashed first.
+    pub fn new_hasher(&self) -> Result<Box<dyn Hasher>> {
+        let mut hasher = self.hash_alg.new_hasher()?;
+        if self.version == SignatureVersion::V6 {
+            hasher.update(self.v6_salt()?);
+        }
+
+        Ok(hasher)
+    }
+
+    /// Returns the salt of a version 6 signature, checking it has the size its hash algorithm
+    /// requires.
+    fn v6_salt(&self) -> Result<&[u8]> {
+        let salt = self
+            .salt
+            .as_ref()
+            .ok_or_else(|| format_err!("missing salt for v6 signature"))?;
+        ensure_eq!(
+            Some(salt.len()),
+            self.hash_alg.salt_size(),
+            "invalid salt size for {:?}",
+            self.hash_alg
+        );
+
+        Ok(salt)
+    }
+
+    /// Returns the exact input a version 6 signature hashes, when made over `data`.
+    ///
+    /// This is the salt, followed by `data`, the hashed part of the signature packet and the
+    /// version 6 trailer, see
+    /// https://www.rfc-editor.org/rfc/rfc9580.html#name-computing-signatures
+    ///
+    /// `data` is what the signature type signs, e.g. the document of a binary signature,
+    /// which must already be normalized for text signatures, or the serialized keys and user
+    /// ids of a certification.
+    pub fn hash_input_v6(&self, data: &[u8]) -> Result<Vec<u8>> {
+        ensure_eq!(
+            self.version,
+            SignatureVersion::V6,
+            "not a version 6 signature"
+        );
+
+        let mut input = self.v6_salt()?.to_vec();
+        input.extend_from_slice(data);
+        let hashed = self.v6_hashed_data()?;
+        input.extend_from_slice(&hashed);
+        input.extend(self.trailer(hashed.len())?);
+
+        Ok(input)
+    }
+
     /// Returns what kind of signature this is.
     pub fn typ(&self) -> SignatureType {
         self.typ
@@ -248,12 +404,39 @@ impl SignatureConfig {
 
                 Ok(res.len())
             }
+            SignatureVersion::V6 => {
+                let res = self.v6_hashed_data()?;
+                hasher.update(&res);
+
+                Ok(res.len())
+            }
             SignatureVersion::Other(version) => {
                 bail!("unsupported signature version {}", version)
             }
         }
     }
 
+    /// The hashed part of a version 6 signature packet.
+    fn v6_hashed_data(&self) -> Result<Vec<u8>> {
+        let mut res = vec![
+            self.version.into(),
+            self.typ as u8,
+            self.pub_alg.into(),
+            self.hash_alg.into(),
+        ];
+
+        // hashed subpackets, with a four-octet length
+        let mut hashed_subpackets = Vec::new();
+        for packet in &self.hashed_subpackets {
+            packet.to_writer(&mut hashed_subpackets)?;
+        }
+
+        res.extend_from_slice(&u32::try_from(hashed_subpackets.len())?.to_be_bytes());
+        res.extend(hashed_subpackets);
+
+        Ok(res)
+    }
+
     pub fn hash_data_to_sign<R>(&self, hasher: &mut dyn Hasher, mut data: R) -> Result<usize>
     where
         R: Read,
@@ -300,6 +483,11 @@ impl SignatureConfig {
                 BigEndian::write_u32(&mut trailer[2..], len as u32);
                 Ok(trailer)
             }
+            SignatureVersion::V6 => {
+                let mut trailer = vec![0x06, 0xFF, 0, 0, 0, 0];
+                BigEndian::write_u32(&mut trailer[2..], len.try_into()?);
+                Ok(trailer)
+            }
             SignatureVersion::Other(version) => {
                 bail!("unsupported signature version {}", version)
             }
@@ -414,6 +602,7 @@ impl fmt::Debug for SignatureConfig {
             .field("hash_alg", &self.hash_alg)
             .field("created", &self.created)
             .field("issuer", &self.issuer)
+            .field("salt", &self.salt.as_ref().map(hex::encode))
             .field("unhashed_subpackets", &self.unhashed_subpackets)
             .field("hashed_subpackets", &self.hashed_subpackets)
             .finish()
@@ -6,7 +6,7 @@ use nom::bytes::streaming::{tag, take};
 use nom::combinator::{complete, map, map_opt, map_parser, map_res, rest};
 use nom::multi::{fold_many_m_n, length_data, many0};
 use nom::number::streaming::{be_u16, be_u32, be_u8};
-use nom::sequence::{pair, tuple};
+use nom::sequence::{pair, preceded, tuple};
 use smallvec::SmallVec;
 
 use crate::crypto::aead::AeadAlgorithm;
@@ -276,6 +276,24 @@ fn issuer_fingerprint(i: &[u8]) -> IResult<&[u8], SubpacketData> {
     )(i)
 }
 
+/// Parse an intended recipient fingerprint subpacket
+/// Ref: https://www.rfc-editor.org/rfc/rfc9580.html#name-intended-recipient-fingerpr
+fn intended_recipient_fingerprint(i: &[u8]) -> IResult<&[u8], SubpacketData> {
+    map(
+        pair(map(be_u8, KeyVersion::from), rest),
+        |(version, fingerprint)| {
+            SubpacketData::IntendedRecipientFingerprint(version, SmallVec::from_slice(fingerprint))
+        },
+    )(i)
+}
+
+/// Parse a key block subpacket, which starts with a reserved zero octet.
+fn key_block(i: &[u8]) -> IResult<&[u8], SubpacketData> {
+    map(preceded(tag(&[0u8][..]), rest), |key: &[u8]| {
+        SubpacketData::KeyBlock(key.to_vec())
+    })(i)
+}
+
 /// Parse a preferred aead subpacket
 fn pref_aead_alg(body: &[u8]) -> IResult<&[u8], SubpacketData> {
     let list: SmallVec<[AeadAlgorithm; 2]> = body.iter().map(|v| AeadAlgorithm::from(*v)).collect();
@@ -283,6 +301,21 @@ fn pref_aead_alg(body: &[u8]) -> IResult<&[u8], SubpacketData> {
     Ok((&b""[..], SubpacketData::PreferredAeadAlgorithms(list)))
 }
 
+/// Parse a preferred aead ciphersuites subpacket
+fn pref_aead_ciphersuites(body: &[u8]) -> IResult<&[u8], SubpacketData> {
+    let list = body
+        .chunks_exact(2)
+        .map(|pair| {
+            (
+                SymmetricKeyAlgorithm::from(pair[0]),
+                AeadAlgorithm::from(pair[1]),
+            )
+        })
+        .collect();
+
+    Ok((&b""[..], SubpacketData::PreferredAeadCiphersuites(list)))
+}
+
 fn subpacket(typ: SubpacketType, is_critical: bool, body: &[u8]) -> IResult<&[u8], Subpacket> {
     use self::SubpacketType::*;
     debug!("parsing subpacket: {:?} {}", typ, hex::encode(body));
@@ -313,6 +346,9 @@ fn subpacket(typ: SubpacketType, is_critical: bool, body: &[u8]) -> IResult<&[u8
         EmbeddedSignature => embedded_sig(body),
         IssuerFingerprint => issuer_fingerprint(body),
         PreferredAead => pref_aead_alg(body),
+        IntendedRecipientFingerprint => intended_recipient_fingerprint(body),
+        KeyBlock => key_block(body),
+        PreferredAeadCiphersuites => pref_aead_ciphersuites(body),
         Experimental(n) => Ok((
             body,
             SubpacketData::Experimental(n, SmallVec::from_slice(body)),
@@ -356,6 +392,10 @@ fn actual_signature(typ: &PublicKeyAlgorithm) -> impl Fn(&[u8]) -> IResult<&[u8]
                 },
             )(i)
         }
+        // the native 64 octet signature
+        &PublicKeyAlgorithm::Ed25519 => map(take(64usize), |v| vec![Mpi::from_slice(v)])(i),
+        // the native 114 octet signature
+        &PublicKeyAlgorithm::Ed448 => map(take(114usize), |v| vec![Mpi::from_slice(v)])(i),
         &PublicKeyAlgorithm::Private100
         | &PublicKeyAlgorithm::Private101
         | &PublicKeyAlgorithm::Private102
@@ -459,6 +499,50 @@ fn v4_parser(
     }
 }
 
+/// Parse a v6 signature packet
+/// Ref: https://www.rfc-editor.org/rfc/rfc9580.html#name-versions-4-and-6-signature-
+fn v6_parser(packet_version: Version) -> impl Fn(&[u8]) -> IResult<&[u8], Signature> {
+    move |i: &[u8]| {
+        let (i, (typ, pub_alg, hash_alg, hsub, usub, ls_hash, salt)) = tuple((
+            // One-octet signature type.
+            map_res(be_u8, SignatureType::try_from),
+            // One-octet public-key algorithm.
+            map(be_u8, PublicKeyAlgorithm::from),
+            // One-octet hash algorithm.
+            map(be_u8, HashAlgorithm::from),
+            // Four-octet scalar octet count for following hashed subpacket data.
+            // Hashed subpacket data set (zero or more subpackets).
+            map_parser(length_data(be_u32), subpackets),
+            // Four-octet scalar octet count for the following unhashed subpacket data.
+            // Unhashed subpacket data set (zero or more subpackets).
+            map_parser(length_data(be_u32), subpackets),
+            // Two-octet field holding the left 16 bits of the signed hash value.
+            take(2usize),
+            // One-octet salt size, followed by the salt.
+            length_data(be_u8),
+        ))(i)?;
+        // One or more multiprecision integers comprising the signature.
+        let (i, sig) = actual_signature(&pub_alg)(i)?;
+        Ok((i, {
+            let mut s = Signature::new(
+                packet_version,
+                SignatureVersion::V6,
+                typ,
+                pub_alg,
+                hash_alg,
+                clone_into_array(ls_hash),
+                sig,
+                hsub,
+                usub,
+            );
+
+            s.config.salt = Some(salt.to_vec());
+
+            s
+        }))
+    }
+}
+
 fn invalid_version(_body: &[u8], version: SignatureVersion) -> IResult<&[u8], Signature> {
     Err(nom::Err::Error(crate::errors::Error::Unsupported(format!(
         "unknown signature version {version:?}"
@@ -473,6 +557,7 @@ fn parse(packet_version: Version) -> impl Fn(&[u8]) -> IResult<&[u8], Signature>
         let (i, signature) = match &version {
             &SignatureVersion::V2 | &SignatureVersion::V3 => v3_parser(packet_version, version)(i),
             &SignatureVersion::V4 | &SignatureVersion::V5 => v4_parser(packet_version, version)(i),
+            &SignatureVersion::V6 => v6_parser(packet_version)(i),
             _ => invalid_version(i, version),
         }?;
         Ok((i, signature))
@@ -1,8 +1,9 @@
 use std::io;
 
 use byteorder::{BigEndian, WriteBytesExt};
-use chrono::Duration;
+use chrono::{DateTime, Duration, Utc};
 
+use crate::crypto::public_key::PublicKeyAlgorithm;
 use crate::errors::Result;
 use crate::packet::signature::types::*;
 use crate::packet::signature::SignatureConfig;
@@ -16,28 +17,41 @@ impl Serialize for Signature {
         match self.config.version {
             SignatureVersion::V2 | SignatureVersion::V3 => self.to_writer_v3(writer),
             SignatureVersion::V4 | SignatureVersion::V5 => self.to_writer_v4(writer),
+            SignatureVersion::V6 => self.to_writer_v6(writer),
             SignatureVersion::Other(version) => bail!("Unsupported signature version {}", version),
         }
     }
 }
 
 impl Subpacket {
+    /// Convert a point in time to OpenPGP u32 format, seconds since the Unix epoch.
+    /// Times that don't fit, like those after 2106, are an error rather than being truncated.
+    fn time_to_u32(t: &DateTime<Utc>) -> Result<u32> {
+        u32::try_from(t.timestamp())
+            .map_err(|_| format_err!("time {} does not fit into a 32 bit timestamp", t))
+    }
+
     /// Convert expiration time "Duration" data to OpenPGP u32 format.
-    /// Use u32:MAX on overflow.
-    fn duration_to_u32(d: &Duration) -> u32 {
-        u32::try_from(d.num_seconds()).unwrap_or(u32::MAX)
+    /// Durations that don't fit are an error.
+    fn duration_to_u32(d: &Duration) -> Result<u32> {
+        u32::try_from(d.num_seconds()).map_err(|_| {
+            format_err!(
+                "duration of {} seconds does not fit into 32 bits",
+                d.num_seconds()
+            )
+        })
     }
 
     fn body_to_writer(&self, writer: &mut impl io::Write) -> Result<()> {
         match &self.data {
             SubpacketData::SignatureCreationTime(t) => {
-                writer.write_u32::<BigEndian>(t.timestamp() as u32)?;
+                writer.write_u32::<BigEndian>(Self::time_to_u32(t)?)?;
             }
             SubpacketData::SignatureExpirationTime(d) => {
-                writer.write_u32::<BigEndian>(Self::duration_to_u32(d))?;
+                writer.write_u32::<BigEndian>(Self::duration_to_u32(d)?)?;
             }
             SubpacketData::KeyExpirationTime(d) => {
-                writer.write_u32::<BigEndian>(Self::duration_to_u32(d))?;
+                writer.write_u32::<BigEndian>(Self::duration_to_u32(d)?)?;
             }
             SubpacketData::Issuer(id) => {
                 writer.write_all(id.as_ref())?;
@@ -113,9 +127,25 @@ impl Subpacket {
                 writer.write_all(&[u8::from(*version)])?;
                 writer.write_all(fp)?;
             }
+            SubpacketData::IntendedRecipientFingerprint(version, fp) => {
+                writer.write_all(&[u8::from(*version)])?;
+                writer.write_all(fp)?;
+            }
+            SubpacketData::KeyBlock(key) => {
+                writer.write_all(&[0])?;
+                writer.write_all(key)?;
+            }
             SubpacketData::PreferredAeadAlgorithms(algs) => {
                 writer.write_all(&algs.iter().map(|&alg| alg.into()).collect::<Vec<_>>())?;
             }
+            SubpacketData::PreferredAeadCiphersuites(suites) => {
+                writer.write_all(
+                    &suites
+                        .iter()
+                        .flat_map(|&(sym_alg, aead)| [u8::from(sym_alg), aead.into()])
+                        .collect::<Vec<_>>(),
+                )?;
+            }
             SubpacketData::Experimental(_, body) => {
                 writer.write_all(body)?;
             }
@@ -171,7 +201,10 @@ impl Subpacket {
             SubpacketData::RegularExpression(regexp) => regexp.len(),
             SubpacketData::ExportableCertification(_) => 1,
             SubpacketData::IssuerFingerprint(_, fp) => 1 + fp.len(),
+            SubpacketData::IntendedRecipientFingerprint(_, fp) => 1 + fp.len(),
+            SubpacketData::KeyBlock(key) => 1 + key.len(),
             SubpacketData::PreferredAeadAlgorithms(algs) => algs.len(),
+            SubpacketData::PreferredAeadCiphersuites(suites) => suites.len() * 2,
             SubpacketData::Experimental(_, body) => body.len(),
             SubpacketData::Other(_, body) => body.len(),
             SubpacketData::SignatureTarget(_, _, hash) => 2 + hash.len(),
@@ -180,6 +213,19 @@ impl Subpacket {
         Ok(len)
     }
 
+    /// The raw type octet, including the critical bit.
+    pub fn raw_type(&self) -> u8 {
+        self.typ().as_u8(self.is_critical)
+    }
+
+    /// The serialized body, without the length and type octets.
+    pub fn body_to_vec(&self) -> Result<Vec<u8>> {
+        let mut buf = Vec::with_capacity(self.body_len()?);
+        self.body_to_writer(&mut buf)?;
+
+        Ok(buf)
+    }
+
     pub fn typ(&self) -> SubpacketType {
         match &self.data {
             SubpacketData::SignatureCreationTime(_) => SubpacketType::SignatureCreationTime,
@@ -209,7 +255,12 @@ impl Subpacket {
             SubpacketData::RegularExpression(_) => SubpacketType::RegularExpression,
             SubpacketData::ExportableCertification(_) => SubpacketType::ExportableCertification,
             SubpacketData::IssuerFingerprint(_, _) => SubpacketType::IssuerFingerprint,
+            SubpacketData::IntendedRecipientFingerprint(_, _) => {
+                SubpacketType::IntendedRecipientFingerprint
+            }
+            SubpacketData::KeyBlock(_) => SubpacketType::KeyBlock,
             SubpacketData::PreferredAeadAlgorithms(_) => SubpacketType::PreferredAead,
+            SubpacketData::PreferredAeadCiphersuites(_) => SubpacketType::PreferredAeadCiphersuites,
             SubpacketData::Experimental(n, _) => SubpacketType::Experimental(*n),
             SubpacketData::Other(n, _) => SubpacketType::Other(*n),
             SubpacketData::SignatureTarget(_, _, _) => SubpacketType::SignatureTarget,
@@ -220,7 +271,7 @@ impl Subpacket {
 impl Serialize for Subpacket {
     fn to_writer<W: io::Write>(&self, writer: &mut W) -> Result<()> {
         write_packet_length(1 + self.body_len()?, writer)?;
-        writer.write_all(&[self.typ().as_u8(self.is_critical)])?;
+        writer.write_all(&[self.raw_type()])?;
         self.body_to_writer(writer)?;
 
         Ok(())
@@ -290,9 +341,57 @@ impl SignatureConfig {
 
         Ok(())
     }
+
+    /// Serializes a v6 signature, whose subpacket areas have four-octet lengths.
+    fn to_writer_v6<W: io::Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_all(&[
+            // type
+            self.typ as u8,
+            // public algorithm
+            u8::from(self.pub_alg),
+            // hash algorithm
+            u8::from(self.hash_alg),
+        ])?;
+
+        // hashed subpackets
+        let mut hashed_subpackets = Vec::new();
+        for packet in &self.hashed_subpackets {
+            packet.to_writer(&mut hashed_subpackets)?;
+        }
+
+        writer.write_u32::<BigEndian>(hashed_subpackets.len().try_into()?)?;
+        writer.write_all(&hashed_subpackets)?;
+
+        // unhashed subpackets
+        let mut unhashed_subpackets = Vec::new();
+        for packet in &self.unhashed_subpackets {
+            packet.to_writer(&mut unhashed_subpackets)?;
+        }
+
+        writer.write_u32::<BigEndian>(unhashed_subpackets.len().try_into()?)?;
+        writer.write_all(&unhashed_subpackets)?;
+
+        Ok(())
+    }
 }
 
 impl Signature {
+    /// Writes the algorithm specific signature values.
+    fn signature_values_to_writer<W: io::Write>(&self, writer: &mut W) -> Result<()> {
+        for val in &self.signature {
+            debug!("writing signature: {}", hex::encode(val));
+            match self.config.pub_alg {
+                // native signatures are not encoded as MPIs
+                PublicKeyAlgorithm::Ed25519 | PublicKeyAlgorithm::Ed448 => {
+                    writer.write_all(val.as_bytes())?
+                }
+                _ => val.to_writer(writer)?,
+            }
+        }
+
+        Ok(())
+    }
+
     /// Serializes a v2 or v3 signature.
     fn to_writer_v3<W: io::Write>(&self, writer: &mut W) -> Result<()> {
         self.config.to_writer_v3(writer)?;
@@ -301,12 +400,7 @@ impl Signature {
         writer.write_all(&self.signed_hash_value)?;
 
         // the actual signature
-        for val in &self.signature {
-            debug!("writing: {}", hex::encode(val));
-            val.to_writer(writer)?;
-        }
-
-        Ok(())
+        self.signature_values_to_writer(writer)
     }
 
     /// Serializes a v4 or v5 signature.
@@ -317,12 +411,27 @@ impl Signature {
         writer.write_all(&self.signed_hash_value)?;
 
         // the actual signature
-        for val in &self.signature {
-            debug!("writing signature: {}", hex::encode(val));
-            val.to_writer(writer)?;
-        }
+        self.signature_values_to_writer(writer)
+    }
 
-        Ok(())
+    /// Serializes a v6 signature.
+    fn to_writer_v6<W: io::Write>(&self, writer: &mut W) -> Result<()> {
+        self.config.to_writer_v6(writer)?;
+
+        // signed hash value
+        writer.write_all(&self.signed_hash_value)?;
+
+        // salt, prefixed with its length
+        let salt = self
+            .config
+            .salt
+            .as_ref()
+            .ok_or_else(|| format_err!("missing salt for v6 signature"))?;
+        writer.write_all(&[salt.len().try_into()?])?;
+        writer.write_all(salt)?;
+
+        // the actual signature
+        self.signature_values_to_writer(writer)
     }
 }
 
@@ -381,6 +490,42 @@ mod tests {
         test_roundtrip("sig-2-keys-2.sig");
     }
 
+    #[test]
+    fn test_subpacket_time_overflow() {
+        use chrono::TimeZone;
+
+        let in_2200 = Utc.with_ymd_and_hms(2200, 1, 1, 0, 0, 0).unwrap();
+        let err = Subpacket::regular(SubpacketData::SignatureCreationTime(in_2200))
+            .to_bytes()
+            .unwrap_err();
+        assert!(err.to_string().contains("32 bit"), "{err}");
+
+        let before_epoch = Utc.with_ymd_and_hms(1960, 1, 1, 0, 0, 0).unwrap();
+        assert!(
+            Subpacket::regular(SubpacketData::SignatureCreationTime(before_epoch))
+                .to_bytes()
+                .is_err()
+        );
+
+        let too_long = Duration::days(200 * 365);
+        assert!(
+            Subpacket::regular(SubpacketData::KeyExpirationTime(too_long))
+                .to_bytes()
+                .is_err()
+        );
+        assert!(
+            Subpacket::regular(SubpacketData::SignatureExpirationTime(too_long))
+                .to_bytes()
+                .is_err()
+        );
+
+        let in_2100 = Utc.with_ymd_and_hms(2100, 1, 1, 0, 0, 0).unwrap();
+        let bytes = Subpacket::regular(SubpacketData::SignatureCreationTime(in_2100))
+            .to_bytes()
+            .unwrap();
+        assert_eq!(bytes[2..], (in_2100.timestamp() as u32).to_be_bytes());
+    }
+
     // Tries to roundtrip a signature containing a name + E-Mail with complicated multibyte unicode characters
     #[test]
     fn packet_signature_roundtrip_openpgp_with_unicode() {
@@ -1,5 +1,5 @@
 use std::fmt;
-use std::io::Read;
+use std::io::{BufReader, Read};
 
 use bstr::{BStr, BString};
 use byteorder::{BigEndian, ByteOrder};
@@ -8,9 +8,11 @@ use iter_read::IterRead;
 use num_enum::{FromPrimitive, IntoPrimitive, TryFromPrimitive};
 
 use crate::crypto::aead::AeadAlgorithm;
+use crate::crypto::ecc_curve::ECCCurve;
 use crate::crypto::hash::HashAlgorithm;
 use crate::crypto::public_key::PublicKeyAlgorithm;
 use crate::crypto::sym::SymmetricKeyAlgorithm;
+use crate::crypto::{ed25519, eddsa};
 use crate::errors::Result;
 use crate::line_writer::LineBreak;
 use crate::normalize_lines::Normalized;
@@ -18,10 +20,14 @@ use crate::packet::signature::SignatureConfig;
 use crate::packet::PacketTrait;
 use crate::ser::Serialize;
 use crate::types::{
-    self, CompressionAlgorithm, KeyId, KeyVersion, Mpi, PublicKeyTrait, Tag, Version,
+    self, CompressionAlgorithm, KeyId, KeyVersion, Mpi, PublicKeyTrait, PublicParams, Tag, Version,
 };
 use smallvec::SmallVec;
 
+/// The bit in the first octet of the Key Server Preferences subpacket, that asks keyservers
+/// to only accept modifications of the key from its owner.
+const KEY_SERVER_NO_MODIFY: u8 = 0x80;
+
 /// Signature Packet
 /// https://tools.ietf.org/html/rfc4880.html#section-5.2
 #[derive(Clone, PartialEq, Eq)]
@@ -90,7 +96,7 @@ impl Signature {
     ///
     /// We also consider `key` a match for `sig` by default, if `sig` contains no issuer-related
     /// subpackets.
-    fn match_identity(sig: &Signature, key: &impl PublicKeyTrait) -> bool {
+    pub(crate) fn match_identity(sig: &Signature, key: &impl PublicKeyTrait) -> bool {
         let issuers = sig.issuer();
         let issuer_fps = sig.issuer_fingerprint();
 
@@ -116,27 +122,83 @@ impl Signature {
             &key.key_id(),
         );
 
-        let mut hasher = self.config.hash_alg.new_hasher()?;
+        let hash = self.data_hash(data)?;
+        key.verify_signature(self.config.hash_alg, &hash, &self.signature)
+    }
+
+    /// Verifies many signatures over their respective data and keys at once.
+    ///
+    /// Ed25519 signatures, made with legacy EdDSA or native Ed25519 keys, are checked together
+    /// in a single batch, which is much faster than verifying them one by one. Signatures made
+    /// with other algorithms are verified sequentially. Fails if any of the signatures is
+    /// invalid.
+    pub fn verify_batch<K>(items: &[(&Signature, &[u8], &K)]) -> Result<()>
+    where
+        K: PublicKeyTrait + AsRef<PublicParams>,
+    {
+        let mut batch = Vec::new();
+        for (sig, data, key) in items {
+            ensure!(
+                Self::match_identity(sig, *key),
+                "verify: No matching issuer or issuer_fingerprint for Key ID: {:?}",
+                &key.key_id(),
+            );
+
+            let hash = sig.data_hash(*data)?;
+            match (*key).as_ref() {
+                PublicParams::EdDSA {
+                    curve: ECCCurve::Ed25519,
+                    q,
+                } => {
+                    let (public, sig) = eddsa::ed25519_parts(q.as_bytes(), &sig.signature)?;
+                    batch.push((public, hash, sig));
+                }
+                PublicParams::Ed25519 { public } => {
+                    batch.push((*public, hash, ed25519::signature_bytes(&sig.signature)?));
+                }
+                _ => key.verify_signature(sig.config.hash_alg, &hash, &sig.signature)?,
+            }
+        }
+
+        let batch: Vec<_> = batch
+            .iter()
+            .map(|(public, hash, sig)| (*public, &hash[..], *sig))
+            .collect();
+        ed25519::verify_batch(&batch)
+    }
+
+    /// Calculates the hash over `data` that this signature signs, and checks it against the
+    /// signed hash value.
+    fn data_hash<R: Read>(&self, data: R) -> Result<Vec<u8>> {
+        let mut hasher = self.config.new_hasher()?;
 
         if matches!(self.typ(), SignatureType::Text) {
-            let normalized = Normalized::new(data.bytes().flat_map(|b| b.ok()), LineBreak::Crlf);
+            // stop at the first read error, and report it once the data is hashed
+            let mut read_error = None;
+            let bytes = BufReader::new(data)
+                .bytes()
+                .map_while(|b| b.map_err(|err| read_error = Some(err)).ok());
+            let normalized = Normalized::new(bytes, LineBreak::Crlf);
 
             self.config
                 .hash_data_to_sign(&mut *hasher, IterRead::new(normalized))?;
+            if let Some(err) = read_error {
+                return Err(err.into());
+            }
         } else {
             self.config.hash_data_to_sign(&mut *hasher, data)?;
         }
         let len = self.config.hash_signature_data(&mut *hasher)?;
         hasher.update(&self.config.trailer(len)?);
 
-        let hash = &hasher.finish()[..];
+        let hash = hasher.finish();
         ensure_eq!(
             &self.signed_hash_value,
             &hash[0..2],
             "signature: invalid signed hash value"
         );
 
-        key.verify_signature(self.config.hash_alg, hash, &self.signature)
+        Ok(hash)
     }
 
     /// Verifies a certification signature type.
@@ -146,22 +208,35 @@ impl Signature {
         tag: Tag,
         id: &impl Serialize,
     ) -> Result<()> {
-        let key_id = key.key_id();
+        self.verify_third_party_certification(key, key, tag, id)
+    }
+
+    /// Verifies a certification made by `signer` over the identity `id` of `signee`.
+    ///
+    /// For self-signatures `signer` and `signee` are the same key, see [`Self::verify_certification`].
+    pub fn verify_third_party_certification(
+        &self,
+        signer: &impl PublicKeyTrait,
+        signee: &impl PublicKeyTrait,
+        tag: Tag,
+        id: &impl Serialize,
+    ) -> Result<()> {
+        let key_id = signer.key_id();
         debug!("verifying certification {:?} {:#?}", key_id, self);
 
         ensure!(
-            Self::match_identity(self, key),
+            Self::match_identity(self, signer),
             "verify_certification: No matching issuer or issuer_fingerprint for Key ID: {:?}",
             key_id,
         );
 
-        let mut hasher = self.config.hash_alg.new_hasher()?;
+        let mut hasher = self.config.new_hasher()?;
 
         // the key
         {
             let mut key_buf = Vec::new();
             // TODO: this is different for V5
-            key.to_writer_old(&mut key_buf)?;
+            signee.to_writer_old(&mut key_buf)?;
             hasher.update(&key_buf);
         }
 
@@ -174,7 +249,7 @@ impl Signature {
                 SignatureVersion::V2 | SignatureVersion::V3 => {
                     // Nothing to do
                 }
-                SignatureVersion::V4 | SignatureVersion::V5 => {
+                SignatureVersion::V4 | SignatureVersion::V5 | SignatureVersion::V6 => {
                     let prefix = match tag {
                         Tag::UserId => 0xB4,
                         Tag::UserAttribute => 0xD1,
@@ -205,7 +280,7 @@ impl Signature {
             "certification: invalid signed hash value"
         );
 
-        key.verify_signature(self.config.hash_alg, hash, &self.signature)
+        signer.verify_signature(self.config.hash_alg, hash, &self.signature)
     }
 
     /// Verifies a key binding (which binds a subkey to the primary key).
@@ -245,7 +320,7 @@ impl Signature {
             self, signer, signee, backsig
         );
 
-        let mut hasher = self.config.hash_alg.new_hasher()?;
+        let mut hasher = self.config.new_hasher()?;
 
         // Hash the two keys:
         // - for a regular binding signature, first the signer (primary), then the signee (subkey)
@@ -297,7 +372,7 @@ impl Signature {
             &key.key_id(),
         );
 
-        let mut hasher = self.config.hash_alg.new_hasher()?;
+        let mut hasher = self.config.new_hasher()?;
 
         {
             let mut key_buf = Vec::new();
@@ -342,6 +417,33 @@ impl Signature {
         self.config.created()
     }
 
+    /// Removes all unhashed subpackets, except for the issuer key ID and embedded signatures.
+    ///
+    /// The unhashed area is not covered by the signature, so this does not affect its
+    /// validity. Embedded back signatures are kept, as some implementations store them here.
+    pub fn strip_unhashed_subpackets(&mut self) {
+        self.config.unhashed_subpackets.retain(|p| {
+            matches!(
+                p.data,
+                SubpacketData::Issuer(_) | SubpacketData::EmbeddedSignature(_)
+            )
+        });
+    }
+
+    /// Returns all subpackets of this signature, together with the area they are stored in.
+    ///
+    /// Only subpackets in the [`SubpacketArea::Hashed`] area are covered by the signature.
+    pub fn subpackets(&self) -> impl Iterator<Item = (SubpacketArea, &Subpacket)> {
+        self.config
+            .hashed_subpackets()
+            .map(|p| (SubpacketArea::Hashed, p))
+            .chain(
+                self.config
+                    .unhashed_subpackets()
+                    .map(|p| (SubpacketArea::Unhashed, p)),
+            )
+    }
+
     pub fn issuer(&self) -> Vec<&KeyId> {
         self.config.issuer()
     }
@@ -350,6 +452,20 @@ impl Signature {
         self.config.issuer_fingerprint()
     }
 
+    /// Returns the fingerprints of the intended recipients of the message this signature
+    /// was made over, see [`SubpacketData::IntendedRecipientFingerprint`].
+    ///
+    /// Only the hashed area is considered, as the unhashed area could be altered by anyone.
+    pub fn intended_recipients(&self) -> Vec<&[u8]> {
+        self.config
+            .hashed_subpackets()
+            .filter_map(|p| match &p.data {
+                SubpacketData::IntendedRecipientFingerprint(_, fp) => Some(fp.as_slice()),
+                _ => None,
+            })
+            .collect()
+    }
+
     pub fn preferred_symmetric_algs(&self) -> &[SymmetricKeyAlgorithm] {
         self.config
             .hashed_subpackets()
@@ -380,6 +496,52 @@ impl Signature {
             .unwrap_or_else(|| &[][..])
     }
 
+    /// Preferred AEAD ciphersuites, from the RFC 9580 subpacket (type 39).
+    ///
+    /// This is the subpacket emitted when generating keys with AEAD preferences.
+    pub fn preferred_aead_ciphersuites(&self) -> &[(SymmetricKeyAlgorithm, AeadAlgorithm)] {
+        self.config
+            .hashed_subpackets()
+            .find_map(|p| match &p.data {
+                SubpacketData::PreferredAeadCiphersuites(d) => Some(&d[..]),
+                _ => None,
+            })
+            .unwrap_or_else(|| &[][..])
+    }
+
+    /// Preferred AEAD algorithms, from the subpacket (type 34) of the earlier
+    /// draft-ietf-openpgp-rfc4880bis drafts. It is only parsed, never emitted.
+    pub fn preferred_aead_algs(&self) -> &[AeadAlgorithm] {
+        self.config
+            .hashed_subpackets()
+            .find_map(|p| match &p.data {
+                SubpacketData::PreferredAeadAlgorithms(d) => Some(&d[..]),
+                _ => None,
+            })
+            .unwrap_or_else(|| &[][..])
+    }
+
+    /// Preferred AEAD ciphersuites, from either encoding.
+    ///
+    /// Uses the RFC 9580 ciphersuites if present. Otherwise the draft AEAD algorithm
+    /// preferences are combined with the preferred symmetric algorithms, in order of the
+    /// symmetric algorithms.
+    pub fn effective_aead_ciphersuites(&self) -> Vec<(SymmetricKeyAlgorithm, AeadAlgorithm)> {
+        let ciphersuites = self.preferred_aead_ciphersuites();
+        if !ciphersuites.is_empty() {
+            return ciphersuites.to_vec();
+        }
+
+        self.preferred_symmetric_algs()
+            .iter()
+            .flat_map(|&sym| {
+                self.preferred_aead_algs()
+                    .iter()
+                    .map(move |&aead| (sym, aead))
+            })
+            .collect()
+    }
+
     pub fn key_server_prefs(&self) -> &[u8] {
         self.config
             .hashed_subpackets()
@@ -390,6 +552,14 @@ impl Signature {
             .unwrap_or_else(|| &[][..])
     }
 
+    /// Returns true if the key owner asks keyservers to only accept modifications of the key,
+    /// like new certifications, from themselves (the "No-modify" key server preference).
+    pub fn key_server_no_modify(&self) -> bool {
+        self.key_server_prefs()
+            .first()
+            .is_some_and(|prefs| prefs & KEY_SERVER_NO_MODIFY != 0)
+    }
+
     pub fn key_flags(&self) -> KeyFlags {
         self.config
             .hashed_subpackets()
@@ -458,6 +628,15 @@ impl Signature {
             })
     }
 
+    /// The signer's key from the key block subpacket in the hashed area, see
+    /// [`SubpacketData::KeyBlock`].
+    pub fn key_block(&self) -> Option<&[u8]> {
+        self.config.hashed_subpackets().find_map(|p| match &p.data {
+            SubpacketData::KeyBlock(d) => Some(&d[..]),
+            _ => None,
+        })
+    }
+
     pub fn preferred_key_server(&self) -> Option<&str> {
         self.config.hashed_subpackets().find_map(|p| match &p.data {
             SubpacketData::PreferredKeyServer(d) => Some(d.as_str()),
@@ -515,6 +694,34 @@ impl Signature {
         })
     }
 
+    /// Returns `true` if the trust expressed by this signature applies to `user_id`.
+    ///
+    /// Without Regular Expression subpackets the trust applies to all User IDs, otherwise
+    /// `user_id` has to match at least one of them. Malformed expressions match nothing.
+    ///
+    /// Requires the `regex` feature.
+    #[cfg(feature = "regex")]
+    pub fn trust_applies_to(&self, user_id: &BStr) -> bool {
+        let mut regexes = self
+            .config
+            .hashed_subpackets()
+            .filter_map(|p| match &p.data {
+                SubpacketData::RegularExpression(d) => Some(d),
+                _ => None,
+            })
+            .peekable();
+
+        if regexes.peek().is_none() {
+            return true;
+        }
+
+        regexes.any(|re| {
+            translate_regex(re)
+                .and_then(|re| regex::bytes::Regex::new(&re).ok())
+                .is_some_and(|re| re.is_match(user_id))
+        })
+    }
+
     pub fn exportable_certification(&self) -> bool {
         self.config
             .hashed_subpackets()
@@ -534,6 +741,7 @@ pub enum SignatureVersion {
     V3 = 3,
     V4 = 4,
     V5 = 5,
+    V6 = 6,
 
     #[num_enum(catch_all)]
     Other(u8),
@@ -670,6 +878,9 @@ pub enum SubpacketType {
     EmbeddedSignature,
     IssuerFingerprint,
     PreferredAead,
+    IntendedRecipientFingerprint,
+    KeyBlock,
+    PreferredAeadCiphersuites,
     Experimental(u8),
     Other(u8),
 }
@@ -702,6 +913,9 @@ impl SubpacketType {
             SubpacketType::EmbeddedSignature => 32,
             SubpacketType::IssuerFingerprint => 33,
             SubpacketType::PreferredAead => 34,
+            SubpacketType::IntendedRecipientFingerprint => 35,
+            SubpacketType::KeyBlock => 38,
+            SubpacketType::PreferredAeadCiphersuites => 39,
             SubpacketType::Experimental(n) => *n,
             SubpacketType::Other(n) => *n,
         };
@@ -746,6 +960,9 @@ impl SubpacketType {
             32 => SubpacketType::EmbeddedSignature,
             33 => SubpacketType::IssuerFingerprint,
             34 => SubpacketType::PreferredAead,
+            35 => SubpacketType::IntendedRecipientFingerprint,
+            38 => SubpacketType::KeyBlock,
+            39 => SubpacketType::PreferredAeadCiphersuites,
             100..=110 => SubpacketType::Experimental(n),
             _ => SubpacketType::Other(n),
         };
@@ -754,6 +971,15 @@ impl SubpacketType {
     }
 }
 
+/// The area of a signature a subpacket is stored in.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SubpacketArea {
+    /// Covered by the signature.
+    Hashed,
+    /// Not covered by the signature, and can be modified by anyone.
+    Unhashed,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Subpacket {
     pub is_critical: bool,
@@ -811,15 +1037,25 @@ pub enum SubpacketData {
     RegularExpression(BString),
     ExportableCertification(bool),
     IssuerFingerprint(KeyVersion, SmallVec<[u8; 20]>),
+    /// List of AEAD algorithms the key holder prefers to use, as defined by the earlier
+    /// draft-ietf-openpgp-rfc4880bis drafts. Superseded by `PreferredAeadCiphersuites`.
     PreferredAeadAlgorithms(SmallVec<[AeadAlgorithm; 2]>),
+    /// The fingerprint of a primary key the signed message was encrypted to.
+    IntendedRecipientFingerprint(KeyVersion, SmallVec<[u8; 20]>),
+    /// The binary encoded transferable public key of the signer, as defined by the
+    /// draft-ietf-openpgp-rfc4880bis drafts. The signature doesn't establish its authenticity.
+    KeyBlock(Vec<u8>),
+    /// List of symmetric and AEAD algorithm pairs that indicate which ciphersuites the key holder prefers to use.
+    PreferredAeadCiphersuites(SmallVec<[(SymmetricKeyAlgorithm, AeadAlgorithm); 4]>),
     Experimental(u8, SmallVec<[u8; 2]>),
     Other(u8, Vec<u8>),
     SignatureTarget(PublicKeyAlgorithm, HashAlgorithm, Vec<u8>),
 }
 
 bitfield! {
+    /// The first two octets of the key flags, bit `n` is bit `n % 8` of octet `n / 8`.
     #[derive(Default, PartialEq, Eq, Copy, Clone)]
-    pub struct KeyFlags(u8);
+    pub struct KeyFlags(u16);
     impl Debug;
 
     pub certify, set_certify: 0;
@@ -829,21 +1065,43 @@ bitfield! {
     pub shared, set_shared: 4;
     pub authentication, set_authentication: 5;
     pub group, set_group: 7;
+    /// Additional decryption subkey (ADSK), flag 0x0400, to which messages should be encrypted
+    /// in addition to the regular encryption subkeys, for example for key escrow.
+    pub adsk, set_adsk: 10;
 }
 
 impl<'a> From<&'a [u8]> for KeyFlags {
     fn from(other: &'a [u8]) -> Self {
-        if other.is_empty() {
-            Default::default()
-        } else {
-            KeyFlags(other[0])
+        match other {
+            [] => Default::default(),
+            [first] => KeyFlags(u16::from(*first)),
+            [first, second, ..] => KeyFlags(u16::from_le_bytes([*first, *second])),
         }
     }
 }
 
+impl std::ops::BitOr for KeyFlags {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        KeyFlags(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitOrAssign for KeyFlags {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
 impl From<KeyFlags> for SmallVec<[u8; 1]> {
     fn from(flags: KeyFlags) -> Self {
-        smallvec![flags.0]
+        let [first, second] = flags.0.to_le_bytes();
+        if second == 0 {
+            smallvec![first]
+        } else {
+            smallvec![first, second]
+        }
     }
 }
 
@@ -887,6 +1145,54 @@ pub enum RevocationCode {
     Other(u8),
 }
 
+/// Translates a regular expression in the OpenPGP dialect (RFC 4880, section 8) into the
+/// syntax of the `regex` crate, or `None` if it is malformed.
+#[cfg(feature = "regex")]
+fn translate_regex(re: &[u8]) -> Option<String> {
+    // the expression is null terminated on the wire
+    let re = re.strip_suffix(&[0]).unwrap_or(re);
+    let re = std::str::from_utf8(re).ok()?;
+
+    let mut out = String::with_capacity(re.len());
+    let mut chars = re.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => out.push_str(&regex::escape(&chars.next()?.to_string())),
+            '[' => {
+                out.push('[');
+                let mut rest = chars.clone();
+                if rest.next() == Some('^') {
+                    chars = rest;
+                    out.push('^');
+                }
+
+                // a leading `]` is a literal, there are no escapes inside of brackets
+                let mut first = true;
+                loop {
+                    match chars.next()? {
+                        ']' if !first => break,
+                        '-' => out.push('-'),
+                        c if "\\[]&~^".contains(c) => {
+                            out.push('\\');
+                            out.push(c);
+                        }
+                        c => out.push(c),
+                    }
+                    first = false;
+                }
+                out.push(']');
+            }
+            '{' | '}' => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+
+    Some(out)
+}
+
 impl fmt::Debug for Signature {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Signature")
@@ -916,6 +1222,8 @@ impl PacketTrait for Signature {
 
 #[cfg(test)]
 mod tests {
+    #![allow(clippy::unwrap_used)]
+
     use super::*;
 
     #[test]
@@ -951,6 +1259,16 @@ mod tests {
         let mut flags = KeyFlags::default();
         flags.set_group(true);
         assert_eq!(flags.0, 0x80);
+
+        let mut flags = KeyFlags::default();
+        flags.set_adsk(true);
+        assert_eq!(flags.0, 0x0400);
+        let bytes: SmallVec<[u8; 1]> = flags.into();
+        assert_eq!(&bytes[..], &[0x00, 0x04]);
+        assert_eq!(KeyFlags::from(&bytes[..]), flags);
+
+        let bytes: SmallVec<[u8; 1]> = KeyFlags::from(&[0x0c, 0x00][..]).into();
+        assert_eq!(&bytes[..], &[0x0c]);
     }
 
     #[test]
@@ -983,6 +1301,9 @@ mod tests {
             EmbeddedSignature,
             IssuerFingerprint,
             PreferredAead,
+            IntendedRecipientFingerprint,
+            KeyBlock,
+            PreferredAeadCiphersuites,
             Experimental(101),
             Other(95),
         ];
@@ -991,4 +1312,275 @@ mod tests {
             assert_eq!(SubpacketType::from_u8(case.as_u8(true)), (case, true));
         }
     }
+
+    #[test]
+    fn test_effective_aead_ciphersuites() {
+        let sig = |hashed_subpackets| {
+            let config = SignatureConfig::new_v4(
+                SignatureVersion::V4,
+                SignatureType::CertPositive,
+                PublicKeyAlgorithm::EdDSA,
+                HashAlgorithm::SHA2_256,
+                hashed_subpackets,
+                vec![],
+            );
+            Signature::from_config(config, [0, 0], vec![])
+        };
+        let sym_prefs = Subpacket::regular(SubpacketData::PreferredSymmetricAlgorithms(
+            SmallVec::from_slice(&[SymmetricKeyAlgorithm::AES256, SymmetricKeyAlgorithm::AES128]),
+        ));
+        let draft_prefs = Subpacket::regular(SubpacketData::PreferredAeadAlgorithms(
+            SmallVec::from_slice(&[AeadAlgorithm::Ocb, AeadAlgorithm::Eax]),
+        ));
+        let rfc_prefs = Subpacket::regular(SubpacketData::PreferredAeadCiphersuites(
+            SmallVec::from_slice(&[(SymmetricKeyAlgorithm::AES128, AeadAlgorithm::Gcm)]),
+        ));
+
+        let draft = sig(vec![sym_prefs.clone(), draft_prefs.clone()]);
+        assert_eq!(
+            draft.preferred_aead_algs(),
+            &[AeadAlgorithm::Ocb, AeadAlgorithm::Eax]
+        );
+        assert!(draft.preferred_aead_ciphersuites().is_empty());
+        assert_eq!(
+            draft.effective_aead_ciphersuites(),
+            vec![
+                (SymmetricKeyAlgorithm::AES256, AeadAlgorithm::Ocb),
+                (SymmetricKeyAlgorithm::AES256, AeadAlgorithm::Eax),
+                (SymmetricKeyAlgorithm::AES128, AeadAlgorithm::Ocb),
+                (SymmetricKeyAlgorithm::AES128, AeadAlgorithm::Eax),
+            ]
+        );
+
+        let both = sig(vec![sym_prefs, draft_prefs, rfc_prefs]);
+        assert_eq!(
+            both.effective_aead_ciphersuites(),
+            vec![(SymmetricKeyAlgorithm::AES128, AeadAlgorithm::Gcm)]
+        );
+
+        assert!(sig(vec![]).effective_aead_ciphersuites().is_empty());
+    }
+
+    #[test]
+    fn test_subpackets() {
+        let key_id = KeyId::from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
+        let config = SignatureConfig::new_v4(
+            SignatureVersion::V4,
+            SignatureType::Binary,
+            PublicKeyAlgorithm::EdDSA,
+            HashAlgorithm::SHA2_256,
+            vec![Subpacket::critical(SubpacketData::SignatureCreationTime(
+                DateTime::from_timestamp(0x01020304, 0).unwrap(),
+            ))],
+            vec![Subpacket::regular(SubpacketData::Issuer(key_id.clone()))],
+        );
+        let sig = Signature::from_config(config, [0, 0], vec![]);
+
+        let subpackets = sig
+            .subpackets()
+            .map(|(area, p)| (area, p.raw_type(), p.body_to_vec().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            subpackets,
+            vec![
+                (SubpacketArea::Hashed, 0x82, vec![1, 2, 3, 4]),
+                (SubpacketArea::Unhashed, 16, key_id.to_vec()),
+            ]
+        );
+
+        // the issuer is only stored in the unprotected area
+        assert!(!sig.subpackets().any(|(area, p)| {
+            area == SubpacketArea::Hashed && p.typ() == SubpacketType::Issuer
+        }));
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn test_trust_applies_to() {
+        let sig = |regexes: &[&str]| {
+            let config = SignatureConfig::new_v4(
+                SignatureVersion::V4,
+                SignatureType::CertGeneric,
+                PublicKeyAlgorithm::EdDSA,
+                HashAlgorithm::SHA2_256,
+                regexes
+                    .iter()
+                    .map(|re| {
+                        let mut re = BString::from(*re);
+                        re.push(0);
+                        Subpacket::regular(SubpacketData::RegularExpression(re))
+                    })
+                    .collect(),
+                vec![],
+            );
+            Signature::from_config(config, [0, 0], vec![])
+        };
+
+        let alice = BStr::new("Alice <alice@example.com>");
+        let bob = BStr::new("Bob <bob@example.org>");
+
+        assert!(sig(&[]).trust_applies_to(alice));
+
+        let scoped = sig(&["<[^>]+[@.]example\\.com>$"]);
+        assert!(scoped.trust_applies_to(alice));
+        assert!(!scoped.trust_applies_to(bob));
+
+        // any of several expressions may match
+        let either = sig(&["example\\.com>$", "example\\.org>$"]);
+        assert!(either.trust_applies_to(alice));
+        assert!(either.trust_applies_to(bob));
+
+        // braces and brackets with special characters are literals in the OpenPGP dialect
+        assert!(sig(&["^a{2}$"]).trust_applies_to(BStr::new("a{2}")));
+        assert!(sig(&["^[]&~]+$"]).trust_applies_to(BStr::new("]&~")));
+
+        // malformed expressions match nothing
+        assert!(!sig(&["(unclosed"]).trust_applies_to(alice));
+        assert!(!sig(&["[unclosed"]).trust_applies_to(alice));
+        assert!(!sig(&["trailing\\"]).trust_applies_to(alice));
+    }
+
+    #[test]
+    fn test_verify_batch() {
+        use crate::composed::{
+            Deserializable, KeyType, SecretKeyParamsBuilder, SignedPublicKey, SignedSecretKey,
+        };
+        use crate::types::KeyTrait;
+
+        type BatchItem<'a> = (&'a Signature, &'a [u8], &'a SignedPublicKey);
+
+        let sign = |key: &SignedSecretKey, pw: &str, data: &[u8]| {
+            let pw = pw.to_string();
+            SignatureConfig::new_v4(
+                SignatureVersion::V4,
+                SignatureType::Binary,
+                key.algorithm(),
+                HashAlgorithm::SHA2_256,
+                vec![Subpacket::regular(SubpacketData::IssuerFingerprint(
+                    KeyVersion::V4,
+                    key.fingerprint().into(),
+                ))],
+                vec![],
+            )
+            .sign(key, || pw, data)
+            .unwrap()
+        };
+
+        // legacy EdDSA and native Ed25519 keys, which are verified together
+        let mut items = Vec::new();
+        for i in 0..4 {
+            let key_type = if i % 2 == 0 {
+                KeyType::EdDSA
+            } else {
+                KeyType::Ed25519
+            };
+            let key = SecretKeyParamsBuilder::default()
+                .key_type(key_type)
+                .can_sign(true)
+                .primary_user_id(format!("Me {i} <me@mail.com>"))
+                .build()
+                .unwrap()
+                .generate()
+                .unwrap()
+                .sign(String::new)
+                .unwrap();
+            let data = format!("hello {i}").into_bytes();
+            items.push((sign(&key, "", &data), data, SignedPublicKey::from(key)));
+        }
+
+        // an RSA signature, which is verified on its own
+        let (rsa_key, _) = SignedSecretKey::from_armor_single(
+            std::fs::File::open("./tests/key-with-password-123.asc").unwrap(),
+        )
+        .unwrap();
+        let data = b"hello rsa".to_vec();
+        items.push((
+            sign(&rsa_key, "123", &data),
+            data,
+            SignedPublicKey::from(rsa_key),
+        ));
+
+        fn refs(items: &[(Signature, Vec<u8>, SignedPublicKey)]) -> Vec<BatchItem<'_>> {
+            items
+                .iter()
+                .map(|(sig, data, key)| (sig, &data[..], key))
+                .collect()
+        }
+        Signature::verify_batch(&refs(&items)).unwrap();
+        Signature::verify_batch::<SignedPublicKey>(&[]).unwrap();
+
+        // a single tampered ed25519 signature fails the whole batch
+        for i in [2, 3] {
+            let mut tampered = items.clone();
+            let last = tampered[i].0.signature.len() - 1;
+            let mut s = tampered[i].0.signature[last].as_bytes().to_vec();
+            s[0] ^= 1;
+            tampered[i].0.signature[last] = Mpi::from_raw(s);
+            assert!(Signature::verify_batch(&refs(&tampered)).is_err());
+        }
+
+        // as does a signature over the wrong data
+        let mut tampered = items.clone();
+        tampered[1].1 = tampered[0].1.clone();
+        assert!(Signature::verify_batch(&refs(&tampered)).is_err());
+
+        // or a bad RSA signature
+        let mut tampered = items;
+        tampered[4].1 = b"hello ed25519".to_vec();
+        assert!(Signature::verify_batch(&refs(&tampered)).is_err());
+    }
+
+    #[test]
+    fn test_hash_input_v6() {
+        use crate::composed::{KeyType, SecretKeyParamsBuilder};
+        use crate::types::{KeyTrait, PublicKeyTrait};
+
+        let key = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::Ed25519)
+            .can_sign(true)
+            .version(KeyVersion::V6)
+            .build()
+            .unwrap()
+            .generate()
+            .unwrap()
+            .sign(String::new)
+            .unwrap();
+
+        let data = b"hello world";
+        let mut config = SignatureConfig::new_v4(
+            SignatureVersion::V6,
+            SignatureType::Binary,
+            key.algorithm(),
+            HashAlgorithm::SHA2_256,
+            vec![Subpacket::regular(SubpacketData::IssuerFingerprint(
+                KeyVersion::V6,
+                key.fingerprint().into(),
+            ))],
+            vec![],
+        );
+        assert!(config.hash_input_v6(data).is_err());
+
+        let sig = config.clone().sign(&key, String::new, &data[..]).unwrap();
+        let salt = sig.config.salt.clone().unwrap();
+        assert_eq!(salt.len(), 16);
+
+        let input = sig.config.hash_input_v6(data).unwrap();
+        assert_eq!(&input[..16], &salt[..]);
+        assert_eq!(&input[16..16 + data.len()], data);
+        assert_eq!(&input[16 + data.len()..][..4], &[6, 0, 27, 8]);
+        let trailer = &input[input.len() - 6..];
+        let hashed_len = input.len() - 16 - data.len() - 6;
+        assert_eq!(&trailer[..2], &[6, 0xff]);
+        assert_eq!(&trailer[2..], &(hashed_len as u32).to_be_bytes());
+
+        let hash = HashAlgorithm::SHA2_256.digest(&input).unwrap();
+        assert_eq!(&hash[..2], &sig.signed_hash_value);
+        key.verify_signature(HashAlgorithm::SHA2_256, &hash, &sig.signature)
+            .unwrap();
+        sig.verify(&key, &data[..]).unwrap();
+
+        config.version = SignatureVersion::V4;
+        config.salt = Some(salt);
+        assert!(config.hash_input_v6(data).is_err());
+    }
 }
@@ -1,5 +1,8 @@
 use std::{fmt, io};
 
+use rand::{CryptoRng, Rng};
+
+use crate::crypto::sym::SymmetricKeyAlgorithm;
 use crate::errors::Result;
 use crate::packet::PacketTrait;
 use crate::ser::Serialize;
@@ -22,6 +25,25 @@ impl SymEncryptedData {
         })
     }
 
+    /// Encrypts the data using the given symmetric key, without integrity protection.
+    ///
+    /// The resulting packet is malleable, an attacker can modify the plaintext without the
+    /// recipient noticing. Only use this for recipients that can't handle
+    /// [`SymEncryptedProtectedData`](crate::packet::SymEncryptedProtectedData) packets.
+    pub fn encrypt_with_rng<R: CryptoRng + Rng>(
+        rng: &mut R,
+        alg: SymmetricKeyAlgorithm,
+        key: &[u8],
+        plaintext: &[u8],
+    ) -> Result<Self> {
+        let data = alg.encrypt_with_rng(rng, key, plaintext)?;
+
+        Ok(SymEncryptedData {
+            packet_version: Default::default(),
+            data,
+        })
+    }
+
     pub fn data(&self) -> &[u8] {
         &self.data
     }
@@ -95,6 +95,87 @@ impl SymEncryptedProtectedData {
         Self::encrypt_with_rng(&mut thread_rng(), alg, key, plaintext)
     }
 
+    /// Encrypts the data using the given symmetric key and AEAD algorithm, into a
+    /// version 2 packet.
+    ///
+    /// `chunk_size` is the encoded chunk size, the actual size is `2^(chunk_size + 6)`.
+    pub fn encrypt_seipdv2_with_rng<R: CryptoRng + Rng>(
+        rng: &mut R,
+        sym_alg: SymmetricKeyAlgorithm,
+        aead: AeadAlgorithm,
+        chunk_size: u8,
+        session_key: &[u8],
+        plaintext: &[u8],
+    ) -> Result<Self> {
+        ensure!(chunk_size <= 16, "chunk size is too large: {}", chunk_size);
+
+        let mut salt = [0u8; 32];
+        rng.fill_bytes(&mut salt);
+
+        let info = v2_info(sym_alg, aead, chunk_size);
+        let (message_key, mut nonce) = v2_message_key(sym_alg, aead, session_key, &salt, &info);
+
+        let expanded_chunk_size = usize::try_from(expand_chunk_size(chunk_size))?;
+        let num_chunks = plaintext.len() / expanded_chunk_size + 1;
+        let mut data = Vec::with_capacity(plaintext.len() + (num_chunks + 1) * aead.tag_size());
+
+        let mut chunk_index: u64 = 0;
+        for chunk in plaintext.chunks(expanded_chunk_size) {
+            let offset = data.len();
+            data.extend_from_slice(chunk);
+            let auth_tag =
+                aead.encrypt_in_place(&sym_alg, &message_key, &nonce, &info, &mut data[offset..])?;
+            data.extend_from_slice(&auth_tag);
+
+            // Update nonce to include the next chunk index
+            chunk_index += 1;
+            let l = nonce.len() - 8;
+            nonce[l..].copy_from_slice(&chunk_index.to_be_bytes());
+        }
+
+        // Associated data is extended with number of plaintext octets.
+        let size = plaintext.len() as u64;
+        let mut final_info = info.to_vec();
+        final_info.extend_from_slice(&size.to_be_bytes());
+
+        // encrypts empty string
+        let final_auth_tag =
+            aead.encrypt_in_place(&sym_alg, &message_key, &nonce, &final_info, &mut [][..])?;
+        data.extend_from_slice(&final_auth_tag);
+
+        Ok(SymEncryptedProtectedData {
+            packet_version: Default::default(),
+            data: Data::V2 {
+                sym_alg,
+                aead,
+                chunk_size,
+                salt,
+                data,
+            },
+        })
+    }
+
+    /// Same as [`encrypt_seipdv2_with_rng`], but uses [`thread_rng`] for RNG.
+    ///
+    /// [`encrypt_seipdv2_with_rng`]: SymEncryptedProtectedData::encrypt_seipdv2_with_rng
+    /// [`thread_rng`]: rand::thread_rng
+    pub fn encrypt_seipdv2(
+        sym_alg: SymmetricKeyAlgorithm,
+        aead: AeadAlgorithm,
+        chunk_size: u8,
+        session_key: &[u8],
+        plaintext: &[u8],
+    ) -> Result<Self> {
+        Self::encrypt_seipdv2_with_rng(
+            &mut thread_rng(),
+            sym_alg,
+            aead,
+            chunk_size,
+            session_key,
+            plaintext,
+        )
+    }
+
     pub fn data(&self) -> &[u8] {
         match &self.data {
             Data::V1 { data } => data,
@@ -130,34 +211,11 @@ impl SymEncryptedProtectedData {
                 salt,
                 data,
             } => {
-                // Initial key material is the session key.
-                let ikm = session_key;
-
-                // Salt is used.
-                let salt = Some(&salt[..]);
-
-                let info = [
-                    Tag::SymEncryptedProtectedData.encode(), // packet type
-                    0x02,                                    // version
-                    (*sym_alg).into(),
-                    (*aead).into(),
-                    *chunk_size,
-                ];
-
+                let info = v2_info(*sym_alg, *aead, *chunk_size);
                 let chunk_size = expand_chunk_size(*chunk_size);
-                let hk = hkdf::Hkdf::<Sha256>::new(salt, ikm);
-                let mut okm = [0u8; 42];
-                hk.expand(&info, &mut okm).expect("42");
-                debug!("info: {} - hkdf: {}", hex::encode(info), hex::encode(okm));
-                let message_key = &okm[..sym_alg.key_size()];
-                let raw_iv_len = aead.nonce_size() - 8;
-                let iv = &okm[sym_alg.key_size()..sym_alg.key_size() + raw_iv_len];
-                let mut nonce = vec![0u8; aead.nonce_size()];
-                nonce[..raw_iv_len].copy_from_slice(iv);
-
-                debug!("message_key: {}", hex::encode(message_key));
-                debug!("iv: {}", hex::encode(iv));
-                debug!("nonce: {}", hex::encode(&nonce));
+                let (message_key, mut nonce) =
+                    v2_message_key(*sym_alg, *aead, session_key, salt, &info);
+                let message_key = &message_key[..];
 
                 let mut data = data.clone();
 
@@ -265,6 +323,43 @@ impl fmt::Debug for SymEncryptedProtectedData {
     }
 }
 
+fn v2_info(sym_alg: SymmetricKeyAlgorithm, aead: AeadAlgorithm, chunk_size: u8) -> [u8; 5] {
+    [
+        Tag::SymEncryptedProtectedData.encode(), // packet type
+        0x02,                                    // version
+        sym_alg.into(),
+        aead.into(),
+        chunk_size,
+    ]
+}
+
+/// Derives the message key and the initial nonce for version 2 packets.
+fn v2_message_key(
+    sym_alg: SymmetricKeyAlgorithm,
+    aead: AeadAlgorithm,
+    session_key: &[u8],
+    salt: &[u8; 32],
+    info: &[u8],
+) -> (Vec<u8>, Vec<u8>) {
+    // Initial key material is the session key, salt is used.
+    let hk = hkdf::Hkdf::<Sha256>::new(Some(&salt[..]), session_key);
+    let mut okm = [0u8; 42];
+    hk.expand(info, &mut okm).expect("42");
+    debug!("info: {} - hkdf: {}", hex::encode(info), hex::encode(okm));
+
+    let message_key = okm[..sym_alg.key_size()].to_vec();
+    let raw_iv_len = aead.nonce_size() - 8;
+    let iv = &okm[sym_alg.key_size()..sym_alg.key_size() + raw_iv_len];
+    let mut nonce = vec![0u8; aead.nonce_size()];
+    nonce[..raw_iv_len].copy_from_slice(iv);
+
+    debug!("message_key: {}", hex::encode(&message_key));
+    debug!("iv: {}", hex::encode(iv));
+    debug!("nonce: {}", hex::encode(&nonce));
+
+    (message_key, nonce)
+}
+
 fn expand_chunk_size(s: u8) -> u32 {
     1u32 << (s as u32 + 6)
 }
@@ -298,3 +393,161 @@ fn parse() -> impl Fn(&[u8]) -> IResult<&[u8], Data> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn roundtrip(aead: AeadAlgorithm) {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+
+        for sym_alg in [SymmetricKeyAlgorithm::AES128, SymmetricKeyAlgorithm::AES256] {
+            for chunk_size in [0u8, 1, 6] {
+                for size in [0usize, 1, 63, 64, 65, 1000, 5000] {
+                    let session_key = sym_alg.new_session_key(&mut rng);
+                    let mut plaintext = vec![0u8; size];
+                    rng.fill(&mut plaintext[..]);
+
+                    let packet = SymEncryptedProtectedData::encrypt_seipdv2_with_rng(
+                        &mut rng,
+                        sym_alg,
+                        aead,
+                        chunk_size,
+                        &session_key,
+                        &plaintext,
+                    )
+                    .unwrap();
+                    assert_eq!(packet.version(), 2);
+
+                    // serialization roundtrip
+                    let bytes = packet.to_bytes().unwrap();
+                    let packet2 =
+                        SymEncryptedProtectedData::from_slice(Version::New, &bytes).unwrap();
+                    assert_eq!(packet, packet2);
+
+                    let decrypted = packet2.decrypt(&session_key, None).unwrap();
+                    assert_eq!(decrypted, plaintext);
+
+                    // tampering is detected
+                    let mut bytes = bytes;
+                    let last = bytes.len() - 1;
+                    bytes[last] ^= 1;
+                    let packet3 =
+                        SymEncryptedProtectedData::from_slice(Version::New, &bytes).unwrap();
+                    assert!(packet3.decrypt(&session_key, None).is_err());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_seipdv2_ocb_roundtrip() {
+        roundtrip(AeadAlgorithm::Ocb);
+    }
+
+    #[test]
+    fn test_seipdv2_eax_roundtrip() {
+        roundtrip(AeadAlgorithm::Eax);
+    }
+
+    /// Returns fixed bytes, to reproduce the random salt of a test vector.
+    struct FixedRng(Vec<u8>);
+
+    impl rand::RngCore for FixedRng {
+        fn next_u32(&mut self) -> u32 {
+            let mut bytes = [0u8; 4];
+            self.fill_bytes(&mut bytes);
+            u32::from_le_bytes(bytes)
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            let mut bytes = [0u8; 8];
+            self.fill_bytes(&mut bytes);
+            u64::from_le_bytes(bytes)
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            dest.copy_from_slice(&self.0.drain(..dest.len()).collect::<Vec<_>>());
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> std::result::Result<(), rand::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    impl CryptoRng for FixedRng {}
+
+    /// Sample AEAD-OCB encryption and decryption, with the password "password".
+    ///
+    /// Ref https://www.rfc-editor.org/rfc/rfc9580.html#name-sample-aead-ocb-encryption-
+    #[test]
+    fn test_seipdv2_ocb_rfc9580() {
+        use crate::composed::{decrypt_session_key_with_password, PlainSessionKey};
+        use crate::packet::SymKeyEncryptedSessionKey;
+
+        let skesk = hex::decode(
+            "061d07020b030856a298d2f5e36453ffcfcc5c11664edb9db42590d7dc46b07241b612c3812c\
+             fffbea00f2347b25641123f887ae60d4fd614e0837d819d36c",
+        )
+        .unwrap();
+        let seipd = hex::decode(
+            "0207020620a661f731fc9a3032b5623326027e3a5d8db5748ebeff0b0c5910d09ecdd641ff9f\
+             d38562758035bc49754ce1bf3fffa7dad0a3b8104f5133cf42a4100a83eef4ca1b4801a8846b\
+             f42bcda7c8ce9d65e212f301cbcd98fdcade694a877ad4247323f6e857",
+        )
+        .unwrap();
+        let session_key = hex::decode("28e79ab82397d3c63de24ac217d7b791").unwrap();
+        // a literal data packet containing "Hello, world!", followed by a padding packet
+        let plaintext = hex::decode(
+            "cb1362000000000048656c6c6f2c20776f726c6421d50eae6aa1649b56aa835b2613902bd2",
+        )
+        .unwrap();
+
+        let skesk = SymKeyEncryptedSessionKey::from_slice(Version::New, &skesk).unwrap();
+        let PlainSessionKey::V6 { key } =
+            decrypt_session_key_with_password(&skesk, || "password".into()).unwrap()
+        else {
+            panic!("unexpected session key");
+        };
+        assert_eq!(key, session_key);
+
+        let packet = SymEncryptedProtectedData::from_slice(Version::New, &seipd).unwrap();
+        assert_eq!(packet.decrypt(&session_key, None).unwrap(), plaintext);
+
+        // encrypting with the same salt reproduces the packet
+        let Data::V2 { salt, .. } = packet.data else {
+            panic!("unexpected version");
+        };
+        let encrypted = SymEncryptedProtectedData::encrypt_seipdv2_with_rng(
+            &mut FixedRng(salt.to_vec()),
+            SymmetricKeyAlgorithm::AES128,
+            AeadAlgorithm::Ocb,
+            6,
+            &session_key,
+            &plaintext,
+        )
+        .unwrap();
+        assert_eq!(encrypted.to_bytes().unwrap(), seipd);
+    }
+
+    #[test]
+    fn test_seipdv2_chunk_size_too_large() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let key = SymmetricKeyAlgorithm::AES128.new_session_key(&mut rng);
+        assert!(SymEncryptedProtectedData::encrypt_seipdv2_with_rng(
+            &mut rng,
+            SymmetricKeyAlgorithm::AES128,
+            AeadAlgorithm::Ocb,
+            17,
+            &key,
+            b"hello",
+        )
+        .is_err());
+    }
+}
@@ -8,6 +8,7 @@ use nom::combinator::{map, map_parser, rest};
 use nom::multi::length_data;
 use nom::number::streaming::{be_u8, le_u16};
 use nom::sequence::pair;
+use num_enum::{FromPrimitive, IntoPrimitive};
 
 use crate::errors::{IResult, Result};
 use crate::packet::{PacketTrait, Signature, SignatureConfigBuilder, SignatureType, Subpacket};
@@ -17,6 +18,17 @@ use crate::util::{packet_length, write_packet_length};
 
 use super::SubpacketData;
 
+/// Encoding of the image in an image attribute.
+/// Ref: https://tools.ietf.org/html/rfc4880.html#section-5.12.1
+#[derive(Debug, PartialEq, Eq, Copy, Clone, FromPrimitive, IntoPrimitive)]
+#[repr(u8)]
+pub enum ImageFormat {
+    Jpeg = 1,
+
+    #[num_enum(catch_all)]
+    Other(u8),
+}
+
 /// User Attribute Packet
 /// https://tools.ietf.org/html/rfc4880.html#section-5.12
 #[derive(Clone, PartialEq, Eq)]
@@ -48,6 +60,18 @@ impl UserAttribute {
         }
     }
 
+    /// Returns the format and the bytes of the image, if this is an image attribute with a
+    /// known header version.
+    pub fn as_image(&self) -> Option<(ImageFormat, &[u8])> {
+        match self {
+            // header version 1: the encoding followed by reserved bytes
+            UserAttribute::Image { header, data, .. } if header.len() >= 2 && header[0] == 1 => {
+                Some((ImageFormat::from(header[1]), data))
+            }
+            _ => None,
+        }
+    }
+
     pub fn packet_len(&self) -> usize {
         match self {
             UserAttribute::Image { ref data, .. } => {
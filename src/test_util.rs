@@ -0,0 +1,43 @@
+//! Helpers shared by the unit tests of several modules.
+
+use std::io;
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::composed::{SecretKeyParamsBuilder, SignedSecretKey};
+
+/// Generates a key from `params` and signs it with `key_pw`.
+///
+/// The rng is seeded with a fixed value, so the key material is the same in every run.
+pub(crate) fn signed_key(params: &SecretKeyParamsBuilder, key_pw: &str) -> SignedSecretKey {
+    let key_pw = key_pw.to_string();
+    params
+        .build()
+        .expect("invalid key parameters")
+        .generate_with_rng(ChaCha8Rng::seed_from_u64(0))
+        .expect("failed to generate secret key")
+        .sign(|| key_pw)
+        .expect("failed to sign key")
+}
+
+/// A reader handing out at most `chunk` bytes per read, to exercise streaming.
+pub(crate) struct ChunkedReader<'a> {
+    data: &'a [u8],
+    chunk: usize,
+}
+
+impl<'a> ChunkedReader<'a> {
+    pub(crate) fn new(data: &'a [u8], chunk: usize) -> Self {
+        Self { data, chunk }
+    }
+}
+
+impl io::Read for ChunkedReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = buf.len().min(self.chunk).min(self.data.len());
+        buf[..n].copy_from_slice(&self.data[..n]);
+        self.data = &self.data[n..];
+        Ok(n)
+    }
+}
@@ -1,5 +1,5 @@
 use crate::crypto::public_key::PublicKeyAlgorithm;
-use crate::types::KeyId;
+use crate::types::{KeyId, KeyVersion};
 
 pub trait KeyTrait: ::std::fmt::Debug {
     fn fingerprint(&self) -> Vec<u8>;
@@ -9,11 +9,17 @@ pub trait KeyTrait: ::std::fmt::Debug {
 
     fn algorithm(&self) -> PublicKeyAlgorithm;
 
+    /// The version of the key packet, which also determines the version of the signatures
+    /// it makes. Defaults to version 4.
+    fn version(&self) -> KeyVersion {
+        KeyVersion::V4
+    }
+
     fn is_signing_key(&self) -> bool {
         use crate::crypto::public_key::PublicKeyAlgorithm::*;
         matches!(
             self.algorithm(),
-            RSA | RSASign | ElgamalSign | DSA | ECDSA | EdDSA
+            RSA | RSASign | ElgamalSign | DSA | ECDSA | EdDSA | Ed25519 | Ed448
         )
     }
 
@@ -22,7 +28,7 @@ pub trait KeyTrait: ::std::fmt::Debug {
 
         matches!(
             self.algorithm(),
-            RSA | RSAEncrypt | ECDH | DiffieHellman | Elgamal
+            RSA | RSAEncrypt | ECDH | DiffieHellman | Elgamal | X25519 | X448
         )
     }
 }
@@ -40,4 +46,8 @@ impl<'a, T: KeyTrait> KeyTrait for &'a T {
     fn algorithm(&self) -> PublicKeyAlgorithm {
         (*self).algorithm()
     }
+
+    fn version(&self) -> KeyVersion {
+        (*self).version()
+    }
 }
@@ -24,6 +24,27 @@ impl KeyId {
     pub fn to_vec(&self) -> Vec<u8> {
         self.0.to_vec()
     }
+
+    /// Returns true if this is the all-zero wildcard key id, used for anonymous recipients.
+    pub fn is_wildcard(&self) -> bool {
+        self.0 == [0u8; 8]
+    }
+
+    /// Returns true if `id` is this key id, or its short form, the last 4 bytes.
+    pub fn matches(&self, id: &[u8]) -> bool {
+        match id.len() {
+            8 => self.0 == id,
+            4 => self.0[4..] == *id,
+            _ => false,
+        }
+    }
+}
+
+/// Formats the key id as `0x` followed by uppercase hex, as used in error messages.
+impl fmt::Display for KeyId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{self:X}")
+    }
 }
 
 impl fmt::Debug for KeyId {
@@ -95,11 +95,15 @@ pub enum Version {
 }
 
 impl Version {
+    /// Writes a packet header in this format.
+    ///
+    /// The old format can only represent tags up to 15, newer packet types are always written
+    /// in the new format.
     pub fn write_header(self, writer: &mut impl io::Write, tag: u8, len: usize) -> Result<()> {
         debug!("write_header {:?} {} {}", self, tag, len);
 
         match self {
-            Version::Old => {
+            Version::Old if tag < 16 => {
                 if len < 256 {
                     // one octet
                     writer.write_all(&[0b1000_0000 | tag << 2, len as u8])?;
@@ -113,7 +117,7 @@ impl Version {
                     writer.write_u32::<BigEndian>(len as u32)?;
                 }
             }
-            Version::New => {
+            Version::Old | Version::New => {
                 writer.write_all(&[0b1100_0000 | tag])?;
                 if len < 192 {
                     writer.write_all(&[len as u8])?;
@@ -141,6 +145,7 @@ pub enum KeyVersion {
     V3 = 3,
     V4 = 4,
     V5 = 5,
+    V6 = 6,
 
     #[num_enum(catch_all)]
     Other(u8),
@@ -181,4 +186,32 @@ mod tests {
 
         assert_eq!(hex::encode(buf), "c2c06f");
     }
+
+    #[test]
+    fn test_write_header_old() {
+        let mut buf = Vec::new();
+        Version::Old
+            .write_header(&mut buf, Tag::Signature.into(), 10)
+            .unwrap();
+        assert_eq!(hex::encode(buf), "880a");
+
+        let mut buf = Vec::new();
+        Version::Old
+            .write_header(&mut buf, Tag::Signature.into(), 302)
+            .unwrap();
+        assert_eq!(hex::encode(buf), "89012e");
+
+        let mut buf = Vec::new();
+        Version::Old
+            .write_header(&mut buf, Tag::Signature.into(), 65536)
+            .unwrap();
+        assert_eq!(hex::encode(buf), "8a00010000");
+
+        // tags above 15 do not fit into an old format header
+        let mut buf = Vec::new();
+        Version::Old
+            .write_header(&mut buf, Tag::UserAttribute.into(), 12875)
+            .unwrap();
+        assert_eq!(hex::encode(buf), "d1ff0000324b");
+    }
 }
@@ -2,9 +2,12 @@ use std::{fmt, io};
 
 use byteorder::{BigEndian, ByteOrder};
 use digest::Digest;
+use sha2::Sha256;
 
+use crate::crypto::aead::AeadAlgorithm;
 use crate::crypto::checksum;
 use crate::crypto::public_key::PublicKeyAlgorithm;
+use crate::crypto::sym::SymmetricKeyAlgorithm;
 use crate::errors::{Error, Result};
 use crate::ser::Serialize;
 use crate::types::*;
@@ -51,12 +54,91 @@ impl EncryptedSecretParams {
         }
     }
 
+    /// Writes the parameters in the version 6 key format, where the s2k fields and the s2k
+    /// specifier are prefixed with their length.
+    pub(crate) fn to_writer_v6<W: io::Write>(&self, writer: &mut W) -> Result<()> {
+        let mut fields = Vec::new();
+        match &self.s2k_params {
+            S2kParams::Aead {
+                sym_alg,
+                aead_mode,
+                s2k,
+                ref nonce,
+            } => {
+                fields.push(u8::from(*sym_alg));
+                fields.push(u8::from(*aead_mode));
+                let s2k = s2k.to_bytes()?;
+                fields.push(s2k.len().try_into()?);
+                fields.extend_from_slice(&s2k);
+                fields.extend_from_slice(nonce);
+            }
+            S2kParams::Cfb {
+                sym_alg,
+                s2k,
+                ref iv,
+            } => {
+                fields.push(u8::from(*sym_alg));
+                let s2k = s2k.to_bytes()?;
+                fields.push(s2k.len().try_into()?);
+                fields.extend_from_slice(&s2k);
+                fields.extend_from_slice(iv);
+            }
+            _ => unsupported_err!("{:?} for v6 keys", self.s2k_params),
+        }
+
+        writer.write_all(&[(&self.s2k_params).into()])?;
+        writer.write_all(&[fields.len().try_into()?])?;
+        writer.write_all(&fields)?;
+        writer.write_all(&self.data)?;
+
+        Ok(())
+    }
+
+    /// Decrypts the secret parameters.
+    ///
+    /// AEAD protected parameters authenticate the public key, use
+    /// [`Self::unlock_with_public_key`] for them.
     pub fn unlock<F>(
         &self,
         pw: F,
         alg: PublicKeyAlgorithm,
         params: &PublicParams,
     ) -> Result<PlainSecretParams>
+    where
+        F: FnOnce() -> String,
+    {
+        self.unlock_inner(pw, alg, params, None)
+    }
+
+    /// Decrypts the secret parameters like [`Self::unlock`], additionally supporting AEAD
+    /// protected parameters.
+    ///
+    /// `version` is the version of the key, `pub_key` its public part and `typ` the tag of
+    /// the secret key packet, all of them are authenticated when the parameters are AEAD
+    /// protected.
+    pub fn unlock_with_public_key<F>(
+        &self,
+        pw: F,
+        alg: PublicKeyAlgorithm,
+        params: &PublicParams,
+        version: KeyVersion,
+        pub_key: &impl Serialize,
+        typ: Tag,
+    ) -> Result<PlainSecretParams>
+    where
+        F: FnOnce() -> String,
+    {
+        let pub_key = pub_key.to_bytes()?;
+        self.unlock_inner(pw, alg, params, Some((version, &pub_key, typ)))
+    }
+
+    fn unlock_inner<F>(
+        &self,
+        pw: F,
+        alg: PublicKeyAlgorithm,
+        params: &PublicParams,
+        pub_key: Option<(KeyVersion, &[u8], Tag)>,
+    ) -> Result<PlainSecretParams>
     where
         F: FnOnce() -> String,
     {
@@ -82,10 +164,29 @@ impl EncryptedSecretParams {
 
                 PlainSecretParams::from_slice(plaintext, alg, params)
             }
-            S2kParams::Aead { .. } => {
-                // let _key = s2k.derive_key(&pw(), sym_alg.key_size())?;
-                // let mut _plaintext = self.data.clone();
-                unimplemented_err!("s2k AEAD")
+            S2kParams::Aead {
+                sym_alg,
+                aead_mode,
+                s2k,
+                nonce,
+            } => {
+                let Some((version, pub_key, typ)) = pub_key else {
+                    bail!("AEAD protection needs the public key, use unlock_with_public_key");
+                };
+                let key = s2k.derive_key(&pw(), sym_alg.key_size())?;
+                let (kek, ad) = aead_key_and_ad(&key, *sym_alg, *aead_mode, version, pub_key, typ);
+
+                if self.data.len() < aead_mode.tag_size() {
+                    return Err(Error::InvalidInput);
+                }
+
+                // Decryption, no checksum, authentication is provided by the AEAD
+                let mut plaintext = self.data.clone();
+                let (plaintext, auth_tag) =
+                    plaintext.split_at_mut(self.data.len() - aead_mode.tag_size());
+                aead_mode.decrypt_in_place(sym_alg, &kek, nonce, &ad, auth_tag, plaintext)?;
+
+                PlainSecretParams::from_slice(plaintext, alg, params)
             }
             S2kParams::Cfb { sym_alg, s2k, iv } => {
                 let key = s2k.derive_key(&pw(), sym_alg.key_size())?;
@@ -132,6 +233,36 @@ impl EncryptedSecretParams {
     }
 }
 
+/// Derives the key encryption key and the associated data, used for AEAD protected secret keys.
+///
+/// Ref https://www.rfc-editor.org/rfc/rfc9580.html#name-secret-key-packet-formats
+pub(crate) fn aead_key_and_ad(
+    key: &[u8],
+    sym_alg: SymmetricKeyAlgorithm,
+    aead_mode: AeadAlgorithm,
+    version: KeyVersion,
+    pub_key: &[u8],
+    typ: Tag,
+) -> (Vec<u8>, Vec<u8>) {
+    let info = [
+        typ.encode(), // packet type
+        version.into(),
+        sym_alg.into(),
+        aead_mode.into(),
+    ];
+
+    let hk = hkdf::Hkdf::<Sha256>::new(None, key);
+    let mut kek = vec![0u8; sym_alg.key_size()];
+    hk.expand(&info, &mut kek)
+        .expect("the key sizes are well below the hkdf limit");
+
+    let mut ad = Vec::with_capacity(1 + pub_key.len());
+    ad.push(typ.encode());
+    ad.extend_from_slice(pub_key);
+
+    (kek, ad)
+}
+
 impl Serialize for EncryptedSecretParams {
     fn to_writer<W: io::Write>(&self, writer: &mut W) -> Result<()> {
         writer.write_all(&[(&self.s2k_params).into()])?;
@@ -184,3 +315,70 @@ impl fmt::Debug for EncryptedSecretParams {
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    use crate::composed::{Deserializable, SignedSecretKey};
+
+    /// The primary key of the sample locked version 6 secret key, protected with Argon2 and
+    /// AES-256 in OCB mode.
+    ///
+    /// Ref https://www.rfc-editor.org/rfc/rfc9580.html#name-sample-locked-version-6-sec
+    #[test]
+    fn test_aead_secret_key_rfc9580() {
+        let (key, _headers) = SignedSecretKey::from_armor_single(
+            std::fs::File::open("./tests/rfc9580/v6-locked-secret-key.asc").unwrap(),
+        )
+        .unwrap();
+        let SecretParams::Encrypted(encrypted) = key.primary_key.secret_params() else {
+            panic!("not encrypted");
+        };
+        let S2kParams::Aead {
+            sym_alg,
+            aead_mode,
+            nonce,
+            ..
+        } = encrypted.string_to_key_params()
+        else {
+            panic!("not AEAD protected");
+        };
+
+        // The Argon2 output for "correct horse battery staple", deriving it needs 2 GiB of
+        // memory, see `test_aead_secret_key_rfc9580_unlock` in the key tests.
+        let derived =
+            hex::decode("832bd2662a5c2b251ee3fc82aec349a766ca539015880133002e5a21960b3bcf")
+                .unwrap();
+        // The secret key of the sample unlocked version 6 secret key.
+        let secret =
+            hex::decode("1972817b12be707e8d5f586ce61361201d344eb266a2c82fde6835762b65b0b7")
+                .unwrap();
+
+        let (kek, ad) = aead_key_and_ad(
+            &derived,
+            *sym_alg,
+            *aead_mode,
+            KeyVersion::V6,
+            &key.primary_key.public_key().to_bytes().unwrap(),
+            Tag::SecretKey,
+        );
+
+        let (ciphertext, auth_tag) = encrypted
+            .data()
+            .split_at(encrypted.data().len() - aead_mode.tag_size());
+        let mut plaintext = ciphertext.to_vec();
+        aead_mode
+            .decrypt_in_place(sym_alg, &kek, nonce, &ad, auth_tag, &mut plaintext)
+            .unwrap();
+        assert_eq!(plaintext, secret);
+
+        let auth_tag = aead_mode
+            .encrypt_in_place(sym_alg, &kek, nonce, &ad, &mut plaintext)
+            .unwrap();
+        plaintext.extend_from_slice(&auth_tag);
+        assert_eq!(plaintext, encrypted.data());
+    }
+}
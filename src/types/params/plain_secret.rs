@@ -2,6 +2,7 @@ use std::hash::Hasher;
 use std::{fmt, io};
 
 use byteorder::{BigEndian, ByteOrder};
+use nom::bytes::streaming::take;
 use nom::combinator::map;
 use nom::sequence::tuple;
 use rsa::RsaPrivateKey;
@@ -15,14 +16,29 @@ use crate::ser::Serialize;
 use crate::types::*;
 use crate::util::TeeWriter;
 
+use super::encrypted_secret::aead_key_and_ad;
+
 #[derive(Clone, PartialEq, Eq, Zeroize, ZeroizeOnDrop)]
 pub enum PlainSecretParams {
-    RSA { d: Mpi, p: Mpi, q: Mpi, u: Mpi },
+    RSA {
+        d: Mpi,
+        p: Mpi,
+        q: Mpi,
+        u: Mpi,
+    },
     DSA(Mpi),
     ECDSA(Mpi),
     ECDH(Mpi),
     Elgamal(Mpi),
     EdDSA(Mpi),
+    /// The native X25519 secret key.
+    X25519([u8; 32]),
+    /// The native Ed25519 secret key.
+    Ed25519([u8; 32]),
+    /// The native X448 secret key.
+    X448([u8; 56]),
+    /// The native Ed448 secret key.
+    Ed448([u8; 57]),
 }
 
 #[derive(Clone, PartialEq, Eq)]
@@ -38,6 +54,10 @@ pub enum PlainSecretParamsRef<'a> {
     ECDH(MpiRef<'a>),
     Elgamal(MpiRef<'a>),
     EdDSA(MpiRef<'a>),
+    X25519(&'a [u8; 32]),
+    Ed25519(&'a [u8; 32]),
+    X448(&'a [u8; 56]),
+    Ed448(&'a [u8; 57]),
 }
 
 impl<'a> PlainSecretParamsRef<'a> {
@@ -54,6 +74,10 @@ impl<'a> PlainSecretParamsRef<'a> {
             PlainSecretParamsRef::ECDH(v) => PlainSecretParams::ECDH((*v).to_owned()),
             PlainSecretParamsRef::Elgamal(v) => PlainSecretParams::Elgamal((*v).to_owned()),
             PlainSecretParamsRef::EdDSA(v) => PlainSecretParams::EdDSA((*v).to_owned()),
+            PlainSecretParamsRef::X25519(v) => PlainSecretParams::X25519(**v),
+            PlainSecretParamsRef::Ed25519(v) => PlainSecretParams::Ed25519(**v),
+            PlainSecretParamsRef::X448(v) => PlainSecretParams::X448(**v),
+            PlainSecretParamsRef::Ed448(v) => PlainSecretParams::Ed448(**v),
         }
     }
 
@@ -84,6 +108,15 @@ impl<'a> PlainSecretParamsRef<'a> {
             PlainSecretParamsRef::EdDSA(x) => {
                 (*x).to_writer(writer)?;
             }
+            PlainSecretParamsRef::X25519(x) | PlainSecretParamsRef::Ed25519(x) => {
+                writer.write_all(&x[..])?;
+            }
+            PlainSecretParamsRef::X448(x) => {
+                writer.write_all(&x[..])?;
+            }
+            PlainSecretParamsRef::Ed448(x) => {
+                writer.write_all(&x[..])?;
+            }
         }
 
         Ok(())
@@ -153,6 +186,24 @@ impl<'a> PlainSecretParamsRef<'a> {
                             secret,
                         }))
                     }
+                    ECCCurve::BrainpoolP256r1
+                    | ECCCurve::BrainpoolP384r1
+                    | ECCCurve::BrainpoolP512r1 => {
+                        let size = crate::crypto::brainpool::field_size(curve)?;
+                        ensure!(d.len() <= size, "invalid secret");
+
+                        let mut secret = vec![0u8; size];
+                        secret[size - d.len()..].copy_from_slice(d.as_bytes());
+
+                        Ok(SecretKeyRepr::ECDHBrainpool(
+                            crate::crypto::ecdh::BrainpoolSecretKey {
+                                oid: curve.oid(),
+                                hash: *hash,
+                                alg_sym: *alg_sym,
+                                secret,
+                            },
+                        ))
+                    }
                     _ => unsupported_err!("curve {:?} for ECDH", curve.to_string()),
                 },
                 _ => unreachable!("inconsistent key state"),
@@ -180,6 +231,26 @@ impl<'a> PlainSecretParamsRef<'a> {
             PlainSecretParamsRef::Elgamal(_) => {
                 unimplemented_err!("Elgamal");
             }
+            PlainSecretParamsRef::X25519(secret) => {
+                Ok(SecretKeyRepr::X25519(crate::crypto::x25519::SecretKey {
+                    secret: **secret,
+                }))
+            }
+            PlainSecretParamsRef::Ed25519(secret) => {
+                Ok(SecretKeyRepr::Ed25519(crate::crypto::ed25519::SecretKey {
+                    secret: **secret,
+                }))
+            }
+            PlainSecretParamsRef::X448(secret) => {
+                Ok(SecretKeyRepr::X448(crate::crypto::x448::SecretKey {
+                    secret: **secret,
+                }))
+            }
+            PlainSecretParamsRef::Ed448(secret) => {
+                Ok(SecretKeyRepr::Ed448(crate::crypto::ed448::SecretKey {
+                    secret: **secret,
+                }))
+            }
             PlainSecretParamsRef::ECDSA(d) => match public_params {
                 PublicParams::ECDSA(params) => match params {
                     EcdsaPublicParams::P256 { .. } => {
@@ -210,6 +281,17 @@ impl<'a> PlainSecretParamsRef<'a> {
                             crate::crypto::ecdsa::SecretKey::Secp256k1(secret),
                         ))
                     }
+                    EcdsaPublicParams::Brainpool { curve, .. } => {
+                        let size = crate::crypto::brainpool::field_size(curve)?;
+                        ensure!(d.len() <= size, "invalid secret");
+
+                        Ok(SecretKeyRepr::ECDSA(
+                            crate::crypto::ecdsa::SecretKey::Brainpool {
+                                secret: (*d).to_owned(),
+                                curve: curve.clone(),
+                            },
+                        ))
+                    }
                     EcdsaPublicParams::Unsupported { curve, .. } => {
                         unsupported_err!("curve {:?} for ECDSA", curve.to_string())
                     }
@@ -242,6 +324,12 @@ impl PlainSecretParams {
         self.as_ref().checksum_sha1()
     }
 
+    /// Writes the parameters in the version 6 key format, which has no checksum.
+    pub(crate) fn to_writer_v6<W: io::Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_all(&[self.string_to_key_id()])?;
+        self.as_ref().to_writer_raw(writer)
+    }
+
     pub fn as_ref(&self) -> PlainSecretParamsRef<'_> {
         match self {
             PlainSecretParams::RSA { d, p, q, u } => PlainSecretParamsRef::RSA {
@@ -255,14 +343,50 @@ impl PlainSecretParams {
             PlainSecretParams::ECDH(v) => PlainSecretParamsRef::ECDH(v.as_ref()),
             PlainSecretParams::Elgamal(v) => PlainSecretParamsRef::Elgamal(v.as_ref()),
             PlainSecretParams::EdDSA(v) => PlainSecretParamsRef::EdDSA(v.as_ref()),
+            PlainSecretParams::X25519(v) => PlainSecretParamsRef::X25519(v),
+            PlainSecretParams::Ed25519(v) => PlainSecretParamsRef::Ed25519(v),
+            PlainSecretParams::X448(v) => PlainSecretParamsRef::X448(v),
+            PlainSecretParams::Ed448(v) => PlainSecretParamsRef::Ed448(v),
         }
     }
 
+    /// Encrypts the secret parameters of a key with the given `version`, using the given
+    /// passphrase and s2k parameters.
+    ///
+    /// AEAD protection authenticates the public key, use [`Self::encrypt_with_public_key`]
+    /// for it.
     pub fn encrypt(
         self,
         passphrase: &str,
         s2k_params: S2kParams,
         version: KeyVersion,
+    ) -> Result<EncryptedSecretParams> {
+        self.encrypt_inner(passphrase, s2k_params, version, None)
+    }
+
+    /// Encrypts the secret parameters like [`Self::encrypt`], additionally supporting AEAD
+    /// protection.
+    ///
+    /// `pub_key` is the public part of the key and `typ` the tag of the secret key packet,
+    /// both are authenticated when using AEAD.
+    pub fn encrypt_with_public_key(
+        self,
+        passphrase: &str,
+        s2k_params: S2kParams,
+        version: KeyVersion,
+        pub_key: &impl Serialize,
+        typ: Tag,
+    ) -> Result<EncryptedSecretParams> {
+        let pub_key = pub_key.to_bytes()?;
+        self.encrypt_inner(passphrase, s2k_params, version, Some((&pub_key, typ)))
+    }
+
+    fn encrypt_inner(
+        self,
+        passphrase: &str,
+        s2k_params: S2kParams,
+        version: KeyVersion,
+        pub_key: Option<(&[u8], Tag)>,
     ) -> Result<EncryptedSecretParams> {
         match &s2k_params {
             S2kParams::Unprotected => bail!("cannot encrypt to uprotected"),
@@ -271,7 +395,7 @@ impl PlainSecretParams {
                 let enc_data = match version {
                     KeyVersion::V2 => unsupported_err!("Encryption for V2 keys is not available"),
                     KeyVersion::V3 => unimplemented_err!("v3 encryption"),
-                    KeyVersion::V4 => {
+                    KeyVersion::V4 | KeyVersion::V6 => {
                         let mut data = Vec::new();
                         self.as_ref()
                             .to_writer_raw(&mut data)
@@ -288,6 +412,34 @@ impl PlainSecretParams {
 
                 Ok(EncryptedSecretParams::new(enc_data, s2k_params))
             }
+            S2kParams::Aead {
+                sym_alg,
+                aead_mode,
+                s2k,
+                nonce,
+            } => {
+                ensure_eq!(
+                    nonce.len(),
+                    aead_mode.nonce_size(),
+                    "invalid nonce size for {:?}",
+                    aead_mode
+                );
+                let Some((pub_key, typ)) = pub_key else {
+                    bail!("AEAD protection needs the public key, use encrypt_with_public_key");
+                };
+                let key = s2k.derive_key(passphrase, sym_alg.key_size())?;
+                let (kek, ad) = aead_key_and_ad(&key, *sym_alg, *aead_mode, version, pub_key, typ);
+
+                // No checksum, authentication is provided by the AEAD
+                let mut data = Vec::new();
+                self.as_ref()
+                    .to_writer_raw(&mut data)
+                    .expect("preallocated vector");
+                let auth_tag = aead_mode.encrypt_in_place(sym_alg, &kek, nonce, &ad, &mut data)?;
+                data.extend_from_slice(&auth_tag);
+
+                Ok(EncryptedSecretParams::new(data, s2k_params))
+            }
             _ => unimplemented_err!("{:?} not implemented yet", s2k_params),
         }
     }
@@ -330,6 +482,10 @@ impl<'a> fmt::Debug for PlainSecretParamsRef<'a> {
             }
             PlainSecretParamsRef::ECDH(_) => write!(f, "PlainSecretParams(ECDH)"),
             PlainSecretParamsRef::EdDSA(_) => write!(f, "PlainSecretParams(EdDSA)"),
+            PlainSecretParamsRef::X25519(_) => write!(f, "PlainSecretParams(X25519)"),
+            PlainSecretParamsRef::Ed25519(_) => write!(f, "PlainSecretParams(Ed25519)"),
+            PlainSecretParamsRef::X448(_) => write!(f, "PlainSecretParams(X448)"),
+            PlainSecretParamsRef::Ed448(_) => write!(f, "PlainSecretParams(Ed448)"),
         }
     }
 }
@@ -346,6 +502,18 @@ fn parse_secret_params(
         PublicKeyAlgorithm::ECDH => map(mpi, |m| PlainSecretParams::ECDH(m.to_owned()))(i),
         PublicKeyAlgorithm::ECDSA => map(mpi, |m| PlainSecretParams::ECDSA(m.to_owned()))(i),
         PublicKeyAlgorithm::EdDSA => map(mpi, |m| PlainSecretParams::EdDSA(m.to_owned()))(i),
+        PublicKeyAlgorithm::X25519 => map(take(32usize), |s: &[u8]| {
+            PlainSecretParams::X25519(s.try_into().expect("size checked"))
+        })(i),
+        PublicKeyAlgorithm::Ed25519 => map(take(32usize), |s: &[u8]| {
+            PlainSecretParams::Ed25519(s.try_into().expect("size checked"))
+        })(i),
+        PublicKeyAlgorithm::X448 => map(take(56usize), |s: &[u8]| {
+            PlainSecretParams::X448(s.try_into().expect("size checked"))
+        })(i),
+        PublicKeyAlgorithm::Ed448 => map(take(57usize), |s: &[u8]| {
+            PlainSecretParams::Ed448(s.try_into().expect("size checked"))
+        })(i),
         _ => Err(nom::Err::Error(crate::errors::Error::ParsingError(
             nom::error::ErrorKind::Switch,
         ))),
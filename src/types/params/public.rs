@@ -36,11 +36,48 @@ pub enum PublicParams {
         curve: ECCCurve,
         q: Mpi,
     },
+    X25519 {
+        /// The native X25519 public key.
+        public: [u8; 32],
+    },
+    Ed25519 {
+        /// The native Ed25519 public key.
+        public: [u8; 32],
+    },
+    X448 {
+        /// The native X448 public key.
+        public: [u8; 56],
+    },
+    Ed448 {
+        /// The native Ed448 public key.
+        public: [u8; 57],
+    },
     Unknown {
         data: Vec<u8>,
     },
 }
 
+impl PublicParams {
+    /// The curve of elliptic curve keys, `None` for other algorithms.
+    pub fn curve(&self) -> Option<ECCCurve> {
+        match self {
+            PublicParams::ECDSA(params) => Some(params.curve()),
+            PublicParams::ECDH { curve, .. } | PublicParams::EdDSA { curve, .. } => {
+                Some(curve.clone())
+            }
+            PublicParams::X25519 { .. } => Some(ECCCurve::Curve25519),
+            PublicParams::Ed25519 { .. } => Some(ECCCurve::Ed25519),
+            // Curve448 keys only exist in the native encoding, without a curve OID
+            PublicParams::X448 { .. }
+            | PublicParams::Ed448 { .. }
+            | PublicParams::RSA { .. }
+            | PublicParams::DSA { .. }
+            | PublicParams::Elgamal { .. }
+            | PublicParams::Unknown { .. } => None,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum EcdsaPublicParams {
     P256 {
@@ -63,6 +100,11 @@ pub enum EcdsaPublicParams {
         /// Stores the original Mpi, to ensure we keep the padding around.
         p: Mpi,
     },
+    /// Brainpool curves, which require the `openssl` feature for signing and verification.
+    Brainpool {
+        curve: ECCCurve,
+        p: Mpi,
+    },
     Unsupported {
         curve: ECCCurve,
         p: Mpi,
@@ -116,6 +158,15 @@ impl EcdsaPublicParams {
                     p: p.to_owned(),
                 })
             }
+            ECCCurve::BrainpoolP256r1 | ECCCurve::BrainpoolP384r1 | ECCCurve::BrainpoolP512r1 => {
+                let size = crate::crypto::brainpool::field_size(&curve)?;
+                ensure!(p.len() <= 1 + 2 * size, "invalid public key length");
+
+                Ok(EcdsaPublicParams::Brainpool {
+                    curve,
+                    p: p.to_owned(),
+                })
+            }
             _ => Ok(EcdsaPublicParams::Unsupported {
                 curve,
                 p: p.to_owned(),
@@ -123,12 +174,30 @@ impl EcdsaPublicParams {
         }
     }
 
+    /// The curve of this key.
+    pub fn curve(&self) -> ECCCurve {
+        match self {
+            EcdsaPublicParams::P256 { .. } => ECCCurve::P256,
+            EcdsaPublicParams::P384 { .. } => ECCCurve::P384,
+            EcdsaPublicParams::P521 { .. } => ECCCurve::P521,
+            EcdsaPublicParams::Secp256k1 { .. } => ECCCurve::Secp256k1,
+            EcdsaPublicParams::Brainpool { curve, .. }
+            | EcdsaPublicParams::Unsupported { curve, .. } => curve.clone(),
+        }
+    }
+
     pub const fn secret_key_length(&self) -> Option<usize> {
         match self {
             EcdsaPublicParams::P256 { .. } => Some(32),
             EcdsaPublicParams::P384 { .. } => Some(48),
             EcdsaPublicParams::P521 { .. } => Some(66),
             EcdsaPublicParams::Secp256k1 { .. } => Some(32),
+            EcdsaPublicParams::Brainpool { curve, .. } => match curve {
+                ECCCurve::BrainpoolP256r1 => Some(32),
+                ECCCurve::BrainpoolP384r1 => Some(48),
+                ECCCurve::BrainpoolP512r1 => Some(64),
+                _ => None,
+            },
             EcdsaPublicParams::Unsupported { .. } => None,
         }
     }
@@ -141,7 +210,8 @@ impl Serialize for EcdsaPublicParams {
             EcdsaPublicParams::P384 { .. } => ECCCurve::P384.oid(),
             EcdsaPublicParams::P521 { .. } => ECCCurve::P521.oid(),
             EcdsaPublicParams::Secp256k1 { .. } => ECCCurve::Secp256k1.oid(),
-            EcdsaPublicParams::Unsupported { curve, .. } => curve.oid(),
+            EcdsaPublicParams::Brainpool { curve, .. }
+            | EcdsaPublicParams::Unsupported { curve, .. } => curve.oid(),
         };
 
         writer.write_all(&[oid.len() as u8])?;
@@ -160,7 +230,7 @@ impl Serialize for EcdsaPublicParams {
             EcdsaPublicParams::Secp256k1 { p, .. } => {
                 p.as_ref().to_writer(writer)?;
             }
-            EcdsaPublicParams::Unsupported { p, .. } => {
+            EcdsaPublicParams::Brainpool { p, .. } | EcdsaPublicParams::Unsupported { p, .. } => {
                 p.as_ref().to_writer(writer)?;
             }
         }
@@ -227,6 +297,15 @@ impl Serialize for PublicParams {
 
                 q.to_writer(writer)?;
             }
+            PublicParams::X25519 { ref public } | PublicParams::Ed25519 { ref public } => {
+                writer.write_all(public)?;
+            }
+            PublicParams::X448 { ref public } => {
+                writer.write_all(public)?;
+            }
+            PublicParams::Ed448 { ref public } => {
+                writer.write_all(public)?;
+            }
             PublicParams::Unknown { ref data } => {
                 writer.write_all(data)?;
             }
@@ -288,6 +367,26 @@ impl fmt::Debug for PublicParams {
                 .field("q", &q)
                 .finish(),
 
+            PublicParams::X25519 { ref public } => f
+                .debug_struct("PublicParams::X25519")
+                .field("public", &hex::encode(public))
+                .finish(),
+
+            PublicParams::Ed25519 { ref public } => f
+                .debug_struct("PublicParams::Ed25519")
+                .field("public", &hex::encode(public))
+                .finish(),
+
+            PublicParams::X448 { ref public } => f
+                .debug_struct("PublicParams::X448")
+                .field("public", &hex::encode(public))
+                .finish(),
+
+            PublicParams::Ed448 { ref public } => f
+                .debug_struct("PublicParams::Ed448")
+                .field("public", &hex::encode(public))
+                .finish(),
+
             PublicParams::Unknown { ref data } => f
                 .debug_struct("PublicParams::Unknown")
                 .field("data", data)
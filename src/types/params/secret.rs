@@ -1,14 +1,15 @@
 use std::io;
 
 use nom::bytes::streaming::take;
-use nom::combinator::{map_res, rest_len};
+use nom::combinator::{all_consuming, map_parser, map_res, rest_len};
+use nom::multi::length_data;
 use nom::number::streaming::be_u8;
 use zeroize::Zeroize;
 
 use crate::crypto::aead::AeadAlgorithm;
 use crate::crypto::public_key::PublicKeyAlgorithm;
 use crate::crypto::sym::SymmetricKeyAlgorithm;
-use crate::errors::{IResult, Result};
+use crate::errors::{Error, IResult, Result};
 use crate::ser::Serialize;
 use crate::types::*;
 
@@ -37,8 +38,13 @@ impl SecretParams {
         }
     }
 
-    pub fn from_slice(data: &[u8], alg: PublicKeyAlgorithm, params: &PublicParams) -> Result<Self> {
-        let (_, params) = parse_secret_fields(alg, params)(data)?;
+    pub fn from_slice(
+        data: &[u8],
+        version: KeyVersion,
+        alg: PublicKeyAlgorithm,
+        params: &PublicParams,
+    ) -> Result<Self> {
+        let (_, params) = parse_secret_fields(version, alg, params)(data)?;
         Ok(params)
     }
 
@@ -55,6 +61,16 @@ impl SecretParams {
             SecretParams::Encrypted(k) => k.checksum(),
         }
     }
+
+    /// Writes the secret parameters in the version 6 key format, which, unlike the
+    /// serialization of older versions, includes the length of the s2k fields and has no
+    /// checksum for unprotected keys.
+    pub fn to_writer_v6<W: io::Write>(&self, writer: &mut W) -> Result<()> {
+        match self {
+            SecretParams::Plain(k) => k.to_writer_v6(writer),
+            SecretParams::Encrypted(k) => k.to_writer_v6(writer),
+        }
+    }
 }
 
 impl Serialize for SecretParams {
@@ -68,12 +84,80 @@ impl Serialize for SecretParams {
 
 /// Parse possibly encrypted private fields of a key.
 fn parse_secret_fields(
+    version: KeyVersion,
     alg: PublicKeyAlgorithm,
     public_params: &PublicParams,
 ) -> impl Fn(&[u8]) -> IResult<&[u8], SecretParams> + '_ {
     move |i: &[u8]| {
         let (i, s2k_usage) = map_res(be_u8, S2kUsage::try_from)(i)?;
-        let (i, enc_params) = match s2k_usage {
+        let (i, enc_params) = match version {
+            KeyVersion::V6 if s2k_usage != S2kUsage::Unprotected => {
+                // v6 keys prefix the s2k fields with their length
+                map_parser(length_data(be_u8), all_consuming(s2k_fields_v6(s2k_usage)))(i)?
+            }
+            _ => s2k_fields(s2k_usage)(i)?,
+        };
+
+        let (i, len) = rest_len(i)?;
+        let (i, data) = take(len)(i)?;
+
+        let res = match s2k_usage {
+            S2kUsage::Unprotected => {
+                let repr = PlainSecretParams::from_slice(data, alg, public_params)?;
+                SecretParams::Plain(repr)
+            }
+            _ => SecretParams::Encrypted(EncryptedSecretParams::new(data.to_vec(), enc_params)),
+        };
+
+        Ok((i, res))
+    }
+}
+
+/// Parse the s2k fields of a v6 key, where the s2k specifier is prefixed with its length.
+///
+/// Ref: https://www.rfc-editor.org/rfc/rfc9580.html#name-secret-key-packet-formats
+fn s2k_fields_v6(s2k_usage: S2kUsage) -> impl Fn(&[u8]) -> IResult<&[u8], S2kParams> {
+    move |i: &[u8]| match s2k_usage {
+        S2kUsage::Aead => {
+            let (i, sym_alg) = map_res(be_u8, SymmetricKeyAlgorithm::try_from)(i)?;
+            let (i, aead_mode) = map_res(be_u8, AeadAlgorithm::try_from)(i)?;
+            let (i, s2k) = map_parser(length_data(be_u8), all_consuming(s2k_parser))(i)?;
+            let (i, nonce) = take(aead_mode.nonce_size())(i)?;
+            Ok((
+                i,
+                S2kParams::Aead {
+                    sym_alg,
+                    aead_mode,
+                    s2k,
+                    nonce: nonce.to_vec(),
+                },
+            ))
+        }
+        S2kUsage::Cfb => {
+            let (i, sym_alg) = map_res(be_u8, SymmetricKeyAlgorithm::try_from)(i)?;
+            let (i, s2k) = map_parser(length_data(be_u8), all_consuming(s2k_parser))(i)?;
+            let (i, iv) = take(sym_alg.block_size())(i)?;
+            Ok((
+                i,
+                S2kParams::Cfb {
+                    sym_alg,
+                    s2k,
+                    iv: iv.to_vec(),
+                },
+            ))
+        }
+        // Only AEAD and CFB with a SHA1 checksum are allowed for v6 keys
+        _ => Err(nom::Err::Error(Error::Unsupported(format!(
+            "s2k usage {:?} for v6 keys",
+            s2k_usage
+        )))),
+    }
+}
+
+/// Parse the s2k fields of keys before v6.
+fn s2k_fields(s2k_usage: S2kUsage) -> impl Fn(&[u8]) -> IResult<&[u8], S2kParams> {
+    move |i: &[u8]| {
+        let res = match s2k_usage {
             // 0 is no encryption
             S2kUsage::Unprotected => (i, S2kParams::Unprotected),
             // symmetric key algorithm
@@ -131,17 +215,6 @@ fn parse_secret_fields(
             }
         };
 
-        let (i, len) = rest_len(i)?;
-        let (i, data) = take(len)(i)?;
-
-        let res = match s2k_usage {
-            S2kUsage::Unprotected => {
-                let repr = PlainSecretParams::from_slice(data, alg, public_params)?;
-                SecretParams::Plain(repr)
-            }
-            _ => SecretParams::Encrypted(EncryptedSecretParams::new(data.to_vec(), enc_params)),
-        };
-
-        Ok((i, res))
+        Ok(res)
     }
 }
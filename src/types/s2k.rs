@@ -13,6 +13,10 @@ use crate::ser::Serialize;
 
 const EXPBIAS: u32 = 6;
 const DEFAULT_ITER_SALTED_COUNT: u8 = 224;
+// The second recommended Argon2 parameter set of RFC 9580, for memory constrained environments.
+const DEFAULT_ARGON2_T: u8 = 3;
+const DEFAULT_ARGON2_P: u8 = 4;
+const DEFAULT_ARGON2_M_ENC: u8 = 16;
 
 /// The available s2k usages.
 ///
@@ -87,6 +91,202 @@ impl S2kParams {
             iv,
         }
     }
+
+    /// Returns a copy of these parameters with new random salt, IV and nonce, so that they can
+    /// be used to protect another key with the same passphrase.
+    pub(crate) fn with_new_randomness<R: Rng + CryptoRng>(&self, mut rng: R) -> Self {
+        let mut params = self.clone();
+        match params {
+            Self::Unprotected => {}
+            Self::LegacyCfb { ref mut iv, .. } => rng.fill(&mut iv[..]),
+            Self::Aead {
+                ref mut s2k,
+                ref mut nonce,
+                ..
+            } => {
+                s2k.renew_salt(&mut rng);
+                rng.fill(&mut nonce[..]);
+            }
+            Self::Cfb {
+                ref mut s2k,
+                ref mut iv,
+                ..
+            }
+            | Self::MaleableCfb {
+                ref mut s2k,
+                ref mut iv,
+                ..
+            } => {
+                s2k.renew_salt(&mut rng);
+                rng.fill(&mut iv[..]);
+            }
+        }
+
+        params
+    }
+
+    /// Create a new set of parameters using Argon2, with the given number of passes `t`, degree
+    /// of parallelism `p` and memory size of `2^m_enc` KiB, and initialises relevant randomized
+    /// values.
+    ///
+    /// - AES256
+    /// - AEAD with `aead_mode`, as Argon2 must only be used with AEAD protection
+    pub fn new_argon2<R: Rng + CryptoRng>(
+        mut rng: R,
+        aead_mode: AeadAlgorithm,
+        t: u8,
+        p: u8,
+        m_enc: u8,
+    ) -> Self {
+        let sym_alg = SymmetricKeyAlgorithm::AES256;
+
+        let mut nonce = vec![0u8; aead_mode.nonce_size()];
+        rng.fill(&mut nonce[..]);
+
+        Self::Aead {
+            sym_alg,
+            aead_mode,
+            s2k: StringToKey::new_argon2(&mut rng, t, p, m_enc),
+            nonce,
+        }
+    }
+
+    /// Create a new set of parameters using Argon2, see [`Self::new_argon2`], with OCB, 3
+    /// passes, a parallelism of 4 and 64 MiB of memory.
+    pub fn new_default_argon2<R: Rng + CryptoRng>(rng: R) -> Self {
+        Self::new_argon2(
+            rng,
+            AeadAlgorithm::Ocb,
+            DEFAULT_ARGON2_T,
+            DEFAULT_ARGON2_P,
+            DEFAULT_ARGON2_M_ENC,
+        )
+    }
+}
+
+/// The S2K parameters a key is, or would be, protected with, without the random salt, IV and
+/// nonce, which are only picked when the key is protected.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum S2kChoice {
+    Unprotected,
+    LegacyCfb {
+        sym_alg: SymmetricKeyAlgorithm,
+    },
+    Aead {
+        sym_alg: SymmetricKeyAlgorithm,
+        aead_mode: AeadAlgorithm,
+        s2k: StringToKeyChoice,
+    },
+    Cfb {
+        sym_alg: SymmetricKeyAlgorithm,
+        s2k: StringToKeyChoice,
+    },
+    MaleableCfb {
+        sym_alg: SymmetricKeyAlgorithm,
+        s2k: StringToKeyChoice,
+    },
+}
+
+impl S2kChoice {
+    /// The choice of [`S2kParams::new_default`].
+    pub fn new_default() -> Self {
+        Self::Cfb {
+            sym_alg: SymmetricKeyAlgorithm::AES256,
+            s2k: StringToKeyChoice::IteratedAndSalted {
+                hash_alg: HashAlgorithm::default(),
+                count: DEFAULT_ITER_SALTED_COUNT,
+            },
+        }
+    }
+
+    /// The choice of [`S2kParams::new_default_argon2`].
+    pub fn new_default_argon2() -> Self {
+        Self::Aead {
+            sym_alg: SymmetricKeyAlgorithm::AES256,
+            aead_mode: AeadAlgorithm::Ocb,
+            s2k: StringToKeyChoice::Argon2 {
+                t: DEFAULT_ARGON2_T,
+                p: DEFAULT_ARGON2_P,
+                m_enc: DEFAULT_ARGON2_M_ENC,
+            },
+        }
+    }
+}
+
+impl From<&S2kParams> for S2kChoice {
+    fn from(value: &S2kParams) -> Self {
+        match value {
+            S2kParams::Unprotected => Self::Unprotected,
+            S2kParams::LegacyCfb { sym_alg, .. } => Self::LegacyCfb { sym_alg: *sym_alg },
+            S2kParams::Aead {
+                sym_alg,
+                aead_mode,
+                s2k,
+                ..
+            } => Self::Aead {
+                sym_alg: *sym_alg,
+                aead_mode: *aead_mode,
+                s2k: s2k.into(),
+            },
+            S2kParams::Cfb { sym_alg, s2k, .. } => Self::Cfb {
+                sym_alg: *sym_alg,
+                s2k: s2k.into(),
+            },
+            S2kParams::MaleableCfb { sym_alg, s2k, .. } => Self::MaleableCfb {
+                sym_alg: *sym_alg,
+                s2k: s2k.into(),
+            },
+        }
+    }
+}
+
+/// A [`StringToKey`] without its salt, see [`S2kChoice`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum StringToKeyChoice {
+    Simple {
+        hash_alg: HashAlgorithm,
+    },
+    Salted {
+        hash_alg: HashAlgorithm,
+    },
+    IteratedAndSalted {
+        hash_alg: HashAlgorithm,
+        count: u8,
+    },
+    Argon2 {
+        t: u8,
+        p: u8,
+        m_enc: u8,
+    },
+    /// A reserved, private or unknown type.
+    Other {
+        typ: u8,
+    },
+}
+
+impl From<&StringToKey> for StringToKeyChoice {
+    fn from(value: &StringToKey) -> Self {
+        match value {
+            StringToKey::Simple { hash_alg } => Self::Simple {
+                hash_alg: *hash_alg,
+            },
+            StringToKey::Salted { hash_alg, .. } => Self::Salted {
+                hash_alg: *hash_alg,
+            },
+            StringToKey::IteratedAndSalted {
+                hash_alg, count, ..
+            } => Self::IteratedAndSalted {
+                hash_alg: *hash_alg,
+                count: *count,
+            },
+            StringToKey::Argon2 { t, p, m_enc, .. } => Self::Argon2 {
+                t: *t,
+                p: *p,
+                m_enc: *m_enc,
+            },
+            _ => Self::Other { typ: value.id() },
+        }
+    }
 }
 
 impl From<u8> for S2kUsage {
@@ -173,6 +373,17 @@ impl StringToKey {
         StringToKey::Argon2 { salt, t, p, m_enc }
     }
 
+    /// Replaces the salt with a random one, if this StringToKey uses a salt.
+    fn renew_salt<R: CryptoRng + Rng>(&mut self, mut rng: R) {
+        match self {
+            Self::Salted { salt, .. } | Self::IteratedAndSalted { salt, .. } => {
+                rng.fill(&mut salt[..])
+            }
+            Self::Argon2 { salt, .. } => rng.fill(&mut salt[..]),
+            _ => {}
+        }
+    }
+
     pub fn id(&self) -> u8 {
         match self {
             Self::Simple { .. } => 0,
@@ -559,6 +770,7 @@ mod tests {
                 .to_armored_string(ArmorOptions {
                     headers: Some(&header),
                     include_checksum: false, // No checksum on v6
+                    ..Default::default()
                 })
                 .expect("encode");
 
@@ -610,6 +822,7 @@ mod tests {
                 .to_armored_string(ArmorOptions {
                     headers: Some(&header),
                     include_checksum: false, // No checksum on v6
+                    ..Default::default()
                 })
                 .expect("encode");
 
@@ -1,3 +1,4 @@
+use crate::crypto::ecc_curve::ECCCurve;
 use crate::crypto::hash::HashAlgorithm;
 use crate::errors::Result;
 use crate::types::{EcdsaPublicParams, Mpi, PublicKeyTrait, PublicParams};
@@ -28,6 +29,19 @@ pub trait SecretKeyTrait: PublicKeyTrait {
         match self.public_params() {
             PublicParams::ECDSA(EcdsaPublicParams::P384 { .. }) => HashAlgorithm::SHA2_384,
             PublicParams::ECDSA(EcdsaPublicParams::P521 { .. }) => HashAlgorithm::SHA2_512,
+            PublicParams::ECDSA(EcdsaPublicParams::Brainpool { curve, .. }) => match curve {
+                ECCCurve::BrainpoolP384r1 => HashAlgorithm::SHA2_384,
+                ECCCurve::BrainpoolP512r1 => HashAlgorithm::SHA2_512,
+                _ => HashAlgorithm::default(),
+            },
+            // RFC 9580 requires a digest of at least 512 bits with Ed448
+            PublicParams::Ed448 { .. } => HashAlgorithm::SHA2_512,
+            // DSA needs a hash at least as long as the subgroup order
+            PublicParams::DSA { q, .. } => match q.as_bytes().len() * 8 {
+                n if n > 384 => HashAlgorithm::SHA2_512,
+                n if n > 256 => HashAlgorithm::SHA2_384,
+                _ => HashAlgorithm::default(),
+            },
             _ => HashAlgorithm::default(),
         }
     }
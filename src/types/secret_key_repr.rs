@@ -1,7 +1,9 @@
 use zeroize::ZeroizeOnDrop;
 
 use crate::crypto::sym::SymmetricKeyAlgorithm;
-use crate::crypto::{checksum, dsa, ecdh, ecdsa, eddsa, rsa, Decryptor};
+use crate::crypto::{
+    checksum, dsa, ecdh, ecdsa, ed25519, ed448, eddsa, rsa, x25519, x448, Decryptor,
+};
 use crate::errors::Result;
 
 use super::Mpi;
@@ -14,7 +16,12 @@ pub enum SecretKeyRepr {
     DSA(dsa::SecretKey),
     ECDSA(ecdsa::SecretKey),
     ECDH(ecdh::SecretKey),
+    ECDHBrainpool(ecdh::BrainpoolSecretKey),
     EdDSA(eddsa::SecretKey),
+    Ed25519(ed25519::SecretKey),
+    X25519(x25519::SecretKey),
+    X448(x448::SecretKey),
+    Ed448(ed448::SecretKey),
 }
 
 impl SecretKeyRepr {
@@ -23,14 +30,27 @@ impl SecretKeyRepr {
         mpis: &[Mpi],
         fingerprint: &[u8],
     ) -> Result<(Vec<u8>, SymmetricKeyAlgorithm)> {
-        let decrypted_key = match self {
-            SecretKeyRepr::RSA(ref priv_key) => priv_key.decrypt(mpis, fingerprint)?,
-            SecretKeyRepr::DSA(_) => bail!("DSA is only used for signing"),
-            SecretKeyRepr::ECDSA(_) => bail!("ECDSA is only used for signing"),
-            SecretKeyRepr::ECDH(ref priv_key) => priv_key.decrypt(mpis, fingerprint)?,
-            SecretKeyRepr::EdDSA(_) => unimplemented_err!("EdDSA"),
-        };
+        let decrypted_key = self.decrypt_raw(mpis, fingerprint)?;
+
+        if matches!(self, SecretKeyRepr::X25519(_) | SecretKeyRepr::X448(_)) {
+            // the symmetric algorithm is stored in the clear, and there is no checksum
+            ensure_eq!(mpis.len(), 3, "missing symmetric algorithm");
+            let alg = SymmetricKeyAlgorithm::from(mpis[2].first().copied().unwrap_or_default());
+            ensure!(
+                alg != SymmetricKeyAlgorithm::Plaintext,
+                "session key algorithm cannot be plaintext"
+            );
+            ensure_eq!(
+                decrypted_key.len(),
+                alg.key_size(),
+                "invalid session key length for {:?}",
+                alg
+            );
 
+            return Ok((decrypted_key, alg));
+        }
+
+        ensure!(!decrypted_key.is_empty(), "empty session key");
         let session_key_algorithm = SymmetricKeyAlgorithm::from(decrypted_key[0]);
         ensure!(
             session_key_algorithm != SymmetricKeyAlgorithm::Plaintext,
@@ -39,25 +59,65 @@ impl SecretKeyRepr {
         let alg = session_key_algorithm;
         debug!("alg: {:?}", alg);
 
-        let (k, checksum) = match self {
-            SecretKeyRepr::ECDH(_) => {
-                let dec_len = decrypted_key.len();
-                (
-                    &decrypted_key[1..dec_len - 2],
-                    &decrypted_key[dec_len - 2..],
-                )
-            }
-            _ => {
-                let key_size = session_key_algorithm.key_size();
-                (
-                    &decrypted_key[1..=key_size],
-                    &decrypted_key[key_size + 1..key_size + 3],
-                )
-            }
-        };
+        let key_size = session_key_algorithm.key_size();
+        ensure!(key_size > 0, "unsupported session key algorithm {:?}", alg);
+        // algorithm octet, the key and a two octet checksum
+        ensure_eq!(
+            decrypted_key.len(),
+            key_size + 3,
+            "invalid session key length for {:?}",
+            alg
+        );
+        let (k, checksum) = (&decrypted_key[1..=key_size], &decrypted_key[key_size + 1..]);
 
         checksum::simple(checksum, k)?;
 
         Ok((k.to_vec(), alg))
     }
+
+    /// Decrypts the session key of a version 6 PKESK, which is not prefixed with the symmetric
+    /// key algorithm.
+    pub fn decrypt_v6(&self, mpis: &[Mpi], fingerprint: &[u8]) -> Result<Vec<u8>> {
+        let decrypted_key = self.decrypt_raw(mpis, fingerprint)?;
+
+        if matches!(self, SecretKeyRepr::X25519(_) | SecretKeyRepr::X448(_)) {
+            // there is no checksum
+            ensure!(
+                matches!(decrypted_key.len(), 16 | 24 | 32),
+                "invalid session key length {}",
+                decrypted_key.len()
+            );
+
+            return Ok(decrypted_key);
+        }
+
+        // the key and a two octet checksum
+        ensure!(
+            matches!(decrypted_key.len(), 18 | 26 | 34),
+            "invalid session key length {}",
+            decrypted_key.len()
+        );
+        let (k, checksum) = decrypted_key.split_at(decrypted_key.len() - 2);
+
+        checksum::simple(checksum, k)?;
+
+        Ok(k.to_vec())
+    }
+
+    fn decrypt_raw(&self, mpis: &[Mpi], fingerprint: &[u8]) -> Result<Vec<u8>> {
+        let decrypted_key = match self {
+            SecretKeyRepr::RSA(ref priv_key) => priv_key.decrypt(mpis, fingerprint)?,
+            SecretKeyRepr::DSA(_) => bail!("DSA is only used for signing"),
+            SecretKeyRepr::ECDSA(_) => bail!("ECDSA is only used for signing"),
+            SecretKeyRepr::ECDH(ref priv_key) => priv_key.decrypt(mpis, fingerprint)?,
+            SecretKeyRepr::ECDHBrainpool(ref priv_key) => priv_key.decrypt(mpis, fingerprint)?,
+            SecretKeyRepr::EdDSA(_) => unimplemented_err!("EdDSA"),
+            SecretKeyRepr::Ed25519(_) => bail!("Ed25519 is only used for signing"),
+            SecretKeyRepr::X25519(ref priv_key) => priv_key.decrypt(mpis, fingerprint)?,
+            SecretKeyRepr::X448(ref priv_key) => priv_key.decrypt(mpis, fingerprint)?,
+            SecretKeyRepr::Ed448(_) => bail!("Ed448 is only used for signing"),
+        };
+
+        Ok(decrypted_key)
+    }
 }
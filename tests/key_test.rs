@@ -22,16 +22,16 @@ use rsa::{
 use smallvec::SmallVec;
 
 use pgp::composed::signed_key::*;
-use pgp::composed::Deserializable;
+use pgp::composed::{Deserializable, StandaloneSignature};
 use pgp::crypto::ecdsa::SecretKey as ECDSASecretKey;
 use pgp::crypto::{
-    ecc_curve::ECCCurve, hash::HashAlgorithm, public_key::PublicKeyAlgorithm,
+    aead::AeadAlgorithm, ecc_curve::ECCCurve, hash::HashAlgorithm, public_key::PublicKeyAlgorithm,
     sym::SymmetricKeyAlgorithm,
 };
 use pgp::errors::Error;
 use pgp::packet::{
-    KeyFlags, Signature, SignatureType, SignatureVersion, Subpacket, SubpacketData, UserAttribute,
-    UserId,
+    ImageFormat, KeyFlags, PacketParser, Signature, SignatureType, SignatureVersion, Subpacket,
+    SubpacketData, UserAttribute, UserId,
 };
 use pgp::ser::Serialize;
 use pgp::types::{
@@ -330,6 +330,197 @@ fn test_parse_openpgp_sample_rsa_private() {
     assert_eq!(pub_key.key_id(), pkey.key_id());
 }
 
+#[test]
+fn test_created_at_unix() {
+    let (key, _) = SignedSecretKey::from_armor_single(
+        File::open("./tests/autocrypt/alice@autocrypt.example.sec.asc").unwrap(),
+    )
+    .unwrap();
+
+    // version, followed by the four byte creation time
+    let mut body = Vec::new();
+    key.primary_key.public_key().to_writer(&mut body).unwrap();
+    let stored = u32::from_be_bytes(body[1..5].try_into().unwrap());
+
+    assert_eq!(key.primary_key.created_at_unix(), stored);
+    let (public, _) = SignedPublicKey::from_armor_single(
+        File::open("./tests/autocrypt/alice@autocrypt.example.pub.asc").unwrap(),
+    )
+    .unwrap();
+    assert_eq!(public.created_at_unix(), stored);
+    assert_eq!(i64::from(stored), key.primary_key.created_at().timestamp());
+    let subkey = &key.secret_subkeys[0].key;
+    assert_eq!(
+        i64::from(subkey.created_at_unix()),
+        subkey.created_at().timestamp()
+    );
+}
+
+#[test]
+fn test_resolve_key_id() {
+    let (alice, _) = SignedPublicKey::from_armor_single(
+        File::open("./tests/autocrypt/alice@autocrypt.example.pub.asc").unwrap(),
+    )
+    .unwrap();
+    let (bob, _) = SignedPublicKey::from_armor_single(
+        File::open("./tests/autocrypt/bob@autocrypt.example.pub.asc").unwrap(),
+    )
+    .unwrap();
+    let keyring = [&bob, &alice];
+
+    let subkey = &alice.public_subkeys[0].key;
+    let id = subkey.key_id();
+    assert_eq!(
+        resolve_key_id(&keyring, id.as_ref()),
+        Some(subkey.fingerprint())
+    );
+    // the short key id
+    assert_eq!(
+        resolve_key_id(&keyring, &id.as_ref()[4..]),
+        Some(subkey.fingerprint())
+    );
+    assert_eq!(
+        resolve_key_id(&keyring, alice.key_id().as_ref()),
+        Some(alice.fingerprint())
+    );
+    assert_eq!(resolve_key_id(&keyring[..1], id.as_ref()), None);
+    assert_eq!(resolve_key_id(&keyring, &id.as_ref()[2..]), None);
+
+    assert_eq!(
+        describe_key_id(&keyring, &id),
+        format!("0x{}", hex::encode_upper(subkey.fingerprint()))
+    );
+    assert_eq!(
+        describe_key_id(&[], &id),
+        format!("0x{}", hex::encode_upper(&id))
+    );
+}
+
+#[test]
+fn test_sks_digest() {
+    let (alice, _) = SignedPublicKey::from_armor_single(
+        File::open("./tests/autocrypt/alice@autocrypt.example.pub.asc").unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        hex::encode(alice.sks_digest().unwrap()),
+        "44ad58fd1e41e863328b1b1ed84c9b1b"
+    );
+
+    // packets are sorted, so the position of a signature doesn't matter
+    let mut moved = alice.clone();
+    let sig = moved.public_subkeys[0].signatures.remove(0);
+    moved.details.direct_signatures.push(sig);
+    assert_eq!(moved.sks_digest().unwrap(), alice.sks_digest().unwrap());
+
+    // neither does a signature that is stored twice
+    let mut duplicated = alice.clone();
+    let sig = duplicated.details.users[0].signatures[0].clone();
+    duplicated.details.users[0].signatures.push(sig);
+    assert_eq!(
+        duplicated.sks_digest().unwrap(),
+        alice.sks_digest().unwrap()
+    );
+
+    let mut stripped = alice.clone();
+    stripped.public_subkeys.clear();
+    assert_ne!(stripped.sks_digest().unwrap(), alice.sks_digest().unwrap());
+}
+
+#[test]
+fn test_weak_preferences() {
+    let file = File::open("./tests/opengpg-interop/testcases/keys/gnupg-v1-003.asc").unwrap();
+    let (key, _headers) = SignedPublicKey::from_armor_single(file).unwrap();
+    // both keys are valid at this time, alice's key expired in 2021
+    let at = DateTime::parse_from_rfc3339("2020-01-01T00:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+
+    assert_eq!(
+        key.weak_preferences(&AlgorithmPolicy::default(), &at),
+        vec![
+            WeakPref::Symmetric(SymmetricKeyAlgorithm::CAST5),
+            WeakPref::Symmetric(SymmetricKeyAlgorithm::TripleDES),
+            WeakPref::Hash(HashAlgorithm::SHA1),
+        ]
+    );
+
+    let policy = AlgorithmPolicy {
+        weak_symmetric_algs: vec![SymmetricKeyAlgorithm::AES128],
+        weak_hash_algs: Vec::new(),
+    };
+    assert_eq!(
+        key.weak_preferences(&policy, &at),
+        vec![WeakPref::Symmetric(SymmetricKeyAlgorithm::AES128)]
+    );
+
+    let (alice, _) = SignedPublicKey::from_armor_single(
+        File::open("./tests/autocrypt/alice@autocrypt.example.pub.asc").unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        alice.weak_preferences(&AlgorithmPolicy::default(), &at),
+        vec![
+            WeakPref::Symmetric(SymmetricKeyAlgorithm::TripleDES),
+            WeakPref::Hash(HashAlgorithm::SHA1),
+        ]
+    );
+
+    // nothing is reported once the key expired
+    let expired = DateTime::parse_from_rfc3339("2022-01-01T00:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+    assert!(alice
+        .weak_preferences(&AlgorithmPolicy::default(), &expired)
+        .is_empty());
+}
+
+#[test]
+fn test_key_server_no_modify() {
+    let (alice, _) = SignedPublicKey::from_armor_single(
+        File::open("./tests/autocrypt/alice@autocrypt.example.pub.asc").unwrap(),
+    )
+    .unwrap();
+    assert!(alice.details.users[0].signatures[0].key_server_no_modify());
+    // the key expired in 2021
+    let created_at = *alice.primary_key.created_at();
+    assert!(alice.key_server_no_modify(&created_at));
+    assert!(!alice.key_server_no_modify(&chrono::Utc::now()));
+
+    let file = File::open("./tests/opengpg-interop/testcases/keys/openkeychain-001.asc").unwrap();
+    let (key, _headers) = SignedPublicKey::from_armor_single(file).unwrap();
+    assert!(key.details.users[0].signatures[0]
+        .key_server_prefs()
+        .is_empty());
+    assert!(!key.details.users[0].signatures[0].key_server_no_modify());
+}
+
+#[test]
+fn test_user_attribute_images() {
+    let file = File::open("./tests/opengpg-interop/testcases/keys/gnupg-v1-003.asc").unwrap();
+    let (key, _headers) = SignedPublicKey::from_armor_single(file).expect("failed to parse key");
+
+    let attributes: Vec<_> = key.user_attributes().collect();
+    assert_eq!(attributes.len(), 1);
+    let (format, image) = attributes[0].as_image().expect("not an image");
+    assert_eq!(format, ImageFormat::Jpeg);
+    assert_eq!(image.len(), 1156);
+    // JPEG start of image marker
+    assert_eq!(&image[..2], &[0xff, 0xd8]);
+
+    for attr in &key.details.user_attributes {
+        attr.verify(&key.primary_key)
+            .expect("invalid self-signature");
+    }
+
+    let unknown = UserAttribute::Unknown {
+        packet_version: Version::New,
+        typ: 100,
+        data: vec![1, 2, 3],
+    };
+    assert!(unknown.as_image().is_none());
+}
+
 #[test]
 fn test_parse_details() {
     let _ = pretty_env_logger::try_init();
@@ -1079,6 +1270,60 @@ fn private_ecc1_verify() {
     assert_eq!(pub_key.key_id(), sk.key_id());
 }
 
+#[test]
+fn same_key_as() {
+    let f = read_file("./tests/openpgp/samplekeys/ecc-sample-1-sec.asc");
+    let (sk, _headers) = SignedSecretKey::from_armor_single(f).expect("failed to parse key");
+    let f = read_file("./tests/openpgp/samplekeys/ecc-sample-2-sec.asc");
+    let (other, _headers) = SignedSecretKey::from_armor_single(f).expect("failed to parse key");
+
+    let mut stripped = sk.clone();
+    stripped.secret_subkeys.clear();
+    stripped.details.users[0].signatures.reverse();
+    stripped.details.users[0]
+        .signatures
+        .push(sk.details.users[0].signatures[0].clone());
+    assert_ne!(sk, stripped);
+    assert!(sk.same_key_as(&stripped));
+    assert!(!sk.same_key_as(&other));
+
+    let pk = sk.public_key().sign(&sk, || "ecc".into()).unwrap();
+    assert!(pk.same_key_as(&sk));
+    assert!(sk.same_key_as(&pk));
+    assert!(!pk.same_key_as(&other));
+}
+
+#[test]
+fn peek_key_metadata_matches_full_parse() {
+    let armored = std::fs::read("./tests/openpgp/samplekeys/ecc-sample-1-sec.asc").unwrap();
+    let (sk, _headers) =
+        SignedSecretKey::from_armor_single(&armored[..]).expect("failed to parse key");
+
+    let meta = peek_key_metadata(&armored).expect("failed to peek");
+    assert_eq!(meta.fingerprint, sk.fingerprint());
+    assert_eq!(meta.key_id, sk.key_id());
+    assert_eq!(meta.algorithm, sk.algorithm());
+    assert_eq!(meta.created_at, *sk.primary_key.created_at());
+    assert!(meta.is_secret);
+    assert_eq!(meta.user_id.as_ref(), Some(&sk.details.users[0].id));
+
+    let binary = sk.to_bytes().unwrap();
+    assert_eq!(peek_key_metadata(&binary).expect("failed to peek"), meta);
+
+    let pk = sk.public_key().sign(&sk, || "ecc".into()).unwrap();
+    let meta = peek_key_metadata(&pk.to_bytes().unwrap()).expect("failed to peek");
+    assert!(!meta.is_secret);
+    assert_eq!(meta.fingerprint, sk.fingerprint());
+
+    // no user ids
+    let mut bare = Vec::new();
+    pgp::packet::write_packet(&mut bare, &sk.primary_key).unwrap();
+    let meta = peek_key_metadata(&bare).expect("failed to peek");
+    assert_eq!(meta.user_id, None);
+
+    assert!(peek_key_metadata(b"").is_err());
+}
+
 #[test]
 fn private_ecc2_verify() {
     let f = read_file("./tests/openpgp/samplekeys/ecc-sample-2-sec.asc");
@@ -1303,3 +1548,432 @@ fn test_encrypted_key() {
 
     let _signed_key = unsigned_pubkey.sign(&key, || "123".into()).unwrap();
 }
+
+#[test]
+fn test_detach_sign_armored() {
+    let (key, _headers) =
+        SignedSecretKey::from_armor_single(read_file("./tests/key-with-password-123.asc"))
+            .expect("failed to parse key");
+    let data = b"hello world\n";
+
+    let armored = key
+        .detach_sign_armored(&data[..], HashAlgorithm::SHA2_256, || "123".into())
+        .unwrap();
+    assert!(armored.starts_with("-----BEGIN PGP SIGNATURE-----\n\n"));
+    assert!(armored.ends_with("\n-----END PGP SIGNATURE-----\n"));
+
+    let (sig, _headers) = StandaloneSignature::from_string(&armored).unwrap();
+    assert_eq!(sig.signature.typ(), SignatureType::Binary);
+    assert!(sig.signature.created().is_some());
+
+    let public_key = SignedPublicKey::from(key);
+    let verify = |data: &[u8]| {
+        sig.verify(&public_key.primary_key, data).is_ok()
+            || public_key
+                .public_subkeys
+                .iter()
+                .any(|subkey| sig.verify(&subkey.key, data).is_ok())
+    };
+    assert!(verify(data));
+    assert!(!verify(b"hello world"));
+}
+
+#[test]
+fn test_old_format_packet_headers() {
+    let (key, _headers) = SignedSecretKey::from_armor_single(read_file(
+        "./tests/opengpg-interop/testcases/messages/gnupg-v1-001-decrypt.asc",
+    ))
+    .expect("failed to parse key");
+
+    let reencode = |bytes: &[u8], version: Version| {
+        let mut out = Vec::new();
+        for packet in PacketParser::new(bytes) {
+            packet
+                .unwrap()
+                .to_writer_with_version(&mut out, version)
+                .unwrap();
+        }
+        out
+    };
+
+    let new_format = reencode(&key.to_bytes().unwrap(), Version::New);
+    let old_format = reencode(&new_format, Version::Old);
+    assert_ne!(new_format, old_format);
+    assert_eq!(
+        old_format[0] & 0b0100_0000,
+        0,
+        "expected an old format header"
+    );
+
+    // the old format key parses to the same key, and keeps its header format
+    let old_key = SignedSecretKey::from_bytes(&old_format[..]).expect("failed to parse key");
+    old_key.verify().expect("invalid key");
+    assert_eq!(old_key.fingerprint(), key.fingerprint());
+    assert_eq!(old_key.to_bytes().unwrap(), old_format);
+    assert_eq!(reencode(&old_format, Version::New), new_format);
+}
+
+#[test]
+fn test_strip_unhashed_subpackets() {
+    let (mut key, _headers) = SignedPublicKey::from_armor_single(read_file(
+        "./tests/autocrypt/alice@autocrypt.example.pub.asc",
+    ))
+    .expect("failed to parse key");
+
+    let tampered = Subpacket::regular(SubpacketData::PolicyURI("https://example.com".into()));
+    let user_sig = &mut key.details.users[0].signatures[0];
+    user_sig.config.unhashed_subpackets.push(tampered.clone());
+    let subkey_sig = &mut key.public_subkeys[0].signatures[0];
+    subkey_sig.config.unhashed_subpackets.push(tampered.clone());
+
+    key.strip_unhashed_subpackets();
+    key.verify().expect("invalid key");
+
+    for sig in [
+        &key.details.users[0].signatures[0],
+        &key.public_subkeys[0].signatures[0],
+    ] {
+        assert!(!sig.config.unhashed_subpackets.contains(&tampered));
+        assert!(sig
+            .config
+            .unhashed_subpackets
+            .iter()
+            .all(|p| matches!(p.data, SubpacketData::Issuer(_))));
+    }
+    assert!(!key.details.users[0].signatures[0].issuer().is_empty());
+}
+
+#[test]
+fn test_public_key_from_base64() {
+    let armored = std::fs::read_to_string("./tests/autocrypt/alice@autocrypt.example.pub.asc")
+        .expect("failed to read key");
+    let (key, _headers) = SignedPublicKey::from_string(&armored).expect("failed to parse key");
+
+    // the armor body, without header, footer and checksum
+    let body: String = armored
+        .lines()
+        .skip_while(|line| !line.is_empty())
+        .filter(|line| !line.starts_with('=') && !line.starts_with("-----"))
+        .map(|line| format!("{line}\n"))
+        .collect();
+    assert_eq!(SignedPublicKey::from_base64(&body).unwrap(), key);
+
+    assert!(matches!(
+        SignedPublicKey::from_base64("not base64!"),
+        Err(Error::Base64DecodeError(_))
+    ));
+    assert!(SignedPublicKey::from_base64("aGVsbG8gd29ybGQ=").is_err());
+
+    let mut bytes = key.to_bytes().unwrap();
+    bytes.extend(key.to_bytes().unwrap());
+    let two_keys =
+        base64::engine::Engine::encode(&base64::engine::general_purpose::STANDARD, bytes);
+    assert!(matches!(
+        SignedPublicKey::from_base64(&two_keys),
+        Err(Error::TooManyPackets)
+    ));
+}
+
+#[test]
+fn test_v6_secret_subkey_roundtrip() {
+    let mut rng = thread_rng();
+    let (key, _) = SignedSecretKey::from_armor_single(
+        File::open("./tests/autocrypt/alice@autocrypt.example.sec.asc").unwrap(),
+    )
+    .unwrap();
+    let subkey = &key.secret_subkeys[0].key;
+
+    // re-encode the encryption subkey material in the v6 format
+    let public_params = subkey.public_params().to_bytes().unwrap();
+    let mut secret = subkey.secret_params().to_bytes().unwrap();
+    assert_eq!(secret[0], 0);
+    // unprotected v6 keys have no checksum
+    secret.truncate(secret.len() - 2);
+
+    let mut body = vec![6];
+    body.extend_from_slice(&subkey.created_at_unix().to_be_bytes());
+    body.push(subkey.algorithm().into());
+    body.extend_from_slice(&(public_params.len() as u32).to_be_bytes());
+    body.extend_from_slice(&public_params);
+    body.extend_from_slice(&secret);
+
+    let v6 = pgp::packet::SecretSubkey::from_slice(Version::New, &body).unwrap();
+    assert_eq!(v6.version(), KeyVersion::V6);
+    assert_eq!(v6.public_params(), subkey.public_params());
+    assert_eq!(v6.to_bytes().unwrap(), body);
+
+    // the key id is the start of the SHA256 fingerprint
+    let fingerprint = v6.fingerprint();
+    assert_eq!(fingerprint.len(), 32);
+    assert_eq!(v6.key_id(), KeyId::from_slice(&fingerprint[..8]).unwrap());
+
+    let aead = S2kParams::Aead {
+        sym_alg: SymmetricKeyAlgorithm::AES128,
+        aead_mode: AeadAlgorithm::Ocb,
+        s2k: StringToKey::new_default(&mut rng),
+        nonce: vec![7; AeadAlgorithm::Ocb.nonce_size()],
+    };
+    for s2k in [S2kParams::new_default(&mut rng), aead] {
+        let mut locked = v6.clone();
+        locked.set_password_with_s2k("hunter2", s2k).unwrap();
+
+        // s2k usage, the length of the s2k fields and the symmetric algorithm
+        let bytes = locked.to_bytes().unwrap();
+        let offset = body.len() - secret.len();
+        assert!(bytes[offset] == 253 || bytes[offset] == 254);
+        assert_eq!(bytes[offset + 2], u8::from(locked_sym_alg(&locked)));
+
+        let parsed = pgp::packet::SecretSubkey::from_slice(Version::New, &bytes).unwrap();
+        assert_eq!(parsed, locked);
+        assert!(parsed.unlock(|| "wrong".into(), |_| Ok(())).is_err());
+
+        let session_key = [42u8; 16];
+        let pkesk = pgp::packet::PublicKeyEncryptedSessionKey::from_session_key(
+            &mut rng,
+            &session_key,
+            SymmetricKeyAlgorithm::AES128,
+            &parsed.public_key(),
+        )
+        .unwrap();
+        assert_eq!(
+            pkesk.decrypt(&parsed, || "hunter2".into()).unwrap(),
+            pgp::PlainSessionKey::V4 {
+                sym_alg: SymmetricKeyAlgorithm::AES128,
+                key: session_key.to_vec(),
+            }
+        );
+    }
+}
+
+fn locked_sym_alg(key: &pgp::packet::SecretSubkey) -> SymmetricKeyAlgorithm {
+    match key.secret_params() {
+        SecretParams::Encrypted(params) => match params.string_to_key_params() {
+            S2kParams::Aead { sym_alg, .. } | S2kParams::Cfb { sym_alg, .. } => *sym_alg,
+            params => panic!("unexpected s2k params {:?}", params),
+        },
+        SecretParams::Plain(_) => panic!("key is not encrypted"),
+    }
+}
+
+/// Ref https://www.rfc-editor.org/rfc/rfc9580.html#name-sample-locked-version-6-sec
+#[test]
+#[ignore = "Argon2 with the sample parameters needs 2 GiB of memory"]
+fn test_aead_secret_key_rfc9580_unlock() {
+    let (key, _headers) =
+        SignedSecretKey::from_armor_single(read_file("./tests/rfc9580/v6-locked-secret-key.asc"))
+            .unwrap();
+    key.verify().unwrap();
+
+    let mut primary_key = key.primary_key.clone();
+    let SecretParams::Encrypted(encrypted) = primary_key.secret_params().clone() else {
+        panic!("not encrypted");
+    };
+    primary_key
+        .remove_password(|| "correct horse battery staple".into())
+        .unwrap();
+
+    // protecting it again with the same parameters reproduces the encrypted key
+    primary_key
+        .set_password_with_s2k(
+            "correct horse battery staple",
+            encrypted.string_to_key_params().clone(),
+        )
+        .unwrap();
+    assert_eq!(primary_key, key.primary_key);
+}
+
+#[test]
+fn test_mixed_version_key() {
+    use pgp::composed::{
+        KeyType, Message, SecretKeyParamsBuilder, SecretSubkey, SeipdVersionPolicy,
+    };
+
+    let mut rng = thread_rng();
+
+    // a v6 primary key, with a v4 encryption subkey
+    let mut key = SecretKeyParamsBuilder::default()
+        .key_type(KeyType::Ed25519)
+        .can_certify(true)
+        .can_sign(true)
+        .version(KeyVersion::V6)
+        .primary_user_id("Me <me@mail.com>".into())
+        .build()
+        .unwrap()
+        .generate()
+        .unwrap()
+        .sign(String::new)
+        .unwrap();
+
+    let (alice, _) = SignedSecretKey::from_armor_single(
+        File::open("./tests/autocrypt/alice@autocrypt.example.sec.asc").unwrap(),
+    )
+    .unwrap();
+    let subkey = alice.secret_subkeys[0].key.clone();
+    assert_eq!(subkey.version(), KeyVersion::V4);
+
+    let mut flags = KeyFlags::default();
+    flags.set_encrypt_comms(true);
+    flags.set_encrypt_storage(true);
+    let subkey = SecretSubkey::new(subkey, flags)
+        .sign(&key.primary_key, String::new)
+        .unwrap();
+    key.secret_subkeys.push(subkey);
+
+    let armor = key.to_armored_string(None.into()).unwrap();
+    let (key, _) = SignedSecretKey::from_string(&armor).unwrap();
+    key.verify().unwrap();
+
+    // fingerprints are computed per key version
+    assert_eq!(key.primary_key.version(), KeyVersion::V6);
+    assert_eq!(key.fingerprint().len(), 32);
+    let subkey = &key.secret_subkeys[0];
+    assert_eq!(subkey.key.version(), KeyVersion::V4);
+    assert_eq!(
+        subkey.key.fingerprint(),
+        alice.secret_subkeys[0].key.fingerprint()
+    );
+    assert_eq!(subkey.key.fingerprint().len(), 20);
+    assert_eq!(subkey.signatures[0].config.version, SignatureVersion::V6);
+
+    let public_key = SignedPublicKey::from(key.clone());
+    let armor = public_key.to_armored_string(None.into()).unwrap();
+    let (public_key, _) = SignedPublicKey::from_string(&armor).unwrap();
+    public_key.verify().unwrap();
+
+    let msg = Message::new_literal("hello.txt", "hello world");
+    let (negotiated, _) = msg
+        .encrypt_to_recipients(
+            &mut rng,
+            SymmetricKeyAlgorithm::AES128,
+            AeadAlgorithm::Ocb,
+            &[&public_key],
+            SeipdVersionPolicy::Negotiate,
+        )
+        .unwrap();
+    // a version 6 PKESK, which names the v4 subkey by its fingerprint
+    let seipdv2 = msg
+        .encrypt_to_keys_seipdv2(
+            &mut rng,
+            SymmetricKeyAlgorithm::AES128,
+            AeadAlgorithm::Ocb,
+            6,
+            &[&public_key.public_subkeys[0].key],
+        )
+        .unwrap();
+    for encrypted in [negotiated, seipdv2] {
+        let (decrypted, key_ids) = encrypted.decrypt(String::new, &[&key]).unwrap();
+        assert_eq!(key_ids, vec![subkey.key.key_id()]);
+        assert_eq!(
+            decrypted.get_content().unwrap().unwrap(),
+            b"hello world".to_vec()
+        );
+    }
+}
+
+#[test]
+fn test_downgrade_to_v4() {
+    use pgp::composed::{KeyType, Message, SecretKeyParamsBuilder, SubkeyParamsBuilder};
+
+    let mut rng = thread_rng();
+    let s2k = S2kParams::new_argon2(&mut rng, AeadAlgorithm::Ocb, 1, 1, 10);
+    let subkey = |key_type: KeyType, sign: bool| {
+        SubkeyParamsBuilder::default()
+            .key_type(key_type)
+            .version(KeyVersion::V6)
+            .can_sign(sign)
+            .can_encrypt(!sign)
+            .passphrase(Some("hunter2".into()))
+            .s2k(Some(s2k.clone()))
+            .build()
+            .unwrap()
+    };
+    let key = SecretKeyParamsBuilder::default()
+        .key_type(KeyType::Ed25519)
+        .version(KeyVersion::V6)
+        .can_certify(true)
+        .primary_user_id("Me <me@mail.com>".into())
+        .passphrase(Some("hunter2".into()))
+        .s2k(Some(s2k.clone()))
+        .expiration(Some(std::time::Duration::from_secs(365 * 24 * 60 * 60)))
+        .subkey(subkey(KeyType::X25519, false))
+        .subkey(subkey(KeyType::Ed25519, true))
+        .build()
+        .unwrap()
+        .generate()
+        .unwrap()
+        .sign(|| "hunter2".into())
+        .unwrap();
+    key.verify().unwrap();
+
+    assert!(key.downgrade_to_v4(|| "wrong".into()).is_err());
+    let downgraded = key.downgrade_to_v4(|| "hunter2".into()).unwrap();
+    downgraded.verify().unwrap();
+
+    // the same key material, in a v4 key with a different fingerprint
+    assert_eq!(downgraded.primary_key.version(), KeyVersion::V4);
+    assert_eq!(downgraded.fingerprint().len(), 20);
+    assert_eq!(downgraded.algorithm(), PublicKeyAlgorithm::EdDSA);
+    let (PublicParams::Ed25519 { public }, PublicParams::EdDSA { q, .. }) = (
+        key.primary_key.public_params(),
+        downgraded.primary_key.public_params(),
+    ) else {
+        panic!("unexpected public params");
+    };
+    assert_eq!(&q.as_bytes()[1..], &public[..]);
+    assert_eq!(
+        downgraded.primary_key.created_at(),
+        key.primary_key.created_at()
+    );
+    assert_eq!(downgraded.expires_at(), key.expires_at());
+    assert_eq!(downgraded.details.users.len(), 1);
+    assert_eq!(downgraded.details.users[0].id, key.details.users[0].id);
+    assert!(!downgraded.primary_key.secret_params().is_encrypted());
+
+    let algorithms = downgraded
+        .secret_subkeys
+        .iter()
+        .map(|subkey| {
+            assert_eq!(subkey.key.version(), KeyVersion::V4);
+            assert_eq!(subkey.signatures[0].config.version, SignatureVersion::V4);
+            (
+                subkey.key.algorithm(),
+                subkey.signatures[0].key_flags().sign(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        algorithms,
+        vec![
+            (PublicKeyAlgorithm::ECDH, false),
+            (PublicKeyAlgorithm::EdDSA, true)
+        ]
+    );
+
+    let public_key = SignedPublicKey::from(downgraded.clone());
+    let armor = public_key.to_armored_string(None.into()).unwrap();
+    let (public_key, _) = SignedPublicKey::from_string(&armor).unwrap();
+    public_key.verify().unwrap();
+
+    let msg = Message::new_literal("hello.txt", "hello world");
+    let signed = msg
+        .clone()
+        .sign(&downgraded, String::new, HashAlgorithm::SHA2_256)
+        .unwrap();
+    signed.verify(&public_key).unwrap();
+
+    let encrypted = msg
+        .encrypt_to_keys(
+            &mut rng,
+            SymmetricKeyAlgorithm::AES128,
+            &[&public_key.public_subkeys[0]],
+        )
+        .unwrap();
+    let (decrypted, _) = encrypted.decrypt(String::new, &[&downgraded]).unwrap();
+    assert_eq!(
+        decrypted.get_content().unwrap().unwrap(),
+        b"hello world".to_vec()
+    );
+
+    // only v6 keys are downgraded
+    assert!(downgraded.downgrade_to_v4(String::new).is_err());
+}
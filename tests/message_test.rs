@@ -344,3 +344,25 @@ fn msg_literal_signature() {
 
     msg.verify(&pkey).unwrap();
 }
+
+#[test]
+fn msg_rfc9580_x25519_seipdv2() {
+    let (decrypt_key, _headers) = SignedSecretKey::from_armor_single(
+        File::open("./tests/rfc9580/v6-secret-key.asc").unwrap(),
+    )
+    .expect("failed to parse key");
+    decrypt_key.verify().expect("invalid key");
+
+    let mut msg_file = File::open("./tests/rfc9580/v6-x25519-ocb-message.asc").unwrap();
+    let (message, _headers) =
+        Message::from_armor_single(&mut msg_file).expect("failed to parse message");
+
+    let (decrypted, key_ids) = message
+        .decrypt(String::new, &[&decrypt_key])
+        .expect("failed to decrypt message");
+    assert_eq!(key_ids, vec![decrypt_key.secret_subkeys[0].key.key_id()]);
+    assert_eq!(
+        decrypted.get_content().unwrap().unwrap(),
+        b"Hello, world!".to_vec()
+    );
+}